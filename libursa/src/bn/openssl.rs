@@ -586,6 +586,27 @@ impl BigNumber {
     }
 }
 
+/// SHA-256 of data that is too large to hash in one piece
+pub struct Sha256Hasher {
+    hasher: Hasher,
+}
+
+impl Sha256Hasher {
+    pub fn new() -> UrsaCryptoResult<Sha256Hasher> {
+        Ok(Sha256Hasher {
+            hasher: Hasher::new(MessageDigest::sha256())?,
+        })
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> UrsaCryptoResult<()> {
+        Ok(self.hasher.update(data)?)
+    }
+
+    pub fn finish(mut self) -> UrsaCryptoResult<Vec<u8>> {
+        Ok(self.hasher.finish()?.to_vec())
+    }
+}
+
 impl Ord for BigNumber {
     fn cmp(&self, other: &BigNumber) -> Ordering {
         self.openssl_bn.cmp(&other.openssl_bn)
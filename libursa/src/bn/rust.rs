@@ -499,6 +499,28 @@ impl BigNumber {
     }
 }
 
+/// SHA-256 of data that is too large to hash in one piece
+pub struct Sha256Hasher {
+    hasher: sha2::Sha256,
+}
+
+impl Sha256Hasher {
+    pub fn new() -> UrsaCryptoResult<Sha256Hasher> {
+        Ok(Sha256Hasher {
+            hasher: sha2::Sha256::new(),
+        })
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> UrsaCryptoResult<()> {
+        self.hasher.update(data);
+        Ok(())
+    }
+
+    pub fn finish(self) -> UrsaCryptoResult<Vec<u8>> {
+        Ok(self.hasher.finalize().as_slice().to_vec())
+    }
+}
+
 impl fmt::Debug for BigNumber {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "BigNumber {{ bn: {} }}", self.bn.to_str_radix(10))
@@ -21,6 +21,15 @@ pub const ITERATION: usize = 4;
 */
 pub const LARGE_NONCE: usize = 80; // number of bits
 pub const LARGE_ALPHATILDE: usize = 2787;
+// Set predicates: the challenge is a SHA-256 hash and every blinding factor
+// must hide a witness of up to LARGE_SET_PRIME + LARGE_VPRIME bits times the challenge
+pub const LARGE_CHALLENGE: usize = 256;
+pub const LARGE_SET_PRIME: usize = 521;
+pub const LARGE_SET_TILDE: usize = 3072;
+// Inequality predicates: each of the four squares of delta has at most half its bits
+// and LARGE_UTILDE must hide it times the challenge with LARGE_NONCE bits to spare.
+// LARGE_ALPHATILDE then also covers the squares times LARGE_VPRIME bits randomness
+pub const LARGE_PREDICATE_DELTA: usize = 2 * (LARGE_UTILDE - LARGE_CHALLENGE - LARGE_NONCE);
 
 // Constants that are used throughout the CL signatures code, so avoiding recomputation.
 lazy_static! {
@@ -35,4 +44,13 @@ lazy_static! {
     pub static ref LARGE_VPRIME_PRIME_VALUE: BigNumber = BIGNUMBER_2
         .exp(&BigNumber::from_u32(LARGE_VPRIME_PRIME - 1).unwrap(), None)
         .unwrap();
+    pub static ref LARGE_CHALLENGE_VALUE: BigNumber = BIGNUMBER_2
+        .exp(&BigNumber::from_u32(LARGE_CHALLENGE).unwrap(), None)
+        .unwrap();
+    // The Mersenne prime 2^521 - 1, larger than the difference of any two encoded attributes
+    pub static ref SET_PREDICATE_PRIME: BigNumber = BIGNUMBER_2
+        .exp(&BigNumber::from_u32(LARGE_SET_PRIME).unwrap(), None)
+        .unwrap()
+        .decrement()
+        .unwrap();
 }
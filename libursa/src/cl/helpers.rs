@@ -1,5 +1,5 @@
 use super::constants::*;
-use bn::{BigNumber, BIGNUMBER_1};
+use bn::{BigNumber, BIGNUMBER_1, BIGNUMBER_2};
 use cl::*;
 use errors::prelude::*;
 use pair::GroupOrderElement;
@@ -469,6 +469,94 @@ pub fn calc_tne<S: ::std::hash::BuildHasher>(
     Ok(tau_list)
 }
 
+/// Calculates `s^rho * (t / z^value)^(-c)` for one value of a set membership predicate
+pub fn calc_tset_in(
+    p_pub_key: &CredentialPrimaryPublicKey,
+    t: &BigNumber,
+    value: &BigNumber,
+    c: &BigNumber,
+    rho: &BigNumber,
+) -> UrsaCryptoResult<BigNumber> {
+    trace!(
+        "Helpers::calc_tset_in: >>> p_pub_key: {:?}, t: {:?}, value: {:?}, c: {:?}, rho: {:?}",
+        p_pub_key,
+        t,
+        value,
+        c,
+        rho
+    );
+
+    let mut ctx = BigNumber::new_context()?;
+
+    let t_value = t.mod_div(
+        &p_pub_key.z.mod_exp(value, &p_pub_key.n, Some(&mut ctx))?,
+        &p_pub_key.n,
+        Some(&mut ctx),
+    )?;
+
+    let t_tau = t_value
+        .mod_exp(c, &p_pub_key.n, Some(&mut ctx))?
+        .inverse(&p_pub_key.n, Some(&mut ctx))?
+        .mod_mul(
+            &p_pub_key.s.mod_exp(rho, &p_pub_key.n, Some(&mut ctx))?,
+            &p_pub_key.n,
+            Some(&mut ctx),
+        )?;
+
+    trace!("Helpers::calc_tset_in: <<< t_tau: {:?}", t_tau);
+
+    Ok(t_tau)
+}
+
+/// Calculates `(t / z^value)^a * z^(b * SET_PREDICATE_PRIME) * s^rho` for one value of a not equal predicate
+pub fn calc_tset_ne(
+    p_pub_key: &CredentialPrimaryPublicKey,
+    t: &BigNumber,
+    value: &BigNumber,
+    a: &BigNumber,
+    b: &BigNumber,
+    rho: &BigNumber,
+) -> UrsaCryptoResult<BigNumber> {
+    trace!(
+        "Helpers::calc_tset_ne: >>> p_pub_key: {:?}, t: {:?}, value: {:?}, a: {:?}, b: {:?}, rho: {:?}",
+        p_pub_key,
+        t,
+        value,
+        a,
+        b,
+        rho
+    );
+
+    let mut ctx = BigNumber::new_context()?;
+
+    let t_value = t.mod_div(
+        &p_pub_key.z.mod_exp(value, &p_pub_key.n, Some(&mut ctx))?,
+        &p_pub_key.n,
+        Some(&mut ctx),
+    )?;
+
+    let t_tau = t_value
+        .mod_exp(a, &p_pub_key.n, Some(&mut ctx))?
+        .mod_mul(
+            &p_pub_key.z.mod_exp(
+                &b.mul(&SET_PREDICATE_PRIME, Some(&mut ctx))?,
+                &p_pub_key.n,
+                Some(&mut ctx),
+            )?,
+            &p_pub_key.n,
+            Some(&mut ctx),
+        )?
+        .mod_mul(
+            &p_pub_key.s.mod_exp(rho, &p_pub_key.n, Some(&mut ctx))?,
+            &p_pub_key.n,
+            Some(&mut ctx),
+        )?;
+
+    trace!("Helpers::calc_tset_ne: <<< t_tau: {:?}", t_tau);
+
+    Ok(t_tau)
+}
+
 fn largest_square_less_than(delta: usize) -> usize {
     (delta as f64).sqrt().floor() as usize
 }
@@ -536,6 +624,151 @@ pub fn four_squares(delta: i32) -> UrsaCryptoResult<HashMap<String, BigNumber>>
     Ok(res)
 }
 
+//Express the natural number `delta` of any size as a sum of four integer squares.
+// Values that fit in `i32` use `four_squares`. For larger values `delta = 4^k * m`,
+// random `x` and `y` are picked until `p = m - x^2 - y^2` is a prime `p = 1 mod 4`
+// and `p` is split into two squares with the Hermite-Serret algorithm
+pub fn four_squares_bn(delta: &BigNumber) -> UrsaCryptoResult<HashMap<String, BigNumber>> {
+    trace!("Helpers::four_squares_bn: >>> delta: {:?}", delta);
+
+    if delta.is_negative() {
+        return Err(err_msg(
+            UrsaCryptoErrorKind::InvalidStructure,
+            format!(
+                "Cannot express a negative number as sum of four squares {:?} ",
+                delta
+            ),
+        ));
+    }
+
+    if delta.num_bits()? < 32 {
+        return four_squares(_small_to_i32(delta)?);
+    }
+
+    let mut m = delta.try_clone()?;
+    let mut k = 0;
+    while !m.is_bit_set(0)? && !m.is_bit_set(1)? {
+        m = m.rshift(2)?;
+        k += 1;
+    }
+
+    let roots = if m.num_bits()? < 32 {
+        let u = four_squares(_small_to_i32(&m)?)?;
+        let mut roots = Vec::new();
+        for i in 0..ITERATION {
+            roots.push(u[&i.to_string()].try_clone()?);
+        }
+        roots
+    } else {
+        _four_squares_large(&m)?
+    };
+
+    let scale = BIGNUMBER_2.exp(&BigNumber::from_u32(k)?, None)?;
+    let mut res = HashMap::new();
+    for (i, root) in roots.iter().enumerate() {
+        res.insert(i.to_string(), root.mul(&scale, None)?);
+    }
+
+    trace!("Helpers::four_squares_bn: <<< res: {:?}", res);
+
+    Ok(res)
+}
+
+fn _small_to_i32(value: &BigNumber) -> UrsaCryptoResult<i32> {
+    value.to_dec()?.parse::<i32>().map_err(|_| {
+        err_msg(
+            UrsaCryptoErrorKind::InvalidStructure,
+            format!("Value {:?} does not fit in i32", value),
+        )
+    })
+}
+
+// `m` must not be divisible by 4
+fn _four_squares_large(m: &BigNumber) -> UrsaCryptoResult<Vec<BigNumber>> {
+    let mut ctx = BigNumber::new_context()?;
+
+    // x^2 + y^2 must be congruent to m - 1 modulo 4 for p = 1 mod 4
+    let (x_odd, y_odd) = match (m.is_bit_set(1)?, m.is_bit_set(0)?) {
+        (false, true) => (false, false),
+        (true, false) => (true, false),
+        (true, true) => (true, true),
+        (false, false) => {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                "Value must not be divisible by 4",
+            ))
+        }
+    };
+
+    let half_bound = isqrt(&m.rshift1()?)?.rshift1()?;
+    let random_root = |odd: bool| -> UrsaCryptoResult<BigNumber> {
+        let root = half_bound.rand_range()?.lshift1()?;
+        if odd {
+            root.increment()
+        } else {
+            Ok(root)
+        }
+    };
+
+    let (x, y, p) = loop {
+        let x = random_root(x_odd)?;
+        let y = random_root(y_odd)?;
+        let p = m
+            .sub(&x.sqr(Some(&mut ctx))?)?
+            .sub(&y.sqr(Some(&mut ctx))?)?;
+        if p.is_prime(Some(&mut ctx))? {
+            break (x, y, p);
+        }
+    };
+
+    // A square root of -1 modulo p is c^((p - 1) / 4) for any quadratic non residue c
+    let p_minus_1 = p.decrement()?;
+    let exp = p_minus_1.rshift(2)?;
+    let sqrt_minus_1 = loop {
+        let c = p.rand_range()?;
+        let s = c.mod_exp(&exp, &p, Some(&mut ctx))?;
+        if s.sqr(Some(&mut ctx))?.modulus(&p, Some(&mut ctx))? == p_minus_1 {
+            break s;
+        }
+    };
+
+    let p_sqrt = isqrt(&p)?;
+    let mut a = p.try_clone()?;
+    let mut b = sqrt_minus_1;
+    while b > p_sqrt {
+        let r = a.modulus(&b, Some(&mut ctx))?;
+        a = b;
+        b = r;
+    }
+    let c = isqrt(&p.sub(&b.sqr(Some(&mut ctx))?)?)?;
+
+    if b.sqr(Some(&mut ctx))?.add(&c.sqr(Some(&mut ctx))?)? != p {
+        return Err(err_msg(
+            UrsaCryptoErrorKind::InvalidState,
+            "Cannot express a prime as sum of two squares",
+        ));
+    }
+
+    Ok(vec![x, y, b, c])
+}
+
+// The largest integer whose square is at most `n`
+fn isqrt(n: &BigNumber) -> UrsaCryptoResult<BigNumber> {
+    let bits = n.num_bits()?;
+    if bits == 0 {
+        return BigNumber::from_u32(0);
+    }
+
+    let mut x = BIGNUMBER_2.exp(&BigNumber::from_u32(((bits + 1) / 2) as usize)?, None)?;
+    loop {
+        let y = x.add(&n.div(&x, None)?)?.rshift1()?;
+        if y >= x {
+            return Ok(x);
+        }
+        x = y;
+    }
+}
+
 pub fn group_element_to_bignum(el: &GroupOrderElement) -> UrsaCryptoResult<BigNumber> {
     Ok(BigNumber::from_bytes(&el.to_bytes()?)?)
 }
@@ -785,6 +1018,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn four_squares_bn_works() {
+        let check = |delta: &BigNumber| {
+            let res = four_squares_bn(delta).unwrap();
+            let mut sum = BigNumber::from_u32(0).unwrap();
+            for i in 0..ITERATION {
+                sum = sum.add(&res[&i.to_string()].sqr(None).unwrap()).unwrap();
+            }
+            assert_eq!(&sum, delta);
+        };
+
+        let res = four_squares_bn(&BigNumber::from_u32(107).unwrap()).unwrap();
+        assert_eq!(res, four_squares(107).unwrap());
+
+        check(&BigNumber::from_dec("2147483648").unwrap());
+        check(&BigNumber::from_dec(&i64::max_value().to_string()).unwrap());
+        check(&BigNumber::from_dec("1600000000000").unwrap());
+        check(
+            &BIGNUMBER_2
+                .exp(&BigNumber::from_u32(120).unwrap(), None)
+                .unwrap(),
+        );
+        check(
+            &BIGNUMBER_2
+                .exp(&BigNumber::from_u32(100).unwrap(), None)
+                .unwrap()
+                .mul(&BigNumber::from_u32(7).unwrap(), None)
+                .unwrap(),
+        );
+        check(
+            &BIGNUMBER_2
+                .exp(&BigNumber::from_u32(LARGE_PREDICATE_DELTA).unwrap(), None)
+                .unwrap()
+                .decrement()
+                .unwrap(),
+        );
+
+        assert!(four_squares_bn(&BigNumber::from_dec("-1").unwrap()).is_err());
+    }
+
     #[test]
     fn transform_u32_to_array_of_u8_works() {
         let int = 0x74BA_7445;
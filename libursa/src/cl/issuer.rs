@@ -660,6 +660,7 @@ impl Issuer {
     }
 
     /// Updates a revocation registry with sets of issued and revoked credential indexes.
+    /// Same as `update_revocation_registry_batch` so `rev_reg` is left unchanged if an index is invalid.
     ///
     /// # Arguments
     /// * `rev_reg` - Revocation registry.
@@ -731,40 +732,124 @@ impl Issuer {
         revoked: BTreeSet<u32>,
         rev_tails_accessor: &RTA,
     ) -> UrsaCryptoResult<RevocationRegistryDelta>
+    where
+        RTA: RevocationTailsAccessor,
+    {
+        Self::update_revocation_registry_batch(
+            rev_reg,
+            max_cred_num,
+            issued,
+            revoked,
+            rev_tails_accessor,
+        )
+    }
+
+    /// Updates a revocation registry with sets of issued and revoked credential indexes
+    /// using a single accumulator update.
+    ///
+    /// Every index is checked before the registry is touched,
+    /// so `rev_reg` is left unchanged if an index is out of range, is both issued and revoked,
+    /// or a tail can't be accessed. Tails are accessed once each in ascending tail order.
+    ///
+    /// # Arguments
+    /// * `rev_reg` - Revocation registry.
+    /// * `max_cred_num` - Max credential number in revocation registry.
+    /// * `issued` - Set of credentials to issue again (un-revoke).
+    /// * `revoked` - Set of credentials to revoke.
+    /// * `rev_tails_accessor` - Revocation registry tails accessor.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// use ursa::cl::SimpleTailsAccessor;
+    /// use ursa::cl::issuer::Issuer;
+    ///
+    /// let mut credential_schema_builder = Issuer::new_credential_schema_builder().unwrap();
+    /// credential_schema_builder.add_attr("name").unwrap();
+    /// let credential_schema = credential_schema_builder.finalize().unwrap();
+    ///
+    /// let mut non_credential_schema_builder = Issuer::new_non_credential_schema_builder().unwrap();
+    /// non_credential_schema_builder.add_attr("master_secret").unwrap();
+    /// let non_credential_schema = non_credential_schema_builder.finalize().unwrap();
+    ///
+    /// let (cred_pub_key, _cred_priv_key, _cred_key_correctness_proof) = Issuer::new_credential_def(&credential_schema, &non_credential_schema, true).unwrap();
+    ///
+    /// let max_cred_num = 5;
+    /// let (_rev_key_pub, _rev_key_priv, mut rev_reg, mut rev_tails_generator) = Issuer::new_revocation_registry_def(&cred_pub_key, max_cred_num, true).unwrap();
+    ///
+    /// let simple_tail_accessor = SimpleTailsAccessor::new(&mut rev_tails_generator).unwrap();
+    ///
+    /// let issued = BTreeSet::new();
+    /// let revoked = (1..=4).collect::<BTreeSet<u32>>();
+    /// let _rev_reg_delta = Issuer::update_revocation_registry_batch(&mut rev_reg, max_cred_num, issued, revoked, &simple_tail_accessor).unwrap();
+    /// ```
+    pub fn update_revocation_registry_batch<RTA>(
+        rev_reg: &mut RevocationRegistry,
+        max_cred_num: u32,
+        issued: BTreeSet<u32>,
+        revoked: BTreeSet<u32>,
+        rev_tails_accessor: &RTA,
+    ) -> UrsaCryptoResult<RevocationRegistryDelta>
     where
         RTA: RevocationTailsAccessor,
     {
         trace!(
-            "Issuer::update_revocation_registry: >>> rev_reg: {:?}, max_cred_num: {:?}, issued: {:?}, revoked: {:?}",
+            "Issuer::update_revocation_registry_batch: >>> rev_reg: {:?}, max_cred_num: {:?}, issued: {:?}, revoked: {:?}",
             rev_reg,
             max_cred_num,
             secret!(&issued),
             secret!(&revoked)
         );
 
-        let prev_acc = rev_reg.accum;
-        for rev_idx in issued.iter() {
-            let tail_id = Self::_get_index(max_cred_num, *rev_idx);
-            rev_tails_accessor.access_tail(tail_id, &mut |tail| {
-                rev_reg.accum = rev_reg.accum.add(tail).unwrap()
-            })?;
+        if let Some(rev_idx) = issued.intersection(&revoked).next() {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                format!("Index {} is both issued and revoked", rev_idx),
+            ));
         }
-        for rev_idx in revoked.iter() {
-            let tail_id = Self::_get_index(max_cred_num, *rev_idx);
+
+        let mut tail_ids = Vec::with_capacity(issued.len() + revoked.len());
+        for (rev_idx, is_issued) in issued
+            .iter()
+            .map(|i| (*i, true))
+            .chain(revoked.iter().map(|i| (*i, false)))
+        {
+            if rev_idx == 0 || rev_idx > max_cred_num {
+                return Err(err_msg(
+                    UrsaCryptoErrorKind::InvalidRevocationAccumulatorIndex,
+                    format!("Index {} is out of the revocation registry range", rev_idx),
+                ));
+            }
+            tail_ids.push((Self::_get_index(max_cred_num, rev_idx), is_issued));
+        }
+        tail_ids.sort_unstable();
+
+        let mut delta = PointG2::new_inf()?;
+        for (tail_id, is_issued) in tail_ids {
+            let mut res = Ok(());
             rev_tails_accessor.access_tail(tail_id, &mut |tail| {
-                rev_reg.accum = rev_reg.accum.sub(tail).unwrap()
+                res = if is_issued {
+                    delta.add(tail)
+                } else {
+                    delta.sub(tail)
+                }
+                .map(|point| delta = point);
             })?;
+            res?;
         }
 
+        let prev_accum = rev_reg.accum;
+        rev_reg.accum = rev_reg.accum.add(&delta)?;
+
         let rev_reg_delta = RevocationRegistryDelta {
-            prev_accum: Some(prev_acc),
+            prev_accum: Some(prev_accum),
             accum: rev_reg.accum,
             issued: HashSet::from_iter(issued),
             revoked: HashSet::from_iter(revoked),
         };
 
         trace!(
-            "Issuer::update_revocation_registry: <<< rev_reg_delta: {:?}",
+            "Issuer::update_revocation_registry_batch: <<< rev_reg_delta: {:?}",
             rev_reg_delta
         );
 
@@ -1525,6 +1610,97 @@ mod tests {
         Issuer::new_revocation_registry_def(&pub_key, 100, false).unwrap();
     }
 
+    #[test]
+    fn update_revocation_registry_batch_works() {
+        MockHelper::inject();
+
+        let (pub_key, _, _) = Issuer::new_credential_def(
+            &mocks::credential_schema(),
+            &mocks::non_credential_schema(),
+            true,
+        )
+        .unwrap();
+        let max_cred_num = 10;
+        let (_, _, rev_reg, mut rev_tails_generator) =
+            Issuer::new_revocation_registry_def(&pub_key, max_cred_num, true).unwrap();
+        let simple_tail_accessor = SimpleTailsAccessor::new(&mut rev_tails_generator).unwrap();
+
+        let mut expected_rev_reg = rev_reg.clone();
+        let mut expected_delta = Issuer::revoke_credential(
+            &mut expected_rev_reg,
+            max_cred_num,
+            2,
+            &simple_tail_accessor,
+        )
+        .unwrap();
+        for rev_idx in &[5, 10] {
+            let delta = Issuer::revoke_credential(
+                &mut expected_rev_reg,
+                max_cred_num,
+                *rev_idx,
+                &simple_tail_accessor,
+            )
+            .unwrap();
+            expected_delta.merge(&delta).unwrap();
+        }
+
+        let mut batch_rev_reg = rev_reg.clone();
+        let revoked = btreeset![2, 5, 10];
+        let batch_delta = Issuer::update_revocation_registry_batch(
+            &mut batch_rev_reg,
+            max_cred_num,
+            BTreeSet::new(),
+            revoked,
+            &simple_tail_accessor,
+        )
+        .unwrap();
+        assert_eq!(expected_rev_reg.accum, batch_rev_reg.accum);
+        assert_eq!(expected_delta.prev_accum, batch_delta.prev_accum);
+        assert_eq!(expected_delta.accum, batch_delta.accum);
+        assert_eq!(expected_delta.revoked, batch_delta.revoked);
+        assert!(batch_delta.issued.is_empty());
+
+        let mut single_rev_reg = rev_reg.clone();
+        Issuer::update_revocation_registry(
+            &mut single_rev_reg,
+            max_cred_num,
+            BTreeSet::new(),
+            btreeset![2, 5, 10],
+            &simple_tail_accessor,
+        )
+        .unwrap();
+        assert_eq!(batch_rev_reg.accum, single_rev_reg.accum);
+
+        let delta = Issuer::update_revocation_registry_batch(
+            &mut batch_rev_reg,
+            max_cred_num,
+            btreeset![5, 10],
+            btreeset![1],
+            &simple_tail_accessor,
+        )
+        .unwrap();
+        assert_eq!(delta.prev_accum, Some(expected_rev_reg.accum));
+        assert_eq!(delta.issued, hashset![5, 10]);
+        assert_eq!(delta.revoked, hashset![1]);
+
+        let unchanged = batch_rev_reg.accum;
+        for (issued, revoked) in vec![
+            (btreeset![3], btreeset![3]),
+            (BTreeSet::new(), btreeset![0]),
+            (btreeset![11], BTreeSet::new()),
+        ] {
+            assert!(Issuer::update_revocation_registry_batch(
+                &mut batch_rev_reg,
+                max_cred_num,
+                issued,
+                revoked,
+                &simple_tail_accessor,
+            )
+            .is_err());
+            assert_eq!(unchanged, batch_rev_reg.accum);
+        }
+    }
+
     #[test]
     fn sign_primary_credential_works() {
         MockHelper::inject();
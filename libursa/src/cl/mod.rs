@@ -7,9 +7,12 @@ pub mod helpers;
 pub mod hash;
 pub mod issuer;
 pub mod prover;
+pub mod tails;
 pub mod verifier;
 
-use bn::BigNumber;
+pub use self::tails::{FileTailsAccessor, FileTailsWriter};
+
+use bn::{BigNumber, BIGNUMBER_1};
 use errors::prelude::*;
 use pair::*;
 
@@ -872,6 +875,10 @@ impl BlindedCredentialSecretsCorrectnessProof {
 pub struct SubProofRequest {
     revealed_attrs: BTreeSet<String>,
     predicates: BTreeSet<Predicate>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    set_predicates: BTreeSet<SetPredicate>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    attribute_predicates: BTreeSet<AttributePredicate>,
 }
 
 /// Builder of “Sub Proof Request”.
@@ -886,6 +893,8 @@ impl SubProofRequestBuilder {
             value: SubProofRequest {
                 revealed_attrs: BTreeSet::new(),
                 predicates: BTreeSet::new(),
+                set_predicates: BTreeSet::new(),
+                attribute_predicates: BTreeSet::new(),
             },
         })
     }
@@ -900,27 +909,109 @@ impl SubProofRequestBuilder {
         attr_name: &str,
         p_type: &str,
         value: i32,
+    ) -> UrsaCryptoResult<()> {
+        self._add_predicate(attr_name, p_type, value.to_string())
+    }
+
+    /// Adds a predicate with a 64 bit bound, e.g. a timestamp in milliseconds.
+    pub fn add_predicate_i64(
+        &mut self,
+        attr_name: &str,
+        p_type: &str,
+        value: i64,
+    ) -> UrsaCryptoResult<()> {
+        self._add_predicate(attr_name, p_type, value.to_string())
+    }
+
+    /// Adds a predicate with a bound of any size.
+    /// The difference between the attribute and the bound must fit in `LARGE_PREDICATE_DELTA` bits.
+    pub fn add_predicate_bn(
+        &mut self,
+        attr_name: &str,
+        p_type: &str,
+        value: &BigNumber,
+    ) -> UrsaCryptoResult<()> {
+        self._add_predicate(attr_name, p_type, value.to_dec()?)
+    }
+
+    fn _add_predicate(
+        &mut self,
+        attr_name: &str,
+        p_type: &str,
+        value: String,
+    ) -> UrsaCryptoResult<()> {
+        let predicate = Predicate {
+            attr_name: attr_name.to_owned(),
+            p_type: PredicateType::from_name(p_type)?,
+            value,
+        };
+
+        self.value.predicates.insert(predicate);
+        Ok(())
+    }
+
+    /// Adds a predicate comparing a hidden attribute of this credential with the hidden attribute
+    /// `other_attr_name` of the sub proof at index `other_sub_proof`, or of this credential if `None`.
+    /// The other sub proof must be added to the proof builder and the proof verifier before this one.
+    ///
+    /// E.g. `add_attribute_predicate("expiry", "GE", Some(0), "issue_date")` requests
+    /// `expiry >= issue_date` where `issue_date` is an attribute of the first credential.
+    pub fn add_attribute_predicate(
+        &mut self,
+        attr_name: &str,
+        p_type: &str,
+        other_sub_proof: Option<usize>,
+        other_attr_name: &str,
+    ) -> UrsaCryptoResult<()> {
+        let predicate = AttributePredicate {
+            attr_name: attr_name.to_owned(),
+            p_type: PredicateType::from_name(p_type)?,
+            other_sub_proof,
+            other_attr_name: other_attr_name.to_owned(),
+        };
+
+        self.value.attribute_predicates.insert(predicate);
+        Ok(())
+    }
+
+    /// Adds a predicate that a hidden attribute is (`IN`) or is not (`NE`)
+    /// equal to one of `values`, given as encoded decimal values.
+    pub fn add_set_predicate(
+        &mut self,
+        attr_name: &str,
+        p_type: &str,
+        values: &[&str],
     ) -> UrsaCryptoResult<()> {
         let p_type = match p_type {
-            "GE" => PredicateType::GE,
-            "LE" => PredicateType::LE,
-            "GT" => PredicateType::GT,
-            "LT" => PredicateType::LT,
+            "IN" => SetPredicateType::IN,
+            "NE" => SetPredicateType::NE,
             p_type => {
                 return Err(err_msg(
                     UrsaCryptoErrorKind::InvalidStructure,
-                    format!("Invalid predicate type: {:?}", p_type),
+                    format!("Invalid set predicate type: {:?}", p_type),
                 ));
             }
         };
 
-        let predicate = Predicate {
+        if values.is_empty() {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                "Set predicate values are empty",
+            ));
+        }
+
+        let mut predicate_values = BTreeSet::new();
+        for value in values {
+            predicate_values.insert(BigNumber::from_dec(value)?.to_dec()?);
+        }
+
+        let predicate = SetPredicate {
             attr_name: attr_name.to_owned(),
             p_type,
-            value,
+            values: predicate_values,
         };
 
-        self.value.predicates.insert(predicate);
+        self.value.set_predicates.insert(predicate);
         Ok(())
     }
 
@@ -930,30 +1021,33 @@ impl SubProofRequestBuilder {
 }
 
 /// Some condition that must be satisfied.
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+///
+/// `value` is kept as a decimal string so bounds of any size can be compared and hashed,
+/// it is serialized as a JSON number when it fits in `i64`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Predicate {
     attr_name: String,
     p_type: PredicateType,
-    value: i32,
+    value: String,
 }
 
 impl Predicate {
-    pub fn get_delta(&self, attr_value: i32) -> i32 {
+    pub fn get_delta(&self, attr_value: &BigNumber) -> UrsaCryptoResult<BigNumber> {
+        let value = BigNumber::from_dec(&self.value)?;
         match self.p_type {
-            PredicateType::GE => attr_value - self.value,
-            PredicateType::GT => attr_value - self.value - 1,
-            PredicateType::LE => self.value - attr_value,
-            PredicateType::LT => self.value - attr_value - 1,
+            PredicateType::GE => attr_value.sub(&value),
+            PredicateType::GT => attr_value.sub(&value)?.sub(&BIGNUMBER_1),
+            PredicateType::LE => value.sub(attr_value),
+            PredicateType::LT => value.sub(attr_value)?.sub(&BIGNUMBER_1),
         }
     }
 
     pub fn get_delta_prime(&self) -> UrsaCryptoResult<BigNumber> {
+        let value = BigNumber::from_dec(&self.value)?;
         match self.p_type {
-            PredicateType::GE => BigNumber::from_dec(&self.value.to_string()),
-            PredicateType::GT => BigNumber::from_dec(&(self.value + 1).to_string()),
-            PredicateType::LE => BigNumber::from_dec(&self.value.to_string()),
-            PredicateType::LT => BigNumber::from_dec(&(self.value - 1).to_string()),
+            PredicateType::GE | PredicateType::LE => Ok(value),
+            PredicateType::GT => value.add(&BIGNUMBER_1),
+            PredicateType::LT => value.sub(&BIGNUMBER_1),
         }
     }
 
@@ -963,6 +1057,82 @@ impl Predicate {
             PredicateType::LE | PredicateType::LT => true,
         }
     }
+
+    // Orders normalized decimal strings by their numeric value
+    fn cmp_value(&self, other: &Predicate) -> ::std::cmp::Ordering {
+        let key = |value: &str| (value.starts_with('-'), value.len());
+        match (key(&self.value), key(&other.value)) {
+            ((false, _), (true, _)) => ::std::cmp::Ordering::Greater,
+            ((true, _), (false, _)) => ::std::cmp::Ordering::Less,
+            ((false, len), (false, other_len)) => {
+                (len, &self.value).cmp(&(other_len, &other.value))
+            }
+            ((true, len), (true, other_len)) => (other_len, &other.value).cmp(&(len, &self.value)),
+        }
+    }
+}
+
+impl Ord for Predicate {
+    fn cmp(&self, other: &Predicate) -> ::std::cmp::Ordering {
+        self.attr_name
+            .cmp(&other.attr_name)
+            .then_with(|| self.p_type.cmp(&other.p_type))
+            .then_with(|| self.cmp_value(other))
+    }
+}
+
+impl PartialOrd for Predicate {
+    fn partial_cmp(&self, other: &Predicate) -> Option<::std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ::serde::ser::Serialize for Predicate {
+    fn serialize<S: ::serde::ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Predicate", 3)?;
+        state.serialize_field("attr_name", &self.attr_name)?;
+        state.serialize_field("p_type", &self.p_type)?;
+        match self.value.parse::<i64>() {
+            Ok(value) => state.serialize_field("value", &value)?,
+            Err(_) => state.serialize_field("value", &self.value)?,
+        }
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> ::serde::de::Deserialize<'a> for Predicate {
+    fn deserialize<D: ::serde::de::Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum PredicateValue {
+            Number(i64),
+            Dec(String),
+        }
+
+        #[derive(Deserialize)]
+        struct PredicateV1 {
+            attr_name: String,
+            p_type: PredicateType,
+            value: PredicateValue,
+        }
+
+        let helper = PredicateV1::deserialize(deserializer)?;
+        let value = match helper.value {
+            PredicateValue::Number(value) => value.to_string(),
+            PredicateValue::Dec(value) => BigNumber::from_dec(&value)
+                .and_then(|value| value.to_dec())
+                .map_err(::serde::de::Error::custom)?,
+        };
+        Ok(Predicate {
+            attr_name: helper.attr_name,
+            p_type: helper.p_type,
+            value,
+        })
+    }
 }
 
 /// Condition type
@@ -975,6 +1145,83 @@ pub enum PredicateType {
     LT,
 }
 
+impl PredicateType {
+    fn from_name(p_type: &str) -> UrsaCryptoResult<PredicateType> {
+        match p_type {
+            "GE" => Ok(PredicateType::GE),
+            "LE" => Ok(PredicateType::LE),
+            "GT" => Ok(PredicateType::GT),
+            "LT" => Ok(PredicateType::LT),
+            p_type => Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                format!("Invalid predicate type: {:?}", p_type),
+            )),
+        }
+    }
+}
+
+/// Condition between two hidden attributes, possibly of different credentials,
+/// e.g. `attr_name >= other_attr_name`.
+///
+/// It is proved as the `Predicate` `attr_name - other_attr_name >= 0`
+/// with the difference of the blinded attributes of both sub proofs.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct AttributePredicate {
+    attr_name: String,
+    p_type: PredicateType,
+    /// Index of the sub proof with `other_attr_name`, `None` for the same sub proof
+    other_sub_proof: Option<usize>,
+    other_attr_name: String,
+}
+
+impl AttributePredicate {
+    /// The attributes of the credential this predicate is requested for
+    pub fn get_attr_names(&self) -> Vec<String> {
+        match self.other_sub_proof {
+            Some(_) => vec![self.attr_name.clone()],
+            None => vec![self.attr_name.clone(), self.other_attr_name.clone()],
+        }
+    }
+
+    /// The predicate over the difference `attr_name - other_attr_name` that is proved
+    pub fn get_difference_predicate(&self) -> Predicate {
+        Predicate {
+            attr_name: self.attr_name.clone(),
+            p_type: self.p_type.clone(),
+            value: "0".to_string(),
+        }
+    }
+}
+
+/// Condition that a hidden attribute is or is not one of a set of public values.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SetPredicate {
+    attr_name: String,
+    p_type: SetPredicateType,
+    values: BTreeSet<String>,
+}
+
+impl SetPredicate {
+    pub fn get_values(&self) -> UrsaCryptoResult<Vec<BigNumber>> {
+        self.values
+            .iter()
+            .map(|value| BigNumber::from_dec(value))
+            .collect()
+    }
+}
+
+/// Set condition type
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum SetPredicateType {
+    /// The attribute is equal to one of the values
+    IN,
+    /// The attribute is not equal to any of the values
+    NE,
+}
+
 /// Proof is complex crypto structure created by prover over multiple credentials that allows to prove that prover:
 /// 1) Knows signature over credentials issued with specific issuer keys (identified by key id)
 /// 2) Credential contains attributes with specific values that prover wants to disclose
@@ -1016,6 +1263,16 @@ pub struct PrimaryProof {
     eq_proof: PrimaryEqualProof,
     #[cfg_attr(feature = "serde", serde(rename = "ge_proofs"))]
     ne_proofs: Vec<PrimaryPredicateInequalityProof>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    set_proofs: Vec<PrimaryPredicateSetProof>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    attribute_proofs: Vec<PrimaryPredicateAttributeProof>,
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize))]
@@ -1070,6 +1327,33 @@ pub struct PrimaryPredicateInequalityProof {
     predicate: Predicate,
 }
 
+/// Proof of a `SetPredicate` over a commitment `t = z^m * s^r` to the hidden attribute `m`.
+///
+/// For `IN` it is an OR proof that `t / z^value` is a power of `s` for one of the values,
+/// `c` and `rho` hold the challenge and response of each value.
+/// For `NE` it proves for each value the knowledge of `a`, `b` with
+/// `a * (m - value) + b * SET_PREDICATE_PRIME = 1`, `a`, `b` and `rho` hold the responses.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct PrimaryPredicateSetProof {
+    t: BigNumber,
+    r: BigNumber,
+    c: Vec<BigNumber>,
+    a: Vec<BigNumber>,
+    b: Vec<BigNumber>,
+    rho: Vec<BigNumber>,
+    predicate: SetPredicate,
+}
+
+/// Proof of an `AttributePredicate`: an inequality proof of its difference predicate
+/// where `mj` is the difference of the blinded attributes of both sub proofs.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct PrimaryPredicateAttributeProof {
+    ne_proof: PrimaryPredicateInequalityProof,
+    predicate: AttributePredicate,
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug)]
 pub struct NonRevocProof {
@@ -1091,6 +1375,8 @@ pub struct InitProof {
 pub struct PrimaryInitProof {
     eq_proof: PrimaryEqualInitProof,
     ne_proofs: Vec<PrimaryPredicateInequalityInitProof>,
+    set_proofs: Vec<PrimaryPredicateSetInitProof>,
+    attribute_proofs: Vec<PrimaryPredicateAttributeInitProof>,
 }
 
 impl PrimaryInitProof {
@@ -1099,6 +1385,12 @@ impl PrimaryInitProof {
         for ne_proof in self.ne_proofs.iter() {
             c_list.append_vec(ne_proof.as_list()?)?;
         }
+        for set_proof in self.set_proofs.iter() {
+            c_list.append_vec(set_proof.as_list()?)?;
+        }
+        for attribute_proof in self.attribute_proofs.iter() {
+            c_list.append_vec(attribute_proof.ne_proof.as_list()?)?;
+        }
         Ok(c_list)
     }
 
@@ -1107,6 +1399,12 @@ impl PrimaryInitProof {
         for ne_proof in self.ne_proofs.iter() {
             tau_list.append_vec(ne_proof.as_tau_list()?)?;
         }
+        for set_proof in self.set_proofs.iter() {
+            tau_list.append_vec(set_proof.as_tau_list()?)?;
+        }
+        for attribute_proof in self.attribute_proofs.iter() {
+            tau_list.append_vec(attribute_proof.ne_proof.as_tau_list()?)?;
+        }
         Ok(tau_list)
     }
 }
@@ -1177,6 +1475,44 @@ impl PrimaryPredicateInequalityInitProof {
     }
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct PrimaryPredicateSetInitProof {
+    c_list: Vec<BigNumber>,
+    tau_list: Vec<BigNumber>,
+    t: BigNumber,
+    r: BigNumber,
+    r_tilde: BigNumber,
+    index: Option<usize>,
+    c: Vec<BigNumber>,
+    a: Vec<BigNumber>,
+    b: Vec<BigNumber>,
+    rho: Vec<BigNumber>,
+    a_tilde: Vec<BigNumber>,
+    b_tilde: Vec<BigNumber>,
+    rho_tilde: Vec<BigNumber>,
+    predicate: SetPredicate,
+}
+
+impl PrimaryPredicateSetInitProof {
+    pub fn as_list(&self) -> UrsaCryptoResult<&Vec<BigNumber>> {
+        Ok(&self.c_list)
+    }
+
+    pub fn as_tau_list(&self) -> UrsaCryptoResult<&Vec<BigNumber>> {
+        Ok(&self.tau_list)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct PrimaryPredicateAttributeInitProof {
+    ne_proof: PrimaryPredicateInequalityInitProof,
+    /// Difference of the attributes
+    m: BigNumber,
+    /// Difference of the attributes blinding factors
+    m_tilde: BigNumber,
+    predicate: AttributePredicate,
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
 pub struct NonRevocProofXList {
@@ -1744,6 +2080,62 @@ mod test {
         assert_eq!(two, one);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn predicate_conversion_works() {
+        let old = r#"{"attr_name":"age","p_type":"GE","value":18}"#;
+        let predicate = serde_json::from_str::<Predicate>(old).unwrap();
+        assert_eq!(predicate.value, "18");
+        assert_eq!(serde_json::to_string(&predicate).unwrap(), old);
+
+        let mut sub_proof_request_builder = SubProofRequestBuilder::new().unwrap();
+        sub_proof_request_builder
+            .add_predicate_i64("timestamp", "LT", -1_600_000_000_000)
+            .unwrap();
+        sub_proof_request_builder
+            .add_predicate_bn(
+                "amount",
+                "GE",
+                &BigNumber::from_dec("340282366920938463463374607431768211456").unwrap(),
+            )
+            .unwrap();
+        let sub_proof_request = sub_proof_request_builder.finalize().unwrap();
+
+        let json = serde_json::to_string(&sub_proof_request.predicates).unwrap();
+        assert!(json.contains(r#""value":-1600000000000"#));
+        assert!(json.contains(r#""value":"340282366920938463463374607431768211456""#));
+        let predicates = serde_json::from_str::<BTreeSet<Predicate>>(&json).unwrap();
+        assert_eq!(predicates, sub_proof_request.predicates);
+
+        let predicate = serde_json::from_str::<Predicate>(
+            r#"{"attr_name":"amount","p_type":"GE","value":"0018"}"#,
+        )
+        .unwrap();
+        assert_eq!(predicate.value, "18");
+        assert!(serde_json::from_str::<Predicate>(
+            r#"{"attr_name":"amount","p_type":"GE","value":"eighteen"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn predicate_ordering_works() {
+        let predicate = |value: &str| Predicate {
+            attr_name: "age".to_string(),
+            p_type: PredicateType::GE,
+            value: value.to_string(),
+        };
+        let values = vec!["-100", "-18", "-5", "0", "5", "18", "100", "4294967296"];
+        let predicates = values
+            .iter()
+            .map(|value| predicate(value))
+            .collect::<Vec<_>>();
+        let sorted = BTreeSet::from_iter(predicates.iter().cloned())
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(sorted, predicates);
+    }
+
     #[test]
     fn demo() {
         let mut credential_schema_builder = Issuer::new_credential_schema_builder().unwrap();
@@ -1,5 +1,5 @@
 use super::helpers::*;
-use bn::BigNumber;
+use bn::{BigNumber, BIGNUMBER_1};
 use cl::constants::*;
 use cl::hash::get_hash_as_int;
 use cl::*;
@@ -984,7 +984,7 @@ impl ProofBuilder {
             non_revoc_init_proof = Some(proof);
         }
 
-        let primary_init_proof = ProofBuilder::_init_primary_proof(
+        let mut primary_init_proof = ProofBuilder::_init_primary_proof(
             &self.common_attributes,
             &credential_pub_key.p_key,
             &credential_signature.p_credential,
@@ -995,6 +995,16 @@ impl ProofBuilder {
             m2_tilde,
         )?;
 
+        for predicate in sub_proof_request.attribute_predicates.iter() {
+            let attribute_proof = self._init_attribute_proof(
+                &credential_pub_key.p_key,
+                &primary_init_proof.eq_proof.m_tilde,
+                credential_values,
+                predicate,
+            )?;
+            primary_init_proof.attribute_proofs.push(attribute_proof);
+        }
+
         self.c_list
             .extend_from_slice(&primary_init_proof.as_c_list()?);
         self.tau_list
@@ -1186,6 +1196,18 @@ impl ProofBuilder {
             .predicates
             .iter()
             .map(|predicate| predicate.attr_name.clone())
+            .chain(
+                sub_proof_request
+                    .set_predicates
+                    .iter()
+                    .map(|predicate| predicate.attr_name.clone()),
+            )
+            .chain(
+                sub_proof_request
+                    .attribute_predicates
+                    .iter()
+                    .flat_map(|predicate| predicate.get_attr_names()),
+            )
             .collect::<BTreeSet<String>>();
 
         if predicates_attrs.difference(&cred_attrs).count() != 0 {
@@ -1261,9 +1283,22 @@ impl ProofBuilder {
             ne_proofs.push(ne_proof);
         }
 
+        let mut set_proofs: Vec<PrimaryPredicateSetInitProof> = Vec::new();
+        for predicate in sub_proof_request.set_predicates.iter() {
+            let set_proof = ProofBuilder::_init_set_proof(
+                issuer_pub_key,
+                &eq_proof.m_tilde,
+                cred_values,
+                predicate,
+            )?;
+            set_proofs.push(set_proof);
+        }
+
         let primary_init_proof = PrimaryInitProof {
             eq_proof,
             ne_proofs,
+            set_proofs,
+            attribute_proofs: Vec::new(),
         };
 
         trace!(
@@ -1402,8 +1437,6 @@ impl ProofBuilder {
         trace!("ProofBuilder::_init_ne_proof: >>> p_pub_key: {:?}, m_tilde: {:?}, cred_values: {:?}, predicate: {:?}",
                p_pub_key, m_tilde, cred_values, predicate);
 
-        let mut ctx = BigNumber::new_context()?;
-
         let attr_value = cred_values
             .attrs_values
             .get(&predicate.attr_name)
@@ -1416,26 +1449,59 @@ impl ProofBuilder {
                     ),
                 )
             })?
-            .value()
-            .to_dec()?
-            .parse::<i32>()
-            .map_err(|_| {
-                err_msg(
-                    UrsaCryptoErrorKind::InvalidStructure,
-                    format!("Value by key '{}' has invalid format", predicate.attr_name),
-                )
-            })?;
+            .value();
+
+        let delta = predicate.get_delta(attr_value)?;
+
+        let mj = m_tilde.get(&predicate.attr_name).ok_or_else(|| {
+            err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                format!(
+                    "Value by key '{}' not found in eq_proof.mtilde",
+                    predicate.attr_name
+                ),
+            )
+        })?;
 
-        let delta = predicate.get_delta(attr_value);
+        let primary_predicate_ne_init_proof =
+            ProofBuilder::_init_ne_proof_for_delta(p_pub_key, &delta, mj, predicate)?;
 
-        if delta < 0 {
+        trace!(
+            "ProofBuilder::_init_ne_proof: <<< primary_predicate_ne_init_proof: {:?}",
+            primary_predicate_ne_init_proof
+        );
+
+        Ok(primary_predicate_ne_init_proof)
+    }
+
+    /// Proves `delta >= 0` for the `delta` of `predicate`,
+    /// `mj` is the blinding factor of the value `predicate` is applied to.
+    fn _init_ne_proof_for_delta(
+        p_pub_key: &CredentialPrimaryPublicKey,
+        delta: &BigNumber,
+        mj: &BigNumber,
+        predicate: &Predicate,
+    ) -> UrsaCryptoResult<PrimaryPredicateInequalityInitProof> {
+        let mut ctx = BigNumber::new_context()?;
+
+        if delta.is_negative() {
             return Err(err_msg(
                 UrsaCryptoErrorKind::InvalidStructure,
                 "Predicate is not satisfied",
             ));
         }
 
-        let u = four_squares(delta)?;
+        if delta.num_bits()? > LARGE_PREDICATE_DELTA as i32 {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                format!(
+                    "Predicate delta does not fit in {} bits",
+                    LARGE_PREDICATE_DELTA
+                ),
+            ));
+        }
+
+        let u = four_squares_bn(delta)?;
 
         let mut r = HashMap::new();
         let mut t = HashMap::new();
@@ -1468,7 +1534,7 @@ impl ProofBuilder {
 
         let t_delta = get_pedersen_commitment(
             &p_pub_key.z,
-            &BigNumber::from_dec(&delta.to_string())?,
+            delta,
             &p_pub_key.s,
             &r_delta,
             &p_pub_key.n,
@@ -1490,16 +1556,6 @@ impl ProofBuilder {
         r_tilde.insert("DELTA".to_string(), bn_rand(LARGE_RTILDE)?);
         let alpha_tilde = bn_rand(LARGE_ALPHATILDE)?;
 
-        let mj = m_tilde.get(&predicate.attr_name).ok_or_else(|| {
-            err_msg(
-                UrsaCryptoErrorKind::InvalidStructure,
-                format!(
-                    "Value by key '{}' not found in eq_proof.mtilde",
-                    predicate.attr_name
-                ),
-            )
-        })?;
-
         let tau_list = calc_tne(
             &p_pub_key,
             &u_tilde,
@@ -1510,7 +1566,7 @@ impl ProofBuilder {
             predicate.is_less(),
         )?;
 
-        let primary_predicate_ne_init_proof = PrimaryPredicateInequalityInitProof {
+        Ok(PrimaryPredicateInequalityInitProof {
             c_list,
             tau_list,
             u,
@@ -1520,14 +1576,262 @@ impl ProofBuilder {
             alpha_tilde,
             predicate: predicate.clone(),
             t,
+        })
+    }
+
+    /// `m_tilde` and `cred_values` are the ones of the sub proof being added,
+    /// the other attribute of `predicate` may be in a sub proof added before.
+    fn _init_attribute_proof(
+        &self,
+        p_pub_key: &CredentialPrimaryPublicKey,
+        m_tilde: &HashMap<String, BigNumber>,
+        cred_values: &CredentialValues,
+        predicate: &AttributePredicate,
+    ) -> UrsaCryptoResult<PrimaryPredicateAttributeInitProof> {
+        trace!("ProofBuilder::_init_attribute_proof: >>> p_pub_key: {:?}, m_tilde: {:?}, cred_values: {:?}, predicate: {:?}",
+               p_pub_key, m_tilde, cred_values, predicate);
+
+        let (other_m_tilde, other_cred_values) = match predicate.other_sub_proof {
+            Some(idx) => {
+                let init_proof = self.init_proofs.get(idx).ok_or_else(|| {
+                    err_msg(
+                        UrsaCryptoErrorKind::InvalidStructure,
+                        format!(
+                            "Attribute predicate refers to sub proof {} that was not added before",
+                            idx
+                        ),
+                    )
+                })?;
+                if init_proof
+                    .sub_proof_request
+                    .revealed_attrs
+                    .contains(&predicate.other_attr_name)
+                {
+                    return Err(err_msg(
+                        UrsaCryptoErrorKind::InvalidStructure,
+                        "ProofRequest can't contain an attribute as a revealed value and as a predicate at the same time",
+                    ));
+                }
+                (
+                    &init_proof.primary_init_proof.eq_proof.m_tilde,
+                    &init_proof.credential_values,
+                )
+            }
+            None => (m_tilde, cred_values),
+        };
+
+        let get_value = |cred_values: &CredentialValues, attr_name: &str| {
+            cred_values
+                .attrs_values
+                .get(attr_name)
+                .map(|value| value.value().try_clone())
+                .unwrap_or_else(|| {
+                    Err(err_msg(
+                        UrsaCryptoErrorKind::InvalidStructure,
+                        format!("Value by key '{}' not found in cred_values", attr_name),
+                    ))
+                })
+        };
+        let get_m_tilde = |m_tilde: &HashMap<String, BigNumber>, attr_name: &str| {
+            m_tilde
+                .get(attr_name)
+                .map(|value| value.try_clone())
+                .unwrap_or_else(|| {
+                    Err(err_msg(
+                        UrsaCryptoErrorKind::InvalidStructure,
+                        format!("Value by key '{}' not found in eq_proof.mtilde", attr_name),
+                    ))
+                })
+        };
+
+        let m = get_value(cred_values, &predicate.attr_name)?
+            .sub(&get_value(other_cred_values, &predicate.other_attr_name)?)?;
+        let m_tilde = get_m_tilde(m_tilde, &predicate.attr_name)?
+            .sub(&get_m_tilde(other_m_tilde, &predicate.other_attr_name)?)?;
+
+        let difference_predicate = predicate.get_difference_predicate();
+        let ne_proof = ProofBuilder::_init_ne_proof_for_delta(
+            p_pub_key,
+            &difference_predicate.get_delta(&m)?,
+            &m_tilde,
+            &difference_predicate,
+        )?;
+
+        let primary_predicate_attribute_init_proof = PrimaryPredicateAttributeInitProof {
+            ne_proof,
+            m,
+            m_tilde,
+            predicate: predicate.clone(),
         };
 
         trace!(
-            "ProofBuilder::_init_ne_proof: <<< primary_predicate_ne_init_proof: {:?}",
-            primary_predicate_ne_init_proof
+            "ProofBuilder::_init_attribute_proof: <<< primary_predicate_attribute_init_proof: {:?}",
+            primary_predicate_attribute_init_proof
         );
 
-        Ok(primary_predicate_ne_init_proof)
+        Ok(primary_predicate_attribute_init_proof)
+    }
+
+    fn _init_set_proof(
+        p_pub_key: &CredentialPrimaryPublicKey,
+        m_tilde: &HashMap<String, BigNumber>,
+        cred_values: &CredentialValues,
+        predicate: &SetPredicate,
+    ) -> UrsaCryptoResult<PrimaryPredicateSetInitProof> {
+        trace!("ProofBuilder::_init_set_proof: >>> p_pub_key: {:?}, m_tilde: {:?}, cred_values: {:?}, predicate: {:?}",
+               p_pub_key, m_tilde, cred_values, predicate);
+
+        let mut ctx = BigNumber::new_context()?;
+
+        let attr_value = cred_values
+            .attrs_values
+            .get(&predicate.attr_name)
+            .ok_or_else(|| {
+                err_msg(
+                    UrsaCryptoErrorKind::InvalidStructure,
+                    format!(
+                        "Value by key '{}' not found in cred_values",
+                        predicate.attr_name
+                    ),
+                )
+            })?
+            .value();
+
+        let mj = m_tilde.get(&predicate.attr_name).ok_or_else(|| {
+            err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                format!(
+                    "Value by key '{}' not found in eq_proof.mtilde",
+                    predicate.attr_name
+                ),
+            )
+        })?;
+
+        let values = predicate.get_values()?;
+
+        let r = bn_rand(LARGE_VPRIME)?;
+        let t = get_pedersen_commitment(
+            &p_pub_key.z,
+            attr_value,
+            &p_pub_key.s,
+            &r,
+            &p_pub_key.n,
+            &mut ctx,
+        )?;
+
+        let r_tilde = bn_rand(LARGE_SET_TILDE)?;
+        let mut tau_list = vec![get_pedersen_commitment(
+            &p_pub_key.z,
+            mj,
+            &p_pub_key.s,
+            &r_tilde,
+            &p_pub_key.n,
+            &mut ctx,
+        )?];
+
+        let mut index = None;
+        let mut c = Vec::new();
+        let mut a = Vec::new();
+        let mut b = Vec::new();
+        let mut rho = Vec::new();
+        let mut a_tilde = Vec::new();
+        let mut b_tilde = Vec::new();
+        let mut rho_tilde = Vec::new();
+
+        match predicate.p_type {
+            SetPredicateType::IN => {
+                index = values.iter().position(|value| value == attr_value);
+                if index.is_none() {
+                    return Err(err_msg(
+                        UrsaCryptoErrorKind::InvalidStructure,
+                        "Predicate is not satisfied",
+                    ));
+                }
+
+                // The branch of the attribute value is proven, the others are simulated
+                // with a random challenge and response
+                for (i, value) in values.iter().enumerate() {
+                    let cur_rho_tilde = bn_rand(LARGE_SET_TILDE)?;
+                    if index == Some(i) {
+                        tau_list.push(p_pub_key.s.mod_exp(
+                            &cur_rho_tilde,
+                            &p_pub_key.n,
+                            Some(&mut ctx),
+                        )?);
+                        c.push(BigNumber::new()?);
+                    } else {
+                        let cur_c = bn_rand(LARGE_CHALLENGE)?;
+                        tau_list.push(calc_tset_in(p_pub_key, &t, value, &cur_c, &cur_rho_tilde)?);
+                        c.push(cur_c);
+                    }
+                    rho_tilde.push(cur_rho_tilde);
+                }
+            }
+            SetPredicateType::NE => {
+                for value in values.iter() {
+                    let delta = attr_value.sub(value)?;
+                    let delta_mod = delta.modulus(&SET_PREDICATE_PRIME, Some(&mut ctx))?;
+
+                    if delta_mod == BigNumber::new()? {
+                        return Err(err_msg(
+                            UrsaCryptoErrorKind::InvalidStructure,
+                            "Predicate is not satisfied",
+                        ));
+                    }
+
+                    // a * delta + b * SET_PREDICATE_PRIME = 1
+                    let cur_a = delta_mod.inverse(&SET_PREDICATE_PRIME, Some(&mut ctx))?;
+                    let cur_b = BIGNUMBER_1
+                        .sub(&cur_a.mul(&delta, Some(&mut ctx))?)?
+                        .div(&SET_PREDICATE_PRIME, Some(&mut ctx))?;
+                    let cur_rho = cur_a.mul(&r, Some(&mut ctx))?.set_negative(true)?;
+
+                    let cur_a_tilde = bn_rand(LARGE_SET_TILDE)?;
+                    let cur_b_tilde = bn_rand(LARGE_SET_TILDE)?;
+                    let cur_rho_tilde = bn_rand(LARGE_SET_TILDE)?;
+
+                    tau_list.push(calc_tset_ne(
+                        p_pub_key,
+                        &t,
+                        value,
+                        &cur_a_tilde,
+                        &cur_b_tilde,
+                        &cur_rho_tilde,
+                    )?);
+
+                    a.push(cur_a);
+                    b.push(cur_b);
+                    rho.push(cur_rho);
+                    a_tilde.push(cur_a_tilde);
+                    b_tilde.push(cur_b_tilde);
+                    rho_tilde.push(cur_rho_tilde);
+                }
+            }
+        }
+
+        let primary_predicate_set_init_proof = PrimaryPredicateSetInitProof {
+            c_list: vec![t.try_clone()?],
+            tau_list,
+            t,
+            r,
+            r_tilde,
+            index,
+            c,
+            a,
+            b,
+            rho,
+            a_tilde,
+            b_tilde,
+            rho_tilde,
+            predicate: predicate.clone(),
+        };
+
+        trace!(
+            "ProofBuilder::_init_set_proof: <<< primary_predicate_set_init_proof: {:?}",
+            primary_predicate_set_init_proof
+        );
+
+        Ok(primary_predicate_set_init_proof)
     }
 
     fn _finalize_eq_proof(
@@ -1644,6 +1948,25 @@ impl ProofBuilder {
             eq_proof
         );
 
+        let primary_predicate_ne_proof = ProofBuilder::_finalize_ne_proof_with_mj(
+            c_h,
+            init_proof,
+            eq_proof.m[&init_proof.predicate.attr_name].try_clone()?,
+        )?;
+
+        trace!(
+            "ProofBuilder::_finalize_ne_proof: <<< primary_predicate_ne_proof: {:?}",
+            primary_predicate_ne_proof
+        );
+
+        Ok(primary_predicate_ne_proof)
+    }
+
+    fn _finalize_ne_proof_with_mj(
+        c_h: &BigNumber,
+        init_proof: &PrimaryPredicateInequalityInitProof,
+        mj: BigNumber,
+    ) -> UrsaCryptoResult<PrimaryPredicateInequalityProof> {
         let mut ctx = BigNumber::new_context()?;
         let mut u = HashMap::new();
         let mut r = HashMap::new();
@@ -1677,21 +2000,130 @@ impl ProofBuilder {
             .mul(&c_h, Some(&mut ctx))?
             .add(&init_proof.alpha_tilde)?;
 
-        let primary_predicate_ne_proof = PrimaryPredicateInequalityProof {
+        Ok(PrimaryPredicateInequalityProof {
             u,
             r,
-            mj: eq_proof.m[&init_proof.predicate.attr_name].try_clone()?,
+            mj,
             alpha,
             t: clone_bignum_map(&init_proof.t)?,
             predicate: init_proof.predicate.clone(),
+        })
+    }
+
+    fn _finalize_attribute_proof(
+        c_h: &BigNumber,
+        init_proof: &PrimaryPredicateAttributeInitProof,
+    ) -> UrsaCryptoResult<PrimaryPredicateAttributeProof> {
+        trace!(
+            "ProofBuilder::_finalize_attribute_proof: >>> c_h: {:?}, init_proof: {:?}",
+            c_h,
+            init_proof
+        );
+
+        // Equals the difference of `m_hat`s of both attributes in their eq proofs
+        let mj = c_h.mul(&init_proof.m, None)?.add(&init_proof.m_tilde)?;
+
+        let primary_predicate_attribute_proof = PrimaryPredicateAttributeProof {
+            ne_proof: ProofBuilder::_finalize_ne_proof_with_mj(c_h, &init_proof.ne_proof, mj)?,
+            predicate: init_proof.predicate.clone(),
         };
 
         trace!(
-            "ProofBuilder::_finalize_ne_proof: <<< primary_predicate_ne_proof: {:?}",
-            primary_predicate_ne_proof
+            "ProofBuilder::_finalize_attribute_proof: <<< primary_predicate_attribute_proof: {:?}",
+            primary_predicate_attribute_proof
         );
 
-        Ok(primary_predicate_ne_proof)
+        Ok(primary_predicate_attribute_proof)
+    }
+
+    fn _finalize_set_proof(
+        c_h: &BigNumber,
+        init_proof: &PrimaryPredicateSetInitProof,
+    ) -> UrsaCryptoResult<PrimaryPredicateSetProof> {
+        trace!(
+            "ProofBuilder::_finalize_set_proof: >>> c_h: {:?}, init_proof: {:?}",
+            c_h,
+            init_proof
+        );
+
+        let mut ctx = BigNumber::new_context()?;
+
+        let r = c_h
+            .mul(&init_proof.r, Some(&mut ctx))?
+            .add(&init_proof.r_tilde)?;
+
+        let mut c = Vec::new();
+        let mut a = Vec::new();
+        let mut b = Vec::new();
+        let mut rho = Vec::new();
+
+        match init_proof.predicate.p_type {
+            SetPredicateType::IN => {
+                let index = init_proof.index.ok_or_else(|| {
+                    err_msg(
+                        UrsaCryptoErrorKind::InvalidState,
+                        "Set predicate init proof has no proven value",
+                    )
+                })?;
+
+                // The challenges of all values must sum up to the proof challenge
+                let mut cur_c = c_h.try_clone()?;
+                for (i, sim_c) in init_proof.c.iter().enumerate() {
+                    if i != index {
+                        cur_c = cur_c.sub(sim_c)?;
+                    }
+                }
+                let cur_c = cur_c.modulus(&LARGE_CHALLENGE_VALUE, Some(&mut ctx))?;
+
+                for (i, (sim_c, rho_tilde)) in init_proof
+                    .c
+                    .iter()
+                    .zip(init_proof.rho_tilde.iter())
+                    .enumerate()
+                {
+                    if i == index {
+                        rho.push(cur_c.mul(&init_proof.r, Some(&mut ctx))?.add(rho_tilde)?);
+                        c.push(cur_c.try_clone()?);
+                    } else {
+                        rho.push(rho_tilde.try_clone()?);
+                        c.push(sim_c.try_clone()?);
+                    }
+                }
+            }
+            SetPredicateType::NE => {
+                for i in 0..init_proof.a.len() {
+                    a.push(
+                        c_h.mul(&init_proof.a[i], Some(&mut ctx))?
+                            .add(&init_proof.a_tilde[i])?,
+                    );
+                    b.push(
+                        c_h.mul(&init_proof.b[i], Some(&mut ctx))?
+                            .add(&init_proof.b_tilde[i])?,
+                    );
+                    rho.push(
+                        c_h.mul(&init_proof.rho[i], Some(&mut ctx))?
+                            .add(&init_proof.rho_tilde[i])?,
+                    );
+                }
+            }
+        }
+
+        let primary_predicate_set_proof = PrimaryPredicateSetProof {
+            t: init_proof.t.try_clone()?,
+            r,
+            c,
+            a,
+            b,
+            rho,
+            predicate: init_proof.predicate.clone(),
+        };
+
+        trace!(
+            "ProofBuilder::_finalize_set_proof: <<< primary_predicate_set_proof: {:?}",
+            primary_predicate_set_proof
+        );
+
+        Ok(primary_predicate_set_proof)
     }
 
     fn _finalize_primary_proof(
@@ -1727,9 +2159,26 @@ impl ProofBuilder {
             ne_proofs.push(ne_proof);
         }
 
+        let mut set_proofs: Vec<PrimaryPredicateSetProof> = Vec::new();
+
+        for init_set_proof in init_proof.set_proofs.iter() {
+            let set_proof = ProofBuilder::_finalize_set_proof(challenge, init_set_proof)?;
+            set_proofs.push(set_proof);
+        }
+
+        let mut attribute_proofs: Vec<PrimaryPredicateAttributeProof> = Vec::new();
+
+        for init_attribute_proof in init_proof.attribute_proofs.iter() {
+            let attribute_proof =
+                ProofBuilder::_finalize_attribute_proof(challenge, init_attribute_proof)?;
+            attribute_proofs.push(attribute_proof);
+        }
+
         let primary_proof = PrimaryProof {
             eq_proof,
             ne_proofs,
+            set_proofs,
+            attribute_proofs,
         };
 
         trace!(
@@ -2523,6 +2972,8 @@ pub mod mocks {
         PrimaryInitProof {
             eq_proof: primary_equal_init_proof(),
             ne_proofs: vec![primary_ne_init_proof()],
+            set_proofs: Vec::new(),
+            attribute_proofs: Vec::new(),
         }
     }
 
@@ -2860,6 +3311,8 @@ pub mod mocks {
         PrimaryProof {
             eq_proof: eq_proof(),
             ne_proofs: vec![ne_proof()],
+            set_proofs: Vec::new(),
+            attribute_proofs: Vec::new(),
         }
     }
 
@@ -2978,7 +3431,7 @@ pub mod mocks {
         Predicate {
             attr_name: "age".to_owned(),
             p_type: PredicateType::GE,
-            value: 18,
+            value: "18".to_owned(),
         }
     }
 }
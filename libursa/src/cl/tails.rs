@@ -0,0 +1,305 @@
+use super::{RevocationTailsAccessor, RevocationTailsGenerator, Tail};
+use bn::Sha256Hasher;
+use errors::prelude::*;
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// The first bytes of every tails file
+pub const TAILS_FILE_MAGIC: &[u8; 8] = b"URSATAIL";
+/// The version of the tails file format written by `FileTailsWriter`
+pub const TAILS_FILE_VERSION: u16 = 1;
+/// The size of the tails file header
+pub const TAILS_FILE_HEADER_SIZE: usize = 18;
+
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Streams the tails from a `RevocationTailsGenerator` to a file
+/// without keeping them in memory.
+///
+/// The file is a header followed by the tails in order, all integers are big endian
+///
+/// | offset | size    | content                                      |
+/// |--------|---------|----------------------------------------------|
+/// | 0      | 8       | `TAILS_FILE_MAGIC`                           |
+/// | 8      | 2       | `TAILS_FILE_VERSION`                         |
+/// | 10     | 4       | the number of tails `n`                      |
+/// | 14     | 4       | the size of each tail `s`                    |
+/// | 18     | `n * s` | each tail as `PointG2::to_bytes`             |
+///
+/// The content hash is the SHA-256 of the whole file including the header.
+/// It should be published in the revocation registry definition so
+/// `FileTailsAccessor::open` can check the file was not modified.
+pub struct FileTailsWriter;
+
+impl FileTailsWriter {
+    /// Write all tails from an unused `rev_tails_generator` to a new file at `path`.
+    /// Returns the content hash of the file.
+    ///
+    /// # Example
+    /// ```
+    /// use ursa::cl::issuer::Issuer;
+    /// use ursa::cl::{FileTailsAccessor, FileTailsWriter};
+    ///
+    /// let mut credential_schema_builder = Issuer::new_credential_schema_builder().unwrap();
+    /// credential_schema_builder.add_attr("name").unwrap();
+    /// let credential_schema = credential_schema_builder.finalize().unwrap();
+    ///
+    /// let mut non_credential_schema_builder = Issuer::new_non_credential_schema_builder().unwrap();
+    /// non_credential_schema_builder.add_attr("master_secret").unwrap();
+    /// let non_credential_schema = non_credential_schema_builder.finalize().unwrap();
+    ///
+    /// let (cred_pub_key, _cred_priv_key, _cred_key_correctness_proof) =
+    ///     Issuer::new_credential_def(&credential_schema, &non_credential_schema, true).unwrap();
+    ///
+    /// let (_rev_key_pub, _rev_key_priv, _rev_reg, mut rev_tails_generator) =
+    ///     Issuer::new_revocation_registry_def(&cred_pub_key, 5, false).unwrap();
+    ///
+    /// let path = std::env::temp_dir().join("ursa_tails_doc_example");
+    /// let tails_hash = FileTailsWriter::write(&path, &mut rev_tails_generator).unwrap();
+    /// let _tails_accessor = FileTailsAccessor::open(&path, &tails_hash).unwrap();
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn write<P: AsRef<Path>>(
+        path: P,
+        rev_tails_generator: &mut RevocationTailsGenerator,
+    ) -> UrsaCryptoResult<Vec<u8>> {
+        if rev_tails_generator.current_index != 0 {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidState,
+                "Tails generator has already been used",
+            ));
+        }
+
+        let file = File::create(path)
+            .map_err(|err| err.to_ursa(UrsaCryptoErrorKind::IOError, "Can't create tails file"))?;
+        let mut writer = BufWriter::new(file);
+        let mut hasher = Sha256Hasher::new()?;
+
+        let mut header = Vec::with_capacity(TAILS_FILE_HEADER_SIZE);
+        header.extend_from_slice(TAILS_FILE_MAGIC);
+        header.extend_from_slice(&TAILS_FILE_VERSION.to_be_bytes());
+        header.extend_from_slice(&rev_tails_generator.count().to_be_bytes());
+        header.extend_from_slice(&(Tail::BYTES_REPR_SIZE as u32).to_be_bytes());
+        write_and_hash(&mut writer, &mut hasher, &header)?;
+
+        while let Some(tail) = rev_tails_generator.try_next()? {
+            write_and_hash(&mut writer, &mut hasher, &tail.to_bytes()?)?;
+        }
+        writer
+            .flush()
+            .map_err(|err| err.to_ursa(UrsaCryptoErrorKind::IOError, "Can't write tails file"))?;
+
+        hasher.finish()
+    }
+}
+
+/// Implementation of `RevocationTailsAccessor` that reads each tail from a file
+/// written by `FileTailsWriter` when it is accessed.
+#[derive(Debug)]
+pub struct FileTailsAccessor {
+    file: Mutex<File>,
+    count: u32,
+}
+
+impl FileTailsAccessor {
+    /// Open the tails file at `path` and check its content hash equals `tails_hash`
+    /// from the revocation registry definition.
+    pub fn open<P: AsRef<Path>>(path: P, tails_hash: &[u8]) -> UrsaCryptoResult<FileTailsAccessor> {
+        let mut file = File::open(path)
+            .map_err(|err| err.to_ursa(UrsaCryptoErrorKind::IOError, "Can't open tails file"))?;
+
+        let mut header = [0u8; TAILS_FILE_HEADER_SIZE];
+        file.read_exact(&mut header).map_err(|err| {
+            err.to_ursa(
+                UrsaCryptoErrorKind::InvalidStructure,
+                "Invalid tails file header",
+            )
+        })?;
+        if &header[0..8] != TAILS_FILE_MAGIC {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                "Not a tails file",
+            ));
+        }
+        let version = u16::from_be_bytes([header[8], header[9]]);
+        if version != TAILS_FILE_VERSION {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                format!("Unsupported tails file version {}", version),
+            ));
+        }
+        let count = u32::from_be_bytes([header[10], header[11], header[12], header[13]]);
+        let tail_size = u32::from_be_bytes([header[14], header[15], header[16], header[17]]);
+        if tail_size as usize != Tail::BYTES_REPR_SIZE {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                format!("Invalid tail size {}", tail_size),
+            ));
+        }
+
+        let len = file
+            .metadata()
+            .map_err(|err| err.to_ursa(UrsaCryptoErrorKind::IOError, "Can't read tails file"))?
+            .len();
+        if len != (TAILS_FILE_HEADER_SIZE + count as usize * Tail::BYTES_REPR_SIZE) as u64 {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                "Tails file length does not match its header",
+            ));
+        }
+
+        if hash_file(&mut file)? != tails_hash {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidStructure,
+                "Tails file hash does not match the revocation registry definition",
+            ));
+        }
+
+        Ok(FileTailsAccessor {
+            file: Mutex::new(file),
+            count,
+        })
+    }
+
+    /// The number of tails in the file
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+impl RevocationTailsAccessor for FileTailsAccessor {
+    fn access_tail(&self, tail_id: u32, accessor: &mut dyn FnMut(&Tail)) -> UrsaCryptoResult<()> {
+        if tail_id >= self.count {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::InvalidRevocationAccumulatorIndex,
+                format!("Tail {} is not in the tails file", tail_id),
+            ));
+        }
+        let mut bytes = vec![0u8; Tail::BYTES_REPR_SIZE];
+        {
+            let mut file = self.file.lock().map_err(|_| {
+                err_msg(
+                    UrsaCryptoErrorKind::InvalidState,
+                    "Tails file lock is poisoned",
+                )
+            })?;
+            let offset = TAILS_FILE_HEADER_SIZE + tail_id as usize * Tail::BYTES_REPR_SIZE;
+            file.seek(SeekFrom::Start(offset as u64))
+                .and_then(|_| file.read_exact(&mut bytes))
+                .map_err(|err| {
+                    err.to_ursa(UrsaCryptoErrorKind::IOError, "Can't read tails file")
+                })?;
+        }
+        accessor(&Tail::from_bytes(&bytes)?);
+        Ok(())
+    }
+}
+
+fn write_and_hash<W: Write>(
+    writer: &mut W,
+    hasher: &mut Sha256Hasher,
+    data: &[u8],
+) -> UrsaCryptoResult<()> {
+    writer
+        .write_all(data)
+        .map_err(|err| err.to_ursa(UrsaCryptoErrorKind::IOError, "Can't write tails file"))?;
+    hasher.update(data)
+}
+
+fn hash_file(file: &mut File) -> UrsaCryptoResult<Vec<u8>> {
+    file.seek(SeekFrom::Start(0))
+        .map_err(|err| err.to_ursa(UrsaCryptoErrorKind::IOError, "Can't read tails file"))?;
+    let mut reader = BufReader::with_capacity(HASH_BUFFER_SIZE, file);
+    let mut hasher = Sha256Hasher::new()?;
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|err| err.to_ursa(UrsaCryptoErrorKind::IOError, "Can't read tails file"))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read])?;
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cl::SimpleTailsAccessor;
+    use pair::{GroupOrderElement, PointG2};
+    use std::fs;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("ursa_{}_{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn file_tails_accessor_works() {
+        let generator = RevocationTailsGenerator::new(
+            5,
+            GroupOrderElement::new().unwrap(),
+            PointG2::new().unwrap(),
+        );
+        let path = temp_path("tails_works");
+        let tails_hash = FileTailsWriter::write(&path, &mut generator.clone()).unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().len() as usize,
+            TAILS_FILE_HEADER_SIZE + 11 * Tail::BYTES_REPR_SIZE
+        );
+
+        let file_accessor = FileTailsAccessor::open(&path, &tails_hash).unwrap();
+        let simple_accessor = SimpleTailsAccessor::new(&mut generator.clone()).unwrap();
+        assert_eq!(file_accessor.count(), 11);
+        for i in 0..file_accessor.count() {
+            let mut expected = None;
+            simple_accessor
+                .access_tail(i, &mut |tail| expected = Some(tail.to_bytes().unwrap()))
+                .unwrap();
+            let mut actual = None;
+            file_accessor
+                .access_tail(i, &mut |tail| actual = Some(tail.to_bytes().unwrap()))
+                .unwrap();
+            assert_eq!(expected, actual);
+        }
+        assert!(file_accessor.access_tail(11, &mut |_| {}).is_err());
+
+        // A used generator would give tails with the wrong indices
+        let mut used = generator;
+        used.try_next().unwrap();
+        assert!(FileTailsWriter::write(&temp_path("tails_used"), &mut used).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_tails_accessor_rejects_modified_file() {
+        let mut generator = RevocationTailsGenerator::new(
+            2,
+            GroupOrderElement::new().unwrap(),
+            PointG2::new().unwrap(),
+        );
+        let path = temp_path("tails_modified");
+        let tails_hash = FileTailsWriter::write(&path, &mut generator).unwrap();
+
+        let mut wrong_hash = tails_hash.clone();
+        wrong_hash[0] ^= 1;
+        assert!(FileTailsAccessor::open(&path, &wrong_hash).is_err());
+
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+        assert!(FileTailsAccessor::open(&path, &tails_hash).is_err());
+
+        bytes.truncate(last);
+        fs::write(&path, &bytes).unwrap();
+        assert!(FileTailsAccessor::open(&path, &tails_hash).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,9 +1,10 @@
 use bn::BigNumber;
-use cl::constants::{ITERATION, LARGE_E_START_VALUE};
+use cl::constants::{ITERATION, LARGE_CHALLENGE_VALUE, LARGE_E_START_VALUE};
 use cl::hash::get_hash_as_int;
 use cl::helpers::*;
 use cl::*;
 use errors::prelude::*;
+use utils::commitment::get_pedersen_commitment;
 
 use std::collections::hash_map::Entry;
 use std::collections::{BTreeSet, HashMap};
@@ -119,6 +120,7 @@ impl ProofVerifier {
             sub_proof_request,
             credential_schema,
         )?;
+        self._check_attribute_predicates(sub_proof_request)?;
 
         self.credentials.push(VerifiableCredential {
             pub_key: credential_pub_key.try_clone()?,
@@ -301,6 +303,20 @@ impl ProofVerifier {
                 &credential.non_credential_schema,
                 &credential.sub_proof_request,
             )?)?;
+
+            for attribute_proof in proof_item.primary_proof.attribute_proofs.iter() {
+                let other_eq_proof = match attribute_proof.predicate.other_sub_proof {
+                    Some(other_idx) => &proof.proofs[other_idx].primary_proof.eq_proof,
+                    None => &proof_item.primary_proof.eq_proof,
+                };
+                tau_list.append_vec(&ProofVerifier::_verify_attribute_predicate(
+                    &credential.pub_key.p_key,
+                    attribute_proof,
+                    &proof_item.primary_proof.eq_proof,
+                    other_eq_proof,
+                    &proof.aggregated_proof.c_hash,
+                )?)?;
+            }
         }
 
         let mut values: Vec<Vec<u8>> = Vec::new();
@@ -341,6 +357,18 @@ impl ProofVerifier {
             .predicates
             .iter()
             .map(|predicate| predicate.attr_name.clone())
+            .chain(
+                sub_proof_request
+                    .set_predicates
+                    .iter()
+                    .map(|predicate| predicate.attr_name.clone()),
+            )
+            .chain(
+                sub_proof_request
+                    .attribute_predicates
+                    .iter()
+                    .flat_map(|predicate| predicate.get_attr_names()),
+            )
             .collect::<BTreeSet<String>>();
 
         if predicates_attrs.difference(&cred_schema.attrs).count() != 0 {
@@ -355,6 +383,48 @@ impl ProofVerifier {
         Ok(())
     }
 
+    fn _check_attribute_predicates(
+        &self,
+        sub_proof_request: &SubProofRequest,
+    ) -> UrsaCryptoResult<()> {
+        for predicate in sub_proof_request.attribute_predicates.iter() {
+            if let Some(other_idx) = predicate.other_sub_proof {
+                let other = self.credentials.get(other_idx).ok_or_else(|| {
+                    err_msg(
+                        UrsaCryptoErrorKind::InvalidStructure,
+                        format!(
+                            "Attribute predicate refers to sub proof {} that was not added before",
+                            other_idx
+                        ),
+                    )
+                })?;
+
+                if !other
+                    .credential_schema
+                    .attrs
+                    .contains(&predicate.other_attr_name)
+                {
+                    return Err(err_msg(
+                        UrsaCryptoErrorKind::InvalidStructure,
+                        "Credential doesn't contain attribute requested in predicate",
+                    ));
+                }
+
+                if other
+                    .sub_proof_request
+                    .revealed_attrs
+                    .contains(&predicate.other_attr_name)
+                {
+                    return Err(err_msg(
+                        UrsaCryptoErrorKind::InvalidStructure,
+                        "ProofRequest can't contain an attribute as a revealed value and as a predicate at the same time",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn _check_verify_params_consistency(
         credentials: &[VerifiableCredential],
         proof: &Proof,
@@ -402,6 +472,34 @@ impl ProofVerifier {
                     "Proof predicates not correspond to requested predicates",
                 ));
             }
+
+            let proof_set_predicates = proof_for_credential
+                .primary_proof
+                .set_proofs
+                .iter()
+                .map(|set_proof| set_proof.predicate.clone())
+                .collect::<BTreeSet<SetPredicate>>();
+
+            if proof_set_predicates != credential.sub_proof_request.set_predicates {
+                return Err(err_msg(
+                    UrsaCryptoErrorKind::ProofRejected,
+                    "Proof set predicates not correspond to requested set predicates",
+                ));
+            }
+
+            let proof_attribute_predicates = proof_for_credential
+                .primary_proof
+                .attribute_proofs
+                .iter()
+                .map(|attribute_proof| attribute_proof.predicate.clone())
+                .collect::<BTreeSet<AttributePredicate>>();
+
+            if proof_attribute_predicates != credential.sub_proof_request.attribute_predicates {
+                return Err(err_msg(
+                    UrsaCryptoErrorKind::ProofRejected,
+                    "Proof attribute predicates not correspond to requested attribute predicates",
+                ));
+            }
         }
 
         trace!("ProofVerifier::_check_verify_params_consistency: <<<");
@@ -435,6 +533,15 @@ impl ProofVerifier {
             )?)
         }
 
+        for set_proof in primary_proof.set_proofs.iter() {
+            t_hat.append(&mut ProofVerifier::_verify_set_predicate(
+                p_pub_key,
+                set_proof,
+                &primary_proof.eq_proof,
+                c_hash,
+            )?)
+        }
+
         trace!(
             "ProofVerifier::_verify_primary_proof: <<< t_hat: {:?}",
             t_hat
@@ -580,6 +687,174 @@ impl ProofVerifier {
         Ok(tau_list)
     }
 
+    fn _verify_attribute_predicate(
+        p_pub_key: &CredentialPrimaryPublicKey,
+        proof: &PrimaryPredicateAttributeProof,
+        eq_proof: &PrimaryEqualProof,
+        other_eq_proof: &PrimaryEqualProof,
+        c_hash: &BigNumber,
+    ) -> UrsaCryptoResult<Vec<BigNumber>> {
+        trace!(
+            "ProofVerifier::_verify_attribute_predicate: >>> p_pub_key: {:?}, proof: {:?}, eq_proof: {:?}, other_eq_proof: {:?}, c_hash: {:?}",
+            p_pub_key,
+            proof,
+            eq_proof,
+            other_eq_proof,
+            c_hash
+        );
+
+        fn get_m_hat<'a>(
+            eq_proof: &'a PrimaryEqualProof,
+            attr_name: &str,
+        ) -> UrsaCryptoResult<&'a BigNumber> {
+            eq_proof.m.get(attr_name).ok_or_else(|| {
+                err_msg(
+                    UrsaCryptoErrorKind::ProofRejected,
+                    format!("Value by key '{}' not found in eq_proof.m", attr_name),
+                )
+            })
+        }
+
+        // Binds the inequality proof to the attributes of both equality proofs
+        let mj = get_m_hat(eq_proof, &proof.predicate.attr_name)?
+            .sub(get_m_hat(other_eq_proof, &proof.predicate.other_attr_name)?)?;
+
+        if proof.ne_proof.mj != mj
+            || proof.ne_proof.predicate != proof.predicate.get_difference_predicate()
+        {
+            return Err(err_msg(
+                UrsaCryptoErrorKind::ProofRejected,
+                "Attribute predicate proof does not correspond to the sub proofs",
+            ));
+        }
+
+        let tau_list = ProofVerifier::_verify_ne_predicate(p_pub_key, &proof.ne_proof, c_hash)?;
+
+        trace!(
+            "ProofVerifier::_verify_attribute_predicate: <<< tau_list: {:?}",
+            tau_list
+        );
+
+        Ok(tau_list)
+    }
+
+    fn _verify_set_predicate(
+        p_pub_key: &CredentialPrimaryPublicKey,
+        proof: &PrimaryPredicateSetProof,
+        eq_proof: &PrimaryEqualProof,
+        c_hash: &BigNumber,
+    ) -> UrsaCryptoResult<Vec<BigNumber>> {
+        trace!(
+            "ProofVerifier::_verify_set_predicate: >>> p_pub_key: {:?}, proof: {:?}, eq_proof: {:?}, c_hash: {:?}",
+            p_pub_key,
+            proof,
+            eq_proof,
+            c_hash
+        );
+
+        let mut ctx = BigNumber::new_context()?;
+
+        let mj = eq_proof.m.get(&proof.predicate.attr_name).ok_or_else(|| {
+            err_msg(
+                UrsaCryptoErrorKind::ProofRejected,
+                format!(
+                    "Value by key '{}' not found in eq_proof.m",
+                    proof.predicate.attr_name
+                ),
+            )
+        })?;
+
+        let values = proof.predicate.get_values()?;
+
+        let mut tau_list = vec![proof
+            .t
+            .mod_exp(c_hash, &p_pub_key.n, Some(&mut ctx))?
+            .inverse(&p_pub_key.n, Some(&mut ctx))?
+            .mod_mul(
+                &get_pedersen_commitment(
+                    &p_pub_key.z,
+                    mj,
+                    &p_pub_key.s,
+                    &proof.r,
+                    &p_pub_key.n,
+                    &mut ctx,
+                )?,
+                &p_pub_key.n,
+                Some(&mut ctx),
+            )?];
+
+        match proof.predicate.p_type {
+            SetPredicateType::IN => {
+                if proof.c.len() != values.len() || proof.rho.len() != values.len() {
+                    return Err(err_msg(
+                        UrsaCryptoErrorKind::ProofRejected,
+                        "Invalid set predicate proof length",
+                    ));
+                }
+
+                let mut c_sum = BigNumber::new()?;
+                for c in proof.c.iter() {
+                    c_sum = c_sum.add(c)?;
+                }
+                if c_sum.modulus(&LARGE_CHALLENGE_VALUE, Some(&mut ctx))?
+                    != c_hash.modulus(&LARGE_CHALLENGE_VALUE, Some(&mut ctx))?
+                {
+                    return Err(err_msg(
+                        UrsaCryptoErrorKind::ProofRejected,
+                        "Set predicate challenges not correspond to proof challenge",
+                    ));
+                }
+
+                for (i, value) in values.iter().enumerate() {
+                    tau_list.push(calc_tset_in(
+                        p_pub_key,
+                        &proof.t,
+                        value,
+                        &proof.c[i],
+                        &proof.rho[i],
+                    )?);
+                }
+            }
+            SetPredicateType::NE => {
+                if proof.a.len() != values.len()
+                    || proof.b.len() != values.len()
+                    || proof.rho.len() != values.len()
+                {
+                    return Err(err_msg(
+                        UrsaCryptoErrorKind::ProofRejected,
+                        "Invalid set predicate proof length",
+                    ));
+                }
+
+                let z_c = p_pub_key
+                    .z
+                    .mod_exp(c_hash, &p_pub_key.n, Some(&mut ctx))?
+                    .inverse(&p_pub_key.n, Some(&mut ctx))?;
+
+                for (i, value) in values.iter().enumerate() {
+                    tau_list.push(
+                        calc_tset_ne(
+                            p_pub_key,
+                            &proof.t,
+                            value,
+                            &proof.a[i],
+                            &proof.b[i],
+                            &proof.rho[i],
+                        )?
+                        .mod_mul(&z_c, &p_pub_key.n, Some(&mut ctx))?,
+                    );
+                }
+            }
+        }
+
+        trace!(
+            "ProofVerifier::_verify_set_predicate: <<< tau_list: {:?},",
+            tau_list
+        );
+
+        Ok(tau_list)
+    }
+
     fn _verify_non_revocation_proof(
         r_pub_key: &CredentialRevocationPublicKey,
         rev_reg: &RevocationRegistry,
@@ -662,6 +937,51 @@ mod tests {
         assert!(sub_proof_request.predicates.contains(&predicate()));
     }
 
+    #[test]
+    fn sub_proof_request_builder_works_for_set_predicate() {
+        let mut sub_proof_request_builder = Verifier::new_sub_proof_request_builder().unwrap();
+        sub_proof_request_builder
+            .add_set_predicate("status", "NE", &["0012"])
+            .unwrap();
+        assert!(sub_proof_request_builder
+            .add_set_predicate("status", "EQ", &["12"])
+            .is_err());
+        assert!(sub_proof_request_builder
+            .add_set_predicate("status", "IN", &[])
+            .is_err());
+        assert!(sub_proof_request_builder
+            .add_set_predicate("status", "IN", &["twelve"])
+            .is_err());
+        let sub_proof_request = sub_proof_request_builder.finalize().unwrap();
+
+        assert!(sub_proof_request.set_predicates.contains(&SetPredicate {
+            attr_name: "status".to_owned(),
+            p_type: SetPredicateType::NE,
+            values: btreeset!["12".to_owned()],
+        }));
+    }
+
+    #[test]
+    fn sub_proof_request_builder_works_for_attribute_predicate() {
+        let mut sub_proof_request_builder = Verifier::new_sub_proof_request_builder().unwrap();
+        sub_proof_request_builder
+            .add_attribute_predicate("expiry", "GE", Some(0), "issue_date")
+            .unwrap();
+        assert!(sub_proof_request_builder
+            .add_attribute_predicate("expiry", "EQ", None, "issue_date")
+            .is_err());
+        let sub_proof_request = sub_proof_request_builder.finalize().unwrap();
+
+        let predicate = AttributePredicate {
+            attr_name: "expiry".to_owned(),
+            p_type: PredicateType::GE,
+            other_sub_proof: Some(0),
+            other_attr_name: "issue_date".to_owned(),
+        };
+        assert!(sub_proof_request.attribute_predicates.contains(&predicate));
+        assert_eq!(predicate.get_attr_names(), vec!["expiry".to_owned()]);
+    }
+
     #[test]
     fn verify_equality_works() {
         MockHelper::inject();
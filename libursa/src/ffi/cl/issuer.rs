@@ -6,7 +6,7 @@ use ffi::ErrorCode;
 use utils::ctypes::*;
 
 use serde_json;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::iter::FromIterator;
 use std::os::raw::{c_char, c_void};
 use std::ptr::null;
@@ -1966,6 +1966,86 @@ pub extern "C" fn ursa_cl_issuer_recovery_credential(
     res
 }
 
+/// Revokes and recovers sets of credentials in a given revocation registry
+/// with a single accumulator update.
+///
+/// Note that revocation registry delta instance deallocation must be performed by
+/// calling ursa_cl_revocation_registry_delta_free.
+///
+/// # Arguments
+/// * `rev_reg` - Reference that contain revocation registry instance pointer.
+/// * `max_cred_num` - Max credential number in revocation registry.
+/// * `issued` - Indexes of the credentials to recover.
+/// * `issued_len` - Number of indexes in `issued`.
+/// * `revoked` - Indexes of the credentials to revoke.
+/// * `revoked_len` - Number of indexes in `revoked`.
+/// * `rev_reg_delta_p` - Reference that will contain revocation registry delta instance pointer.
+#[no_mangle]
+pub extern "C" fn ursa_cl_issuer_update_revocation_registry_batch(
+    rev_reg: *const c_void,
+    max_cred_num: u32,
+    issued: *const u32,
+    issued_len: usize,
+    revoked: *const u32,
+    revoked_len: usize,
+    ctx_tails: *const c_void,
+    take_tail: FFITailTake,
+    put_tail: FFITailPut,
+    rev_reg_delta_p: *mut *const c_void,
+) -> ErrorCode {
+    trace!("ursa_cl_issuer_update_revocation_registry_batch: >>> rev_reg: {:?}, max_cred_num: {:?}, issued: {:?}, issued_len: {:?}, \
+    revoked: {:?}, revoked_len: {:?}, ctx_tails {:?}, take_tail {:?}, put_tail {:?}, rev_reg_delta_p {:?}",
+           rev_reg, max_cred_num, issued, issued_len, revoked, revoked_len, ctx_tails, take_tail, put_tail, rev_reg_delta_p);
+
+    check_useful_mut_c_reference!(rev_reg, RevocationRegistry, ErrorCode::CommonInvalidParam1);
+    check_useful_hashset!(
+        issued,
+        issued_len,
+        ErrorCode::CommonInvalidParam3,
+        ErrorCode::CommonInvalidParam4
+    );
+    check_useful_hashset!(
+        revoked,
+        revoked_len,
+        ErrorCode::CommonInvalidParam5,
+        ErrorCode::CommonInvalidParam6
+    );
+    check_useful_c_ptr!(rev_reg_delta_p, ErrorCode::CommonInvalidParam10);
+
+    trace!("ursa_cl_issuer_update_revocation_registry_batch: entities: rev_reg: {:?}, issued: {:?}, revoked: {:?}",
+           rev_reg, secret!(&issued), secret!(&revoked));
+
+    let issued: HashSet<u32> = issued;
+    let revoked: HashSet<u32> = revoked;
+
+    let rta = FFITailsAccessor::new(ctx_tails, take_tail, put_tail);
+    let res = match Issuer::update_revocation_registry_batch(
+        rev_reg,
+        max_cred_num,
+        BTreeSet::from_iter(issued),
+        BTreeSet::from_iter(revoked),
+        &rta,
+    ) {
+        Ok(rev_reg_delta) => {
+            unsafe {
+                *rev_reg_delta_p = Box::into_raw(Box::new(rev_reg_delta)) as *const c_void;
+                trace!(
+                    "ursa_cl_issuer_update_revocation_registry_batch: *rev_reg_delta_p: {:?}",
+                    *rev_reg_delta_p
+                );
+            }
+            ErrorCode::Success
+        }
+        Err(err) => err.into(),
+    };
+
+    trace!(
+        "ursa_cl_issuer_update_revocation_registry_batch: <<< res: {:?}",
+        res
+    );
+    res
+}
+
 #[no_mangle]
 pub extern "C" fn ursa_cl_issuer_merge_revocation_registry_deltas(
     revoc_reg_delta: *const c_void,
@@ -2942,6 +3022,61 @@ mod tests {
         _free_revocation_registry_def(rev_key_pub, rev_key_priv, rev_reg, rev_tails_generator);
     }
 
+    #[test]
+    fn ursa_cl_issuer_update_revocation_registry_batch_works() {
+        let (credential_pub_key, credential_priv_key, credential_key_correctness_proof) =
+            _credential_def();
+        let (rev_key_pub, rev_key_priv, rev_reg, rev_tails_generator) =
+            _revocation_registry_def(credential_pub_key);
+        let tail_storage = FFISimpleTailStorage::new(rev_tails_generator);
+
+        let issued_h = vec![1, 2, 3];
+        let revoked_h = vec![4, 5];
+
+        let mut rev_reg_delta_p: *const c_void = ptr::null();
+
+        let err_code = ursa_cl_issuer_update_revocation_registry_batch(
+            rev_reg,
+            5,
+            issued_h.as_ptr(),
+            issued_h.len(),
+            revoked_h.as_ptr(),
+            revoked_h.len(),
+            tail_storage.get_ctx(),
+            FFISimpleTailStorage::tail_take,
+            FFISimpleTailStorage::tail_put,
+            &mut rev_reg_delta_p,
+        );
+        assert_eq!(err_code, ErrorCode::Success);
+        assert!(!rev_reg_delta_p.is_null());
+
+        let err_code = ursa_cl_revocation_registry_delta_free(rev_reg_delta_p);
+        assert_eq!(err_code, ErrorCode::Success);
+
+        let mut rev_reg_delta_p: *const c_void = ptr::null();
+        let err_code = ursa_cl_issuer_update_revocation_registry_batch(
+            rev_reg,
+            5,
+            issued_h.as_ptr(),
+            issued_h.len(),
+            issued_h.as_ptr(),
+            issued_h.len(),
+            tail_storage.get_ctx(),
+            FFISimpleTailStorage::tail_take,
+            FFISimpleTailStorage::tail_put,
+            &mut rev_reg_delta_p,
+        );
+        assert_ne!(err_code, ErrorCode::Success);
+        assert!(rev_reg_delta_p.is_null());
+
+        _free_credential_def(
+            credential_pub_key,
+            credential_priv_key,
+            credential_key_correctness_proof,
+        );
+        _free_revocation_registry_def(rev_key_pub, rev_key_priv, rev_reg, rev_tails_generator);
+    }
+
     #[test]
     fn ursa_cl_issuer_merge_revoc_deltas_works() {
         let (credential_pub_key, credential_priv_key, credential_key_correctness_proof) =
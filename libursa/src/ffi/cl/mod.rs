@@ -1,3 +1,4 @@
+use bn::BigNumber;
 use cl::issuer::Issuer;
 use cl::verifier::Verifier;
 use cl::*;
@@ -859,6 +860,193 @@ pub extern "C" fn ursa_cl_sub_proof_request_builder_add_predicate(
     res
 }
 
+/// Adds predicate with a 64 bit value to sub proof request.
+///
+/// # Arguments
+/// * `sub_proof_request_builder` - Reference that contains sub proof request builder instance pointer.
+/// * `attr_name` - Related attribute
+/// * `p_type` - Predicate type (`GE`, `LE`, `GT` or `LT`).
+/// * `value` - Requested value.
+#[no_mangle]
+pub extern "C" fn ursa_cl_sub_proof_request_builder_add_predicate_i64(
+    sub_proof_request_builder: *const c_void,
+    attr_name: *const c_char,
+    p_type: *const c_char,
+    value: i64,
+) -> ErrorCode {
+    trace!("ursa_cl_sub_proof_request_builder_add_predicate_i64: >>> sub_proof_request_builder: {:?}, attr_name: {:?}, p_type: {:?}, value: {:?}",
+           sub_proof_request_builder, attr_name, p_type, value);
+
+    check_useful_mut_c_reference!(
+        sub_proof_request_builder,
+        SubProofRequestBuilder,
+        ErrorCode::CommonInvalidParam1
+    );
+    check_useful_c_str!(attr_name, ErrorCode::CommonInvalidParam2);
+    check_useful_c_str!(p_type, ErrorCode::CommonInvalidParam3);
+
+    trace!("ursa_cl_sub_proof_request_builder_add_predicate_i64: entities: >>> sub_proof_request_builder: {:?}, attr_name: {:?}, p_type: {:?}, value: {:?}",
+           sub_proof_request_builder, attr_name, p_type, value);
+
+    let res = match sub_proof_request_builder.add_predicate_i64(&attr_name, &p_type, value) {
+        Ok(_) => ErrorCode::Success,
+        Err(err) => err.into(),
+    };
+
+    trace!(
+        "ursa_cl_sub_proof_request_builder_add_predicate_i64: <<< res: {:?}",
+        res
+    );
+    res
+}
+
+/// Adds predicate with a value of any size to sub proof request.
+///
+/// # Arguments
+/// * `sub_proof_request_builder` - Reference that contains sub proof request builder instance pointer.
+/// * `attr_name` - Related attribute
+/// * `p_type` - Predicate type (`GE`, `LE`, `GT` or `LT`).
+/// * `value` - Requested value as decimal string.
+#[no_mangle]
+pub extern "C" fn ursa_cl_sub_proof_request_builder_add_predicate_dec(
+    sub_proof_request_builder: *const c_void,
+    attr_name: *const c_char,
+    p_type: *const c_char,
+    value: *const c_char,
+) -> ErrorCode {
+    trace!("ursa_cl_sub_proof_request_builder_add_predicate_dec: >>> sub_proof_request_builder: {:?}, attr_name: {:?}, p_type: {:?}, value: {:?}",
+           sub_proof_request_builder, attr_name, p_type, value);
+
+    check_useful_mut_c_reference!(
+        sub_proof_request_builder,
+        SubProofRequestBuilder,
+        ErrorCode::CommonInvalidParam1
+    );
+    check_useful_c_str!(attr_name, ErrorCode::CommonInvalidParam2);
+    check_useful_c_str!(p_type, ErrorCode::CommonInvalidParam3);
+    check_useful_c_str!(value, ErrorCode::CommonInvalidParam4);
+
+    trace!("ursa_cl_sub_proof_request_builder_add_predicate_dec: entities: >>> sub_proof_request_builder: {:?}, attr_name: {:?}, p_type: {:?}, value: {:?}",
+           sub_proof_request_builder, attr_name, p_type, value);
+
+    let res = match BigNumber::from_dec(&value)
+        .and_then(|value| sub_proof_request_builder.add_predicate_bn(&attr_name, &p_type, &value))
+    {
+        Ok(_) => ErrorCode::Success,
+        Err(err) => err.into(),
+    };
+
+    trace!(
+        "ursa_cl_sub_proof_request_builder_add_predicate_dec: <<< res: {:?}",
+        res
+    );
+    res
+}
+
+/// Adds predicate comparing two hidden attributes to sub proof request.
+///
+/// # Arguments
+/// * `sub_proof_request_builder` - Reference that contains sub proof request builder instance pointer.
+/// * `attr_name` - Related attribute
+/// * `p_type` - Predicate type (`GE`, `LE`, `GT` or `LT`).
+/// * `other_sub_proof` - Index of the sub proof containing `other_attr_name`, negative for the same sub proof.
+/// * `other_attr_name` - Attribute to compare with.
+#[no_mangle]
+pub extern "C" fn ursa_cl_sub_proof_request_builder_add_attribute_predicate(
+    sub_proof_request_builder: *const c_void,
+    attr_name: *const c_char,
+    p_type: *const c_char,
+    other_sub_proof: i32,
+    other_attr_name: *const c_char,
+) -> ErrorCode {
+    trace!("ursa_cl_sub_proof_request_builder_add_attribute_predicate: >>> sub_proof_request_builder: {:?}, attr_name: {:?}, p_type: {:?}, other_sub_proof: {:?}, other_attr_name: {:?}",
+           sub_proof_request_builder, attr_name, p_type, other_sub_proof, other_attr_name);
+
+    check_useful_mut_c_reference!(
+        sub_proof_request_builder,
+        SubProofRequestBuilder,
+        ErrorCode::CommonInvalidParam1
+    );
+    check_useful_c_str!(attr_name, ErrorCode::CommonInvalidParam2);
+    check_useful_c_str!(p_type, ErrorCode::CommonInvalidParam3);
+    check_useful_c_str!(other_attr_name, ErrorCode::CommonInvalidParam5);
+
+    let other_sub_proof = if other_sub_proof < 0 {
+        None
+    } else {
+        Some(other_sub_proof as usize)
+    };
+
+    trace!("ursa_cl_sub_proof_request_builder_add_attribute_predicate: entities: >>> sub_proof_request_builder: {:?}, attr_name: {:?}, p_type: {:?}, other_sub_proof: {:?}, other_attr_name: {:?}",
+           sub_proof_request_builder, attr_name, p_type, other_sub_proof, other_attr_name);
+
+    let res = match sub_proof_request_builder.add_attribute_predicate(
+        &attr_name,
+        &p_type,
+        other_sub_proof,
+        &other_attr_name,
+    ) {
+        Ok(_) => ErrorCode::Success,
+        Err(err) => err.into(),
+    };
+
+    trace!(
+        "ursa_cl_sub_proof_request_builder_add_attribute_predicate: <<< res: {:?}",
+        res
+    );
+    res
+}
+
+/// Adds set predicate to sub proof request.
+///
+/// # Arguments
+/// * `sub_proof_request_builder` - Reference that contains sub proof request builder instance pointer.
+/// * `attr_name` - Related attribute
+/// * `p_type` - Set predicate type (`IN` or `NE`).
+/// * `values` - Array of requested encoded values as decimal strings.
+/// * `values_len` - Length of `values` array.
+#[no_mangle]
+pub extern "C" fn ursa_cl_sub_proof_request_builder_add_set_predicate(
+    sub_proof_request_builder: *const c_void,
+    attr_name: *const c_char,
+    p_type: *const c_char,
+    values: *const *const c_char,
+    values_len: usize,
+) -> ErrorCode {
+    trace!("ursa_cl_sub_proof_request_builder_add_set_predicate: >>> sub_proof_request_builder: {:?}, attr_name: {:?}, p_type: {:?}, values: {:?}, values_len: {:?}",
+           sub_proof_request_builder, attr_name, p_type, values, values_len);
+
+    check_useful_mut_c_reference!(
+        sub_proof_request_builder,
+        SubProofRequestBuilder,
+        ErrorCode::CommonInvalidParam1
+    );
+    check_useful_c_str!(attr_name, ErrorCode::CommonInvalidParam2);
+    check_useful_c_str!(p_type, ErrorCode::CommonInvalidParam3);
+    check_useful_c_str_array!(
+        values,
+        values_len,
+        ErrorCode::CommonInvalidParam4,
+        ErrorCode::CommonInvalidParam5
+    );
+
+    trace!("ursa_cl_sub_proof_request_builder_add_set_predicate: entities: >>> sub_proof_request_builder: {:?}, attr_name: {:?}, p_type: {:?}, values: {:?}",
+           sub_proof_request_builder, attr_name, p_type, values);
+
+    let values = values.iter().map(String::as_str).collect::<Vec<&str>>();
+
+    let res = match sub_proof_request_builder.add_set_predicate(&attr_name, &p_type, &values) {
+        Ok(_) => ErrorCode::Success,
+        Err(err) => err.into(),
+    };
+
+    trace!(
+        "ursa_cl_sub_proof_request_builder_add_set_predicate: <<< res: {:?}",
+        res
+    );
+    res
+}
+
 /// Deallocates sub proof request builder and returns sub proof request entity instead.
 ///
 /// Note: Sub proof request instance deallocation must be performed by
@@ -1400,6 +1588,102 @@ mod tests {
         _free_sub_proof_request_builder(sub_proof_request_builder);
     }
 
+    #[test]
+    fn ursa_cl_sub_proof_request_builder_add_predicate_i64_and_dec_works() {
+        let sub_proof_request_builder = _sub_proof_request_builder();
+
+        let attr_name = CString::new("timestamp").unwrap();
+        let p_type = CString::new("LT").unwrap();
+
+        let err_code = ursa_cl_sub_proof_request_builder_add_predicate_i64(
+            sub_proof_request_builder,
+            attr_name.as_ptr(),
+            p_type.as_ptr(),
+            1_600_000_000_000,
+        );
+        assert_eq!(err_code, ErrorCode::Success);
+
+        let value = CString::new("340282366920938463463374607431768211456").unwrap();
+        let err_code = ursa_cl_sub_proof_request_builder_add_predicate_dec(
+            sub_proof_request_builder,
+            attr_name.as_ptr(),
+            p_type.as_ptr(),
+            value.as_ptr(),
+        );
+        assert_eq!(err_code, ErrorCode::Success);
+
+        let value = CString::new("not a number").unwrap();
+        let err_code = ursa_cl_sub_proof_request_builder_add_predicate_dec(
+            sub_proof_request_builder,
+            attr_name.as_ptr(),
+            p_type.as_ptr(),
+            value.as_ptr(),
+        );
+        assert_ne!(err_code, ErrorCode::Success);
+
+        _free_sub_proof_request_builder(sub_proof_request_builder);
+    }
+
+    #[test]
+    fn ursa_cl_sub_proof_request_builder_add_attribute_predicate_works() {
+        let sub_proof_request_builder = _sub_proof_request_builder();
+
+        let attr_name = CString::new("age").unwrap();
+        let p_type = CString::new("GE").unwrap();
+        let other_attr_name = CString::new("height").unwrap();
+
+        let err_code = ursa_cl_sub_proof_request_builder_add_attribute_predicate(
+            sub_proof_request_builder,
+            attr_name.as_ptr(),
+            p_type.as_ptr(),
+            -1,
+            other_attr_name.as_ptr(),
+        );
+        assert_eq!(err_code, ErrorCode::Success);
+
+        let p_type = CString::new("EQ").unwrap();
+        let err_code = ursa_cl_sub_proof_request_builder_add_attribute_predicate(
+            sub_proof_request_builder,
+            attr_name.as_ptr(),
+            p_type.as_ptr(),
+            0,
+            other_attr_name.as_ptr(),
+        );
+        assert_eq!(err_code, ErrorCode::CommonInvalidStructure);
+
+        _free_sub_proof_request_builder(sub_proof_request_builder);
+    }
+
+    #[test]
+    fn ursa_cl_sub_proof_request_builder_add_set_predicate_works() {
+        let sub_proof_request_builder = _sub_proof_request_builder();
+
+        let attr_name = CString::new("status").unwrap();
+        let p_type = CString::new("IN").unwrap();
+        let values = vec![CString::new("1").unwrap(), CString::new("2").unwrap()];
+        let values_p = values.iter().map(|v| v.as_ptr()).collect::<Vec<_>>();
+
+        let err_code = ursa_cl_sub_proof_request_builder_add_set_predicate(
+            sub_proof_request_builder,
+            attr_name.as_ptr(),
+            p_type.as_ptr(),
+            values_p.as_ptr(),
+            values_p.len(),
+        );
+        assert_eq!(err_code, ErrorCode::Success);
+
+        let err_code = ursa_cl_sub_proof_request_builder_add_set_predicate(
+            sub_proof_request_builder,
+            attr_name.as_ptr(),
+            p_type.as_ptr(),
+            values_p.as_ptr(),
+            0,
+        );
+        assert_eq!(err_code, ErrorCode::CommonInvalidParam5);
+
+        _free_sub_proof_request_builder(sub_proof_request_builder);
+    }
+
     #[test]
     fn ursa_cl_sub_proof_request_builder_finalize_works() {
         let sub_proof_request_builder = _sub_proof_request_builder();
@@ -14,7 +14,8 @@ use amcl_wrapper::{
 use keys::{KeyGenOption, PrivateKey as UrsaPrivateKey, PublicKey as UrsaPublicKey};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
 
 use CryptoError;
 
@@ -732,8 +733,96 @@ macro_rules! bls_tests_impl {
     };
 }
 
+/// The smallest seed `derive_path_sk` accepts
+pub const MIN_SEED_SIZE: usize = 32;
+/// The purpose index for BLS12-381 keys in EIP-2334 paths i.e. `m/12381/...`
+pub const BLS_PURPOSE: u32 = 12381;
+
+const LAMPORT_CHUNKS: usize = 255;
+const DIGEST_SIZE: usize = 32;
+
+/// Derive the BLS private key at the EIP-2334 `path` e.g. `m/12381/3600/0/0` from `seed`
+/// using the EIP-2333 key tree <https://eips.ethereum.org/EIPS/eip-2333>.
+/// The path `m` is the master key. Wallets derive the keys for both signature groups
+/// with this since the private key is the same field element.
+/// The bbs crate has the master and child derivation steps for BBS+ keys.
+pub fn derive_path_sk(seed: &[u8], path: &str) -> Result<PrivateKey, CryptoError> {
+    if seed.len() < MIN_SEED_SIZE {
+        return Err(CryptoError::KeyGenError(format!(
+            "The seed must be at least {} bytes, found {}",
+            MIN_SEED_SIZE,
+            seed.len()
+        )));
+    }
+    let mut parts = path.trim().split('/');
+    if parts.next() != Some("m") {
+        return Err(CryptoError::ParseError(
+            "A path must start with \"m\"".to_string(),
+        ));
+    }
+    let indices = parts
+        .map(|p| {
+            p.parse::<u32>()
+                .map_err(|_| CryptoError::ParseError(format!("Invalid path index \"{}\"", p)))
+        })
+        .collect::<Result<Vec<u32>, CryptoError>>()?;
+    Ok(indices.into_iter().fold(hkdf_mod_r(seed), |parent, index| {
+        let mut compressed_lamport_pk = parent_sk_to_lamport_pk(&parent, index);
+        let sk = hkdf_mod_r(&compressed_lamport_pk);
+        compressed_lamport_pk.zeroize();
+        sk
+    }))
+}
+
+/// HKDF_mod_r with an empty key_info
+fn hkdf_mod_r(ikm: &[u8]) -> PrivateKey {
+    let mut salt = Sha256::digest(b"BLS-SIG-KEYGEN-SALT-");
+    let mut ikm = ikm.to_vec();
+    ikm.push(0); // IKM || I2OSP(0, 1)
+    let info = [0u8, PRIVATE_KEY_SIZE as u8]; // key_info || I2OSP(L, 2)
+    let mut okm = [0u8; PRIVATE_KEY_SIZE];
+    loop {
+        let h = hkdf::Hkdf::<Sha256>::new(Some(&salt[..]), &ikm);
+        h.expand(&info[..], &mut okm).unwrap();
+        let sk = PrivateKey::from(&okm);
+        if !sk.is_zero() {
+            ikm.zeroize();
+            okm.zeroize();
+            return sk;
+        }
+        salt = Sha256::digest(&salt[..]);
+    }
+}
+
+fn parent_sk_to_lamport_pk(parent: &PrivateKey, index: u32) -> Vec<u8> {
+    let salt = index.to_be_bytes();
+    // I2OSP(parent_SK, 32)
+    let mut ikm = parent.to_bytes()[PRIVATE_KEY_SIZE - DIGEST_SIZE..].to_vec();
+    let mut not_ikm = ikm.iter().map(|b| !*b).collect::<Vec<u8>>();
+
+    let mut lamport_pk = Sha256::new();
+    for i in [&ikm, &not_ikm].iter() {
+        let mut lamport_sk = ikm_to_lamport_sk(i.as_slice(), &salt[..]);
+        for chunk in lamport_sk.chunks(DIGEST_SIZE) {
+            lamport_pk.update(Sha256::digest(chunk));
+        }
+        lamport_sk.zeroize();
+    }
+    ikm.zeroize();
+    not_ikm.zeroize();
+    lamport_pk.finalize().to_vec()
+}
+
+fn ikm_to_lamport_sk(ikm: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut okm = vec![0u8; LAMPORT_CHUNKS * DIGEST_SIZE];
+    let h = hkdf::Hkdf::<Sha256>::new(Some(salt), ikm);
+    h.expand(&[], okm.as_mut_slice()).unwrap();
+    okm
+}
+
 pub mod prelude {
     pub use super::{
+        derive_path_sk,
         normal::*,
         small::{
             generate as small_generate, AggregatedPublicKey as SmallAggregatedPublicKey,
@@ -741,7 +830,7 @@ pub mod prelude {
             ProofOfPossession as SmallProofOfPossession, PublicKey as SmallPublicKey,
             Signature as SmallSignature, SignatureGroup as SmallSignatureGroup,
         },
-        PrivateKey,
+        PrivateKey, BLS_PURPOSE, MIN_SEED_SIZE,
     };
 }
 
@@ -807,11 +896,14 @@ fn set_pairs_g2_g1(t: &(G2, G1)) -> (&G1, &G2) {
 #[cfg(test)]
 mod tests {
     use super::normal::{
-        generate as normal_generate, Generator as NormalGenerator, Signature as NormalSignature,
+        generate as normal_generate, Generator as NormalGenerator, PublicKey as NormalPublicKey,
+        Signature as NormalSignature,
     };
     use super::small::{
-        generate as small_generate, Generator as SmallGenerator, Signature as SmallSignature,
+        generate as small_generate, Generator as SmallGenerator, PublicKey as SmallPublicKey,
+        Signature as SmallSignature,
     };
+    use super::{derive_path_sk, BLS_PURPOSE, MIN_SEED_SIZE};
     use amcl_wrapper::{
         constants::{GroupG1_SIZE, MODBYTES},
         field_elem::FieldElement,
@@ -836,4 +928,57 @@ mod tests {
         let sig = SmallSignature::new(msg.to_bytes().as_slice(), None, &sk);
         assert_eq!(sig.to_bytes().len(), GroupG1_SIZE);
     }
+
+    // https://eips.ethereum.org/EIPS/eip-2333#test-cases
+    // The keys are the big endian encoding of the decimal values in the EIP
+    #[test]
+    fn eip2333_vectors() {
+        let vectors = [
+            (
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+                "0d7359d57963ab8fbbde1852dcf553fedbc31f464d80ee7d40ae683122b45070",
+                0u32,
+                "2d18bd6c14e6d15bf8b5085c9b74f3daae3b03cc2014770a599d8c1539e50f8e",
+            ),
+            (
+                "3141592653589793238462643383279502884197169399375105820974944592",
+                "41c9e07822b092a93fd6797396338c3ada4170cc81829fdfce6b5d34bd5e7ec7",
+                3141592653,
+                "384843fad5f3d777ea39de3e47a8f999ae91f89e42bffa993d91d9782d152a0f",
+            ),
+            (
+                "0099ff991111002299dd7744ee3355bbdd8844115566cc55663355668888cc00",
+                "3cfa341ab3910a7d00d933d8f7c4fe87c91798a0397421d6b19fd5b815132e80",
+                4294967295,
+                "40e86285582f35b28821340f6a53b448588efa575bc4d88c32ef8567b8d9479b",
+            ),
+            (
+                "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+                "2a0e28ffa5fbbe2f8e7aad4ed94f745d6bf755c51182e119bb1694fe61d3afca",
+                42,
+                "455c0dc9fccb3395825d92a60d2672d69416be1c2578a87a7a3d3ced11ebb88d",
+            ),
+        ];
+        for (seed, master, index, child) in vectors.iter() {
+            let seed = hex::decode(seed).unwrap();
+            let master_sk = derive_path_sk(&seed, "m").unwrap();
+            assert_eq!(hex::encode(&master_sk.to_bytes()[MODBYTES - 32..]), *master);
+            let child_sk = derive_path_sk(&seed, &format!("m/{}", index)).unwrap();
+            assert_eq!(hex::encode(&child_sk.to_bytes()[MODBYTES - 32..]), *child);
+        }
+
+        // The derived keys sign in both groups
+        let seed = hex::decode(vectors[0].0).unwrap();
+        let sk = derive_path_sk(&seed, &format!("m/{}/3600/0/0", BLS_PURPOSE)).unwrap();
+        let g = NormalGenerator::generator();
+        let pk = NormalPublicKey::new(&sk, &g);
+        assert!(NormalSignature::new(b"message", None, &sk).verify(b"message", None, &pk, &g));
+        let g = SmallGenerator::generator();
+        let pk = SmallPublicKey::new(&sk, &g);
+        assert!(SmallSignature::new(b"message", None, &sk).verify(b"message", None, &pk, &g));
+
+        assert!(derive_path_sk(&seed, "12381/3600").is_err());
+        assert!(derive_path_sk(&seed, "m/12381/-1").is_err());
+        assert!(derive_path_sk(&[0u8; MIN_SEED_SIZE - 1], "m").is_err());
+    }
 }
@@ -165,6 +165,36 @@ macro_rules! check_useful_c_str {
     };
 }
 
+#[cfg(any(feature = "cl", feature = "cl_native"))]
+macro_rules! check_useful_c_str_array {
+    ($ptrs:ident, $ptrs_len:ident, $err1:expr, $err2:expr) => {
+        if $ptrs.is_null() {
+            set_current_error(&err_msg($err1.into(), "Invalid pointer has been passed"));
+            return $err1;
+        }
+
+        if $ptrs_len == 0 {
+            set_current_error(&err_msg(
+                $err2.into(),
+                "Array length must be greater than 0",
+            ));
+            return $err2;
+        }
+
+        let $ptrs = match unsafe { ::std::slice::from_raw_parts($ptrs, $ptrs_len) }
+            .iter()
+            .map(|ptr| c_str_to_string(*ptr))
+            .collect::<Result<Option<Vec<String>>, _>>()
+        {
+            Ok(Some(val)) => val,
+            _ => {
+                set_current_error(&err_msg($err1.into(), "Invalid pointer has been passed"));
+                return $err1;
+            }
+        };
+    };
+}
+
 macro_rules! check_useful_opt_c_str {
     ($x:ident, $e:expr) => {
         let $x = match c_str_to_string($x) {
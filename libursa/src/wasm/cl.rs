@@ -3,6 +3,7 @@ use wasm_bindgen::prelude::*;
 
 use std::collections::HashSet;
 
+use bn;
 use cl;
 use cl::RevocationTailsAccessor;
 
@@ -336,6 +337,28 @@ impl SubProofRequest {
     pub fn addPredicate(&mut self, attribute: &str, p_type: &str, value: i32) {
         self.0.add_predicate(attribute, p_type, value).unwrap();
     }
+
+    pub fn addPredicateDec(&mut self, attribute: &str, p_type: &str, value: &str) {
+        let value = bn::BigNumber::from_dec(value).unwrap();
+        self.0.add_predicate_bn(attribute, p_type, &value).unwrap();
+    }
+
+    pub fn addAttributePredicate(
+        &mut self,
+        attribute: &str,
+        p_type: &str,
+        other_sub_proof: Option<u32>,
+        other_attribute: &str,
+    ) {
+        self.0
+            .add_attribute_predicate(
+                attribute,
+                p_type,
+                other_sub_proof.map(|idx| idx as usize),
+                other_attribute,
+            )
+            .unwrap();
+    }
 }
 
 #[wasm_bindgen]
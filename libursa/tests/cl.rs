@@ -1,5 +1,7 @@
 #[cfg(feature = "serde")]
 extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 #[cfg(any(feature = "cl_native"))]
 extern crate ursa;
 
@@ -22,7 +24,9 @@ mod cl_tests {
 
     mod test {
         use super::*;
-        use ursa::cl::NonCredentialSchemaBuilder;
+        use serde_json;
+        use ursa::bn::BigNumber;
+        use ursa::cl::{NonCredentialSchemaBuilder, Proof};
         use ursa::errors::prelude::*;
 
         #[test]
@@ -490,56 +494,648 @@ mod cl_tests {
             )
             .unwrap();
 
-            // 8. Prover processes credential signature
+            // 8. Prover processes credential signature
+            Prover::process_credential_signature(
+                &mut credential_signature,
+                &credential_values,
+                &signature_correctness_proof,
+                &credential_secrets_blinding_factors,
+                &credential_pub_key,
+                &credential_issuance_nonce,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+            // 9. Verifier create sub proof request
+            let sub_proof_request = helpers::gvt_sub_proof_request();
+
+            // 10. Verifier creates nonce
+            let nonce = new_nonce().unwrap();
+
+            // 11. Prover creates proof
+            let mut proof_builder = Prover::new_proof_builder().unwrap();
+            proof_builder.add_common_attribute(LINK_SECRET).unwrap();
+            proof_builder
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_signature,
+                    &credential_values,
+                    &credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            let proof = proof_builder.finalize(&nonce).unwrap();
+
+            // 12. Verifier verifies proof
+            let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+            proof_verifier
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert!(proof_verifier.verify(&proof, &nonce).unwrap());
+        }
+
+        #[test]
+        fn anoncreds_works_for_set_predicates() {
+            HLCryptoDefaultLogger::init(None).ok();
+
+            // 1. Issuer creates credential schema
+            let credential_schema = helpers::gvt_credential_schema();
+            let non_credential_schema = helpers::non_credential_schema();
+
+            // 2. Issuer creates credential definition
+            let (credential_pub_key, credential_priv_key, credential_key_correctness_proof) =
+                Issuer::new_credential_def(&credential_schema, &non_credential_schema, false)
+                    .unwrap();
+
+            // 3. Issuer creates credential values
+            let credential_values =
+                helpers::gvt_credential_values(&Prover::new_master_secret().unwrap());
+
+            // 4. Issuer creates nonce used by Prover to create correctness proof for blinded secrets
+            let credential_nonce = new_nonce().unwrap();
+
+            // 5. Prover blinds hidden attributes
+            let (
+                blinded_credential_secrets,
+                credential_secrets_blinding_factors,
+                blinded_credential_secrets_correctness_proof,
+            ) = Prover::blind_credential_secrets(
+                &credential_pub_key,
+                &credential_key_correctness_proof,
+                &credential_values,
+                &credential_nonce,
+            )
+            .unwrap();
+
+            // 6. Prover creates nonce used by Issuer to create correctness proof for signature
+            let credential_issuance_nonce = new_nonce().unwrap();
+
+            // 7. Issuer signs credential values
+            let (mut credential_signature, signature_correctness_proof) = Issuer::sign_credential(
+                PROVER_ID,
+                &blinded_credential_secrets,
+                &blinded_credential_secrets_correctness_proof,
+                &credential_nonce,
+                &credential_issuance_nonce,
+                &credential_values,
+                &credential_pub_key,
+                &credential_priv_key,
+            )
+            .unwrap();
+
+            // 8. Prover processes credential signature
+            Prover::process_credential_signature(
+                &mut credential_signature,
+                &credential_values,
+                &signature_correctness_proof,
+                &credential_secrets_blinding_factors,
+                &credential_pub_key,
+                &credential_issuance_nonce,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+            // 9. Verifier create sub proof request with set predicates
+            let mut gvt_sub_proof_request_builder =
+                Verifier::new_sub_proof_request_builder().unwrap();
+            gvt_sub_proof_request_builder
+                .add_revealed_attr("name")
+                .unwrap();
+            gvt_sub_proof_request_builder
+                .add_set_predicate("height", "IN", &["160", "175", "190"])
+                .unwrap();
+            gvt_sub_proof_request_builder
+                .add_set_predicate("age", "NE", &["17", "65"])
+                .unwrap();
+            let sub_proof_request = gvt_sub_proof_request_builder.finalize().unwrap();
+
+            // 10. Verifier creates nonce
+            let nonce = new_nonce().unwrap();
+
+            // 11. Prover creates proof
+            let mut proof_builder = Prover::new_proof_builder().unwrap();
+            proof_builder.add_common_attribute(LINK_SECRET).unwrap();
+            proof_builder
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_signature,
+                    &credential_values,
+                    &credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            let proof = proof_builder.finalize(&nonce).unwrap();
+
+            // 12. Verifier verifies proof
+            let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+            proof_verifier
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert!(proof_verifier.verify(&proof, &nonce).unwrap());
+
+            // 13. Verifier verifies proof after serialization
+            let proof: Proof =
+                serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+            let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+            proof_verifier
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert!(proof_verifier.verify(&proof, &nonce).unwrap());
+
+            // 14. Verifier rejects proof for another set of values
+            let mut gvt_sub_proof_request_builder =
+                Verifier::new_sub_proof_request_builder().unwrap();
+            gvt_sub_proof_request_builder
+                .add_revealed_attr("name")
+                .unwrap();
+            gvt_sub_proof_request_builder
+                .add_set_predicate("height", "IN", &["175", "190"])
+                .unwrap();
+            gvt_sub_proof_request_builder
+                .add_set_predicate("age", "NE", &["17", "65"])
+                .unwrap();
+            let other_sub_proof_request = gvt_sub_proof_request_builder.finalize().unwrap();
+
+            let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+            proof_verifier
+                .add_sub_proof_request(
+                    &other_sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(
+                UrsaCryptoErrorKind::ProofRejected,
+                proof_verifier.verify(&proof, &nonce).unwrap_err().kind()
+            );
+        }
+
+        #[test]
+        fn anoncreds_works_for_i64_and_big_number_predicates() {
+            HLCryptoDefaultLogger::init(None).ok();
+
+            // 1. Issuer creates credential schema
+            let credential_schema = helpers::gvt_credential_schema();
+            let non_credential_schema = helpers::non_credential_schema();
+
+            // 2. Issuer creates credential definition
+            let (credential_pub_key, credential_priv_key, credential_key_correctness_proof) =
+                Issuer::new_credential_def(&credential_schema, &non_credential_schema, false)
+                    .unwrap();
+
+            // 3. Issuer creates credential values
+            let credential_values =
+                helpers::gvt_credential_values(&Prover::new_master_secret().unwrap());
+
+            // 4. Issuer creates nonce used by Prover to create correctness proof for blinded secrets
+            let credential_nonce = new_nonce().unwrap();
+
+            // 5. Prover blinds hidden attributes
+            let (
+                blinded_credential_secrets,
+                credential_secrets_blinding_factors,
+                blinded_credential_secrets_correctness_proof,
+            ) = Prover::blind_credential_secrets(
+                &credential_pub_key,
+                &credential_key_correctness_proof,
+                &credential_values,
+                &credential_nonce,
+            )
+            .unwrap();
+
+            // 6. Prover creates nonce used by Issuer to create correctness proof for signature
+            let credential_issuance_nonce = new_nonce().unwrap();
+
+            // 7. Issuer signs credential values
+            let (mut credential_signature, signature_correctness_proof) = Issuer::sign_credential(
+                PROVER_ID,
+                &blinded_credential_secrets,
+                &blinded_credential_secrets_correctness_proof,
+                &credential_nonce,
+                &credential_issuance_nonce,
+                &credential_values,
+                &credential_pub_key,
+                &credential_priv_key,
+            )
+            .unwrap();
+
+            // 8. Prover processes credential signature
+            Prover::process_credential_signature(
+                &mut credential_signature,
+                &credential_values,
+                &signature_correctness_proof,
+                &credential_secrets_blinding_factors,
+                &credential_pub_key,
+                &credential_issuance_nonce,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+            // 9. Verifier create sub proof request with predicates wider than i32
+            let mut gvt_sub_proof_request_builder =
+                Verifier::new_sub_proof_request_builder().unwrap();
+            gvt_sub_proof_request_builder
+                .add_revealed_attr("name")
+                .unwrap();
+            gvt_sub_proof_request_builder
+                .add_predicate_i64("age", "GT", -1_600_000_000_000)
+                .unwrap();
+            gvt_sub_proof_request_builder
+                .add_predicate_i64("age", "LT", 1_600_000_000_000)
+                .unwrap();
+            gvt_sub_proof_request_builder
+                .add_predicate_bn(
+                    "sex",
+                    "GE",
+                    &BigNumber::from_dec(
+                        "5944657099558967239210949258394887428692050081607692519917050011144233115000",
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+            gvt_sub_proof_request_builder
+                .add_predicate_bn(
+                    "sex",
+                    "LT",
+                    &BigNumber::from_dec("2")
+                        .unwrap()
+                        .exp(&BigNumber::from_u32(255).unwrap(), None)
+                        .unwrap(),
+                )
+                .unwrap();
+            let sub_proof_request = gvt_sub_proof_request_builder.finalize().unwrap();
+
+            // 10. Verifier creates nonce
+            let nonce = new_nonce().unwrap();
+
+            // 11. Prover creates proof
+            let mut proof_builder = Prover::new_proof_builder().unwrap();
+            proof_builder.add_common_attribute(LINK_SECRET).unwrap();
+            proof_builder
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_signature,
+                    &credential_values,
+                    &credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            let proof = proof_builder.finalize(&nonce).unwrap();
+
+            // 12. Verifier verifies proof after serialization
+            let proof: Proof =
+                serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+            let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+            proof_verifier
+                .add_sub_proof_request(
+                    &sub_proof_request,
+                    &credential_schema,
+                    &non_credential_schema,
+                    &credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert!(proof_verifier.verify(&proof, &nonce).unwrap());
+
+            // 13. Prover can't create proof for not satisfied i64 predicate
+            let mut gvt_sub_proof_request_builder =
+                Verifier::new_sub_proof_request_builder().unwrap();
+            gvt_sub_proof_request_builder
+                .add_predicate_i64("age", "GE", 1_600_000_000_000)
+                .unwrap();
+            let sub_proof_request = gvt_sub_proof_request_builder.finalize().unwrap();
+
+            let mut proof_builder = Prover::new_proof_builder().unwrap();
+            proof_builder.add_common_attribute(LINK_SECRET).unwrap();
+            let res = proof_builder.add_sub_proof_request(
+                &sub_proof_request,
+                &credential_schema,
+                &non_credential_schema,
+                &credential_signature,
+                &credential_values,
+                &credential_pub_key,
+                None,
+                None,
+            );
+            assert_eq!(
+                UrsaCryptoErrorKind::InvalidStructure,
+                res.unwrap_err().kind()
+            );
+        }
+
+        #[test]
+        fn anoncreds_works_for_attribute_predicates_across_credentials() {
+            HLCryptoDefaultLogger::init(None).ok();
+
+            // 1. Prover creates master secret
+            let master_secret = Prover::new_master_secret().unwrap();
+
+            let gvt_credential_values = helpers::gvt_credential_values(&master_secret);
+
+            // 2. Issuer creates and signs GVT credential for Prover
+            let gvt_credential_schema = helpers::gvt_credential_schema();
+            let non_credential_schema = helpers::non_credential_schema();
+            let (
+                gvt_credential_pub_key,
+                gvt_credential_priv_key,
+                gvt_credential_key_correctness_proof,
+            ) = Issuer::new_credential_def(&gvt_credential_schema, &non_credential_schema, false)
+                .unwrap();
+
+            let gvt_credential_nonce = new_nonce().unwrap();
+
+            let (
+                gvt_blinded_credential_secrets,
+                gvt_credential_secrets_blinding_factors,
+                gvt_blinded_credential_secrets_correctness_proof,
+            ) = Prover::blind_credential_secrets(
+                &gvt_credential_pub_key,
+                &gvt_credential_key_correctness_proof,
+                &gvt_credential_values,
+                &gvt_credential_nonce,
+            )
+            .unwrap();
+
+            let gvt_credential_issuance_nonce = new_nonce().unwrap();
+
+            let (mut gvt_credential_signature, gvt_signature_correctness_proof) =
+                Issuer::sign_credential(
+                    PROVER_ID,
+                    &gvt_blinded_credential_secrets,
+                    &gvt_blinded_credential_secrets_correctness_proof,
+                    &gvt_credential_nonce,
+                    &gvt_credential_issuance_nonce,
+                    &gvt_credential_values,
+                    &gvt_credential_pub_key,
+                    &gvt_credential_priv_key,
+                )
+                .unwrap();
+
+            // 3. Prover processes GVT credential
+            Prover::process_credential_signature(
+                &mut gvt_credential_signature,
+                &gvt_credential_values,
+                &gvt_signature_correctness_proof,
+                &gvt_credential_secrets_blinding_factors,
+                &gvt_credential_pub_key,
+                &gvt_credential_issuance_nonce,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+            // 4. Issuer creates and signs XYZ credential for Prover
+            let xyz_credential_schema = helpers::xyz_credential_schema();
+            let (
+                xyz_credential_pub_key,
+                xyz_credential_priv_key,
+                xyz_credential_key_correctness_proof,
+            ) = Issuer::new_credential_def(&xyz_credential_schema, &non_credential_schema, false)
+                .unwrap();
+
+            let xyz_credential_nonce = new_nonce().unwrap();
+            let xyz_credential_values = helpers::xyz_credential_values(&master_secret);
+
+            let (
+                xyz_blinded_credential_secrets,
+                xyz_credential_secrets_blinding_factors,
+                xyz_blinded_credential_secrets_correctness_proof,
+            ) = Prover::blind_credential_secrets(
+                &xyz_credential_pub_key,
+                &xyz_credential_key_correctness_proof,
+                &xyz_credential_values,
+                &xyz_credential_nonce,
+            )
+            .unwrap();
+
+            let xyz_credential_issuance_nonce = new_nonce().unwrap();
+
+            let (mut xyz_credential_signature, xyz_signature_correctness_proof) =
+                Issuer::sign_credential(
+                    PROVER_ID,
+                    &xyz_blinded_credential_secrets,
+                    &xyz_blinded_credential_secrets_correctness_proof,
+                    &xyz_credential_nonce,
+                    &xyz_credential_issuance_nonce,
+                    &xyz_credential_values,
+                    &xyz_credential_pub_key,
+                    &xyz_credential_priv_key,
+                )
+                .unwrap();
+
+            // 5. Prover processes XYZ credential
             Prover::process_credential_signature(
-                &mut credential_signature,
-                &credential_values,
-                &signature_correctness_proof,
-                &credential_secrets_blinding_factors,
-                &credential_pub_key,
-                &credential_issuance_nonce,
+                &mut xyz_credential_signature,
+                &xyz_credential_values,
+                &xyz_signature_correctness_proof,
+                &xyz_credential_secrets_blinding_factors,
+                &xyz_credential_pub_key,
+                &xyz_credential_issuance_nonce,
                 None,
                 None,
                 None,
             )
             .unwrap();
 
-            // 9. Verifier create sub proof request
-            let sub_proof_request = helpers::gvt_sub_proof_request();
+            // 6. Verifier creates sub proof requests comparing hidden attributes:
+            // GVT height >= GVT age and XYZ period <= GVT age
+            let mut gvt_sub_proof_request_builder =
+                Verifier::new_sub_proof_request_builder().unwrap();
+            gvt_sub_proof_request_builder
+                .add_revealed_attr("name")
+                .unwrap();
+            gvt_sub_proof_request_builder
+                .add_attribute_predicate("height", "GE", None, "age")
+                .unwrap();
+            let gvt_sub_proof_request = gvt_sub_proof_request_builder.finalize().unwrap();
 
-            // 10. Verifier creates nonce
+            let mut xyz_sub_proof_request_builder =
+                Verifier::new_sub_proof_request_builder().unwrap();
+            xyz_sub_proof_request_builder
+                .add_revealed_attr("status")
+                .unwrap();
+            xyz_sub_proof_request_builder
+                .add_attribute_predicate("period", "LE", Some(0), "age")
+                .unwrap();
+            let xyz_sub_proof_request = xyz_sub_proof_request_builder.finalize().unwrap();
+
+            // 7. Verifier creates nonce
             let nonce = new_nonce().unwrap();
 
-            // 11. Prover creates proof
+            // 8. Prover creates proof for GVT and XYZ sub proof requests
             let mut proof_builder = Prover::new_proof_builder().unwrap();
             proof_builder.add_common_attribute(LINK_SECRET).unwrap();
             proof_builder
                 .add_sub_proof_request(
-                    &sub_proof_request,
-                    &credential_schema,
+                    &gvt_sub_proof_request,
+                    &gvt_credential_schema,
                     &non_credential_schema,
-                    &credential_signature,
-                    &credential_values,
-                    &credential_pub_key,
+                    &gvt_credential_signature,
+                    &gvt_credential_values,
+                    &gvt_credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            proof_builder
+                .add_sub_proof_request(
+                    &xyz_sub_proof_request,
+                    &xyz_credential_schema,
+                    &non_credential_schema,
+                    &xyz_credential_signature,
+                    &xyz_credential_values,
+                    &xyz_credential_pub_key,
                     None,
                     None,
                 )
                 .unwrap();
             let proof = proof_builder.finalize(&nonce).unwrap();
 
-            // 12. Verifier verifies proof
+            // 9. Verifier verifies proof after serialization
+            let proof: Proof =
+                serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
             let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
             proof_verifier
                 .add_sub_proof_request(
-                    &sub_proof_request,
-                    &credential_schema,
+                    &gvt_sub_proof_request,
+                    &gvt_credential_schema,
                     &non_credential_schema,
-                    &credential_pub_key,
+                    &gvt_credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            proof_verifier
+                .add_sub_proof_request(
+                    &xyz_sub_proof_request,
+                    &xyz_credential_schema,
+                    &non_credential_schema,
+                    &xyz_credential_pub_key,
                     None,
                     None,
                 )
                 .unwrap();
             assert!(proof_verifier.verify(&proof, &nonce).unwrap());
+
+            // 10. Verifier rejects proof for a request without the cross credential predicate
+            let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+            proof_verifier
+                .add_sub_proof_request(
+                    &gvt_sub_proof_request,
+                    &gvt_credential_schema,
+                    &non_credential_schema,
+                    &gvt_credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            proof_verifier
+                .add_sub_proof_request(
+                    &helpers::xyz_sub_proof_request(),
+                    &xyz_credential_schema,
+                    &non_credential_schema,
+                    &xyz_credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(
+                UrsaCryptoErrorKind::ProofRejected,
+                proof_verifier.verify(&proof, &nonce).unwrap_err().kind()
+            );
+
+            // 11. Verifier can't refer to a sub proof that was not added before
+            let mut proof_verifier = Verifier::new_proof_verifier().unwrap();
+            let res = proof_verifier.add_sub_proof_request(
+                &xyz_sub_proof_request,
+                &xyz_credential_schema,
+                &non_credential_schema,
+                &xyz_credential_pub_key,
+                None,
+                None,
+            );
+            assert_eq!(
+                UrsaCryptoErrorKind::InvalidStructure,
+                res.unwrap_err().kind()
+            );
+
+            // 12. Prover can't create proof for not satisfied XYZ period >= GVT age
+            let mut xyz_sub_proof_request_builder =
+                Verifier::new_sub_proof_request_builder().unwrap();
+            xyz_sub_proof_request_builder
+                .add_attribute_predicate("period", "GE", Some(0), "age")
+                .unwrap();
+            let xyz_sub_proof_request = xyz_sub_proof_request_builder.finalize().unwrap();
+
+            let mut proof_builder = Prover::new_proof_builder().unwrap();
+            proof_builder.add_common_attribute(LINK_SECRET).unwrap();
+            proof_builder
+                .add_sub_proof_request(
+                    &gvt_sub_proof_request,
+                    &gvt_credential_schema,
+                    &non_credential_schema,
+                    &gvt_credential_signature,
+                    &gvt_credential_values,
+                    &gvt_credential_pub_key,
+                    None,
+                    None,
+                )
+                .unwrap();
+            let res = proof_builder.add_sub_proof_request(
+                &xyz_sub_proof_request,
+                &xyz_credential_schema,
+                &non_credential_schema,
+                &xyz_credential_signature,
+                &xyz_credential_values,
+                &xyz_credential_pub_key,
+                None,
+                None,
+            );
+            assert_eq!(
+                UrsaCryptoErrorKind::InvalidStructure,
+                res.unwrap_err().kind()
+            );
         }
 
         #[test]
@@ -5014,6 +5610,119 @@ mod cl_tests {
             );
         }
 
+        #[test]
+        fn proof_builder_add_sub_proof_works_for_credential_not_satisfied_requested_set_predicate()
+        {
+            HLCryptoDefaultLogger::init(None).ok();
+
+            // 1. Issuer creates credential schema
+            let credential_schema = helpers::gvt_credential_schema();
+            let non_credential_schema = helpers::non_credential_schema();
+
+            // 2. Issuer creates credential definition
+            let (credential_pub_key, credential_priv_key, credential_key_correctness_proof) =
+                Issuer::new_credential_def(&credential_schema, &non_credential_schema, false)
+                    .unwrap();
+
+            // 3. Issuer creates credential values
+            let credential_values =
+                helpers::gvt_credential_values(&Prover::new_master_secret().unwrap());
+
+            // 4. Issuer creates nonce used by Prover to create correctness proof for blinded secrets
+            let credential_nonce = new_nonce().unwrap();
+
+            // 5. Prover blinds hidden attributes
+            let (
+                blinded_credential_secrets,
+                credential_secrets_blinding_factors,
+                blinded_credential_secrets_correctness_proof,
+            ) = Prover::blind_credential_secrets(
+                &credential_pub_key,
+                &credential_key_correctness_proof,
+                &credential_values,
+                &credential_nonce,
+            )
+            .unwrap();
+
+            // 6. Prover creates nonce used by Issuer to create correctness proof for signature
+            let credential_issuance_nonce = new_nonce().unwrap();
+
+            // 7. Issuer signs credential values
+            let (mut credential_signature, signature_correctness_proof) = Issuer::sign_credential(
+                PROVER_ID,
+                &blinded_credential_secrets,
+                &blinded_credential_secrets_correctness_proof,
+                &credential_nonce,
+                &credential_issuance_nonce,
+                &credential_values,
+                &credential_pub_key,
+                &credential_priv_key,
+            )
+            .unwrap();
+
+            // 8. Prover processes credential signature
+            Prover::process_credential_signature(
+                &mut credential_signature,
+                &credential_values,
+                &signature_correctness_proof,
+                &credential_secrets_blinding_factors,
+                &credential_pub_key,
+                &credential_issuance_nonce,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+            // 9. Prover creates proof by credential value not in the requested set
+            let mut gvt_sub_proof_request_builder =
+                Verifier::new_sub_proof_request_builder().unwrap();
+            gvt_sub_proof_request_builder
+                .add_set_predicate("height", "IN", &["160", "190"])
+                .unwrap();
+            let sub_proof_request = gvt_sub_proof_request_builder.finalize().unwrap();
+
+            let mut proof_builder = Prover::new_proof_builder().unwrap();
+            let res = proof_builder.add_sub_proof_request(
+                &sub_proof_request,
+                &credential_schema,
+                &non_credential_schema,
+                &credential_signature,
+                &credential_values,
+                &credential_pub_key,
+                None,
+                None,
+            );
+            assert_eq!(
+                UrsaCryptoErrorKind::InvalidStructure,
+                res.unwrap_err().kind()
+            );
+
+            // 10. Prover creates proof by credential value equal to a value it must differ from
+            let mut gvt_sub_proof_request_builder =
+                Verifier::new_sub_proof_request_builder().unwrap();
+            gvt_sub_proof_request_builder
+                .add_set_predicate("age", "NE", &["17", "28"])
+                .unwrap();
+            let sub_proof_request = gvt_sub_proof_request_builder.finalize().unwrap();
+
+            let mut proof_builder = Prover::new_proof_builder().unwrap();
+            let res = proof_builder.add_sub_proof_request(
+                &sub_proof_request,
+                &credential_schema,
+                &non_credential_schema,
+                &credential_signature,
+                &credential_values,
+                &credential_pub_key,
+                None,
+                None,
+            );
+            assert_eq!(
+                UrsaCryptoErrorKind::InvalidStructure,
+                res.unwrap_err().kind()
+            );
+        }
+
         #[test]
         fn proof_verifier_add_sub_proof_request_works_for_credential_schema_not_satisfied_to_sub_proof_request(
         ) {
@@ -0,0 +1,180 @@
+#ifndef __bbs__included__
+#define __bbs__included__
+
+#include <stdint.h>
+
+typedef enum {
+    BBS_INVALID_HANDLE = -1000,
+    BBS_PANIC = -1,
+    BBS_SUCCESS = 0,
+    BBS_KEY_GEN_ERROR = 1,
+    BBS_MESSAGE_COUNT_MISMATCH = 2,
+    BBS_SIGNATURE_INCORRECT_SIZE = 3,
+    BBS_SIGNATURE_VALUE_INCORRECT_SIZE = 4,
+    BBS_MALFORMED_SIGNATURE = 5,
+    BBS_MALFORMED_SECRET_KEY = 6,
+    BBS_MALFORMED_PUBLIC_KEY = 7,
+    BBS_POKVC_ERROR = 8,
+    BBS_INVALID_NUMBER_OF_BYTES = 9,
+    BBS_INVALID_PROOF = 10,
+    BBS_BATCH_VERIFICATION_FAILED = 11,
+    BBS_GENERAL_ERROR = 12,
+} bbs_error_t;
+
+struct ByteArray {
+    uintptr_t length;
+    const uint8_t *data;
+};
+
+struct ByteBuffer {
+    int64_t len;
+    uint8_t *data;
+};
+
+struct ExternError {
+    bbs_error_t code;
+    char* message; /* note: nullable */
+};
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+extern void bbs_bytebuffer_free(struct ByteBuffer buffer);
+extern void bbs_string_free(char *s);
+
+extern int32_t bbs_blinding_factor_size(void);
+extern int32_t bbs_secret_key_size(void);
+extern int32_t bbs_short_public_key_size(void);
+extern int32_t bbs_signature_size(void);
+
+extern int32_t bbs_generate_key_pair(uint32_t message_count,
+                                     struct ByteBuffer* public_key,
+                                     struct ByteBuffer* secret_key,
+                                     struct ExternError* err);
+
+extern int32_t bbs_generate_short_key_pair(const struct ByteArray* const seed,
+                                           struct ByteBuffer* public_key,
+                                           struct ByteBuffer* secret_key,
+                                           struct ExternError* err);
+
+extern int32_t bbs_short_to_public_key(const struct ByteArray* const short_public_key,
+                                       uint32_t message_count,
+                                       struct ByteBuffer* public_key,
+                                       struct ExternError* err);
+
+extern uint64_t bbs_sign_context_init(struct ExternError* err);
+extern int32_t bbs_sign_context_set_secret_key(uint64_t handle,
+                                               const struct ByteArray* const value,
+                                               struct ExternError* err);
+extern int32_t bbs_sign_context_set_public_key(uint64_t handle,
+                                               const struct ByteArray* const value,
+                                               struct ExternError* err);
+extern int32_t bbs_sign_context_add_message_bytes(uint64_t handle,
+                                                  const struct ByteArray* const message,
+                                                  struct ExternError* err);
+extern int32_t bbs_sign_context_finish(uint64_t handle,
+                                       struct ByteBuffer* signature,
+                                       struct ExternError* err);
+extern void bbs_sign_context_free(uint64_t handle, struct ExternError* err);
+
+extern uint64_t bbs_verify_context_init(struct ExternError* err);
+extern int32_t bbs_verify_context_set_public_key(uint64_t handle,
+                                                 const struct ByteArray* const value,
+                                                 struct ExternError* err);
+extern int32_t bbs_verify_context_set_signature(uint64_t handle,
+                                                const struct ByteArray* const value,
+                                                struct ExternError* err);
+extern int32_t bbs_verify_context_add_message_bytes(uint64_t handle,
+                                                    const struct ByteArray* const message,
+                                                    struct ExternError* err);
+extern int32_t bbs_verify_context_finish(uint64_t handle, struct ExternError* err);
+extern void bbs_verify_context_free(uint64_t handle, struct ExternError* err);
+
+extern uint64_t bbs_blind_commitment_context_init(struct ExternError* err);
+extern int32_t bbs_blind_commitment_context_set_public_key(uint64_t handle,
+                                                           const struct ByteArray* const value,
+                                                           struct ExternError* err);
+extern int32_t bbs_blind_commitment_context_set_nonce_bytes(uint64_t handle,
+                                                            const struct ByteArray* const value,
+                                                            struct ExternError* err);
+extern int32_t bbs_blind_commitment_context_add_message_bytes(uint64_t handle,
+                                                              uint32_t index,
+                                                              const struct ByteArray* const message,
+                                                              struct ExternError* err);
+extern int32_t bbs_blind_commitment_context_finish(uint64_t handle,
+                                                   struct ByteBuffer* commitment,
+                                                   struct ByteBuffer* blinding_factor,
+                                                   struct ExternError* err);
+extern void bbs_blind_commitment_context_free(uint64_t handle, struct ExternError* err);
+
+extern uint64_t bbs_blind_sign_context_init(struct ExternError* err);
+extern int32_t bbs_blind_sign_context_set_secret_key(uint64_t handle,
+                                                     const struct ByteArray* const value,
+                                                     struct ExternError* err);
+extern int32_t bbs_blind_sign_context_set_public_key(uint64_t handle,
+                                                     const struct ByteArray* const value,
+                                                     struct ExternError* err);
+extern int32_t bbs_blind_sign_context_set_commitment(uint64_t handle,
+                                                     const struct ByteArray* const value,
+                                                     struct ExternError* err);
+extern int32_t bbs_blind_sign_context_set_nonce_bytes(uint64_t handle,
+                                                      const struct ByteArray* const value,
+                                                      struct ExternError* err);
+extern int32_t bbs_blind_sign_context_add_message_bytes(uint64_t handle,
+                                                        uint32_t index,
+                                                        const struct ByteArray* const message,
+                                                        struct ExternError* err);
+extern int32_t bbs_blind_sign_context_finish(uint64_t handle,
+                                             struct ByteBuffer* blind_signature,
+                                             struct ExternError* err);
+extern void bbs_blind_sign_context_free(uint64_t handle, struct ExternError* err);
+
+extern int32_t bbs_unblind_signature(const struct ByteArray* const blind_signature,
+                                     const struct ByteArray* const blinding_factor,
+                                     struct ByteBuffer* signature,
+                                     struct ExternError* err);
+
+extern uint64_t bbs_create_proof_context_init(struct ExternError* err);
+extern int32_t bbs_create_proof_context_set_public_key(uint64_t handle,
+                                                       const struct ByteArray* const value,
+                                                       struct ExternError* err);
+extern int32_t bbs_create_proof_context_set_signature(uint64_t handle,
+                                                      const struct ByteArray* const value,
+                                                      struct ExternError* err);
+extern int32_t bbs_create_proof_context_set_nonce_bytes(uint64_t handle,
+                                                        const struct ByteArray* const value,
+                                                        struct ExternError* err);
+extern int32_t bbs_create_proof_context_add_message_bytes(uint64_t handle,
+                                                          const struct ByteArray* const message,
+                                                          struct ExternError* err);
+extern int32_t bbs_create_proof_context_reveal(uint64_t handle,
+                                               uint32_t index,
+                                               struct ExternError* err);
+extern int32_t bbs_create_proof_context_finish(uint64_t handle,
+                                               struct ByteBuffer* proof,
+                                               struct ExternError* err);
+extern void bbs_create_proof_context_free(uint64_t handle, struct ExternError* err);
+
+extern uint64_t bbs_verify_proof_context_init(struct ExternError* err);
+extern int32_t bbs_verify_proof_context_set_public_key(uint64_t handle,
+                                                       const struct ByteArray* const value,
+                                                       struct ExternError* err);
+extern int32_t bbs_verify_proof_context_set_proof(uint64_t handle,
+                                                  const struct ByteArray* const value,
+                                                  struct ExternError* err);
+extern int32_t bbs_verify_proof_context_set_nonce_bytes(uint64_t handle,
+                                                        const struct ByteArray* const value,
+                                                        struct ExternError* err);
+extern int32_t bbs_verify_proof_context_add_message_bytes(uint64_t handle,
+                                                          uint32_t index,
+                                                          const struct ByteArray* const message,
+                                                          struct ExternError* err);
+extern int32_t bbs_verify_proof_context_finish(uint64_t handle, struct ExternError* err);
+extern void bbs_verify_proof_context_free(uint64_t handle, struct ExternError* err);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
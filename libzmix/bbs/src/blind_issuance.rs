@@ -0,0 +1,473 @@
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::messages::*;
+use crate::pok_vc::prelude::*;
+use crate::signature::prelude::*;
+use crate::{
+    Commitment, CommitmentBuilder, GeneratorG1, HashElem, ProofChallenge, ProofNonce,
+    SignatureBlinding, SignatureMessage, ToVariableLengthBytes, FR_COMPRESSED_SIZE,
+    G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
+};
+use pairing_plus::serdes::SerDes;
+use rand::{thread_rng, CryptoRng, RngCore};
+use serde::{
+    de::{Error as DError, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt::Formatter;
+use std::io::{Cursor, Read};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{ExtendedBlindSignatureContext, ExtendedBlindSignatureContextCommitting};
+}
+
+/// The holder's side of an `ExtendedBlindSignatureContext` before the challenge is known.
+/// Other proofs about the committed messages, like predicates, can be linked by
+/// committing them with `HiddenMessage::ExternalBlinding` and adding their bytes
+/// to the challenge.
+#[derive(Debug, Clone)]
+pub struct ExtendedBlindSignatureContextCommitting {
+    commitment: Commitment,
+    committed_messages: BTreeSet<usize>,
+    committed: ProverCommittedG1,
+    secrets: Vec<SignatureMessage>,
+}
+
+impl ExtendedBlindSignatureContextCommitting {
+    /// Commit to `messages` at their indices in `verkey` using `blinding_factor`
+    pub fn new(
+        verkey: &PublicKey,
+        messages: &BTreeMap<usize, HiddenMessage>,
+        blinding_factor: &SignatureBlinding,
+    ) -> Result<Self, BBSError> {
+        Self::new_with_rng(verkey, messages, blinding_factor, &mut thread_rng())
+    }
+
+    /// Same as `new` but the proof blinding factors are generated from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        verkey: &PublicKey,
+        messages: &BTreeMap<usize, HiddenMessage>,
+        blinding_factor: &SignatureBlinding,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        let mut builder = CommitmentBuilder::new();
+        let mut committing = ProverCommittingG1::new();
+        let mut secrets = Vec::with_capacity(messages.len() + 1);
+
+        // h0^blinding_factor*hi^mi.....
+        builder.add(verkey.h0, blinding_factor);
+        committing.commit_with_rng(verkey.h0, rng);
+        secrets.push(SignatureMessage(blinding_factor.0));
+        for (i, m) in messages {
+            if *i >= verkey.message_count() {
+                return Err(BBSErrorKind::PublicKeyGeneratorMessageCountMismatch(
+                    *i,
+                    verkey.message_count(),
+                )
+                .into());
+            }
+            let message = match m {
+                HiddenMessage::ProofSpecificBlinding(m) => {
+                    committing.commit_with_rng(verkey.h[*i], rng);
+                    *m
+                }
+                HiddenMessage::ExternalBlinding(m, b) => {
+                    committing.commit_with(verkey.h[*i], b);
+                    *m
+                }
+            };
+            builder.add(verkey.h[*i], message);
+            secrets.push(message);
+        }
+
+        Ok(Self {
+            commitment: builder.finalize(),
+            committed_messages: messages.keys().copied().collect(),
+            committed: committing.finish(),
+            secrets,
+        })
+    }
+
+    /// The bytes to include in the Fiat-Shamir challenge
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.committed.to_bytes();
+        append_statement(&mut bytes, &self.commitment, &self.committed_messages);
+        bytes
+    }
+
+    /// Compute the responses for `challenge` and create the context sent to the issuer
+    pub fn gen_proof(
+        self,
+        challenge: &ProofChallenge,
+    ) -> Result<ExtendedBlindSignatureContext, BBSError> {
+        let proof_of_hidden_messages = self
+            .committed
+            .gen_proof(challenge, self.secrets.as_slice())?;
+        Ok(ExtendedBlindSignatureContext {
+            commitment: self.commitment,
+            committed_messages: self.committed_messages,
+            challenge_hash: *challenge,
+            proof_of_hidden_messages,
+        })
+    }
+}
+
+/// Contains the data used for computing a blind signature like `BlindSignatureContext`
+/// but also carries the indices of the committed messages. The issuer can check the
+/// committed messages do not overlap the messages it signs and link other proofs to
+/// the committed messages with `get_resp_for_message`.
+#[derive(Debug, Clone, Default)]
+pub struct ExtendedBlindSignatureContext {
+    /// The blinded signature commitment
+    pub commitment: Commitment,
+    /// The indices of the committed messages
+    pub committed_messages: BTreeSet<usize>,
+    /// The challenge hash for the Fiat-Shamir heuristic
+    pub challenge_hash: ProofChallenge,
+    /// The proof for the hidden messages
+    pub proof_of_hidden_messages: ProofG1,
+}
+
+impl ExtendedBlindSignatureContext {
+    /// Create a context for `messages` where the challenge only includes `nonce`
+    pub fn new(
+        verkey: &PublicKey,
+        messages: &BTreeMap<usize, HiddenMessage>,
+        nonce: &ProofNonce,
+    ) -> Result<(Self, SignatureBlinding), BBSError> {
+        Self::new_with_rng(verkey, messages, nonce, &mut thread_rng())
+    }
+
+    /// Same as `new` but the signature blinding and proof blinding factors are generated from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        verkey: &PublicKey,
+        messages: &BTreeMap<usize, HiddenMessage>,
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<(Self, SignatureBlinding), BBSError> {
+        let blinding_factor = Signature::generate_blinding_with_rng(rng);
+        let committing = ExtendedBlindSignatureContextCommitting::new_with_rng(
+            verkey,
+            messages,
+            &blinding_factor,
+            rng,
+        )?;
+        let mut challenge_bytes = committing.to_bytes();
+        challenge_bytes.extend_from_slice(&nonce.to_bytes_uncompressed_form()[..]);
+        let challenge = ProofChallenge::hash(&challenge_bytes);
+        Ok((committing.gen_proof(&challenge)?, blinding_factor))
+    }
+
+    /// The bytes used by the holder for the Fiat-Shamir challenge.
+    /// Bytes from other proofs and the nonce are appended to compute the challenge.
+    pub fn get_bytes_for_challenge(&self, verkey: &PublicKey) -> Result<Vec<u8>, BBSError> {
+        let bases = self.bases(verkey)?;
+        let mut bytes = Vec::new();
+        for b in bases.iter() {
+            b.0.serialize(&mut bytes, false).unwrap();
+        }
+        self.proof_of_hidden_messages
+            .commitment
+            .serialize(&mut bytes, false)
+            .unwrap();
+        append_statement(&mut bytes, &self.commitment, &self.committed_messages);
+        Ok(bytes)
+    }
+
+    /// Verify the proof of committed messages where the challenge only includes `nonce`
+    pub fn verify(&self, verkey: &PublicKey, nonce: &ProofNonce) -> Result<bool, BBSError> {
+        let mut challenge_bytes = self.get_bytes_for_challenge(verkey)?;
+        challenge_bytes.extend_from_slice(&nonce.to_bytes_uncompressed_form()[..]);
+        self.verify_with_challenge(verkey, &ProofChallenge::hash(&challenge_bytes))
+    }
+
+    /// Verify the proof of committed messages against a challenge computed by
+    /// the caller from `get_bytes_for_challenge` and any other proofs
+    pub fn verify_with_challenge(
+        &self,
+        verkey: &PublicKey,
+        challenge: &ProofChallenge,
+    ) -> Result<bool, BBSError> {
+        if *challenge != self.challenge_hash {
+            return Ok(false);
+        }
+        let bases = self.bases(verkey)?;
+        Ok(self
+            .proof_of_hidden_messages
+            .verify(bases.as_slice(), &self.commitment, challenge)?)
+    }
+
+    /// Check that `messages` supplied by the issuer and the committed messages
+    /// are all the messages in `verkey` without overlapping
+    pub fn check_issuer_messages(
+        &self,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        verkey: &PublicKey,
+    ) -> Result<(), BBSError> {
+        for i in messages.keys() {
+            if self.committed_messages.contains(i) {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Message {} is committed by the holder", i),
+                }
+                .into());
+            }
+        }
+        if messages.len() + self.committed_messages.len() != verkey.message_count()
+            || messages.keys().any(|i| *i >= verkey.message_count())
+        {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "The issuer and committed messages do not match the public key".to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Get the response from the proof of committed messages for the message at `index`.
+    /// Used to link the committed message to other proofs.
+    pub fn get_resp_for_message(&self, index: usize) -> Result<SignatureMessage, BBSError> {
+        if !self.committed_messages.contains(&index) {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Message {} is not committed", index),
+            }
+            .into());
+        }
+        // The first response is for the signature blinding
+        let position = 1 + self.committed_messages.range(..index).count();
+        match self.proof_of_hidden_messages.responses.get(position) {
+            Some(r) => Ok(SignatureMessage(*r)),
+            None => Err(BBSErrorKind::InvalidNumberOfBytes(
+                self.committed_messages.len() + 1,
+                self.proof_of_hidden_messages.responses.len(),
+            )
+            .into()),
+        }
+    }
+
+    fn bases(&self, verkey: &PublicKey) -> Result<Vec<GeneratorG1>, BBSError> {
+        let mut bases = Vec::with_capacity(self.committed_messages.len() + 1);
+        bases.push(verkey.h0);
+        for i in &self.committed_messages {
+            if *i >= verkey.message_count() {
+                return Err(BBSErrorKind::PublicKeyGeneratorMessageCountMismatch(
+                    *i,
+                    verkey.message_count(),
+                )
+                .into());
+            }
+            bases.push(verkey.h[*i]);
+        }
+        Ok(bases)
+    }
+
+    fn to_bytes(&self, compressed: bool) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.commitment
+            .0
+            .serialize(&mut output, compressed)
+            .unwrap();
+        output.extend_from_slice(&(self.committed_messages.len() as u32).to_be_bytes()[..]);
+        for i in &self.committed_messages {
+            output.extend_from_slice(&(*i as u32).to_be_bytes()[..]);
+        }
+        self.challenge_hash
+            .0
+            .serialize(&mut output, compressed)
+            .unwrap();
+        output.append(&mut self.proof_of_hidden_messages.to_bytes(compressed));
+        output
+    }
+
+    fn from_bytes(data: &[u8], g1_size: usize, compressed: bool) -> Result<Self, BBSError> {
+        let min_size = g1_size * 2 + FR_COMPRESSED_SIZE + 8;
+        if data.len() < min_size {
+            return Err(BBSError::from(BBSErrorKind::InvalidNumberOfBytes(
+                min_size,
+                data.len(),
+            )));
+        }
+        let mut cursor = Cursor::new(data);
+
+        let commitment = Commitment(slice_to_elem!(&mut cursor, G1, compressed)?);
+
+        let mut length_bytes = [0u8; 4];
+        cursor.read_exact(&mut length_bytes)?;
+        let length = u32::from_be_bytes(length_bytes) as usize;
+        let size = length
+            .checked_mul(4)
+            .and_then(|s| s.checked_add(min_size))
+            .ok_or_else(|| {
+                BBSError::from(BBSErrorKind::GeneralError {
+                    msg: format!("Invalid number of committed messages {}", length),
+                })
+            })?;
+        if data.len() < size {
+            return Err(BBSError::from(BBSErrorKind::InvalidNumberOfBytes(
+                size,
+                data.len(),
+            )));
+        }
+        let mut committed_messages = BTreeSet::new();
+        for _ in 0..length {
+            cursor.read_exact(&mut length_bytes)?;
+            committed_messages.insert(u32::from_be_bytes(length_bytes) as usize);
+        }
+
+        let challenge_hash = ProofChallenge(slice_to_elem!(&mut cursor, Fr, compressed)?);
+
+        let end = cursor.position() as usize;
+        let proof_of_hidden_messages = ProofG1::from_bytes(&data[end..], g1_size, compressed)?;
+        Ok(Self {
+            commitment,
+            committed_messages,
+            challenge_hash,
+            proof_of_hidden_messages,
+        })
+    }
+}
+
+impl ToVariableLengthBytes for ExtendedBlindSignatureContext {
+    type Output = Self;
+    type Error = BBSError;
+
+    fn to_bytes_compressed_form(&self) -> Vec<u8> {
+        self.to_bytes(true)
+    }
+
+    fn from_bytes_compressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self::Output, Self::Error> {
+        Self::from_bytes(data.as_ref(), G1_COMPRESSED_SIZE, true)
+    }
+
+    fn to_bytes_uncompressed_form(&self) -> Vec<u8> {
+        self.to_bytes(false)
+    }
+
+    fn from_bytes_uncompressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self::Output, Self::Error> {
+        Self::from_bytes(data.as_ref(), G1_UNCOMPRESSED_SIZE, false)
+    }
+}
+
+try_from_impl!(ExtendedBlindSignatureContext, BBSError);
+serdes_impl!(ExtendedBlindSignatureContext);
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(ExtendedBlindSignatureContext);
+
+/// The commitment and committed indices are part of the statement
+fn append_statement(bytes: &mut Vec<u8>, commitment: &Commitment, indices: &BTreeSet<usize>) {
+    commitment.0.serialize(bytes, false).unwrap();
+    bytes.extend_from_slice(&(indices.len() as u32).to_be_bytes()[..]);
+    for i in indices {
+        bytes.extend_from_slice(&(*i as u32).to_be_bytes()[..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn extended_blind_signature_round_trip() {
+        let (pk, sk) = Issuer::new_keys(4).unwrap();
+        let nonce = Issuer::generate_signing_nonce();
+        let link_secret = Prover::new_link_secret();
+        let mut committed = BTreeMap::new();
+        committed.insert(2, HiddenMessage::ProofSpecificBlinding(link_secret));
+        let (ctx, blinding) =
+            Prover::new_extended_blind_signature_context(&pk, &committed, &nonce).unwrap();
+
+        let bytes = ctx.to_bytes_compressed_form();
+        let ctx = ExtendedBlindSignatureContext::from_bytes_compressed_form(&bytes).unwrap();
+        assert_eq!(
+            ctx.committed_messages
+                .iter()
+                .copied()
+                .collect::<Vec<usize>>(),
+            vec![2]
+        );
+
+        let messages = sm_map![
+            0 => b"message_0",
+            1 => b"message_1",
+            3 => b"message_3"
+        ];
+        let blind_signature =
+            Issuer::extended_blind_sign(&ctx, &messages, &sk, &pk, &nonce).unwrap();
+        let msgs = vec![messages[&0], messages[&1], link_secret, messages[&3]];
+        let res = Prover::complete_signature(&pk, msgs.as_slice(), &blind_signature, &blinding);
+        assert!(res.is_ok());
+
+        // The response for the committed message links it to other proofs
+        assert!(ctx.get_resp_for_message(2).is_ok());
+        assert!(ctx.get_resp_for_message(1).is_err());
+    }
+
+    #[test]
+    fn extended_blind_signature_context_malformed_bytes() {
+        let (pk, _) = Issuer::new_keys(4).unwrap();
+        let nonce = Issuer::generate_signing_nonce();
+        let mut committed = BTreeMap::new();
+        committed.insert(
+            2,
+            HiddenMessage::ProofSpecificBlinding(Prover::new_link_secret()),
+        );
+        let (ctx, _) =
+            Prover::new_extended_blind_signature_context(&pk, &committed, &nonce).unwrap();
+        let bytes = ctx.to_bytes_compressed_form();
+
+        for i in 0..bytes.len() {
+            assert!(
+                ExtendedBlindSignatureContext::from_bytes_compressed_form(&bytes[..i]).is_err()
+            );
+        }
+        // The number of committed messages is larger than the data
+        let mut bytes = bytes;
+        bytes[G1_COMPRESSED_SIZE..G1_COMPRESSED_SIZE + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(ExtendedBlindSignatureContext::from_bytes_compressed_form(&bytes).is_err());
+    }
+
+    #[test]
+    fn extended_blind_signature_rejects_bad_messages() {
+        let (pk, sk) = Issuer::new_keys(3).unwrap();
+        let nonce = Issuer::generate_signing_nonce();
+        let mut committed = BTreeMap::new();
+        committed.insert(
+            0,
+            HiddenMessage::ProofSpecificBlinding(Prover::new_link_secret()),
+        );
+        let (ctx, _) =
+            Prover::new_extended_blind_signature_context(&pk, &committed, &nonce).unwrap();
+
+        // Overwrites the committed message
+        let messages = sm_map![
+            0 => b"message_0",
+            1 => b"message_1",
+            2 => b"message_2"
+        ];
+        assert!(Issuer::extended_blind_sign(&ctx, &messages, &sk, &pk, &nonce).is_err());
+
+        // Leaves a message unsigned
+        let messages = sm_map![1 => b"message_1"];
+        assert!(Issuer::extended_blind_sign(&ctx, &messages, &sk, &pk, &nonce).is_err());
+
+        // Wrong nonce
+        let messages = sm_map![
+            1 => b"message_1",
+            2 => b"message_2"
+        ];
+        let other_nonce = Issuer::generate_signing_nonce();
+        assert!(Issuer::extended_blind_sign(&ctx, &messages, &sk, &pk, &other_nonce).is_err());
+        assert!(Issuer::extended_blind_sign(&ctx, &messages, &sk, &pk, &nonce).is_ok());
+
+        // The committed indices are part of the proof
+        let mut ctx = ctx;
+        ctx.committed_messages = [1].iter().copied().collect();
+        assert!(!ctx.verify(&pk, &nonce).unwrap());
+    }
+}
@@ -0,0 +1,559 @@
+//! Encodings of signatures and proofs used as the `proofValue` of
+//! W3C verifiable credential data integrity proofs.
+//!
+//! `BbsBlsSignature2020` and `BbsBlsSignatureProof2020` use the BBS+ signatures
+//! and proofs in this crate and encode them with standard base64.
+//!
+//! The `bbs-2023` cryptosuite uses the ciphersuite signatures and proofs in `ietf`.
+//! Its proof values are a 3 byte header followed by a CBOR array, all
+//! encoded as multibase base64url without padding.
+
+use crate::errors::prelude::*;
+use crate::ietf::{proof::Proof, signature::Signature as CiphersuiteSignature};
+use crate::keys::DeterministicPublicKey;
+use crate::pok_sig::PoKOfSignatureProof;
+use crate::signature::Signature;
+use crate::ToVariableLengthBytes;
+use multibase::Base;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{
+        Bbs2023BaseProof, Bbs2023DerivedProof, BbsBlsSignature2020, BbsBlsSignatureProof2020,
+        BBS_2023_BASE_PROOF_HEADER, BBS_2023_DERIVED_PROOF_HEADER,
+    };
+}
+
+/// The header bytes of a `bbs-2023` base proof value
+pub const BBS_2023_BASE_PROOF_HEADER: [u8; 3] = [0xd9, 0x5d, 0x02];
+/// The header bytes of a `bbs-2023` derived proof value
+pub const BBS_2023_DERIVED_PROOF_HEADER: [u8; 3] = [0xd9, 0x5d, 0x03];
+
+/// The `proofValue` of a `BbsBlsSignature2020` proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BbsBlsSignature2020 {
+    /// The signature over the canonical statements
+    pub signature: Signature,
+}
+
+impl BbsBlsSignature2020 {
+    /// Encode as base64 of the compressed signature
+    pub fn to_proof_value(&self) -> String {
+        Base::Base64Pad.encode(&self.signature.to_bytes_compressed_form()[..])
+    }
+
+    /// Decode from base64 of the compressed signature
+    pub fn from_proof_value<I: AsRef<str>>(value: I) -> Result<Self, BBSError> {
+        let bytes = decode_base(Base::Base64Pad, value)?;
+        let signature = Signature::try_from(bytes.as_slice())?;
+        Ok(Self { signature })
+    }
+}
+
+/// The `proofValue` of a `BbsBlsSignatureProof2020` proof.
+/// The revealed statements are recorded as a bitvector ahead of the proof.
+#[derive(Debug, Clone)]
+pub struct BbsBlsSignatureProof2020 {
+    /// The number of messages in the signature
+    pub message_count: usize,
+    /// The indices of the revealed messages
+    pub revealed_messages: BTreeSet<usize>,
+    /// The signature proof of knowledge
+    pub proof: PoKOfSignatureProof,
+}
+
+impl BbsBlsSignatureProof2020 {
+    /// Convert to bytes as
+    /// message_count (2 bytes big endian) || revealed bitvector || compressed proof
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = (self.message_count as u16).to_be_bytes().to_vec();
+        out.append(&mut revealed_to_bitvector(
+            self.message_count,
+            &self.revealed_messages,
+        ));
+        out.append(&mut self.proof.to_bytes_compressed_form());
+        out
+    }
+
+    /// Convert from the output of `to_bytes`
+    pub fn from_bytes<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        let data = data.as_ref();
+        if data.len() < 2 {
+            return Err(malformed("BbsBlsSignatureProof2020 is too short"));
+        }
+        let message_count = u16::from_be_bytes(*array_ref![data, 0, 2]) as usize;
+        let bitvector_length = message_count / 8 + 1;
+        if data.len() < 2 + bitvector_length {
+            return Err(malformed("BbsBlsSignatureProof2020 is too short"));
+        }
+        let revealed_messages = bitvector_to_revealed(&data[2..2 + bitvector_length]);
+        if revealed_messages.iter().any(|i| *i >= message_count) {
+            return Err(malformed("Revealed index is out of range"));
+        }
+        let proof = PoKOfSignatureProof::from_bytes_compressed_form(&data[2 + bitvector_length..])?;
+        Ok(Self {
+            message_count,
+            revealed_messages,
+            proof,
+        })
+    }
+
+    /// Encode as base64 of `to_bytes`
+    pub fn to_proof_value(&self) -> String {
+        Base::Base64Pad.encode(self.to_bytes())
+    }
+
+    /// Decode from base64 of `to_bytes`
+    pub fn from_proof_value<I: AsRef<str>>(value: I) -> Result<Self, BBSError> {
+        Self::from_bytes(decode_base(Base::Base64Pad, value)?)
+    }
+}
+
+/// The `proofValue` of a `bbs-2023` base proof created by the issuer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bbs2023BaseProof {
+    /// The ciphersuite signature
+    pub signature: CiphersuiteSignature,
+    /// The header that was signed i.e. the hash of the proof options and mandatory statements
+    pub header: Vec<u8>,
+    /// The issuer public key
+    pub public_key: DeterministicPublicKey,
+    /// The key used to randomize blank node labels
+    pub hmac_key: Vec<u8>,
+    /// The JSON pointers to the statements that must always be disclosed
+    pub mandatory_pointers: Vec<String>,
+}
+
+impl Bbs2023BaseProof {
+    /// Convert to the header followed by the CBOR array
+    /// [signature, header, public_key, hmac_key, mandatory_pointers]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let value = Cbor::Array(vec![
+            Cbor::Bytes(self.signature.to_bytes_compressed_form().to_vec()),
+            Cbor::Bytes(self.header.clone()),
+            Cbor::Bytes(self.public_key.to_bytes_compressed_form().to_vec()),
+            Cbor::Bytes(self.hmac_key.clone()),
+            Cbor::Array(
+                self.mandatory_pointers
+                    .iter()
+                    .map(|p| Cbor::Text(p.clone()))
+                    .collect(),
+            ),
+        ]);
+        let mut out = BBS_2023_BASE_PROOF_HEADER.to_vec();
+        value.encode(&mut out);
+        out
+    }
+
+    /// Convert from the output of `to_bytes`
+    pub fn from_bytes<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        let mut items = decode_with_header(data.as_ref(), BBS_2023_BASE_PROOF_HEADER, 5)?;
+        let mandatory_pointers = items
+            .pop()
+            .unwrap()
+            .into_array()?
+            .into_iter()
+            .map(Cbor::into_text)
+            .collect::<Result<Vec<String>, BBSError>>()?;
+        let hmac_key = items.pop().unwrap().into_bytes()?;
+        let public_key =
+            DeterministicPublicKey::try_from(items.pop().unwrap().into_bytes()?.as_slice())?;
+        let header = items.pop().unwrap().into_bytes()?;
+        let signature =
+            CiphersuiteSignature::try_from(items.pop().unwrap().into_bytes()?.as_slice())?;
+        Ok(Self {
+            signature,
+            header,
+            public_key,
+            hmac_key,
+            mandatory_pointers,
+        })
+    }
+
+    /// Encode as multibase base64url without padding
+    pub fn to_proof_value(&self) -> String {
+        multibase::encode(Base::Base64Url, self.to_bytes())
+    }
+
+    /// Decode the output of `to_proof_value`
+    pub fn from_proof_value<I: AsRef<str>>(value: I) -> Result<Self, BBSError> {
+        Self::from_bytes(decode_multibase(Base::Base64Url, value)?)
+    }
+}
+
+/// The `proofValue` of a `bbs-2023` derived proof created by the holder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bbs2023DerivedProof {
+    /// The ciphersuite proof
+    pub proof: Proof,
+    /// Maps the canonical blank node label indices to the randomized label indices
+    pub label_map: BTreeMap<usize, usize>,
+    /// The indices of the mandatory statements among the disclosed statements
+    pub mandatory_indexes: Vec<usize>,
+    /// The indices of the selectively disclosed statements among the non-mandatory statements
+    pub selective_indexes: Vec<usize>,
+    /// The presentation header bound to the proof
+    pub presentation_header: Vec<u8>,
+}
+
+impl Bbs2023DerivedProof {
+    /// Convert to the header followed by the CBOR array
+    /// [proof, label_map, mandatory_indexes, selective_indexes, presentation_header]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let indexes = |v: &[usize]| Cbor::Array(v.iter().map(|i| Cbor::Uint(*i as u64)).collect());
+        let value = Cbor::Array(vec![
+            Cbor::Bytes(self.proof.to_bytes_compressed_form()),
+            Cbor::Map(
+                self.label_map
+                    .iter()
+                    .map(|(k, v)| (Cbor::Uint(*k as u64), Cbor::Uint(*v as u64)))
+                    .collect(),
+            ),
+            indexes(&self.mandatory_indexes),
+            indexes(&self.selective_indexes),
+            Cbor::Bytes(self.presentation_header.clone()),
+        ]);
+        let mut out = BBS_2023_DERIVED_PROOF_HEADER.to_vec();
+        value.encode(&mut out);
+        out
+    }
+
+    /// Convert from the output of `to_bytes`
+    pub fn from_bytes<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        let indexes = |c: Cbor| -> Result<Vec<usize>, BBSError> {
+            c.into_array()?.into_iter().map(Cbor::into_usize).collect()
+        };
+        let mut items = decode_with_header(data.as_ref(), BBS_2023_DERIVED_PROOF_HEADER, 5)?;
+        let presentation_header = items.pop().unwrap().into_bytes()?;
+        let selective_indexes = indexes(items.pop().unwrap())?;
+        let mandatory_indexes = indexes(items.pop().unwrap())?;
+        let label_map = match items.pop().unwrap() {
+            Cbor::Map(entries) => entries
+                .into_iter()
+                .map(|(k, v)| Ok((k.into_usize()?, v.into_usize()?)))
+                .collect::<Result<BTreeMap<usize, usize>, BBSError>>()?,
+            _ => return Err(malformed("Expected a CBOR map")),
+        };
+        let proof = Proof::from_bytes_compressed_form(items.pop().unwrap().into_bytes()?)?;
+        Ok(Self {
+            proof,
+            label_map,
+            mandatory_indexes,
+            selective_indexes,
+            presentation_header,
+        })
+    }
+
+    /// Encode as multibase base64url without padding
+    pub fn to_proof_value(&self) -> String {
+        multibase::encode(Base::Base64Url, self.to_bytes())
+    }
+
+    /// Decode the output of `to_proof_value`
+    pub fn from_proof_value<I: AsRef<str>>(value: I) -> Result<Self, BBSError> {
+        Self::from_bytes(decode_multibase(Base::Base64Url, value)?)
+    }
+}
+
+fn malformed(msg: &str) -> BBSError {
+    BBSErrorKind::GeneralError {
+        msg: msg.to_string(),
+    }
+    .into()
+}
+
+fn decode_base<I: AsRef<str>>(base: Base, value: I) -> Result<Vec<u8>, BBSError> {
+    base.decode(value)
+        .map_err(|e| malformed(&format!("Invalid {:?}: {}", base, e)))
+}
+
+fn decode_multibase<I: AsRef<str>>(base: Base, value: I) -> Result<Vec<u8>, BBSError> {
+    let value = value.as_ref();
+    match value.chars().next() {
+        Some(c) if c == base.code() => decode_base(base, &value[c.len_utf8()..]),
+        _ => Err(malformed(&format!("Expected multibase {:?}", base))),
+    }
+}
+
+fn decode_with_header(data: &[u8], header: [u8; 3], len: usize) -> Result<Vec<Cbor>, BBSError> {
+    if data.len() < header.len() || data[..header.len()] != header {
+        return Err(malformed("Invalid proof value header"));
+    }
+    let mut cursor = &data[header.len()..];
+    let items = Cbor::decode(&mut cursor, 0)?.into_array()?;
+    if !cursor.is_empty() || items.len() != len {
+        return Err(malformed("Invalid proof value components"));
+    }
+    Ok(items)
+}
+
+/// Bit `i % 8` of byte `i / 8` is set for each revealed index
+/// then the bytes are stored in reverse order
+fn revealed_to_bitvector(total: usize, revealed: &BTreeSet<usize>) -> Vec<u8> {
+    let mut bytes = vec![0u8; total / 8 + 1];
+    for r in revealed {
+        bytes[r / 8] |= 1u8 << (r % 8);
+    }
+    bytes.reverse();
+    bytes
+}
+
+fn bitvector_to_revealed(data: &[u8]) -> BTreeSet<usize> {
+    let mut revealed = BTreeSet::new();
+    for (i, byte) in data.iter().rev().enumerate() {
+        for bit in 0..8 {
+            if byte & (1u8 << bit) != 0 {
+                revealed.insert(i * 8 + bit);
+            }
+        }
+    }
+    revealed
+}
+
+/// The maximum nesting allowed when decoding
+const CBOR_MAX_DEPTH: usize = 4;
+
+/// The subset of CBOR needed by the proof values
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Cbor {
+    Uint(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Cbor>),
+    Map(Vec<(Cbor, Cbor)>),
+}
+
+impl Cbor {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Cbor::Uint(v) => encode_head(0, *v, out),
+            Cbor::Bytes(b) => {
+                encode_head(2, b.len() as u64, out);
+                out.extend_from_slice(b);
+            }
+            Cbor::Text(t) => {
+                encode_head(3, t.len() as u64, out);
+                out.extend_from_slice(t.as_bytes());
+            }
+            Cbor::Array(items) => {
+                encode_head(4, items.len() as u64, out);
+                for i in items {
+                    i.encode(out);
+                }
+            }
+            Cbor::Map(entries) => {
+                encode_head(5, entries.len() as u64, out);
+                for (k, v) in entries {
+                    k.encode(out);
+                    v.encode(out);
+                }
+            }
+        }
+    }
+
+    fn decode(data: &mut &[u8], depth: usize) -> Result<Self, BBSError> {
+        if depth > CBOR_MAX_DEPTH {
+            return Err(malformed("CBOR is nested too deeply"));
+        }
+        let (major, value) = decode_head(data)?;
+        match major {
+            0 => Ok(Cbor::Uint(value)),
+            2 => Ok(Cbor::Bytes(take(data, value)?.to_vec())),
+            3 => String::from_utf8(take(data, value)?.to_vec())
+                .map(Cbor::Text)
+                .map_err(|_| malformed("Invalid CBOR text string")),
+            4 => {
+                let mut items = Vec::new();
+                for _ in 0..value {
+                    items.push(Self::decode(data, depth + 1)?);
+                }
+                Ok(Cbor::Array(items))
+            }
+            5 => {
+                let mut entries = Vec::new();
+                for _ in 0..value {
+                    let k = Self::decode(data, depth + 1)?;
+                    let v = Self::decode(data, depth + 1)?;
+                    entries.push((k, v));
+                }
+                Ok(Cbor::Map(entries))
+            }
+            _ => Err(malformed("Unsupported CBOR type")),
+        }
+    }
+
+    fn into_array(self) -> Result<Vec<Cbor>, BBSError> {
+        match self {
+            Cbor::Array(items) => Ok(items),
+            _ => Err(malformed("Expected a CBOR array")),
+        }
+    }
+
+    fn into_bytes(self) -> Result<Vec<u8>, BBSError> {
+        match self {
+            Cbor::Bytes(b) => Ok(b),
+            _ => Err(malformed("Expected a CBOR byte string")),
+        }
+    }
+
+    fn into_text(self) -> Result<String, BBSError> {
+        match self {
+            Cbor::Text(t) => Ok(t),
+            _ => Err(malformed("Expected a CBOR text string")),
+        }
+    }
+
+    fn into_usize(self) -> Result<usize, BBSError> {
+        match self {
+            Cbor::Uint(v) if v <= usize::MAX as u64 => Ok(v as usize),
+            _ => Err(malformed("Expected a CBOR unsigned integer")),
+        }
+    }
+}
+
+/// Write the major type and argument using the shortest form
+fn encode_head(major: u8, value: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    if value < 24 {
+        out.push(major | value as u8);
+    } else if value <= u64::from(u8::MAX) {
+        out.push(major | 24);
+        out.push(value as u8);
+    } else if value <= u64::from(u16::MAX) {
+        out.push(major | 25);
+        out.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= u64::from(u32::MAX) {
+        out.push(major | 26);
+        out.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn decode_head(data: &mut &[u8]) -> Result<(u8, u64), BBSError> {
+    let initial = take(data, 1)?[0];
+    let value = match initial & 0x1f {
+        v if v < 24 => u64::from(v),
+        24 => u64::from(take(data, 1)?[0]),
+        25 => u64::from(u16::from_be_bytes(*array_ref![take(data, 2)?, 0, 2])),
+        26 => u64::from(u32::from_be_bytes(*array_ref![take(data, 4)?, 0, 4])),
+        27 => u64::from_be_bytes(*array_ref![take(data, 8)?, 0, 8]),
+        _ => return Err(malformed("Indefinite length CBOR is not supported")),
+    };
+    Ok((initial >> 5, value))
+}
+
+fn take<'a>(data: &mut &'a [u8], len: u64) -> Result<&'a [u8], BBSError> {
+    if len > data.len() as u64 {
+        return Err(malformed("Unexpected end of CBOR"));
+    }
+    let (head, rest) = data.split_at(len as usize);
+    *data = rest;
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ietf::{keygen, messages_to_scalars, sk_to_pk, Ciphersuite};
+    use crate::prelude::*;
+
+    #[test]
+    fn bbs_bls_2020_round_trip() {
+        let (pk, sk) = Issuer::new_keys(5).unwrap();
+        let messages = (0..5)
+            .map(|_| SignatureMessage::random())
+            .collect::<Vec<SignatureMessage>>();
+        let signature = Signature::new(&messages, &sk, &pk).unwrap();
+        let sig_value = BbsBlsSignature2020 { signature }.to_proof_value();
+        let sig_2020 = BbsBlsSignature2020::from_proof_value(&sig_value).unwrap();
+        assert!(sig_2020.signature.verify(&messages, &pk).unwrap());
+
+        let revealed = [0usize, 3].iter().copied().collect::<BTreeSet<usize>>();
+        let proof_messages = messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                if revealed.contains(&i) {
+                    pm_revealed_raw!(*m)
+                } else {
+                    pm_hidden_raw!(*m)
+                }
+            })
+            .collect::<Vec<ProofMessage>>();
+        let pok = PoKOfSignature::init(&sig_2020.signature, &pk, &proof_messages).unwrap();
+        let challenge = ProofChallenge::hash(pok.to_bytes());
+        let proof = BbsBlsSignatureProof2020 {
+            message_count: 5,
+            revealed_messages: revealed.clone(),
+            proof: pok.gen_proof(&challenge).unwrap(),
+        };
+        let bytes = proof.to_bytes();
+        assert_eq!(&bytes[..3], &[0u8, 5, 0b0000_1001][..]);
+
+        let proof_2020 =
+            BbsBlsSignatureProof2020::from_proof_value(proof.to_proof_value()).unwrap();
+        assert_eq!(proof_2020.message_count, 5);
+        assert_eq!(proof_2020.revealed_messages, revealed);
+        let revealed_messages = revealed
+            .iter()
+            .map(|i| (*i, messages[*i]))
+            .collect::<BTreeMap<usize, SignatureMessage>>();
+        assert!(proof_2020
+            .proof
+            .verify(&pk, &revealed_messages, &challenge)
+            .unwrap()
+            .is_valid());
+    }
+
+    #[test]
+    fn bbs_2023_round_trip() {
+        let suite = Ciphersuite::Bls12381Sha256;
+        let sk = keygen(suite, &[7u8; 32], b"data integrity tests", None).unwrap();
+        let pk = sk_to_pk(&sk);
+        let header = vec![1u8; 64];
+        let messages = messages_to_scalars(suite, &[&b"_:c14n0 a"[..], b"_:c14n1 b", b"c"]);
+        let signature = CiphersuiteSignature::new(suite, &sk, &pk, &header, &messages).unwrap();
+
+        let base = Bbs2023BaseProof {
+            signature,
+            header: header.clone(),
+            public_key: pk,
+            hmac_key: vec![2u8; 32],
+            mandatory_pointers: vec!["/issuer".to_string(), "/credentialSubject/id".to_string()],
+        };
+        let value = base.to_proof_value();
+        assert!(value.starts_with("u2V0C"));
+        assert_eq!(Bbs2023BaseProof::from_proof_value(&value).unwrap(), base);
+        assert!(Bbs2023DerivedProof::from_proof_value(&value).is_err());
+
+        let disclosed = [0usize, 2].iter().copied().collect::<BTreeSet<usize>>();
+        let proof = Proof::new(
+            suite,
+            &pk,
+            &base.signature,
+            &header,
+            b"nonce",
+            &messages,
+            &disclosed,
+        )
+        .unwrap();
+        let derived = Bbs2023DerivedProof {
+            proof,
+            label_map: [(0usize, 1usize), (1, 300)].iter().copied().collect(),
+            mandatory_indexes: vec![0],
+            selective_indexes: vec![1],
+            presentation_header: b"nonce".to_vec(),
+        };
+        let value = derived.to_proof_value();
+        assert!(value.starts_with("u2V0D"));
+        assert_eq!(
+            Bbs2023DerivedProof::from_proof_value(&value).unwrap(),
+            derived
+        );
+        assert!(Bbs2023BaseProof::from_proof_value(&value).is_err());
+    }
+}
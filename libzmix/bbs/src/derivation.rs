@@ -0,0 +1,205 @@
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::SignatureMessage;
+use blake2::digest::generic_array::GenericArray;
+use pairing_plus::{bls12_381::Fr, hash_to_field::BaseFromRO};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{
+        derive_child_sk, derive_link_secret, derive_master_sk, derive_path_sk, parse_path,
+        BLS_PURPOSE, MIN_SEED_SIZE,
+    };
+}
+
+/// The smallest seed `derive_master_sk` accepts
+pub const MIN_SEED_SIZE: usize = 32;
+/// The purpose index for BLS12-381 keys in EIP-2334 paths i.e. `m/12381/...`
+pub const BLS_PURPOSE: u32 = 12381;
+
+const LAMPORT_CHUNKS: usize = 255;
+const DIGEST_SIZE: usize = 32;
+const OKM_SIZE: usize = 48;
+
+/// Derive the master secret key from `seed` as defined in EIP-2333
+/// <https://eips.ethereum.org/EIPS/eip-2333>
+pub fn derive_master_sk<I: AsRef<[u8]>>(seed: I) -> Result<SecretKey, BBSError> {
+    let seed = seed.as_ref();
+    if seed.len() < MIN_SEED_SIZE {
+        return Err(BBSErrorKind::GeneralError {
+            msg: format!(
+                "The seed must be at least {} bytes, found {}",
+                MIN_SEED_SIZE,
+                seed.len()
+            ),
+        }
+        .into());
+    }
+    Ok(hkdf_mod_r(seed))
+}
+
+/// Derive the child secret key at `index` from `parent` as defined in EIP-2333
+pub fn derive_child_sk(parent: &SecretKey, index: u32) -> SecretKey {
+    let mut compressed_lamport_pk = parent_sk_to_lamport_pk(parent, index);
+    let sk = hkdf_mod_r(&compressed_lamport_pk);
+    compressed_lamport_pk.zeroize();
+    sk
+}
+
+/// Parse an EIP-2334 path like `m/12381/3600/0/0` into its indices
+pub fn parse_path<I: AsRef<str>>(path: I) -> Result<Vec<u32>, BBSError> {
+    let mut parts = path.as_ref().trim().split('/');
+    if parts.next() != Some("m") {
+        return Err(BBSErrorKind::GeneralError {
+            msg: "A path must start with \"m\"".to_string(),
+        }
+        .into());
+    }
+    parts
+        .map(|p| {
+            p.parse::<u32>().map_err(|_| {
+                BBSErrorKind::GeneralError {
+                    msg: format!("Invalid path index \"{}\"", p),
+                }
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// Derive the secret key at `path` from `seed`
+pub fn derive_path_sk<I: AsRef<[u8]>, P: AsRef<str>>(
+    seed: I,
+    path: P,
+) -> Result<SecretKey, BBSError> {
+    let indices = parse_path(path)?;
+    let master = derive_master_sk(seed)?;
+    Ok(indices
+        .into_iter()
+        .fold(master, |sk, index| derive_child_sk(&sk, index)))
+}
+
+/// Derive a link secret at `path` from `seed` so it can be restored from a backup of the seed
+pub fn derive_link_secret<I: AsRef<[u8]>, P: AsRef<str>>(
+    seed: I,
+    path: P,
+) -> Result<SignatureMessage, BBSError> {
+    let sk = derive_path_sk(seed, path)?;
+    Ok(SignatureMessage(sk.0))
+}
+
+/// HKDF_mod_r with an empty key_info
+fn hkdf_mod_r(ikm: &[u8]) -> SecretKey {
+    let mut salt = Sha256::digest(b"BLS-SIG-KEYGEN-SALT-");
+    let mut ikm = ikm.to_vec();
+    ikm.push(0); // IKM || I2OSP(0, 1)
+    let info = [0u8, OKM_SIZE as u8]; // key_info || I2OSP(L, 2)
+    let mut okm = [0u8; OKM_SIZE];
+    loop {
+        let h = hkdf::Hkdf::<Sha256>::new(Some(&salt[..]), &ikm);
+        h.expand(&info[..], &mut okm).unwrap();
+        let sk = Fr::from_okm(GenericArray::from_slice(&okm[..]));
+        if sk != Fr::default() {
+            ikm.zeroize();
+            okm.zeroize();
+            return SecretKey(sk);
+        }
+        salt = Sha256::digest(&salt[..]);
+    }
+}
+
+fn parent_sk_to_lamport_pk(parent: &SecretKey, index: u32) -> Vec<u8> {
+    let salt = index.to_be_bytes();
+    let mut ikm = parent.to_bytes_compressed_form();
+    let mut not_ikm = ikm;
+    not_ikm.iter_mut().for_each(|b| *b = !*b);
+
+    let mut lamport_pk = Sha256::new();
+    for i in [&ikm, &not_ikm].iter() {
+        let mut lamport_sk = ikm_to_lamport_sk(&i[..], &salt[..]);
+        for chunk in lamport_sk.chunks(DIGEST_SIZE) {
+            lamport_pk.input(Sha256::digest(chunk));
+        }
+        lamport_sk.zeroize();
+    }
+    ikm.zeroize();
+    not_ikm.zeroize();
+    lamport_pk.result().to_vec()
+}
+
+fn ikm_to_lamport_sk(ikm: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut okm = vec![0u8; LAMPORT_CHUNKS * DIGEST_SIZE];
+    let h = hkdf::Hkdf::<Sha256>::new(Some(salt), ikm);
+    h.expand(&[], okm.as_mut_slice()).unwrap();
+    okm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // https://eips.ethereum.org/EIPS/eip-2333#test-cases
+    // The keys are the big endian encoding of the decimal values in the EIP
+    #[test]
+    fn eip2333_vectors() {
+        let vectors = [
+            (
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+                "0d7359d57963ab8fbbde1852dcf553fedbc31f464d80ee7d40ae683122b45070",
+                0u32,
+                "2d18bd6c14e6d15bf8b5085c9b74f3daae3b03cc2014770a599d8c1539e50f8e",
+            ),
+            (
+                "3141592653589793238462643383279502884197169399375105820974944592",
+                "41c9e07822b092a93fd6797396338c3ada4170cc81829fdfce6b5d34bd5e7ec7",
+                3141592653,
+                "384843fad5f3d777ea39de3e47a8f999ae91f89e42bffa993d91d9782d152a0f",
+            ),
+            (
+                "0099ff991111002299dd7744ee3355bbdd8844115566cc55663355668888cc00",
+                "3cfa341ab3910a7d00d933d8f7c4fe87c91798a0397421d6b19fd5b815132e80",
+                4294967295,
+                "40e86285582f35b28821340f6a53b448588efa575bc4d88c32ef8567b8d9479b",
+            ),
+            (
+                "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+                "2a0e28ffa5fbbe2f8e7aad4ed94f745d6bf755c51182e119bb1694fe61d3afca",
+                42,
+                "455c0dc9fccb3395825d92a60d2672d69416be1c2578a87a7a3d3ced11ebb88d",
+            ),
+        ];
+        for (seed, master, index, child) in vectors.iter() {
+            let master_sk = derive_master_sk(hex::decode(seed).unwrap()).unwrap();
+            assert_eq!(hex::encode(master_sk.to_bytes_compressed_form()), *master);
+            let child_sk = derive_child_sk(&master_sk, *index);
+            assert_eq!(hex::encode(child_sk.to_bytes_compressed_form()), *child);
+        }
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(
+            parse_path("m/12381/3600/0/0").unwrap(),
+            vec![BLS_PURPOSE, 3600, 0, 0]
+        );
+        assert!(parse_path("m").unwrap().is_empty());
+        assert!(parse_path("12381/3600").is_err());
+        assert!(parse_path("m/12381/-1").is_err());
+        assert!(parse_path("m/4294967296").is_err());
+
+        let seed = [7u8; MIN_SEED_SIZE];
+        let sk = derive_path_sk(&seed[..], "m/12381/0").unwrap();
+        let expected = derive_child_sk(
+            &derive_child_sk(&derive_master_sk(&seed[..]).unwrap(), BLS_PURPOSE),
+            0,
+        );
+        assert_eq!(sk, expected);
+        assert_eq!(
+            derive_link_secret(&seed[..], "m/12381/0").unwrap(),
+            SignatureMessage(expected.0)
+        );
+        assert!(derive_master_sk(&seed[1..]).is_err());
+    }
+}
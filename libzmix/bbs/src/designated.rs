@@ -0,0 +1,535 @@
+use crate::errors::prelude::*;
+use crate::pok_sig::prelude::*;
+use crate::pok_vc::prelude::*;
+use crate::{
+    multi_scalar_mul_const_time_g1, rand_non_zero_fr, HashElem, ProofChallenge, ProofNonce,
+    ProofRequest, RandomElem, SignatureMessage, SignatureProof, ToVariableLengthBytes,
+    FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
+};
+use ff_zeroize::Field;
+use pairing_plus::{
+    bls12_381::{Fr, G1},
+    serdes::SerDes,
+    CurveProjective,
+};
+use rand::{thread_rng, CryptoRng, RngCore};
+use serde::{
+    de::{Error as DError, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use zeroize::Zeroize;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{
+        DesignatedVerifierProof, DesignatedVerifierPublicKey, DesignatedVerifierSecretKey,
+    };
+}
+
+/// The secret key `x` of a designated verifier
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DesignatedVerifierSecretKey(pub(crate) Fr);
+
+impl DesignatedVerifierSecretKey {
+    to_fixed_length_bytes_impl!(
+        DesignatedVerifierSecretKey,
+        Fr,
+        FR_COMPRESSED_SIZE,
+        FR_COMPRESSED_SIZE
+    );
+}
+
+default_zero_impl!(DesignatedVerifierSecretKey, Fr);
+from_impl!(DesignatedVerifierSecretKey, Fr, FR_COMPRESSED_SIZE);
+display_impl!(DesignatedVerifierSecretKey);
+serdes_impl!(DesignatedVerifierSecretKey);
+random_elem_impl!(DesignatedVerifierSecretKey, |rng| {
+    Self(rand_non_zero_fr(rng))
+});
+
+impl Zeroize for DesignatedVerifierSecretKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for DesignatedVerifierSecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// The public key `g1^x` of a designated verifier
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DesignatedVerifierPublicKey(pub(crate) G1);
+
+impl DesignatedVerifierPublicKey {
+    /// Compute the public key for `secret_key`
+    pub fn new(secret_key: &DesignatedVerifierSecretKey) -> Self {
+        let mut y = G1::one();
+        y.mul_assign(secret_key.0);
+        Self(y)
+    }
+
+    to_fixed_length_bytes_impl!(
+        DesignatedVerifierPublicKey,
+        G1,
+        G1_COMPRESSED_SIZE,
+        G1_UNCOMPRESSED_SIZE
+    );
+}
+
+default_zero_impl!(DesignatedVerifierPublicKey, G1);
+as_ref_impl!(DesignatedVerifierPublicKey, G1);
+from_impl!(
+    DesignatedVerifierPublicKey,
+    G1,
+    G1_COMPRESSED_SIZE,
+    G1_UNCOMPRESSED_SIZE
+);
+display_impl!(DesignatedVerifierPublicKey);
+serdes_impl!(DesignatedVerifierPublicKey);
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(DesignatedVerifierPublicKey);
+
+/// A signature proof of knowledge that only convinces the designated verifier.
+///
+/// The Fiat-Shamir challenge `c` is split into `c - c_v` for the signature proof and `c_v`
+/// for a proof of knowledge of the verifier's secret key, so the proof shows the prover knows
+/// a signature *or* the verifier's secret key. The prover simulates the second branch.
+/// The verifier knows it did not create the proof but anyone else only learns that
+/// the prover or the verifier did, since the verifier can create the same proof
+/// with `DesignatedVerifierProof::simulate`.
+#[derive(Debug, Clone)]
+pub struct DesignatedVerifierProof {
+    /// The signature proof of knowledge for the challenge `c - c_v`
+    pub signature_proof: SignatureProof,
+    /// `g1^z * Y^-c_v` for the verifier's public key `Y`
+    pub(crate) commitment: G1,
+    /// `c_v`
+    pub(crate) challenge: Fr,
+    /// `z`
+    pub(crate) response: Fr,
+}
+
+impl DesignatedVerifierProof {
+    /// Create the proof from a signature proof of knowledge for a `proof_request`
+    /// with a designated verifier
+    pub fn new(
+        pok: PoKOfSignature,
+        proof_request: &ProofRequest,
+        nonce: &ProofNonce,
+    ) -> Result<Self, BBSError> {
+        Self::new_with_rng(pok, proof_request, nonce, &mut thread_rng())
+    }
+
+    /// Same as `new` but the simulated branch is generated from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        pok: PoKOfSignature,
+        proof_request: &ProofRequest,
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        let verifier_key = designated_verifier(proof_request)?;
+
+        // Simulate the proof of knowledge of the verifier's secret key
+        let challenge = rand_non_zero_fr(rng);
+        let response = rand_non_zero_fr(rng);
+        let commitment = simulate_commitment(verifier_key, &challenge, &response);
+
+        let mut c = compute_challenge(pok.to_bytes(), verifier_key, &commitment, nonce);
+        c.0.sub_assign(&challenge);
+        let revealed_messages = pok.revealed_messages.clone();
+        Ok(Self {
+            signature_proof: SignatureProof {
+                revealed_messages,
+                proof: pok.gen_proof(&c)?,
+            },
+            commitment,
+            challenge,
+            response,
+        })
+    }
+
+    /// Create a proof for `proof_request` that reveals `revealed_messages` without a signature
+    /// using the designated verifier's secret key. This is what makes the proof non-transferable.
+    ///
+    /// `A'` and `A bar` do not depend on the messages and can be rerandomized,
+    /// so they are taken from any earlier proof for the same issuer.
+    /// All other values are simulated for the challenge of the signature proof.
+    pub fn simulate(
+        proof_request: &ProofRequest,
+        revealed_messages: &BTreeMap<usize, SignatureMessage>,
+        earlier_proof: &PoKOfSignatureProof,
+        secret_key: &DesignatedVerifierSecretKey,
+        nonce: &ProofNonce,
+    ) -> Result<Self, BBSError> {
+        Self::simulate_with_rng(
+            proof_request,
+            revealed_messages,
+            earlier_proof,
+            secret_key,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Same as `simulate` but all randomness is generated from `rng`
+    pub fn simulate_with_rng<R: RngCore + CryptoRng>(
+        proof_request: &ProofRequest,
+        revealed_messages: &BTreeMap<usize, SignatureMessage>,
+        earlier_proof: &PoKOfSignatureProof,
+        secret_key: &DesignatedVerifierSecretKey,
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        let verifier_key = designated_verifier(proof_request)?;
+        if DesignatedVerifierPublicKey::new(secret_key) != *verifier_key {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "The secret key does not belong to the designated verifier".to_string(),
+            }
+            .into());
+        }
+        let vk = &proof_request.verification_key;
+        check_revealed(proof_request, revealed_messages)?;
+
+        let r = rand_non_zero_fr(rng);
+        let mut a_prime = earlier_proof.a_prime;
+        a_prime.mul_assign(r);
+        let mut a_bar = earlier_proof.a_bar;
+        a_bar.mul_assign(r);
+        let mut d = G1::one();
+        d.mul_assign(rand_non_zero_fr(rng));
+        let c_s = ProofChallenge::random_with_rng(rng);
+
+        // a_bar / d == a_prime^{-e} * h_0^r2
+        let mut a_bar_d = a_bar;
+        a_bar_d.sub_assign(&d);
+        let proof_vc_1 = simulate_proof(&[a_prime, vk.h0.0], a_bar_d, &c_s, rng);
+
+        // g1 * h1^-m1 * h2^-m2.... == d^-r3 * h_0^s_prime * h_j^m_j for revealed m_i and hidden m_j
+        let mut bases = vec![d, vk.h0.0];
+        let mut instance = G1::one();
+        for i in 0..vk.message_count() {
+            match revealed_messages.get(&i) {
+                Some(m) => {
+                    let mut h = vk.h[i].0;
+                    h.mul_assign(m.0);
+                    instance.add_assign(&h);
+                }
+                None => bases.push(vk.h[i].0),
+            }
+        }
+        instance.negate();
+        let proof_vc_2 = simulate_proof(&bases, instance, &c_s, rng);
+
+        let proof = PoKOfSignatureProof {
+            a_prime,
+            a_bar,
+            d,
+            proof_vc_1,
+            proof_vc_2,
+            inequalities: Vec::new(),
+        };
+
+        // Prove knowledge of the secret key for the rest of the challenge
+        let blinding = rand_non_zero_fr(rng);
+        let mut commitment = G1::one();
+        commitment.mul_assign(blinding);
+        let revealed = revealed_messages
+            .keys()
+            .copied()
+            .collect::<BTreeSet<usize>>();
+        let mut challenge = compute_challenge(
+            proof.get_bytes_for_challenge(revealed, vk),
+            verifier_key,
+            &commitment,
+            nonce,
+        )
+        .0;
+        challenge.sub_assign(&c_s.0);
+        let mut response = challenge;
+        response.mul_assign(&secret_key.0);
+        response.add_assign(&blinding);
+
+        Ok(Self {
+            signature_proof: SignatureProof {
+                revealed_messages: revealed_messages.clone(),
+                proof,
+            },
+            commitment,
+            challenge,
+            response,
+        })
+    }
+
+    /// Verify the proof for `proof_request`. Returns the revealed messages.
+    /// Only the designated verifier should accept them since it could have created the proof itself.
+    pub fn verify(
+        &self,
+        proof_request: &ProofRequest,
+        nonce: &ProofNonce,
+    ) -> Result<BTreeMap<usize, SignatureMessage>, BBSError> {
+        let verifier_key = designated_verifier(proof_request)?;
+        check_revealed(proof_request, &self.signature_proof.revealed_messages)?;
+
+        let mut c = compute_challenge(
+            self.signature_proof.proof.get_bytes_for_challenge(
+                proof_request.revealed_messages.clone(),
+                &proof_request.verification_key,
+            ),
+            verifier_key,
+            &self.commitment,
+            nonce,
+        );
+        c.0.sub_assign(&self.challenge);
+        match self.signature_proof.proof.verify(
+            &proof_request.verification_key,
+            &self.signature_proof.revealed_messages,
+            &c,
+        )? {
+            PoKOfSignatureProofStatus::Success => {}
+            e => return Err(BBSErrorKind::InvalidProof { status: e }.into()),
+        }
+
+        // g1^z == commitment * Y^c_v
+        if simulate_commitment(verifier_key, &self.challenge, &self.response) != self.commitment {
+            return Err(BBSErrorKind::InvalidProof {
+                status: PoKOfSignatureProofStatus::BadSignature,
+            }
+            .into());
+        }
+        Ok(self.signature_proof.revealed_messages.clone())
+    }
+
+    pub(crate) fn to_bytes(&self, compressed: bool) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.commitment.serialize(&mut output, compressed).unwrap();
+        self.challenge.serialize(&mut output, compressed).unwrap();
+        self.response.serialize(&mut output, compressed).unwrap();
+        output.extend_from_slice(self.signature_proof.to_bytes(compressed).as_slice());
+        output
+    }
+
+    pub(crate) fn from_bytes(
+        data: &[u8],
+        g1_size: usize,
+        compressed: bool,
+    ) -> Result<Self, BBSError> {
+        let min_len = g1_size + FR_COMPRESSED_SIZE * 2;
+        if data.len() < min_len {
+            return Err(BBSErrorKind::InvalidNumberOfBytes(min_len, data.len()).into());
+        }
+        let mut c = &data[..g1_size];
+        let commitment = slice_to_elem!(&mut c, G1, compressed)?;
+        let mut c = &data[g1_size..g1_size + FR_COMPRESSED_SIZE];
+        let challenge = slice_to_elem!(&mut c, Fr, compressed)?;
+        let mut c = &data[g1_size + FR_COMPRESSED_SIZE..min_len];
+        let response = slice_to_elem!(&mut c, Fr, compressed)?;
+        let signature_proof = SignatureProof::from_bytes(&data[min_len..], g1_size, compressed)?;
+        Ok(Self {
+            signature_proof,
+            commitment,
+            challenge,
+            response,
+        })
+    }
+}
+
+impl ToVariableLengthBytes for DesignatedVerifierProof {
+    type Output = DesignatedVerifierProof;
+    type Error = BBSError;
+
+    /// Convert to raw bytes using compressed form for each element.
+    fn to_bytes_compressed_form(&self) -> Vec<u8> {
+        self.to_bytes(true)
+    }
+
+    /// Convert from compressed form raw bytes.
+    fn from_bytes_compressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        Self::from_bytes(data.as_ref(), G1_COMPRESSED_SIZE, true)
+    }
+
+    fn to_bytes_uncompressed_form(&self) -> Vec<u8> {
+        self.to_bytes(false)
+    }
+
+    fn from_bytes_uncompressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self::Output, Self::Error> {
+        Self::from_bytes(data.as_ref(), G1_UNCOMPRESSED_SIZE, false)
+    }
+}
+
+impl Default for DesignatedVerifierProof {
+    fn default() -> Self {
+        Self {
+            signature_proof: SignatureProof::default(),
+            commitment: G1::zero(),
+            challenge: Fr::zero(),
+            response: Fr::zero(),
+        }
+    }
+}
+
+try_from_impl!(DesignatedVerifierProof, BBSError);
+serdes_impl!(DesignatedVerifierProof);
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(DesignatedVerifierProof);
+
+fn designated_verifier(
+    proof_request: &ProofRequest,
+) -> Result<&DesignatedVerifierPublicKey, BBSError> {
+    match &proof_request.designated_verifier {
+        Some(k) if !k.0.is_zero() => Ok(k),
+        _ => Err(BBSErrorKind::GeneralError {
+            msg: "The proof request has no designated verifier".to_string(),
+        }
+        .into()),
+    }
+}
+
+fn check_revealed(
+    proof_request: &ProofRequest,
+    revealed_messages: &BTreeMap<usize, SignatureMessage>,
+) -> Result<(), BBSError> {
+    if revealed_messages
+        .keys()
+        .copied()
+        .collect::<BTreeSet<usize>>()
+        != proof_request.revealed_messages
+    {
+        return Err(BBSErrorKind::GeneralError {
+            msg: "Revealed messages do not match the proof request".to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// `g1^response * Y^-challenge`
+fn simulate_commitment(
+    verifier_key: &DesignatedVerifierPublicKey,
+    challenge: &Fr,
+    response: &Fr,
+) -> G1 {
+    let mut c = *challenge;
+    c.negate();
+    multi_scalar_mul_const_time_g1([G1::one(), verifier_key.0], [*response, c])
+}
+
+/// A proof for `bases` and `instance` that verifies for `challenge` with random responses
+fn simulate_proof<R: RngCore + CryptoRng>(
+    bases: &[G1],
+    instance: G1,
+    challenge: &ProofChallenge,
+    rng: &mut R,
+) -> ProofG1 {
+    let responses = bases
+        .iter()
+        .map(|_| rand_non_zero_fr(rng))
+        .collect::<Vec<Fr>>();
+    let mut points = bases.to_vec();
+    points.push(instance);
+    let mut scalars = responses.clone();
+    scalars.push(challenge.0);
+    ProofG1 {
+        commitment: multi_scalar_mul_const_time_g1(&points, &scalars),
+        responses,
+    }
+}
+
+/// The verifier's key and the commitment are included so the split of the challenge is bound to them
+fn compute_challenge(
+    mut bytes: Vec<u8>,
+    verifier_key: &DesignatedVerifierPublicKey,
+    commitment: &G1,
+    nonce: &ProofNonce,
+) -> ProofChallenge {
+    verifier_key.0.serialize(&mut bytes, false).unwrap();
+    commitment.serialize(&mut bytes, false).unwrap();
+    bytes.extend_from_slice(&nonce.to_bytes_uncompressed_form()[..]);
+    ProofChallenge::hash(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn designated_verifier_proof() {
+        let (pk, sk) = Issuer::new_keys(4).unwrap();
+        let messages = vec![
+            SignatureMessage::hash(b"patient"),
+            SignatureMessage::hash(b"diagnosis"),
+            SignatureMessage::hash(b"clinic"),
+            SignatureMessage::hash(b"date"),
+        ];
+        let signature = Signature::new(messages.as_slice(), &sk, &pk).unwrap();
+        let (verifier_pk, verifier_sk) = Verifier::new_designated_verifier_keys();
+
+        let proof_request =
+            Verifier::new_designated_proof_request(&[2], &pk, &verifier_pk).unwrap();
+        let proof_request =
+            ProofRequest::try_from(proof_request.to_bytes_compressed_form()).unwrap();
+        assert_eq!(proof_request.designated_verifier, Some(verifier_pk));
+        let nonce = Verifier::generate_proof_nonce();
+        let proof_messages = vec![
+            pm_hidden_raw!(messages[0]),
+            pm_hidden_raw!(messages[1]),
+            pm_revealed_raw!(messages[2]),
+            pm_hidden_raw!(messages[3]),
+        ];
+        let pok =
+            Prover::commit_signature_pok(&proof_request, proof_messages.as_slice(), &signature)
+                .unwrap();
+        let proof = Prover::generate_designated_signature_pok(pok, &proof_request, &nonce).unwrap();
+        let proof = DesignatedVerifierProof::try_from(proof.to_bytes_compressed_form()).unwrap();
+        let revealed =
+            Verifier::verify_designated_signature_pok(&proof_request, &proof, &nonce).unwrap();
+        assert_eq!(revealed[&2], messages[2]);
+
+        // Bound to the nonce and the verifier
+        assert!(Verifier::verify_designated_signature_pok(
+            &proof_request,
+            &proof,
+            &Verifier::generate_proof_nonce()
+        )
+        .is_err());
+        let (other_pk, _) = Verifier::new_designated_verifier_keys();
+        let other_request = Verifier::new_designated_proof_request(&[2], &pk, &other_pk).unwrap();
+        assert!(Verifier::verify_designated_signature_pok(&other_request, &proof, &nonce).is_err());
+        let plain_request = Verifier::new_proof_request(&[2], &pk).unwrap();
+        assert!(Verifier::verify_designated_signature_pok(&plain_request, &proof, &nonce).is_err());
+
+        // The verifier can create an equally valid proof for messages that were never signed
+        let mut forged_messages = BTreeMap::new();
+        forged_messages.insert(2, SignatureMessage::hash(b"another clinic"));
+        let forged = DesignatedVerifierProof::simulate(
+            &proof_request,
+            &forged_messages,
+            &proof.signature_proof.proof,
+            &verifier_sk,
+            &nonce,
+        )
+        .unwrap();
+        assert_eq!(
+            forged.verify(&proof_request, &nonce).unwrap(),
+            forged_messages
+        );
+        let (_, other_sk) = Verifier::new_designated_verifier_keys();
+        assert!(DesignatedVerifierProof::simulate(
+            &proof_request,
+            &forged_messages,
+            &proof.signature_proof.proof,
+            &other_sk,
+            &nonce,
+        )
+        .is_err());
+    }
+}
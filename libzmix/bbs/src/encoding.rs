@@ -0,0 +1,592 @@
+use crate::errors::prelude::*;
+use crate::{SignatureMessage, ToVariableLengthBytes, FR_COMPRESSED_SIZE};
+use serde::{
+    de::{Error as DError, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::Formatter;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{MessageEncoding, MessageSchema, MessageValue, I64_OFFSET, MAX_EXACT_BYTES};
+}
+
+/// The most bytes `SignatureMessage::from_bytes_exact` can encode
+pub const MAX_EXACT_BYTES: usize = FR_COMPRESSED_SIZE - 1;
+
+/// Added to signed values so the order of the encoded values matches
+/// the order of the signed values. Values in [-I64_OFFSET, I64_OFFSET) are encoded
+/// in [0, 2^58 - 1], the largest value range predicates can be proven over.
+pub const I64_OFFSET: u64 = 1 << 57;
+/// Marks the start of the bytes encoded by `from_bytes_exact`
+/// so leading zero bytes are kept
+const EXACT_BYTES_MARKER: u8 = 1;
+
+/// Integer encodings place the value in the low 8 bytes of the field element.
+/// Numerically larger values are larger field elements so range predicates
+/// can be proven over them. Signed values, dates, times and decimals add
+/// an offset of `I64_OFFSET` so negative values are still ordered.
+/// Signed values outside [-I64_OFFSET, I64_OFFSET) still round trip
+/// but are not ordered and range predicates cannot be proven over them.
+impl SignatureMessage {
+    /// Encode an unsigned integer
+    pub fn from_u64(value: u64) -> Self {
+        Self::from_low_bytes(&value.to_be_bytes())
+    }
+
+    /// Decode a message created with `from_u64`
+    pub fn to_u64(&self) -> Result<u64, BBSError> {
+        let bytes = self.to_bytes_compressed_form();
+        let (high, low) = bytes.split_at(FR_COMPRESSED_SIZE - 8);
+        if high.iter().any(|b| *b != 0) {
+            return Err(decode_error("the message is larger than 64 bits"));
+        }
+        Ok(u64::from_be_bytes(*array_ref![low, 0, 8]))
+    }
+
+    /// Encode a signed integer with an offset of `I64_OFFSET`
+    pub fn from_i64(value: i64) -> Self {
+        Self::from_u64((value as u64).wrapping_add(I64_OFFSET))
+    }
+
+    /// Decode a message created with `from_i64`
+    pub fn to_i64(&self) -> Result<i64, BBSError> {
+        Ok(self.to_u64()?.wrapping_sub(I64_OFFSET) as i64)
+    }
+
+    /// Encode a proleptic Gregorian date as the number of days since 1970-01-01
+    pub fn from_date(year: i32, month: u32, day: u32) -> Result<Self, BBSError> {
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Invalid date {}-{}-{}", year, month, day),
+            }
+            .into());
+        }
+        Ok(Self::from_i64(days_from_civil(year, month, day)))
+    }
+
+    /// Decode a message created with `from_date` into `(year, month, day)`
+    pub fn to_date(&self) -> Result<(i32, u32, u32), BBSError> {
+        let days = self.to_i64()?;
+        // Keeps the year in range of an i32
+        if days.unsigned_abs() > i32::MAX as u64 * 365 {
+            return Err(decode_error("the date is out of range"));
+        }
+        Ok(civil_from_days(days))
+    }
+
+    /// Encode a time as the number of seconds since 1970-01-01T00:00:00Z
+    pub fn from_datetime(unix_seconds: i64) -> Self {
+        Self::from_i64(unix_seconds)
+    }
+
+    /// Decode a message created with `from_datetime` into the seconds since 1970-01-01T00:00:00Z
+    pub fn to_datetime(&self) -> Result<i64, BBSError> {
+        self.to_i64()
+    }
+
+    /// Encode a decimal string like `-12.5` as an integer with `scale` fractional digits
+    /// e.g. `-12.5` with a scale of 2 is encoded as `-1250`.
+    /// Values with more than `scale` fractional digits are rejected instead of rounded.
+    pub fn from_decimal<I: AsRef<str>>(value: I, scale: u8) -> Result<Self, BBSError> {
+        let value = value.as_ref();
+        let invalid = || -> BBSError {
+            BBSErrorKind::GeneralError {
+                msg: format!("Invalid decimal {} with scale {}", value, scale),
+            }
+            .into()
+        };
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(d) => (true, d),
+            None => (false, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let (integer, fraction) = match digits.find('.') {
+            Some(i) => (&digits[..i], &digits[i + 1..]),
+            None => (digits, ""),
+        };
+        if (integer.is_empty() && fraction.is_empty())
+            || fraction.len() > scale as usize
+            || !integer
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let mut scaled = 0i64;
+        let padding = "0".repeat(scale as usize - fraction.len());
+        for c in integer
+            .chars()
+            .chain(fraction.chars())
+            .chain(padding.chars())
+        {
+            let digit = i64::from(c.to_digit(10).unwrap());
+            scaled = scaled
+                .checked_mul(10)
+                .and_then(|s| {
+                    if negative {
+                        s.checked_sub(digit)
+                    } else {
+                        s.checked_add(digit)
+                    }
+                })
+                .ok_or_else(invalid)?;
+        }
+        Ok(Self::from_i64(scaled))
+    }
+
+    /// Decode a message created with `from_decimal` using the same `scale`
+    pub fn to_decimal(&self, scale: u8) -> Result<String, BBSError> {
+        let value = self.to_i64()?;
+        let digits = value.unsigned_abs().to_string();
+        let scale = scale as usize;
+        let digits = if digits.len() <= scale {
+            format!("{}{}", "0".repeat(scale + 1 - digits.len()), digits)
+        } else {
+            digits
+        };
+        let sign = if value < 0 { "-" } else { "" };
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        if fraction.is_empty() {
+            Ok(format!("{}{}", sign, integer))
+        } else {
+            Ok(format!("{}{}.{}", sign, integer, fraction))
+        }
+    }
+
+    /// Encode at most `MAX_EXACT_BYTES` bytes directly instead of hashing them
+    /// so they can be recovered when revealed
+    pub fn from_bytes_exact<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        let data = data.as_ref();
+        if data.len() > MAX_EXACT_BYTES {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Expected at most {} bytes, found {}",
+                    MAX_EXACT_BYTES,
+                    data.len()
+                ),
+            }
+            .into());
+        }
+        let mut bytes = vec![EXACT_BYTES_MARKER];
+        bytes.extend_from_slice(data);
+        Ok(Self::from_low_bytes(&bytes))
+    }
+
+    /// Decode a message created with `from_bytes_exact`
+    pub fn to_bytes_exact(&self) -> Result<Vec<u8>, BBSError> {
+        let bytes = self.to_bytes_compressed_form();
+        match bytes.iter().position(|b| *b != 0) {
+            Some(i) if bytes[i] == EXACT_BYTES_MARKER => Ok(bytes[i + 1..].to_vec()),
+            _ => Err(decode_error("the message is not exact bytes")),
+        }
+    }
+
+    /// Big endian `data` is always less than the modulus
+    /// since it is shorter than a field element
+    fn from_low_bytes(data: &[u8]) -> Self {
+        let mut bytes = [0u8; FR_COMPRESSED_SIZE];
+        bytes[FR_COMPRESSED_SIZE - data.len()..].copy_from_slice(data);
+        Self::from(bytes)
+    }
+}
+
+/// How a message was encoded into a field element
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MessageEncoding {
+    /// `SignatureMessage::hash`, the value cannot be recovered
+    Hash,
+    /// `SignatureMessage::from_u64`
+    U64,
+    /// `SignatureMessage::from_i64`
+    I64,
+    /// `SignatureMessage::from_date`
+    Date,
+    /// `SignatureMessage::from_datetime`
+    DateTime,
+    /// `SignatureMessage::from_decimal` with the scale
+    Decimal(u8),
+    /// `SignatureMessage::from_bytes_exact`
+    Bytes,
+}
+
+impl MessageEncoding {
+    /// Decode `message` according to this encoding
+    pub fn decode(self, message: &SignatureMessage) -> Result<MessageValue, BBSError> {
+        Ok(match self {
+            MessageEncoding::Hash => MessageValue::Hashed(*message),
+            MessageEncoding::U64 => MessageValue::U64(message.to_u64()?),
+            MessageEncoding::I64 => MessageValue::I64(message.to_i64()?),
+            MessageEncoding::Date => {
+                let (year, month, day) = message.to_date()?;
+                MessageValue::Date(year, month, day)
+            }
+            MessageEncoding::DateTime => MessageValue::DateTime(message.to_datetime()?),
+            MessageEncoding::Decimal(scale) => MessageValue::Decimal(message.to_decimal(scale)?),
+            MessageEncoding::Bytes => MessageValue::Bytes(message.to_bytes_exact()?),
+        })
+    }
+
+    /// Encode `value` according to this encoding
+    pub fn encode(self, value: &MessageValue) -> Result<SignatureMessage, BBSError> {
+        match (self, value) {
+            (MessageEncoding::Hash, MessageValue::Hashed(m)) => Ok(*m),
+            (MessageEncoding::U64, MessageValue::U64(v)) => Ok(SignatureMessage::from_u64(*v)),
+            (MessageEncoding::I64, MessageValue::I64(v)) => Ok(SignatureMessage::from_i64(*v)),
+            (MessageEncoding::Date, MessageValue::Date(y, m, d)) => {
+                SignatureMessage::from_date(*y, *m, *d)
+            }
+            (MessageEncoding::DateTime, MessageValue::DateTime(v)) => {
+                Ok(SignatureMessage::from_datetime(*v))
+            }
+            (MessageEncoding::Decimal(scale), MessageValue::Decimal(v)) => {
+                SignatureMessage::from_decimal(v, scale)
+            }
+            (MessageEncoding::Bytes, MessageValue::Bytes(v)) => {
+                SignatureMessage::from_bytes_exact(v)
+            }
+            (e, v) => Err(BBSErrorKind::GeneralError {
+                msg: format!("Cannot encode {:?} as {:?}", v, e),
+            }
+            .into()),
+        }
+    }
+
+    fn to_bytes(self, out: &mut Vec<u8>) {
+        match self {
+            MessageEncoding::Hash => out.push(0),
+            MessageEncoding::U64 => out.push(1),
+            MessageEncoding::I64 => out.push(2),
+            MessageEncoding::Date => out.push(3),
+            MessageEncoding::DateTime => out.push(4),
+            MessageEncoding::Decimal(scale) => {
+                out.push(5);
+                out.push(scale);
+            }
+            MessageEncoding::Bytes => out.push(6),
+        }
+    }
+
+    fn from_bytes(data: &mut &[u8]) -> Result<Self, BBSError> {
+        let (tag, rest) = data
+            .split_first()
+            .ok_or(BBSErrorKind::InvalidNumberOfBytes(1, 0))?;
+        *data = rest;
+        Ok(match tag {
+            0 => MessageEncoding::Hash,
+            1 => MessageEncoding::U64,
+            2 => MessageEncoding::I64,
+            3 => MessageEncoding::Date,
+            4 => MessageEncoding::DateTime,
+            5 => {
+                let (scale, rest) = data
+                    .split_first()
+                    .ok_or(BBSErrorKind::InvalidNumberOfBytes(1, 0))?;
+                *data = rest;
+                MessageEncoding::Decimal(*scale)
+            }
+            6 => MessageEncoding::Bytes,
+            t => {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Unknown message encoding {}", t),
+                }
+                .into())
+            }
+        })
+    }
+}
+
+/// A message value before encoding or after decoding
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MessageValue {
+    /// A hashed message which is kept as the field element
+    Hashed(SignatureMessage),
+    /// An unsigned integer
+    U64(u64),
+    /// A signed integer
+    I64(i64),
+    /// A date as `(year, month, day)`
+    Date(i32, u32, u32),
+    /// Seconds since 1970-01-01T00:00:00Z
+    DateTime(i64),
+    /// A decimal string
+    Decimal(String),
+    /// Raw bytes
+    Bytes(Vec<u8>),
+}
+
+/// Describes the encoding of the message at each index of a credential.
+/// Issuers publish the schema with their public key so verifiers
+/// can decode revealed messages and know which range predicates make sense.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MessageSchema {
+    /// The encoding of each message by index
+    pub encodings: Vec<MessageEncoding>,
+}
+
+impl MessageSchema {
+    /// Create a schema with the encoding of each message by index
+    pub fn new(encodings: Vec<MessageEncoding>) -> Self {
+        Self { encodings }
+    }
+
+    /// The number of messages described by the schema
+    pub fn message_count(&self) -> usize {
+        self.encodings.len()
+    }
+
+    /// Encode all the message values in index order
+    pub fn encode(&self, values: &[MessageValue]) -> Result<Vec<SignatureMessage>, BBSError> {
+        if values.len() != self.encodings.len() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Expected {} messages, found {}",
+                    self.encodings.len(),
+                    values.len()
+                ),
+            }
+            .into());
+        }
+        self.encodings
+            .iter()
+            .zip(values.iter())
+            .map(|(e, v)| e.encode(v))
+            .collect()
+    }
+
+    /// Decode revealed messages keyed by their index
+    pub fn decode(
+        &self,
+        messages: &BTreeMap<usize, SignatureMessage>,
+    ) -> Result<BTreeMap<usize, MessageValue>, BBSError> {
+        messages
+            .iter()
+            .map(|(i, m)| {
+                let encoding = self.encodings.get(*i).ok_or_else(|| {
+                    BBSError::from(BBSErrorKind::GeneralError {
+                        msg: format!("No encoding for message {}", i),
+                    })
+                })?;
+                Ok((*i, encoding.decode(m)?))
+            })
+            .collect()
+    }
+
+    /// Convert to bytes as the message count (4 bytes big endian)
+    /// followed by a tag for each encoding
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = (self.encodings.len() as u32).to_be_bytes().to_vec();
+        for e in &self.encodings {
+            e.to_bytes(&mut out);
+        }
+        out
+    }
+
+    /// Convert from the output of `to_bytes`
+    pub fn from_bytes<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        let data = data.as_ref();
+        if data.len() < 4 {
+            return Err(BBSErrorKind::InvalidNumberOfBytes(4, data.len()).into());
+        }
+        let count = u32::from_be_bytes(*array_ref![data, 0, 4]) as usize;
+        let mut data = &data[4..];
+        // Every encoding takes at least one byte
+        if data.len() < count {
+            return Err(BBSErrorKind::InvalidNumberOfBytes(count, data.len()).into());
+        }
+        let mut encodings = Vec::with_capacity(count);
+        for _ in 0..count {
+            encodings.push(MessageEncoding::from_bytes(&mut data)?);
+        }
+        if !data.is_empty() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Unexpected bytes after the message schema".to_string(),
+            }
+            .into());
+        }
+        Ok(Self { encodings })
+    }
+}
+
+impl ToVariableLengthBytes for MessageSchema {
+    type Output = MessageSchema;
+    type Error = BBSError;
+
+    fn to_bytes_compressed_form(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_bytes_compressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self::Output, Self::Error> {
+        Self::from_bytes(data)
+    }
+
+    fn to_bytes_uncompressed_form(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn from_bytes_uncompressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self::Output, Self::Error> {
+        Self::from_bytes(data)
+    }
+}
+
+try_from_impl!(MessageSchema, BBSError);
+serdes_impl!(MessageSchema);
+
+fn decode_error(reason: &str) -> BBSError {
+    BBSErrorKind::GeneralError {
+        msg: format!("Cannot decode message: {}", reason),
+    }
+    .into()
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 using the algorithm from
+/// <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let y = i64::from(year) - if month <= 2 { 1 } else { 0 };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let m = i64::from(month);
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// The inverse of `days_from_civil`
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as i32, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HashElem;
+
+    #[test]
+    fn round_trip() {
+        for v in [0u64, 1, 255, u64::MAX].iter() {
+            assert_eq!(SignatureMessage::from_u64(*v).to_u64().unwrap(), *v);
+        }
+        for v in [i64::MIN, -1, 0, 1, i64::MAX].iter() {
+            assert_eq!(SignatureMessage::from_i64(*v).to_i64().unwrap(), *v);
+        }
+        for (y, m, d) in [(1970, 1, 1), (2000, 2, 29), (1899, 12, 31), (-44, 3, 15)].iter() {
+            let date = SignatureMessage::from_date(*y, *m, *d).unwrap();
+            assert_eq!(date.to_date().unwrap(), (*y, *m, *d));
+        }
+        assert_eq!(
+            SignatureMessage::from_date(1970, 1, 2).unwrap(),
+            SignatureMessage::from_i64(1)
+        );
+        assert!(SignatureMessage::from_date(2001, 2, 29).is_err());
+        // Wraps to i64::MIN
+        let min = SignatureMessage::from_u64((1u64 << 63).wrapping_add(I64_OFFSET));
+        assert_eq!(min.to_i64().unwrap(), i64::MIN);
+        assert!(min.to_date().is_err());
+        assert_eq!(
+            SignatureMessage::from_datetime(-86_400)
+                .to_datetime()
+                .unwrap(),
+            -86_400
+        );
+        for (v, scale, expected) in [
+            ("12.5", 2, "12.50"),
+            ("-0.05", 2, "-0.05"),
+            ("+7", 0, "7"),
+            ("-3", 1, "-3.0"),
+        ]
+        .iter()
+        {
+            let m = SignatureMessage::from_decimal(v, *scale).unwrap();
+            assert_eq!(m.to_decimal(*scale).unwrap(), *expected);
+        }
+        assert!(SignatureMessage::from_decimal("1.234", 2).is_err());
+        assert!(SignatureMessage::from_decimal("1e3", 2).is_err());
+        for v in [&b""[..], b"\x00\x00a", &[0xffu8; MAX_EXACT_BYTES][..]].iter() {
+            let m = SignatureMessage::from_bytes_exact(v).unwrap();
+            assert_eq!(m.to_bytes_exact().unwrap(), v.to_vec());
+        }
+        assert!(SignatureMessage::from_bytes_exact([0u8; MAX_EXACT_BYTES + 1]).is_err());
+        assert!(SignatureMessage::hash(b"message").to_u64().is_err());
+    }
+
+    #[test]
+    fn ordering() {
+        let offset = I64_OFFSET as i64;
+        let values = [-offset, -1000, -1, 0, 1, 1000, offset - 1];
+        let messages = values
+            .iter()
+            .map(|v| SignatureMessage::from_i64(*v))
+            .collect::<Vec<SignatureMessage>>();
+        let mut sorted = messages.clone();
+        sorted.sort();
+        assert_eq!(messages, sorted);
+
+        let earlier = SignatureMessage::from_date(1999, 12, 31).unwrap();
+        let later = SignatureMessage::from_date(2000, 1, 1).unwrap();
+        assert!(earlier < later);
+
+        // Within the range predicates can be proven over
+        assert_eq!(SignatureMessage::from_i64(-offset).to_u64().unwrap(), 0);
+        assert_eq!(
+            SignatureMessage::from_i64(offset - 1).to_u64().unwrap(),
+            (1 << 58) - 1
+        );
+    }
+
+    #[test]
+    fn schema() {
+        let schema = MessageSchema::new(vec![
+            MessageEncoding::Hash,
+            MessageEncoding::U64,
+            MessageEncoding::Date,
+            MessageEncoding::Decimal(2),
+            MessageEncoding::Bytes,
+        ]);
+        let values = vec![
+            MessageValue::Hashed(SignatureMessage::hash(b"Alice")),
+            MessageValue::U64(42),
+            MessageValue::Date(1990, 6, 1),
+            MessageValue::Decimal("19.99".to_string()),
+            MessageValue::Bytes(b"US".to_vec()),
+        ];
+        let messages = schema.encode(&values).unwrap();
+        let revealed = [1usize, 2, 3, 4]
+            .iter()
+            .map(|i| (*i, messages[*i]))
+            .collect::<BTreeMap<usize, SignatureMessage>>();
+        let decoded = schema.decode(&revealed).unwrap();
+        for (i, v) in decoded {
+            assert_eq!(v, values[i]);
+        }
+        assert!(schema.encode(&values[1..]).is_err());
+        assert!(MessageEncoding::U64.encode(&values[2]).is_err());
+
+        let bytes = schema.to_bytes_compressed_form();
+        assert_eq!(MessageSchema::try_from(bytes.as_slice()).unwrap(), schema);
+        assert!(MessageSchema::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -51,6 +51,12 @@ pub enum BBSErrorKind {
         /// The status of the invalid proof
         status: PoKOfSignatureProofStatus,
     },
+    /// One or more items in a batch failed verification
+    #[fail(display = "Batch verification failed for items {:?}", indices)]
+    BatchVerificationFailed {
+        /// The indices of the items that failed
+        indices: Vec<usize>,
+    },
     /// A Generic error
     #[fail(display = "{:?}", msg)]
     GeneralError {
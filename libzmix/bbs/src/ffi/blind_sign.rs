@@ -0,0 +1,276 @@
+use super::{finish_context, missing, output_bytes, ByteArray};
+use crate::errors::prelude::*;
+use crate::issuer::Issuer;
+use crate::keys::prelude::*;
+use crate::prover::Prover;
+use crate::signature::prelude::*;
+use crate::{
+    BlindSignatureContext, HashElem, ProofNonce, SignatureBlinding, SignatureMessage,
+    ToVariableLengthBytes,
+};
+use ffi_support::{ByteBuffer, ConcurrentHandleMap, ExternError};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// The inputs collected by `bbs_blind_commitment_context_*`
+#[derive(Debug, Default)]
+pub struct BlindCommitmentContext {
+    public_key: Option<PublicKey>,
+    nonce: Option<ProofNonce>,
+    messages: BTreeMap<usize, SignatureMessage>,
+}
+
+/// The inputs collected by `bbs_blind_sign_context_*`
+#[derive(Debug, Default)]
+pub struct BlindSignContext {
+    secret_key: Option<SecretKey>,
+    public_key: Option<PublicKey>,
+    commitment: Option<BlindSignatureContext>,
+    nonce: Option<ProofNonce>,
+    messages: BTreeMap<usize, SignatureMessage>,
+}
+
+lazy_static::lazy_static! {
+    /// The open blind commitment contexts
+    pub static ref BLIND_COMMITMENT_CONTEXT: ConcurrentHandleMap<BlindCommitmentContext> =
+        ConcurrentHandleMap::new();
+    /// The open blind signing contexts
+    pub static ref BLIND_SIGN_CONTEXT: ConcurrentHandleMap<BlindSignContext> =
+        ConcurrentHandleMap::new();
+}
+
+/// Remove a blind commitment context that will not be finished
+#[no_mangle]
+pub extern "C" fn bbs_blind_commitment_context_free(handle: u64, err: &mut ExternError) {
+    ffi_support::call_with_result(err, || BLIND_COMMITMENT_CONTEXT.delete_u64(handle))
+}
+/// Remove a blind signing context that will not be finished
+#[no_mangle]
+pub extern "C" fn bbs_blind_sign_context_free(handle: u64, err: &mut ExternError) {
+    ffi_support::call_with_result(err, || BLIND_SIGN_CONTEXT.delete_u64(handle))
+}
+
+/// Create a context for committing to the messages the issuer will not see.
+/// Returns 0 if an error occurs.
+#[no_mangle]
+pub extern "C" fn bbs_blind_commitment_context_init(err: &mut ExternError) -> u64 {
+    BLIND_COMMITMENT_CONTEXT.insert_with_output(err, BlindCommitmentContext::default)
+}
+
+/// Set the public key of the issuer
+#[no_mangle]
+pub extern "C" fn bbs_blind_commitment_context_set_public_key(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    BLIND_COMMITMENT_CONTEXT.call_with_result_mut(
+        err,
+        handle,
+        move |ctx| -> Result<i32, BBSError> {
+            ctx.public_key = Some(PublicKey::try_from(value.as_slice())?);
+            Ok(1)
+        },
+    )
+}
+
+/// Set the nonce from the issuer. The bytes are hashed to a nonce.
+#[no_mangle]
+pub extern "C" fn bbs_blind_commitment_context_set_nonce_bytes(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    BLIND_COMMITMENT_CONTEXT.call_with_result_mut(
+        err,
+        handle,
+        move |ctx| -> Result<i32, BBSError> {
+            ctx.nonce = Some(ProofNonce::hash(value));
+            Ok(1)
+        },
+    )
+}
+
+/// Add a message to commit to at `index`. The bytes are hashed to a message.
+#[no_mangle]
+pub extern "C" fn bbs_blind_commitment_context_add_message_bytes(
+    handle: u64,
+    index: u32,
+    message: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let message = message.to_vec();
+    BLIND_COMMITMENT_CONTEXT.call_with_result_mut(
+        err,
+        handle,
+        move |ctx| -> Result<i32, BBSError> {
+            ctx.messages
+                .insert(index as usize, SignatureMessage::hash(message));
+            Ok(1)
+        },
+    )
+}
+
+/// Commit to the messages and remove the context.
+/// `commitment` is sent to the issuer and `blinding_factor` is kept to unblind the signature.
+/// Caller will need to call `bbs_bytebuffer_free` on `commitment` and `blinding_factor`
+/// to free the memory.
+/// If an error occurs, caller will need to call `bbs_string_free`
+/// on `err.message` to free the memory.
+#[no_mangle]
+pub extern "C" fn bbs_blind_commitment_context_finish(
+    handle: u64,
+    commitment: &mut ByteBuffer,
+    blinding_factor: &mut ByteBuffer,
+    err: &mut ExternError,
+) -> i32 {
+    let mut blinding = None;
+    *commitment = finish_context(
+        &BLIND_COMMITMENT_CONTEXT,
+        handle,
+        err,
+        |ctx: BlindCommitmentContext| {
+            let public_key = ctx.public_key.ok_or_else(|| missing("public key"))?;
+            let nonce = ctx.nonce.ok_or_else(|| missing("nonce"))?;
+            let (context, b) =
+                Prover::new_blind_signature_context(&public_key, &ctx.messages, &nonce)?;
+            blinding = Some(b);
+            Ok(ByteBuffer::from_vec(context.to_bytes_compressed_form()))
+        },
+    );
+    match blinding {
+        Some(b) => {
+            *blinding_factor = ByteBuffer::from_vec(b.to_bytes_compressed_form().to_vec());
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Create a context for signing a commitment and the known messages.
+/// Returns 0 if an error occurs.
+#[no_mangle]
+pub extern "C" fn bbs_blind_sign_context_init(err: &mut ExternError) -> u64 {
+    BLIND_SIGN_CONTEXT.insert_with_output(err, BlindSignContext::default)
+}
+
+/// Set the secret key to sign with
+#[no_mangle]
+pub extern "C" fn bbs_blind_sign_context_set_secret_key(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    BLIND_SIGN_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.secret_key = Some(SecretKey::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Set the public key to sign with
+#[no_mangle]
+pub extern "C" fn bbs_blind_sign_context_set_public_key(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    BLIND_SIGN_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.public_key = Some(PublicKey::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Set the commitment from `bbs_blind_commitment_context_finish`
+#[no_mangle]
+pub extern "C" fn bbs_blind_sign_context_set_commitment(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    BLIND_SIGN_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.commitment = Some(BlindSignatureContext::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Set the nonce that was sent to the prover. The bytes are hashed to a nonce.
+#[no_mangle]
+pub extern "C" fn bbs_blind_sign_context_set_nonce_bytes(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    BLIND_SIGN_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.nonce = Some(ProofNonce::hash(value));
+        Ok(1)
+    })
+}
+
+/// Add a known message to sign at `index`. The bytes are hashed to a message.
+#[no_mangle]
+pub extern "C" fn bbs_blind_sign_context_add_message_bytes(
+    handle: u64,
+    index: u32,
+    message: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let message = message.to_vec();
+    BLIND_SIGN_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.messages
+            .insert(index as usize, SignatureMessage::hash(message));
+        Ok(1)
+    })
+}
+
+/// Verify the commitment, sign it and the known messages and remove the context.
+/// Caller will need to call `bbs_bytebuffer_free` on `blind_signature` to free the memory.
+/// If an error occurs, caller will need to call `bbs_string_free`
+/// on `err.message` to free the memory.
+#[no_mangle]
+pub extern "C" fn bbs_blind_sign_context_finish(
+    handle: u64,
+    blind_signature: &mut ByteBuffer,
+    err: &mut ExternError,
+) -> i32 {
+    *blind_signature = finish_context(&BLIND_SIGN_CONTEXT, handle, err, |ctx| {
+        let secret_key = ctx.secret_key.ok_or_else(|| missing("secret key"))?;
+        let public_key = ctx.public_key.ok_or_else(|| missing("public key"))?;
+        let commitment = ctx.commitment.ok_or_else(|| missing("commitment"))?;
+        let nonce = ctx.nonce.ok_or_else(|| missing("nonce"))?;
+        let signature =
+            Issuer::blind_sign(&commitment, &ctx.messages, &secret_key, &public_key, &nonce)?;
+        Ok(ByteBuffer::from_vec(
+            signature.to_bytes_compressed_form().to_vec(),
+        ))
+    });
+    err.get_code().is_success() as i32
+}
+
+/// Unblind a blind signature with the blinding factor from `bbs_blind_commitment_context_finish`.
+/// Caller will need to call `bbs_bytebuffer_free` on `signature` to free the memory.
+/// If an error occurs, caller will need to call `bbs_string_free`
+/// on `err.message` to free the memory.
+#[no_mangle]
+pub extern "C" fn bbs_unblind_signature(
+    blind_signature: &ByteArray,
+    blinding_factor: &ByteArray,
+    signature: &mut ByteBuffer,
+    err: &mut ExternError,
+) -> i32 {
+    let blind_signature = blind_signature.to_vec();
+    let blinding_factor = blinding_factor.to_vec();
+    output_bytes(signature, err, move || {
+        let blind_signature = BlindSignature::try_from(blind_signature.as_slice())?;
+        let blinding_factor = SignatureBlinding::try_from(blinding_factor.as_slice())?;
+        Ok(blind_signature
+            .to_unblinded(&blinding_factor)
+            .to_bytes_compressed_form()
+            .to_vec())
+    })
+}
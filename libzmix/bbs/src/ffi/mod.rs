@@ -0,0 +1,474 @@
+// FFI functions for BBS+ keys, signatures and proofs
+//
+// Signing, blind signing and proving use contexts that are created with `*_init`,
+// filled in with the `*_set_*`, `*_add_*` and `*_reveal` functions and consumed by `*_finish`.
+// `*_finish` removes the context whether it succeeds or not. Contexts that are abandoned
+// before `*_finish` are removed with `*_free`.
+//
+// Example of how to sign and verify from C
+// #include "bbs.h"
+//
+// int sign_and_verify(struct ByteArray* message) {
+//     struct ByteBuffer public_key;
+//     struct ByteBuffer secret_key;
+//     struct ByteBuffer signature;
+//     struct ExternError err;
+//     uint64_t handle;
+//     int32_t valid;
+//
+//     if (!bbs_generate_key_pair(1, &public_key, &secret_key, &err)) {
+//         bbs_string_free(err.message);
+//         return 0;
+//     }
+//
+//     handle = bbs_sign_context_init(&err);
+//     bbs_sign_context_set_secret_key(handle, (struct ByteArray*)&secret_key, &err);
+//     bbs_sign_context_set_public_key(handle, (struct ByteArray*)&public_key, &err);
+//     bbs_sign_context_add_message_bytes(handle, message, &err);
+//     if (!bbs_sign_context_finish(handle, &signature, &err)) {
+//         bbs_string_free(err.message);
+//         return 0;
+//     }
+//
+//     handle = bbs_verify_context_init(&err);
+//     bbs_verify_context_set_public_key(handle, (struct ByteArray*)&public_key, &err);
+//     bbs_verify_context_set_signature(handle, (struct ByteArray*)&signature, &err);
+//     bbs_verify_context_add_message_bytes(handle, message, &err);
+//     valid = bbs_verify_context_finish(handle, &err);
+//
+//     bbs_bytebuffer_free(public_key);
+//     bbs_bytebuffer_free(secret_key);
+//     bbs_bytebuffer_free(signature);
+//     return valid;
+// }
+
+/// Contexts for blind signing and unblinding
+pub mod blind_sign;
+/// Contexts for creating and verifying signature proofs of knowledge
+pub mod proof;
+/// Contexts for signing and verifying signatures
+pub mod sign;
+
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::signature::prelude::*;
+use crate::{ToVariableLengthBytes, FR_COMPRESSED_SIZE};
+use ffi_support::{ByteBuffer, ConcurrentHandleMap, ErrorCode, ExternError, IntoFfi};
+use std::convert::TryFrom;
+
+/// Used for receiving a ByteBuffer from C that was allocated by either C or Rust.
+/// If Rust allocated, then the outgoing struct is ffi_support::ByteBuffer
+/// Caller is responsible for calling free where applicable.
+///
+/// C will not notice a difference and can use the same struct
+#[repr(C)]
+pub struct ByteArray {
+    length: usize,
+    data: *const u8,
+}
+
+impl Default for ByteArray {
+    fn default() -> ByteArray {
+        ByteArray {
+            length: 0,
+            data: std::ptr::null(),
+        }
+    }
+}
+
+impl ByteArray {
+    /// Copy the bytes into a vector
+    pub fn to_vec(&self) -> Vec<u8> {
+        if self.data.is_null() || self.length == 0 {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(self.data, self.length).to_vec() }
+        }
+    }
+
+    /// Copy the bytes into a vector or `None` if `data` is null
+    pub fn to_opt_vec(&self) -> Option<Vec<u8>> {
+        if self.data.is_null() {
+            None
+        } else if self.length == 0 {
+            Some(Vec::new())
+        } else {
+            Some(unsafe { std::slice::from_raw_parts(self.data, self.length).to_vec() })
+        }
+    }
+}
+
+impl From<&Vec<u8>> for ByteArray {
+    fn from(input: &Vec<u8>) -> ByteArray {
+        ByteArray {
+            length: input.len(),
+            data: input.as_slice().as_ptr(),
+        }
+    }
+}
+
+impl From<&[u8]> for ByteArray {
+    fn from(input: &[u8]) -> ByteArray {
+        ByteArray {
+            length: input.len(),
+            data: input.as_ptr(),
+        }
+    }
+}
+
+/// Free the memory of a `ByteBuffer` returned by any of these functions
+#[no_mangle]
+pub extern "C" fn bbs_bytebuffer_free(buffer: ByteBuffer) {
+    ffi_support::abort_on_panic::with_abort_on_panic(|| buffer.destroy())
+}
+
+/// Free the memory of an `ExternError` message
+///
+/// # Safety
+/// `s` must be null or a message from an `ExternError` that was not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn bbs_string_free(s: *mut std::os::raw::c_char) {
+    ffi_support::abort_on_panic::with_abort_on_panic(|| ffi_support::destroy_c_string(s))
+}
+
+/// The `ExternError` codes for each `BBSErrorKind`
+pub mod bbs_error_codes {
+    /// `BBSErrorKind::KeyGenError`
+    pub const KEY_GEN_ERROR: i32 = 1;
+    /// `BBSErrorKind::PublicKeyGeneratorMessageCountMismatch`
+    pub const MESSAGE_COUNT_MISMATCH: i32 = 2;
+    /// `BBSErrorKind::SignatureIncorrectSize`
+    pub const SIGNATURE_INCORRECT_SIZE: i32 = 3;
+    /// `BBSErrorKind::SignatureValueIncorrectSize`
+    pub const SIGNATURE_VALUE_INCORRECT_SIZE: i32 = 4;
+    /// `BBSErrorKind::MalformedSignature`
+    pub const MALFORMED_SIGNATURE: i32 = 5;
+    /// `BBSErrorKind::MalformedSecretKey`
+    pub const MALFORMED_SECRET_KEY: i32 = 6;
+    /// `BBSErrorKind::MalformedPublicKey`
+    pub const MALFORMED_PUBLIC_KEY: i32 = 7;
+    /// `BBSErrorKind::PoKVCError`
+    pub const POKVC_ERROR: i32 = 8;
+    /// `BBSErrorKind::InvalidNumberOfBytes`
+    pub const INVALID_NUMBER_OF_BYTES: i32 = 9;
+    /// `BBSErrorKind::InvalidProof`
+    pub const INVALID_PROOF: i32 = 10;
+    /// `BBSErrorKind::BatchVerificationFailed`
+    pub const BATCH_VERIFICATION_FAILED: i32 = 11;
+    /// `BBSErrorKind::GeneralError`
+    pub const GENERAL_ERROR: i32 = 12;
+}
+
+impl From<BBSError> for ExternError {
+    fn from(err: BBSError) -> Self {
+        let code = match err.kind() {
+            BBSErrorKind::KeyGenError => bbs_error_codes::KEY_GEN_ERROR,
+            BBSErrorKind::PublicKeyGeneratorMessageCountMismatch(..) => {
+                bbs_error_codes::MESSAGE_COUNT_MISMATCH
+            }
+            BBSErrorKind::SignatureIncorrectSize(..) => bbs_error_codes::SIGNATURE_INCORRECT_SIZE,
+            BBSErrorKind::SignatureValueIncorrectSize => {
+                bbs_error_codes::SIGNATURE_VALUE_INCORRECT_SIZE
+            }
+            BBSErrorKind::MalformedSignature => bbs_error_codes::MALFORMED_SIGNATURE,
+            BBSErrorKind::MalformedSecretKey => bbs_error_codes::MALFORMED_SECRET_KEY,
+            BBSErrorKind::MalformedPublicKey => bbs_error_codes::MALFORMED_PUBLIC_KEY,
+            BBSErrorKind::PoKVCError { .. } => bbs_error_codes::POKVC_ERROR,
+            BBSErrorKind::InvalidNumberOfBytes(..) => bbs_error_codes::INVALID_NUMBER_OF_BYTES,
+            BBSErrorKind::InvalidProof { .. } => bbs_error_codes::INVALID_PROOF,
+            BBSErrorKind::BatchVerificationFailed { .. } => {
+                bbs_error_codes::BATCH_VERIFICATION_FAILED
+            }
+            BBSErrorKind::GeneralError { .. } => bbs_error_codes::GENERAL_ERROR,
+        };
+        ExternError::new_error(ErrorCode::new(code), err.to_string())
+    }
+}
+
+/// Return the number of bytes in a blinding factor - 32 bytes
+#[no_mangle]
+pub extern "C" fn bbs_blinding_factor_size() -> i32 {
+    FR_COMPRESSED_SIZE as i32
+}
+
+/// Return the number of bytes in a secret key - 32 bytes
+#[no_mangle]
+pub extern "C" fn bbs_secret_key_size() -> i32 {
+    FR_COMPRESSED_SIZE as i32
+}
+
+/// Return the number of bytes in a short public key - 96 bytes
+#[no_mangle]
+pub extern "C" fn bbs_short_public_key_size() -> i32 {
+    DETERMINISTIC_PUBLIC_KEY_COMPRESSED_SIZE as i32
+}
+
+/// Return the number of bytes in a signature or blind signature - 112 bytes
+#[no_mangle]
+pub extern "C" fn bbs_signature_size() -> i32 {
+    SIGNATURE_COMPRESSED_SIZE as i32
+}
+
+/// Create a new keypair that can sign `message_count` messages.
+/// Caller will need to call `bbs_bytebuffer_free` on `public_key` and `secret_key`
+/// to free the memory.
+/// If an error occurs, caller will need to call `bbs_string_free`
+/// on `err.message` to free the memory.
+#[no_mangle]
+pub extern "C" fn bbs_generate_key_pair(
+    message_count: u32,
+    public_key: &mut ByteBuffer,
+    secret_key: &mut ByteBuffer,
+    err: &mut ExternError,
+) -> i32 {
+    let mut sk = None;
+    *public_key = ffi_support::call_with_result(
+        err,
+        std::panic::AssertUnwindSafe(|| -> Result<ByteBuffer, BBSError> {
+            let (pk, s) = generate(message_count as usize)?;
+            sk = Some(s);
+            Ok(ByteBuffer::from_vec(pk.to_bytes_compressed_form()))
+        }),
+    );
+    match sk {
+        Some(s) => {
+            *secret_key = ByteBuffer::from_vec(s.to_bytes_compressed_form().to_vec());
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Create a new keypair with a short public key.
+/// If `seed` is null the keys are random otherwise they are derived from `seed`.
+/// Caller will need to call `bbs_bytebuffer_free` on `public_key` and `secret_key`
+/// to free the memory.
+/// If an error occurs, caller will need to call `bbs_string_free`
+/// on `err.message` to free the memory.
+#[no_mangle]
+pub extern "C" fn bbs_generate_short_key_pair(
+    seed: &ByteArray,
+    public_key: &mut ByteBuffer,
+    secret_key: &mut ByteBuffer,
+    err: &mut ExternError,
+) -> i32 {
+    let seed = seed.to_opt_vec();
+    let mut sk = None;
+    *public_key = ffi_support::call_with_result(
+        err,
+        std::panic::AssertUnwindSafe(|| -> Result<ByteBuffer, BBSError> {
+            let (dpk, s) = DeterministicPublicKey::new(seed.map(KeyGenOption::UseSeed))?;
+            sk = Some(s);
+            Ok(ByteBuffer::from_vec(
+                dpk.to_bytes_compressed_form().to_vec(),
+            ))
+        }),
+    );
+    match sk {
+        Some(s) => {
+            *secret_key = ByteBuffer::from_vec(s.to_bytes_compressed_form().to_vec());
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Convert a short public key to a public key that can sign `message_count` messages.
+/// Caller will need to call `bbs_bytebuffer_free` on `public_key` to free the memory.
+/// If an error occurs, caller will need to call `bbs_string_free`
+/// on `err.message` to free the memory.
+#[no_mangle]
+pub extern "C" fn bbs_short_to_public_key(
+    short_public_key: &ByteArray,
+    message_count: u32,
+    public_key: &mut ByteBuffer,
+    err: &mut ExternError,
+) -> i32 {
+    let short_public_key = short_public_key.to_vec();
+    output_bytes(public_key, err, move || {
+        let dpk = DeterministicPublicKey::try_from(short_public_key.as_slice())?;
+        Ok(dpk
+            .to_public_key(message_count as usize)?
+            .to_bytes_compressed_form())
+    })
+}
+
+/// Run `f` and write its bytes to `out`.
+/// Returns 1 if `f` succeeds and 0 if it fails and `err` is set.
+pub(crate) fn output_bytes<F>(out: &mut ByteBuffer, err: &mut ExternError, f: F) -> i32
+where
+    F: std::panic::UnwindSafe + FnOnce() -> Result<Vec<u8>, BBSError>,
+{
+    *out = ffi_support::call_with_result(err, || f().map(ByteBuffer::from_vec));
+    err.get_code().is_success() as i32
+}
+
+/// Remove the context for `handle` and finish it with `f`.
+/// The context is removed whether `f` succeeds or not.
+pub(crate) fn finish_context<T, R, F>(
+    map: &ConcurrentHandleMap<T>,
+    handle: u64,
+    err: &mut ExternError,
+    f: F,
+) -> R::Value
+where
+    F: FnOnce(T) -> Result<R, BBSError>,
+    R: IntoFfi,
+{
+    ffi_support::call_with_result(
+        err,
+        std::panic::AssertUnwindSafe(|| -> Result<R, ExternError> {
+            let ctx = map.remove_u64(handle)?.ok_or_else(|| {
+                ExternError::new_error(ErrorCode::INVALID_HANDLE, "The context is no longer valid")
+            })?;
+            Ok(f(ctx)?)
+        }),
+    )
+}
+
+/// The error for a context that is finished before `name` is set
+pub(crate) fn missing(name: &str) -> BBSError {
+    BBSError::from_kind(BBSErrorKind::GeneralError {
+        msg: format!("The {} must be set before finish", name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::proof::*;
+    use crate::ffi::sign::*;
+    use std::collections::BTreeMap;
+
+    fn success() -> ExternError {
+        ExternError::success()
+    }
+
+    #[test]
+    fn sign_verify_and_prove() {
+        let mut err = success();
+        let mut public_key = ByteBuffer::default();
+        let mut secret_key = ByteBuffer::default();
+        assert_eq!(
+            bbs_generate_key_pair(3, &mut public_key, &mut secret_key, &mut err),
+            1
+        );
+        let public_key = public_key.destroy_into_vec();
+        let secret_key = secret_key.destroy_into_vec();
+        let messages = [
+            b"message 1".to_vec(),
+            b"message 2".to_vec(),
+            b"message 3".to_vec(),
+        ];
+
+        let handle = bbs_sign_context_init(&mut err);
+        assert_eq!(
+            bbs_sign_context_set_secret_key(handle, &(&secret_key).into(), &mut err),
+            1
+        );
+        assert_eq!(
+            bbs_sign_context_set_public_key(handle, &(&public_key).into(), &mut err),
+            1
+        );
+        for m in messages.iter() {
+            assert_eq!(
+                bbs_sign_context_add_message_bytes(handle, &m.into(), &mut err),
+                1
+            );
+        }
+        let mut signature = ByteBuffer::default();
+        assert_eq!(bbs_sign_context_finish(handle, &mut signature, &mut err), 1);
+        let signature = signature.destroy_into_vec();
+        assert_eq!(signature.len(), bbs_signature_size() as usize);
+
+        let handle = bbs_verify_context_init(&mut err);
+        bbs_verify_context_set_public_key(handle, &(&public_key).into(), &mut err);
+        bbs_verify_context_set_signature(handle, &(&signature).into(), &mut err);
+        for m in messages.iter() {
+            bbs_verify_context_add_message_bytes(handle, &m.into(), &mut err);
+        }
+        assert_eq!(bbs_verify_context_finish(handle, &mut err), 1);
+
+        let nonce = b"proof nonce".to_vec();
+        let handle = bbs_create_proof_context_init(&mut err);
+        bbs_create_proof_context_set_public_key(handle, &(&public_key).into(), &mut err);
+        bbs_create_proof_context_set_signature(handle, &(&signature).into(), &mut err);
+        bbs_create_proof_context_set_nonce_bytes(handle, &(&nonce).into(), &mut err);
+        for m in messages.iter() {
+            bbs_create_proof_context_add_message_bytes(handle, &m.into(), &mut err);
+        }
+        assert_eq!(bbs_create_proof_context_reveal(handle, 1, &mut err), 1);
+        let mut proof = ByteBuffer::default();
+        assert_eq!(
+            bbs_create_proof_context_finish(handle, &mut proof, &mut err),
+            1
+        );
+        let proof = proof.destroy_into_vec();
+
+        let handle = bbs_verify_proof_context_init(&mut err);
+        bbs_verify_proof_context_set_public_key(handle, &(&public_key).into(), &mut err);
+        bbs_verify_proof_context_set_proof(handle, &(&proof).into(), &mut err);
+        bbs_verify_proof_context_set_nonce_bytes(handle, &(&nonce).into(), &mut err);
+        bbs_verify_proof_context_add_message_bytes(handle, 1, &(&messages[1]).into(), &mut err);
+        assert_eq!(bbs_verify_proof_context_finish(handle, &mut err), 1);
+
+        let handle = bbs_verify_proof_context_init(&mut err);
+        bbs_verify_proof_context_set_public_key(handle, &(&public_key).into(), &mut err);
+        bbs_verify_proof_context_set_proof(handle, &(&proof).into(), &mut err);
+        bbs_verify_proof_context_set_nonce_bytes(handle, &(&nonce).into(), &mut err);
+        bbs_verify_proof_context_add_message_bytes(handle, 1, &(&messages[0]).into(), &mut err);
+        assert_eq!(bbs_verify_proof_context_finish(handle, &mut err), 0);
+    }
+
+    #[test]
+    fn finish_without_key() {
+        let mut err = success();
+        let handle = bbs_sign_context_init(&mut err);
+        let mut signature = ByteBuffer::default();
+        assert_eq!(bbs_sign_context_finish(handle, &mut signature, &mut err), 0);
+        assert_eq!(err.get_code().code(), bbs_error_codes::GENERAL_ERROR);
+        unsafe { err.manually_release() };
+
+        let mut err = success();
+        assert_eq!(bbs_sign_context_finish(handle, &mut signature, &mut err), 0);
+        assert_eq!(err.get_code(), ErrorCode::INVALID_HANDLE);
+        unsafe { err.manually_release() };
+    }
+
+    /// The name and parameter count of every function in `text`
+    /// where each declaration starts with `prefix`
+    fn declarations(text: &str, prefix: &str) -> BTreeMap<String, usize> {
+        text.split(prefix)
+            .skip(1)
+            .filter_map(|decl| {
+                let name = decl.split('(').next()?.split_whitespace().last()?;
+                if !name.starts_with("bbs_") {
+                    return None;
+                }
+                let params = decl.split('(').nth(1)?.split(')').next()?;
+                let count = params
+                    .split(',')
+                    .filter(|p| !p.trim().is_empty() && p.trim() != "void")
+                    .count();
+                Some((name.to_string(), count))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn header_matches_exported_functions() {
+        let header = include_str!("../../include/bbs.h");
+        let sources = [
+            include_str!("mod.rs"),
+            include_str!("blind_sign.rs"),
+            include_str!("proof.rs"),
+            include_str!("sign.rs"),
+        ];
+        let mut exported = BTreeMap::new();
+        for source in sources.iter() {
+            exported.append(&mut declarations(source, "extern \"C\" fn "));
+        }
+        assert_eq!(exported.len(), 51);
+        assert_eq!(declarations(header, "\nextern "), exported);
+    }
+}
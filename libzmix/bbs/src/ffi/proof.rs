@@ -0,0 +1,255 @@
+use super::{finish_context, missing, ByteArray};
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::messages::*;
+use crate::prover::Prover;
+use crate::signature::prelude::*;
+use crate::verifier::Verifier;
+use crate::{HashElem, ProofNonce, SignatureMessage, SignatureProof, ToVariableLengthBytes};
+use ffi_support::{ByteBuffer, ConcurrentHandleMap, ExternError};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+/// The inputs collected by `bbs_create_proof_context_*`
+#[derive(Debug, Default)]
+pub struct CreateProofContext {
+    public_key: Option<PublicKey>,
+    signature: Option<Signature>,
+    nonce: Option<ProofNonce>,
+    messages: Vec<SignatureMessage>,
+    revealed: BTreeSet<usize>,
+}
+
+/// The inputs collected by `bbs_verify_proof_context_*`
+#[derive(Debug, Default)]
+pub struct VerifyProofContext {
+    public_key: Option<PublicKey>,
+    proof: Option<SignatureProof>,
+    nonce: Option<ProofNonce>,
+    messages: BTreeMap<usize, SignatureMessage>,
+}
+
+lazy_static::lazy_static! {
+    /// The open proof creation contexts
+    pub static ref CREATE_PROOF_CONTEXT: ConcurrentHandleMap<CreateProofContext> =
+        ConcurrentHandleMap::new();
+    /// The open proof verification contexts
+    pub static ref VERIFY_PROOF_CONTEXT: ConcurrentHandleMap<VerifyProofContext> =
+        ConcurrentHandleMap::new();
+}
+
+/// Remove a proof creation context that will not be finished
+#[no_mangle]
+pub extern "C" fn bbs_create_proof_context_free(handle: u64, err: &mut ExternError) {
+    ffi_support::call_with_result(err, || CREATE_PROOF_CONTEXT.delete_u64(handle))
+}
+/// Remove a proof verification context that will not be finished
+#[no_mangle]
+pub extern "C" fn bbs_verify_proof_context_free(handle: u64, err: &mut ExternError) {
+    ffi_support::call_with_result(err, || VERIFY_PROOF_CONTEXT.delete_u64(handle))
+}
+
+/// Create a context for proving knowledge of a signature.
+/// Returns 0 if an error occurs.
+#[no_mangle]
+pub extern "C" fn bbs_create_proof_context_init(err: &mut ExternError) -> u64 {
+    CREATE_PROOF_CONTEXT.insert_with_output(err, CreateProofContext::default)
+}
+
+/// Set the public key of the signer
+#[no_mangle]
+pub extern "C" fn bbs_create_proof_context_set_public_key(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    CREATE_PROOF_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.public_key = Some(PublicKey::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Set the signature to prove knowledge of
+#[no_mangle]
+pub extern "C" fn bbs_create_proof_context_set_signature(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    CREATE_PROOF_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.signature = Some(Signature::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Set the nonce from the verifier. The bytes are hashed to a nonce.
+#[no_mangle]
+pub extern "C" fn bbs_create_proof_context_set_nonce_bytes(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    CREATE_PROOF_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.nonce = Some(ProofNonce::hash(value));
+        Ok(1)
+    })
+}
+
+/// Add the next signed message. The bytes are hashed to a message.
+/// Messages are hidden unless they are revealed with `bbs_create_proof_context_reveal`.
+#[no_mangle]
+pub extern "C" fn bbs_create_proof_context_add_message_bytes(
+    handle: u64,
+    message: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let message = message.to_vec();
+    CREATE_PROOF_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.messages.push(SignatureMessage::hash(message));
+        Ok(1)
+    })
+}
+
+/// Reveal the message at `index` in the proof
+#[no_mangle]
+pub extern "C" fn bbs_create_proof_context_reveal(
+    handle: u64,
+    index: u32,
+    err: &mut ExternError,
+) -> i32 {
+    CREATE_PROOF_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.revealed.insert(index as usize);
+        Ok(1)
+    })
+}
+
+/// Create the proof and remove the context.
+/// Caller will need to call `bbs_bytebuffer_free` on `proof` to free the memory.
+/// If an error occurs, caller will need to call `bbs_string_free`
+/// on `err.message` to free the memory.
+#[no_mangle]
+pub extern "C" fn bbs_create_proof_context_finish(
+    handle: u64,
+    proof: &mut ByteBuffer,
+    err: &mut ExternError,
+) -> i32 {
+    *proof = finish_context(&CREATE_PROOF_CONTEXT, handle, err, |ctx| {
+        let public_key = ctx.public_key.ok_or_else(|| missing("public key"))?;
+        let signature = ctx.signature.ok_or_else(|| missing("signature"))?;
+        let nonce = ctx.nonce.ok_or_else(|| missing("nonce"))?;
+        let revealed = ctx.revealed.iter().copied().collect::<Vec<usize>>();
+        let proof_request = Verifier::new_proof_request(revealed.as_slice(), &public_key)?;
+        let proof_messages = ctx
+            .messages
+            .into_iter()
+            .enumerate()
+            .map(|(i, m)| {
+                if proof_request.revealed_messages.contains(&i) {
+                    ProofMessage::Revealed(m)
+                } else {
+                    ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(m))
+                }
+            })
+            .collect::<Vec<ProofMessage>>();
+        let pok =
+            Prover::commit_signature_pok(&proof_request, proof_messages.as_slice(), &signature)?;
+        let challenge = Prover::create_challenge_hash(std::slice::from_ref(&pok), None, &nonce)?;
+        let proof = Prover::generate_signature_pok(pok, &challenge)?;
+        Ok(ByteBuffer::from_vec(proof.to_bytes_compressed_form()))
+    });
+    err.get_code().is_success() as i32
+}
+
+/// Create a context for verifying a proof of knowledge of a signature.
+/// Returns 0 if an error occurs.
+#[no_mangle]
+pub extern "C" fn bbs_verify_proof_context_init(err: &mut ExternError) -> u64 {
+    VERIFY_PROOF_CONTEXT.insert_with_output(err, VerifyProofContext::default)
+}
+
+/// Set the public key of the signer
+#[no_mangle]
+pub extern "C" fn bbs_verify_proof_context_set_public_key(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    VERIFY_PROOF_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.public_key = Some(PublicKey::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Set the proof to verify
+#[no_mangle]
+pub extern "C" fn bbs_verify_proof_context_set_proof(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    VERIFY_PROOF_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.proof = Some(SignatureProof::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Set the nonce that was sent to the prover. The bytes are hashed to a nonce.
+#[no_mangle]
+pub extern "C" fn bbs_verify_proof_context_set_nonce_bytes(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    VERIFY_PROOF_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.nonce = Some(ProofNonce::hash(value));
+        Ok(1)
+    })
+}
+
+/// Add a message the proof must reveal at `index`. The bytes are hashed to a message.
+#[no_mangle]
+pub extern "C" fn bbs_verify_proof_context_add_message_bytes(
+    handle: u64,
+    index: u32,
+    message: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let message = message.to_vec();
+    VERIFY_PROOF_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.messages
+            .insert(index as usize, SignatureMessage::hash(message));
+        Ok(1)
+    })
+}
+
+/// Verify the proof and remove the context.
+/// Returns 1 if the proof is valid and reveals exactly the added messages
+/// and 0 if it does not or an error occurs.
+/// If an error occurs, caller will need to call `bbs_string_free`
+/// on `err.message` to free the memory.
+#[no_mangle]
+pub extern "C" fn bbs_verify_proof_context_finish(handle: u64, err: &mut ExternError) -> i32 {
+    finish_context(&VERIFY_PROOF_CONTEXT, handle, err, |ctx| {
+        let public_key = ctx.public_key.ok_or_else(|| missing("public key"))?;
+        let proof = ctx.proof.ok_or_else(|| missing("proof"))?;
+        let nonce = ctx.nonce.ok_or_else(|| missing("nonce"))?;
+        if proof.revealed_messages != ctx.messages {
+            return Ok(0);
+        }
+        let revealed = ctx.messages.keys().copied().collect::<Vec<usize>>();
+        let proof_request = Verifier::new_proof_request(revealed.as_slice(), &public_key)?;
+        match Verifier::verify_signature_pok(&proof_request, &proof, &nonce) {
+            Ok(_) => Ok(1),
+            Err(e) => match e.kind() {
+                BBSErrorKind::InvalidProof { .. } => Ok(0),
+                _ => Err(e),
+            },
+        }
+    })
+}
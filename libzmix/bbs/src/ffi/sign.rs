@@ -0,0 +1,173 @@
+use super::{finish_context, missing, ByteArray};
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::signature::prelude::*;
+use crate::{HashElem, SignatureMessage};
+use ffi_support::{ByteBuffer, ConcurrentHandleMap, ExternError};
+use std::convert::TryFrom;
+
+/// The inputs collected by `bbs_sign_context_*`
+#[derive(Debug, Default)]
+pub struct SignContext {
+    secret_key: Option<SecretKey>,
+    public_key: Option<PublicKey>,
+    messages: Vec<SignatureMessage>,
+}
+
+/// The inputs collected by `bbs_verify_context_*`
+#[derive(Debug, Default)]
+pub struct VerifyContext {
+    public_key: Option<PublicKey>,
+    signature: Option<Signature>,
+    messages: Vec<SignatureMessage>,
+}
+
+lazy_static::lazy_static! {
+    /// The open signing contexts
+    pub static ref SIGN_CONTEXT: ConcurrentHandleMap<SignContext> = ConcurrentHandleMap::new();
+    /// The open verification contexts
+    pub static ref VERIFY_CONTEXT: ConcurrentHandleMap<VerifyContext> = ConcurrentHandleMap::new();
+}
+
+/// Remove a signing context that will not be finished
+#[no_mangle]
+pub extern "C" fn bbs_sign_context_free(handle: u64, err: &mut ExternError) {
+    ffi_support::call_with_result(err, || SIGN_CONTEXT.delete_u64(handle))
+}
+/// Remove a verification context that will not be finished
+#[no_mangle]
+pub extern "C" fn bbs_verify_context_free(handle: u64, err: &mut ExternError) {
+    ffi_support::call_with_result(err, || VERIFY_CONTEXT.delete_u64(handle))
+}
+
+/// Create a context for signing messages in order.
+/// Returns 0 if an error occurs.
+#[no_mangle]
+pub extern "C" fn bbs_sign_context_init(err: &mut ExternError) -> u64 {
+    SIGN_CONTEXT.insert_with_output(err, SignContext::default)
+}
+
+/// Set the secret key to sign with
+#[no_mangle]
+pub extern "C" fn bbs_sign_context_set_secret_key(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    SIGN_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.secret_key = Some(SecretKey::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Set the public key to sign with
+#[no_mangle]
+pub extern "C" fn bbs_sign_context_set_public_key(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    SIGN_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.public_key = Some(PublicKey::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Add the next message to sign. The bytes are hashed to a message.
+#[no_mangle]
+pub extern "C" fn bbs_sign_context_add_message_bytes(
+    handle: u64,
+    message: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let message = message.to_vec();
+    SIGN_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.messages.push(SignatureMessage::hash(message));
+        Ok(1)
+    })
+}
+
+/// Sign the messages and remove the context.
+/// Caller will need to call `bbs_bytebuffer_free` on `signature` to free the memory.
+/// If an error occurs, caller will need to call `bbs_string_free`
+/// on `err.message` to free the memory.
+#[no_mangle]
+pub extern "C" fn bbs_sign_context_finish(
+    handle: u64,
+    signature: &mut ByteBuffer,
+    err: &mut ExternError,
+) -> i32 {
+    *signature = finish_context(&SIGN_CONTEXT, handle, err, |ctx| {
+        let secret_key = ctx.secret_key.ok_or_else(|| missing("secret key"))?;
+        let public_key = ctx.public_key.ok_or_else(|| missing("public key"))?;
+        let signature = Signature::new(ctx.messages.as_slice(), &secret_key, &public_key)?;
+        Ok(ByteBuffer::from_vec(
+            signature.to_bytes_compressed_form().to_vec(),
+        ))
+    });
+    err.get_code().is_success() as i32
+}
+
+/// Create a context for verifying a signature on messages in order.
+/// Returns 0 if an error occurs.
+#[no_mangle]
+pub extern "C" fn bbs_verify_context_init(err: &mut ExternError) -> u64 {
+    VERIFY_CONTEXT.insert_with_output(err, VerifyContext::default)
+}
+
+/// Set the public key to verify with
+#[no_mangle]
+pub extern "C" fn bbs_verify_context_set_public_key(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    VERIFY_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.public_key = Some(PublicKey::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Set the signature to verify
+#[no_mangle]
+pub extern "C" fn bbs_verify_context_set_signature(
+    handle: u64,
+    value: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let value = value.to_vec();
+    VERIFY_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.signature = Some(Signature::try_from(value.as_slice())?);
+        Ok(1)
+    })
+}
+
+/// Add the next signed message. The bytes are hashed to a message.
+#[no_mangle]
+pub extern "C" fn bbs_verify_context_add_message_bytes(
+    handle: u64,
+    message: &ByteArray,
+    err: &mut ExternError,
+) -> i32 {
+    let message = message.to_vec();
+    VERIFY_CONTEXT.call_with_result_mut(err, handle, move |ctx| -> Result<i32, BBSError> {
+        ctx.messages.push(SignatureMessage::hash(message));
+        Ok(1)
+    })
+}
+
+/// Verify the signature and remove the context.
+/// Returns 1 if the signature is valid and 0 if it is not or an error occurs.
+/// If an error occurs, caller will need to call `bbs_string_free`
+/// on `err.message` to free the memory.
+#[no_mangle]
+pub extern "C" fn bbs_verify_context_finish(handle: u64, err: &mut ExternError) -> i32 {
+    finish_context(&VERIFY_CONTEXT, handle, err, |ctx| {
+        let public_key = ctx.public_key.ok_or_else(|| missing("public key"))?;
+        let signature = ctx.signature.ok_or_else(|| missing("signature"))?;
+        Ok(signature.verify(ctx.messages.as_slice(), &public_key)? as i32)
+    })
+}
@@ -0,0 +1,461 @@
+//! Implements the BBS signature ciphersuites defined in
+//! <https://datatracker.ietf.org/doc/draft-irtf-cfrg-bbs-signatures/>.
+//!
+//! Unlike the BBS+ signature in the rest of this crate, signatures are `(A, e)`
+//! and all generators are derived from the ciphersuite instead of the public key.
+//! The public key is just `W = BP2 * SK`, the same value as `DeterministicPublicKey`.
+//! Messages are octet strings mapped to scalars with `messages_to_scalars`.
+//!
+//! All encodings follow the draft so signatures and proofs interoperate with other
+//! implementations of the same ciphersuite.
+
+use crate::errors::prelude::*;
+use crate::keys::{DeterministicPublicKey, SecretKey};
+use crate::{GeneratorG1, SignatureMessage, FR_UNCOMPRESSED_SIZE};
+use blake2::digest::generic_array::GenericArray;
+use ff_zeroize::{Field, PrimeField};
+use pairing_plus::{
+    bls12_381::{Fr, FrRepr, G1, G2},
+    hash_to_field::{BaseFromRO, ExpandMsg, ExpandMsgXmd, ExpandMsgXof},
+    serdes::SerDes,
+    CurveProjective,
+};
+use rand::prelude::*;
+
+mod hash_to_curve;
+/// Methods and structs for creating ciphersuite proofs of knowledge
+pub mod proof;
+/// Methods and structs for creating ciphersuite signatures
+pub mod signature;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{
+        create_generators, keygen, messages_to_scalars, proof::Proof, signature::Signature,
+        sk_to_pk, Ciphersuite,
+    };
+}
+
+/// The ciphersuites defined in the draft
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Ciphersuite {
+    /// BLS12-381 with expand_message_xmd using SHA-256
+    Bls12381Sha256,
+    /// BLS12-381 with expand_message_xof using SHAKE-256
+    Bls12381Shake256,
+}
+
+impl Ciphersuite {
+    /// The ciphersuite identifier
+    pub fn id(self) -> &'static [u8] {
+        match self {
+            Ciphersuite::Bls12381Sha256 => b"BBS_BLS12381G1_XMD:SHA-256_SSWU_RO_",
+            Ciphersuite::Bls12381Shake256 => b"BBS_BLS12381G1_XOF:SHAKE-256_SSWU_RO_",
+        }
+    }
+
+    /// The api identifier used by the interface that hashes messages to scalars
+    /// i.e. ciphersuite_id || "H2G_HM2S_"
+    pub fn api_id(self) -> Vec<u8> {
+        self.with_id(b"H2G_HM2S_")
+    }
+
+    /// The base point `P1` of the ciphersuite
+    pub fn p1(self) -> GeneratorG1 {
+        let mut seed = self.api_id();
+        seed.extend_from_slice(b"BP_MESSAGE_GENERATOR_SEED");
+        self.generators_from_seed(&seed, 1)[0]
+    }
+
+    /// Hash an octet string to a scalar as described in section 4.2.1
+    pub fn hash_to_scalar<I: AsRef<[u8]>, D: AsRef<[u8]>>(self, msg: I, dst: D) -> Fr {
+        let okm = self.expand_message(msg.as_ref(), dst.as_ref(), FR_UNCOMPRESSED_SIZE);
+        Fr::from_okm(GenericArray::from_slice(&okm[..]))
+    }
+
+    pub(crate) fn with_id(self, suffix: &[u8]) -> Vec<u8> {
+        let mut out = self.id().to_vec();
+        out.extend_from_slice(suffix);
+        out
+    }
+
+    pub(crate) fn with_api_id(self, suffix: &[u8]) -> Vec<u8> {
+        let mut out = self.api_id();
+        out.extend_from_slice(suffix);
+        out
+    }
+
+    pub(crate) fn expand_message(self, msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+        match self {
+            Ciphersuite::Bls12381Sha256 => {
+                ExpandMsgXmd::<sha2::Sha256>::expand_message(msg, dst, len)
+            }
+            Ciphersuite::Bls12381Shake256 => {
+                ExpandMsgXof::<sha3::Shake256>::expand_message(msg, dst, len)
+            }
+        }
+    }
+
+    pub(crate) fn hash_to_curve_g1(self, msg: &[u8], dst: &[u8]) -> G1 {
+        match self {
+            Ciphersuite::Bls12381Sha256 => {
+                hash_to_curve::hash_to_curve_g1::<ExpandMsgXmd<sha2::Sha256>>(msg, dst)
+            }
+            Ciphersuite::Bls12381Shake256 => {
+                hash_to_curve::hash_to_curve_g1::<ExpandMsgXof<sha3::Shake256>>(msg, dst)
+            }
+        }
+    }
+
+    /// Section 4.1.1 with an explicit generator seed
+    pub(crate) fn generators_from_seed(self, seed: &[u8], count: usize) -> Vec<GeneratorG1> {
+        let seed_dst = self.with_api_id(b"SIG_GENERATOR_SEED_");
+        let generator_dst = self.with_api_id(b"SIG_GENERATOR_DST_");
+        let mut v = self.expand_message(seed, &seed_dst, FR_UNCOMPRESSED_SIZE);
+        let mut generators = Vec::with_capacity(count);
+        for i in 1..=count {
+            v.extend_from_slice(&(i as u64).to_be_bytes()[..]);
+            v = self.expand_message(&v, &seed_dst, FR_UNCOMPRESSED_SIZE);
+            generators.push(GeneratorG1(self.hash_to_curve_g1(&v, &generator_dst)));
+        }
+        generators
+    }
+}
+
+/// Generate a secret key from `key_material` and `key_info` as described in section 3.4.1.
+/// `key_dst` defaults to api_id || "KEYGEN_DST_".
+pub fn keygen(
+    suite: Ciphersuite,
+    key_material: &[u8],
+    key_info: &[u8],
+    key_dst: Option<&[u8]>,
+) -> Result<SecretKey, BBSError> {
+    if key_material.len() < 32 {
+        return Err(BBSErrorKind::GeneralError {
+            msg: format!(
+                "Key material must be at least 32 bytes, found {}",
+                key_material.len()
+            ),
+        }
+        .into());
+    }
+    if key_info.len() > u16::MAX as usize {
+        return Err(BBSErrorKind::GeneralError {
+            msg: format!("Key info is too long, found {}", key_info.len()),
+        }
+        .into());
+    }
+    let key_dst = key_dst
+        .map(|d| d.to_vec())
+        .unwrap_or_else(|| suite.with_api_id(b"KEYGEN_DST_"));
+
+    let mut derive_input = key_material.to_vec();
+    derive_input.extend_from_slice(&(key_info.len() as u16).to_be_bytes()[..]);
+    derive_input.extend_from_slice(key_info);
+
+    let sk = SecretKey(suite.hash_to_scalar(&derive_input, &key_dst));
+    sk.validate()?;
+    Ok(sk)
+}
+
+/// Compute the public key `W = BP2 * SK`
+pub fn sk_to_pk(sk: &SecretKey) -> DeterministicPublicKey {
+    let mut w = G2::one();
+    w.mul_assign(sk.0);
+    DeterministicPublicKey(w)
+}
+
+/// Create `count` generators for the ciphersuite as described in section 4.1.1.
+/// The first generator is `Q_1`, the remaining ones are the message generators `H_i`.
+pub fn create_generators(suite: Ciphersuite, count: usize) -> Vec<GeneratorG1> {
+    let mut seed = suite.api_id();
+    seed.extend_from_slice(b"MESSAGE_GENERATOR_SEED");
+    suite.generators_from_seed(&seed, count)
+}
+
+/// Map each octet string message to a scalar as described in section 4.1.2
+pub fn messages_to_scalars<M: AsRef<[u8]>>(
+    suite: Ciphersuite,
+    messages: &[M],
+) -> Vec<SignatureMessage> {
+    let dst = suite.with_api_id(b"MAP_MSG_TO_SCALAR_AS_HASH_");
+    messages
+        .iter()
+        .map(|m| SignatureMessage(suite.hash_to_scalar(m.as_ref(), &dst)))
+        .collect()
+}
+
+/// Compute the domain value that binds a signature to the public key, generators and header
+pub(crate) fn calculate_domain(
+    suite: Ciphersuite,
+    pk: &DeterministicPublicKey,
+    q_1: &GeneratorG1,
+    h_points: &[GeneratorG1],
+    header: &[u8],
+) -> Fr {
+    let mut dom_input = Vec::new();
+    push_g2(&mut dom_input, &pk.0);
+    push_int(&mut dom_input, h_points.len());
+    push_g1(&mut dom_input, &q_1.0);
+    for h in h_points {
+        push_g1(&mut dom_input, &h.0);
+    }
+    dom_input.extend_from_slice(&suite.api_id());
+    push_int(&mut dom_input, header.len());
+    dom_input.extend_from_slice(header);
+    suite.hash_to_scalar(&dom_input, suite.with_api_id(b"H2S_"))
+}
+
+/// Compute B = P1 + Q_1 * domain + H_1 * msg_1 + ... + H_L * msg_L
+pub(crate) fn compute_b(
+    suite: Ciphersuite,
+    q_1: &GeneratorG1,
+    h_points: &[GeneratorG1],
+    domain: &Fr,
+    messages: &[SignatureMessage],
+) -> G1 {
+    let mut bases = Vec::with_capacity(messages.len() + 2);
+    let mut scalars = Vec::with_capacity(messages.len() + 2);
+    bases.push(suite.p1().0);
+    scalars.push(Fr::from_repr(FrRepr::from(1)).unwrap());
+    bases.push(q_1.0);
+    scalars.push(*domain);
+    for (h, m) in h_points.iter().zip(messages.iter()) {
+        bases.push(h.0);
+        scalars.push(m.0);
+    }
+    crate::multi_scalar_mul_const_time_g1(&bases, &scalars)
+}
+
+/// Create `count` uniformly random scalars from `rng`
+pub(crate) fn calculate_random_scalars<R: RngCore + CryptoRng>(
+    count: usize,
+    rng: &mut R,
+) -> Vec<Fr> {
+    (0..count)
+        .map(|_| {
+            let mut okm = [0u8; FR_UNCOMPRESSED_SIZE];
+            rng.fill_bytes(&mut okm);
+            Fr::from_okm(GenericArray::from_slice(&okm[..]))
+        })
+        .collect()
+}
+
+pub(crate) fn push_g1(out: &mut Vec<u8>, p: &G1) {
+    p.serialize(out, true).unwrap();
+}
+
+pub(crate) fn push_g2(out: &mut Vec<u8>, p: &G2) {
+    p.serialize(out, true).unwrap();
+}
+
+pub(crate) fn push_scalar(out: &mut Vec<u8>, s: &Fr) {
+    s.serialize(out, true).unwrap();
+}
+
+pub(crate) fn push_int(out: &mut Vec<u8>, i: usize) {
+    out.extend_from_slice(&(i as u64).to_be_bytes()[..]);
+}
+
+/// Read a canonical scalar that must not be zero
+pub(crate) fn read_non_zero_scalar(data: &mut &[u8]) -> Result<Fr, BBSError> {
+    let s = Fr::deserialize(data, true)?;
+    if s.is_zero() {
+        return Err(BBSErrorKind::GeneralError {
+            msg: "Invalid zero scalar".to_string(),
+        }
+        .into());
+    }
+    Ok(s)
+}
+
+/// Read a compressed point that must not be the identity
+pub(crate) fn read_non_identity_g1(data: &mut &[u8], compressed: bool) -> Result<G1, BBSError> {
+    let p = G1::deserialize(data, compressed)?;
+    if p.is_zero() {
+        return Err(BBSErrorKind::GeneralError {
+            msg: "Invalid identity point".to_string(),
+        }
+        .into());
+    }
+    Ok(p)
+}
+
+/// Inputs shared by the signature and proof fixtures of the draft
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+    use crate::keys::SecretKey;
+    use std::convert::TryFrom;
+
+    pub const HEADER: &str = "11223344556677889900aabbccddeeff";
+    pub const PRESENTATION_HEADER: &str =
+        "bed231d880675ed101ead304512e043ade9958dd0241ea70b4b3957fba941501";
+    pub const MESSAGES: [&str; 10] = [
+        "9872ad089e452c7b6e283dfac2a80d58e8d0ff71cc4d5e310a1debdda4a45f02",
+        "c344136d9ab02da4dd5908bbba913ae6f58c2cc844b802a6f811f5fb075f9b80",
+        "7372e9daa5ed31e6cd5c825eac1b855e84476a1d94932aa348e07b73",
+        "77fe97eb97a1ebe2e81e4e3597a3ee740a66e9ef2412472c",
+        "496694774c5604ab1b2544eababcf0f53278ff50",
+        "515ae153e22aae04ad16f759e07237b4",
+        "d183ddc6e2665aa4e2f088af",
+        "ac55fb33a75909ed",
+        "96012096",
+        "",
+    ];
+    /// The seed of the mocked random scalars, "3.141592653589793238462643383279"
+    const MOCKED_SCALARS_SEED: &str =
+        "332e313431353932363533353839373933323338343632363433333833323739";
+
+    /// The key pair the draft derives with `keygen` for each ciphersuite
+    pub fn key_pair(suite: Ciphersuite) -> (SecretKey, DeterministicPublicKey) {
+        let sk = match suite {
+            Ciphersuite::Bls12381Sha256 => {
+                "60e55110f76883a13d030b2f6bd11883422d5abde717569fc0731f51237169fc"
+            }
+            Ciphersuite::Bls12381Shake256 => {
+                "2eee0f60a8a3a8bec0ee942bfd46cbdae9a0738ee68f5a64e7238311cf09a079"
+            }
+        };
+        let sk = SecretKey::try_from(hex::decode(sk).unwrap()).unwrap();
+        let pk = sk_to_pk(&sk);
+        (sk, pk)
+    }
+
+    pub fn messages(suite: Ciphersuite, count: usize) -> Vec<SignatureMessage> {
+        let messages = MESSAGES[..count]
+            .iter()
+            .map(|m| hex::decode(m).unwrap())
+            .collect::<Vec<Vec<u8>>>();
+        messages_to_scalars(suite, &messages)
+    }
+
+    pub fn scalar_hex(s: &Fr) -> String {
+        let mut out = Vec::new();
+        push_scalar(&mut out, s);
+        hex::encode(out)
+    }
+
+    /// mocked_calculate_random_scalars from the draft's test vector appendix
+    pub fn mocked_random_scalars(suite: Ciphersuite, count: usize) -> Vec<Fr> {
+        let seed = hex::decode(MOCKED_SCALARS_SEED).unwrap();
+        let dst = suite.with_api_id(b"MOCK_RANDOM_SCALARS_DST_");
+        let v = suite.expand_message(&seed, &dst, count * FR_UNCOMPRESSED_SIZE);
+        v.chunks(FR_UNCOMPRESSED_SIZE)
+            .map(|okm| Fr::from_okm(GenericArray::from_slice(okm)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_MATERIAL: &str =
+        "746869732d49532d6a7573742d616e2d546573742d494b4d2d746f2d67656e65726174652d246528724074232d6b6579";
+    const KEY_INFO: &str =
+        "746869732d49532d736f6d652d6b65792d6d657461646174612d746f2d62652d757365642d696e2d746573742d6b65792d67656e";
+
+    #[test]
+    fn p1_sha256() {
+        assert_eq!(
+            "a8ce256102840821a3e94ea9025e4662b205762f9776b3a766c872b948f1fd225e7c59698588e70d11406d161b4e28c9",
+            hex::encode(&Ciphersuite::Bls12381Sha256.p1().to_bytes_compressed_form()[..])
+        );
+    }
+
+    #[test]
+    fn p1_shake256() {
+        assert_eq!(
+            "8929dfbc7e6642c4ed9cba0856e493f8b9d7d5fcb0c31ef8fdcd34d50648a56c795e106e9eada6e0bda386b414150755",
+            hex::encode(&Ciphersuite::Bls12381Shake256.p1().to_bytes_compressed_form()[..])
+        );
+    }
+
+    #[test]
+    fn keygen_sha256() {
+        let sk = keygen(
+            Ciphersuite::Bls12381Sha256,
+            &hex::decode(KEY_MATERIAL).unwrap(),
+            &hex::decode(KEY_INFO).unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            "60e55110f76883a13d030b2f6bd11883422d5abde717569fc0731f51237169fc",
+            hex::encode(&sk.to_bytes_compressed_form()[..])
+        );
+        assert_eq!(
+            "a820f230f6ae38503b86c70dc50b61c58a77e45c39ab25c0652bbaa8fa136f2851bd4781c9dcde39fc9d1d52c9e60268061e7d7632171d91aa8d460acee0e96f1e7c4cfb12d3ff9ab5d5dc91c277db75c845d649ef3c4f63aebc364cd55ded0c",
+            hex::encode(&sk_to_pk(&sk).to_bytes_compressed_form()[..])
+        );
+    }
+
+    #[test]
+    fn keygen_shake256() {
+        let sk = keygen(
+            Ciphersuite::Bls12381Shake256,
+            &hex::decode(KEY_MATERIAL).unwrap(),
+            &hex::decode(KEY_INFO).unwrap(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            "2eee0f60a8a3a8bec0ee942bfd46cbdae9a0738ee68f5a64e7238311cf09a079",
+            hex::encode(&sk.to_bytes_compressed_form()[..])
+        );
+    }
+
+    #[test]
+    fn keygen_shake256_public_key() {
+        let (_, pk) = fixtures::key_pair(Ciphersuite::Bls12381Shake256);
+        assert_eq!(
+            "92d37d1d6cd38fea3a873953333eab23a4c0377e3e049974eb62bd45949cdeb18fb0490edcd4429adff56e65cbce42cf188b31bddbd619e419b99c2c41b38179eb001963bc3decaae0d9f702c7a8c004f207f46c734a5eae2e8e82833f3e7ea5",
+            hex::encode(&pk.to_bytes_compressed_form()[..])
+        );
+    }
+
+    #[test]
+    fn messages_to_scalars_sha256() {
+        let scalars = fixtures::messages(Ciphersuite::Bls12381Sha256, 1);
+        assert_eq!(
+            "1cb5bb86114b34dc438a911617655a1db595abafac92f47c5001799cf624b430",
+            fixtures::scalar_hex(&scalars[0].0)
+        );
+    }
+
+    #[test]
+    fn mocked_random_scalars() {
+        use fixtures::scalar_hex;
+        assert_eq!(
+            "04f8e2518993c4383957ad14eb13a023c4ad0c67d01ec86eeb902e732ed6df3f",
+            scalar_hex(&fixtures::mocked_random_scalars(Ciphersuite::Bls12381Sha256, 10)[0])
+        );
+        assert_eq!(
+            "1004262112c3eaa95941b2b0d1311c09c845db0099a50e67eda628ad26b43083",
+            scalar_hex(&fixtures::mocked_random_scalars(Ciphersuite::Bls12381Shake256, 10)[0])
+        );
+    }
+
+    #[test]
+    fn keygen_short_material() {
+        assert!(keygen(Ciphersuite::Bls12381Sha256, &[0u8; 31], &[], None).is_err());
+    }
+
+    #[test]
+    fn generators_are_distinct() {
+        let gens = create_generators(Ciphersuite::Bls12381Sha256, 5);
+        assert_eq!(gens.len(), 5);
+        assert!(!gens.contains(&Ciphersuite::Bls12381Sha256.p1()));
+        for i in 0..gens.len() {
+            for j in (i + 1)..gens.len() {
+                assert_ne!(gens[i], gens[j]);
+            }
+        }
+        // The sequence is a prefix of any longer sequence
+        assert_eq!(
+            gens[..],
+            create_generators(Ciphersuite::Bls12381Sha256, 7)[..5]
+        );
+    }
+}
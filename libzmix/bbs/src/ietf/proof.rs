@@ -0,0 +1,659 @@
+use super::{
+    calculate_domain, calculate_random_scalars, create_generators, push_g1, push_int, push_scalar,
+    read_non_identity_g1, read_non_zero_scalar, signature::Signature, Ciphersuite,
+};
+use crate::errors::prelude::*;
+use crate::keys::DeterministicPublicKey;
+use crate::{
+    multi_scalar_mul_const_time_g1, multi_scalar_mul_var_time_g1, SignatureMessage,
+    ToVariableLengthBytes, FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
+};
+use ff_zeroize::Field;
+use pairing_plus::{
+    bls12_381::{Bls12, Fq12, Fr, G1, G2},
+    serdes::SerDes,
+    CurveAffine, CurveProjective, Engine,
+};
+use rand::{thread_rng, CryptoRng, RngCore};
+use serde::{
+    de::{Error as DError, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt::Formatter;
+
+/// A zero-knowledge proof of a ciphersuite signature that selectively discloses messages
+/// as defined in section 3.5.3 of the draft
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    /// \overline{A}
+    pub(crate) a_bar: G1,
+    /// \overline{B}
+    pub(crate) b_bar: G1,
+    /// D
+    pub(crate) d: G1,
+    /// \hat{e}
+    pub(crate) e_hat: Fr,
+    /// \hat{r1}
+    pub(crate) r1_hat: Fr,
+    /// \hat{r3}
+    pub(crate) r3_hat: Fr,
+    /// \hat{m}_j for each undisclosed message in index order
+    pub(crate) m_hat: Vec<Fr>,
+    /// The challenge
+    pub(crate) challenge: Fr,
+}
+
+impl Proof {
+    /// Create a proof for `signature` over all `messages` that discloses
+    /// the messages at `disclosed_indices`. The indices are 0 based.
+    /// `header` must be the same as the one used when signing and `presentation_header`
+    /// is bound to the proof i.e. a nonce from the verifier.
+    pub fn new(
+        suite: Ciphersuite,
+        verkey: &DeterministicPublicKey,
+        signature: &Signature,
+        header: &[u8],
+        presentation_header: &[u8],
+        messages: &[SignatureMessage],
+        disclosed_indices: &BTreeSet<usize>,
+    ) -> Result<Self, BBSError> {
+        Self::new_with_rng(
+            suite,
+            verkey,
+            signature,
+            header,
+            presentation_header,
+            messages,
+            disclosed_indices,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Same as `new` but the random scalars are generated from `rng`
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        suite: Ciphersuite,
+        verkey: &DeterministicPublicKey,
+        signature: &Signature,
+        header: &[u8],
+        presentation_header: &[u8],
+        messages: &[SignatureMessage],
+        disclosed_indices: &BTreeSet<usize>,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        let undisclosed_count = messages.len().saturating_sub(disclosed_indices.len());
+        let random_scalars = calculate_random_scalars(5 + undisclosed_count, rng);
+        Self::new_with_random_scalars(
+            suite,
+            verkey,
+            signature,
+            header,
+            presentation_header,
+            messages,
+            disclosed_indices,
+            &random_scalars,
+        )
+    }
+
+    /// ProofGen with the random scalars supplied by the caller
+    /// in the order r1, r2, e~, r1~, r3~, m~_j...
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new_with_random_scalars(
+        suite: Ciphersuite,
+        verkey: &DeterministicPublicKey,
+        signature: &Signature,
+        header: &[u8],
+        presentation_header: &[u8],
+        messages: &[SignatureMessage],
+        disclosed_indices: &BTreeSet<usize>,
+        random_scalars: &[Fr],
+    ) -> Result<Self, BBSError> {
+        signature.validate()?;
+        check_disclosed_indices(disclosed_indices, messages.len())?;
+        let undisclosed_count = messages.len() - disclosed_indices.len();
+        if random_scalars.len() != 5 + undisclosed_count {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Expected {} random scalars, found {}",
+                    5 + undisclosed_count,
+                    random_scalars.len()
+                ),
+            }
+            .into());
+        }
+
+        let generators = create_generators(suite, messages.len() + 1);
+        let (q_1, h_points) = generators.split_first().unwrap();
+        let domain = calculate_domain(suite, verkey, q_1, h_points, header);
+
+        let (r1, r2, e_tilde, r1_tilde, r3_tilde) = (
+            random_scalars[0],
+            random_scalars[1],
+            random_scalars[2],
+            random_scalars[3],
+            random_scalars[4],
+        );
+        let m_tilde = &random_scalars[5..];
+
+        // D = B * r2
+        let mut d = super::compute_b(suite, q_1, h_points, &domain, messages);
+        d.mul_assign(r2);
+        // Abar = A * (r1 * r2)
+        let mut r1_r2 = r1;
+        r1_r2.mul_assign(&r2);
+        let mut a_bar = signature.a;
+        a_bar.mul_assign(r1_r2);
+        // Bbar = D * r1 - Abar * e
+        let mut neg_e = signature.e;
+        neg_e.negate();
+        let b_bar = multi_scalar_mul_const_time_g1([d, a_bar], [r1, neg_e]);
+        // T1 = Abar * e~ + D * r1~
+        let t1 = multi_scalar_mul_const_time_g1([a_bar, d], [e_tilde, r1_tilde]);
+        // T2 = D * r3~ + H_j1 * m~_j1 + ... + H_jU * m~_jU
+        let mut bases = vec![d];
+        let mut scalars = vec![r3_tilde];
+        let mut undisclosed = Vec::with_capacity(undisclosed_count);
+        for (i, m) in messages.iter().enumerate() {
+            if !disclosed_indices.contains(&i) {
+                bases.push(h_points[i].0);
+                undisclosed.push(m.0);
+            }
+        }
+        scalars.extend_from_slice(m_tilde);
+        let t2 = multi_scalar_mul_const_time_g1(&bases, &scalars);
+
+        let disclosed = disclosed_indices
+            .iter()
+            .map(|i| (*i, messages[*i]))
+            .collect::<BTreeMap<usize, SignatureMessage>>();
+        let challenge = calculate_challenge(
+            suite,
+            &[a_bar, b_bar, d, t1, t2],
+            &disclosed,
+            &domain,
+            presentation_header,
+        );
+
+        // r3 = r2^-1
+        let r3 = r2.inverse().ok_or_else(|| BBSErrorKind::GeneralError {
+            msg: "Invalid random scalar".to_string(),
+        })?;
+        let m_hat = m_tilde
+            .iter()
+            .zip(undisclosed.iter())
+            .map(|(m_tilde, m)| add_mul(m_tilde, m, &challenge))
+            .collect();
+
+        let mut neg_r1 = r1;
+        neg_r1.negate();
+        let mut neg_r3 = r3;
+        neg_r3.negate();
+        Ok(Self {
+            a_bar,
+            b_bar,
+            d,
+            e_hat: add_mul(&e_tilde, &signature.e, &challenge),
+            r1_hat: add_mul(&r1_tilde, &neg_r1, &challenge),
+            r3_hat: add_mul(&r3_tilde, &neg_r3, &challenge),
+            m_hat,
+            challenge,
+        })
+    }
+
+    /// Verify the proof against the `disclosed_messages` keyed by their 0 based index
+    /// as described in section 3.5.4
+    pub fn verify(
+        &self,
+        suite: Ciphersuite,
+        verkey: &DeterministicPublicKey,
+        header: &[u8],
+        presentation_header: &[u8],
+        disclosed_messages: &BTreeMap<usize, SignatureMessage>,
+    ) -> Result<bool, BBSError> {
+        if verkey.0.is_zero() {
+            return Err(BBSErrorKind::MalformedPublicKey.into());
+        }
+        let message_count = self.m_hat.len() + disclosed_messages.len();
+        check_disclosed_indices(&disclosed_messages.keys().copied().collect(), message_count)?;
+
+        let generators = create_generators(suite, message_count + 1);
+        let (q_1, h_points) = generators.split_first().unwrap();
+        let domain = calculate_domain(suite, verkey, q_1, h_points, header);
+
+        // T1 = Bbar * c + Abar * e^ + D * r1^
+        let t1 = multi_scalar_mul_var_time_g1(
+            [self.b_bar, self.a_bar, self.d],
+            [self.challenge, self.e_hat, self.r1_hat],
+        );
+
+        // Bv = P1 + Q_1 * domain + H_i1 * msg_i1 + ... + H_iR * msg_iR
+        // T2 = Bv * c + D * r3^ + H_j1 * m^_j1 + ... + H_jU * m^_jU
+        let mut bases = Vec::with_capacity(message_count + 3);
+        let mut scalars = Vec::with_capacity(message_count + 3);
+        bases.push(suite.p1().0);
+        scalars.push(self.challenge);
+        bases.push(q_1.0);
+        scalars.push(mul(&domain, &self.challenge));
+        bases.push(self.d);
+        scalars.push(self.r3_hat);
+        let mut m_hat = self.m_hat.iter();
+        for (i, h) in h_points.iter().enumerate() {
+            bases.push(h.0);
+            match disclosed_messages.get(&i) {
+                Some(m) => scalars.push(mul(&m.0, &self.challenge)),
+                None => scalars.push(*m_hat.next().unwrap()),
+            }
+        }
+        let t2 = multi_scalar_mul_var_time_g1(&bases, &scalars);
+
+        let challenge = calculate_challenge(
+            suite,
+            &[self.a_bar, self.b_bar, self.d, t1, t2],
+            disclosed_messages,
+            &domain,
+            presentation_header,
+        );
+        if challenge != self.challenge {
+            return Ok(false);
+        }
+
+        // e(Abar, W) * e(Bbar, -BP2) == 1
+        let mut neg_g2 = G2::one();
+        neg_g2.negate();
+        let a1 = self.a_bar.into_affine().prepare();
+        let a2 = verkey.0.into_affine().prepare();
+        let b1 = self.b_bar.into_affine().prepare();
+        let b2 = neg_g2.into_affine().prepare();
+        Ok(
+            match Bls12::final_exponentiation(&Bls12::miller_loop(&[(&a1, &a2), (&b1, &b2)])) {
+                None => false,
+                Some(product) => product == Fq12::one(),
+            },
+        )
+    }
+
+    /// The number of undisclosed messages in the proof
+    pub fn undisclosed_message_count(&self) -> usize {
+        self.m_hat.len()
+    }
+
+    /// Convert to raw bytes. The compressed form is the octet encoding defined by the draft.
+    pub(crate) fn to_bytes(&self, compressed: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        for p in [self.a_bar, self.b_bar, self.d].iter() {
+            p.serialize(&mut out, compressed).unwrap();
+        }
+        push_scalar(&mut out, &self.e_hat);
+        push_scalar(&mut out, &self.r1_hat);
+        push_scalar(&mut out, &self.r3_hat);
+        for m in &self.m_hat {
+            push_scalar(&mut out, m);
+        }
+        push_scalar(&mut out, &self.challenge);
+        out
+    }
+
+    /// Convert from raw bytes. Identity points and zero scalars are rejected.
+    pub(crate) fn from_bytes(
+        data: &[u8],
+        g1_size: usize,
+        compressed: bool,
+    ) -> Result<Self, BBSError> {
+        let min_size = g1_size * 3 + FR_COMPRESSED_SIZE * 4;
+        if data.len() < min_size || (data.len() - min_size) % FR_COMPRESSED_SIZE > 0 {
+            return Err(BBSErrorKind::InvalidNumberOfBytes(min_size, data.len()).into());
+        }
+        let mut data = data;
+        let mut points = [G1::zero(); 3];
+        for p in points.iter_mut() {
+            *p = read_non_identity_g1(&mut data, compressed)?;
+        }
+        let e_hat = read_non_zero_scalar(&mut data)?;
+        let r1_hat = read_non_zero_scalar(&mut data)?;
+        let r3_hat = read_non_zero_scalar(&mut data)?;
+        let mut m_hat = Vec::with_capacity(data.len() / FR_COMPRESSED_SIZE - 1);
+        while data.len() > FR_COMPRESSED_SIZE {
+            m_hat.push(read_non_zero_scalar(&mut data)?);
+        }
+        let challenge = read_non_zero_scalar(&mut data)?;
+        Ok(Self {
+            a_bar: points[0],
+            b_bar: points[1],
+            d: points[2],
+            e_hat,
+            r1_hat,
+            r3_hat,
+            m_hat,
+            challenge,
+        })
+    }
+}
+
+impl ToVariableLengthBytes for Proof {
+    type Output = Proof;
+    type Error = BBSError;
+
+    /// Convert the proof to the octet encoding defined by the draft
+    fn to_bytes_compressed_form(&self) -> Vec<u8> {
+        self.to_bytes(true)
+    }
+
+    /// Convert the octet encoding defined by the draft into a proof
+    fn from_bytes_compressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        Self::from_bytes(data.as_ref(), G1_COMPRESSED_SIZE, true)
+    }
+
+    fn to_bytes_uncompressed_form(&self) -> Vec<u8> {
+        self.to_bytes(false)
+    }
+
+    fn from_bytes_uncompressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self::Output, Self::Error> {
+        Self::from_bytes(data.as_ref(), G1_UNCOMPRESSED_SIZE, false)
+    }
+}
+
+impl Default for Proof {
+    fn default() -> Self {
+        Self {
+            a_bar: G1::zero(),
+            b_bar: G1::zero(),
+            d: G1::zero(),
+            e_hat: Fr::zero(),
+            r1_hat: Fr::zero(),
+            r3_hat: Fr::zero(),
+            m_hat: Vec::new(),
+            challenge: Fr::zero(),
+        }
+    }
+}
+
+try_from_impl!(Proof, BBSError);
+serdes_impl!(Proof);
+multibase_impl!(Proof);
+
+/// Compute the challenge as described in section 4.3.3.
+/// `points` are Abar, Bbar, D, T1 and T2.
+fn calculate_challenge(
+    suite: Ciphersuite,
+    points: &[G1; 5],
+    disclosed_messages: &BTreeMap<usize, SignatureMessage>,
+    domain: &Fr,
+    presentation_header: &[u8],
+) -> Fr {
+    let mut c_input = Vec::new();
+    push_int(&mut c_input, disclosed_messages.len());
+    for (i, m) in disclosed_messages {
+        push_int(&mut c_input, *i);
+        push_scalar(&mut c_input, &m.0);
+    }
+    for p in points {
+        push_g1(&mut c_input, p);
+    }
+    push_scalar(&mut c_input, domain);
+    push_int(&mut c_input, presentation_header.len());
+    c_input.extend_from_slice(presentation_header);
+    suite.hash_to_scalar(&c_input, suite.with_api_id(b"H2S_"))
+}
+
+fn check_disclosed_indices(
+    indices: &BTreeSet<usize>,
+    message_count: usize,
+) -> Result<(), BBSError> {
+    match indices.iter().next_back() {
+        Some(i) if *i >= message_count => Err(BBSErrorKind::GeneralError {
+            msg: format!(
+                "Disclosed index {} is out of range for {} messages",
+                i, message_count
+            ),
+        }
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+/// a + b * c
+fn add_mul(a: &Fr, b: &Fr, c: &Fr) -> Fr {
+    let mut t = mul(b, c);
+    t.add_assign(a);
+    t
+}
+
+fn mul(a: &Fr, b: &Fr) -> Fr {
+    let mut t = *a;
+    t.mul_assign(b);
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ietf::{fixtures, keygen, messages_to_scalars, sk_to_pk};
+
+    const HEADER: &[u8] = b"signature header";
+    const PRESENTATION_HEADER: &[u8] = b"presentation header";
+
+    fn setup(suite: Ciphersuite) -> (DeterministicPublicKey, Signature, Vec<SignatureMessage>) {
+        let sk = keygen(suite, &[9u8; 32], b"proof tests", None).unwrap();
+        let pk = sk_to_pk(&sk);
+        let messages =
+            messages_to_scalars(suite, &[&b"alice"[..], b"bob", b"charlie", b"dave", b"eve"]);
+        let sig = Signature::new(suite, &sk, &pk, HEADER, &messages).unwrap();
+        (pk, sig, messages)
+    }
+
+    fn disclosed(
+        messages: &[SignatureMessage],
+        indices: &BTreeSet<usize>,
+    ) -> BTreeMap<usize, SignatureMessage> {
+        indices.iter().map(|i| (*i, messages[*i])).collect()
+    }
+
+    #[test]
+    fn proof_gen_verify() {
+        for suite in [Ciphersuite::Bls12381Sha256, Ciphersuite::Bls12381Shake256].iter() {
+            let (pk, sig, messages) = setup(*suite);
+            for indices in [vec![], vec![0, 2, 4], vec![0, 1, 2, 3, 4]].iter() {
+                let indices = indices.iter().copied().collect::<BTreeSet<usize>>();
+                let proof = Proof::new(
+                    *suite,
+                    &pk,
+                    &sig,
+                    HEADER,
+                    PRESENTATION_HEADER,
+                    &messages,
+                    &indices,
+                )
+                .unwrap();
+                assert_eq!(proof.undisclosed_message_count(), 5 - indices.len());
+                let revealed = disclosed(&messages, &indices);
+                assert!(proof
+                    .verify(*suite, &pk, HEADER, PRESENTATION_HEADER, &revealed)
+                    .unwrap());
+                assert!(!proof
+                    .verify(*suite, &pk, HEADER, b"another header", &revealed)
+                    .unwrap());
+                assert!(!proof
+                    .verify(*suite, &pk, b"", PRESENTATION_HEADER, &revealed)
+                    .unwrap());
+            }
+        }
+    }
+
+    // ProofGen fixtures from the draft, generated with the mocked random scalars
+    #[test]
+    fn proof_gen_vectors() {
+        let vectors: [(Ciphersuite, usize, &[usize], &str); 6] = [
+            (
+                Ciphersuite::Bls12381Sha256,
+                1,
+                &[0],
+                "94916292a7a6bade28456c601d3af33fcf39278d6594b467e128a3f83686a104ef2b2fcf72df0215eeaf69262ffe8194a19fab31a82ddbe06908985abc4c9825788b8a1610942d12b7f5debbea8985296361206dbace7af0cc834c80f33e0aadaeea5597befbb651827b5eed5a66f1a959bb46cfd5ca1a817a14475960f69b32c54db7587b5ee3ab665fbd37b506830a49f21d592f5e634f47cee05a025a2f8f94e73a6c15f02301d1178a92873b6e8634bafe4983c3e15a663d64080678dbf29417519b78af042be2b3e1c4d08b8d520ffab008cbaaca5671a15b22c239b38e940cfeaa5e72104576a9ec4a6fad78c532381aeaa6fb56409cef56ee5c140d455feeb04426193c57086c9b6d397d9418",
+            ),
+            (
+                Ciphersuite::Bls12381Sha256,
+                10,
+                &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+                "b1f468aec2001c4f54cb56f707c6222a43e5803a25b2253e67b2210ab2ef9eab52db2d4b379935c4823281eaf767fd37b08ce80dc65de8f9769d27099ae649ad4c9b4bd2cc23edcba52073a298087d2495e6d57aaae051ef741adf1cbce65c64a73c8c97264177a76c4a03341956d2ae45ed3438ce598d5cda4f1bf9507fecef47855480b7b30b5e4052c92a4360110c67327365763f5aa9fb85ddcbc2975449b8c03db1216ca66b310f07d0ccf12ab460cdc6003b677fed36d0a23d0818a9d4d098d44f749e91008cf50e8567ef936704c8277b7710f41ab7e6e16408ab520edc290f9801349aee7b7b4e318e6a76e028e1dea911e2e7baec6a6a174da1a22362717fbae1cd961d7bf4adce1d31c2ab",
+            ),
+            (
+                Ciphersuite::Bls12381Sha256,
+                10,
+                &[0, 2, 4, 6],
+                "a2ed608e8e12ed21abc2bf154e462d744a367c7f1f969bdbf784a2a134c7db2d340394223a5397a3011b1c340ebc415199462ba6f31106d8a6da8b513b37a47afe93c9b3474d0d7a354b2edc1b88818b063332df774c141f7a07c48fe50d452f897739228c88afc797916dca01e8f03bd9c5375c7a7c59996e514bb952a436afd24457658acbaba5ddac2e693ac481356918cd38025d86b28650e909defe9604a7259f44386b861608be742af7775a2e71a6070e5836f5f54dc43c60096834a5b6da295bf8f081f72b7cdf7f3b4347fb3ff19edaa9e74055c8ba46dbcb7594fb2b06633bb5324192eb9be91be0d33e453b4d3127459de59a5e2193c900816f049a02cb9127dac894418105fa1641d5a206ec9c42177af9316f433417441478276ca0303da8f941bf2e0222a43251cf5c2bf6eac1961890aa740534e519c1767e1223392a3a286b0f4d91f7f25217a7862b8fcc1810cdcfddde2a01c80fcc90b632585fec12dc4ae8fea1918e9ddeb9414623a457e88f53f545841f9d5dcb1f8e160d1560770aa79d65e2eca8edeaecb73fb7e995608b820c4a64de6313a370ba05dc25ed7c1d185192084963652f2870341bdaa4b1a37f8c06348f38a4f80c5a2650a21d59f09e8305dcd3fc3ac30e2a",
+            ),
+            (
+                Ciphersuite::Bls12381Shake256,
+                1,
+                &[0],
+                "89e4ab0c160880e0c2f12a754b9c051ed7f5fccfee3d5cbbb62e1239709196c737fff4303054660f8fcd08267a5de668a2e395ebe8866bdcb0dff9786d7014fa5e3c8cf7b41f8d7510e27d307f18032f6b788e200b9d6509f40ce1d2f962ceedb023d58ee44d660434e6ba60ed0da1a5d2cde031b483684cd7c5b13295a82f57e209b584e8fe894bcc964117bf3521b43d8e2eb59ce31f34d68b39f05bb2c625e4de5e61e95ff38bfd62ab07105d016414b45b01625c69965ad3c8a933e7b25d93daeb777302b966079827a99178240e6c3f13b7db2fb1f14790940e239d775ab32f539bdf9f9b582b250b05882996832652f7f5d3b6e04744c73ada1702d6791940ccbd75e719537f7ace6ee817298d",
+            ),
+            (
+                Ciphersuite::Bls12381Shake256,
+                10,
+                &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+                "91b0f598268c57b67bc9e55327c3c2b9b1654be89a0cf963ab392fa9e1637c565241d71fd6d7bbd7dfe243de85a9bac8b7461575c1e13b5055fed0b51fd0ec1433096607755b2f2f9ba6dc614dfa456916ca0d7fc6482b39c679cfb747a50ea1b3dd7ed57aaadc348361e2501a17317352e555a333e014e8e7d71eef808ae4f8fbdf45cd19fde45038bb310d5135f5205fc550b077e381fb3a3543dca31a0d8bba97bc0b660a5aa239eb74921e184aa3035fa01eaba32f52029319ec3df4fa4a4f716edb31a6ce19a19dbb971380099345070bd0fdeecf7c4774a33e0a116e069d5e215992fb637984802066dee6919146ae50b70ea52332dfe57f6e05c66e99f1764d8b890d121d65bfcc2984886ee0",
+            ),
+            (
+                Ciphersuite::Bls12381Shake256,
+                10,
+                &[0, 2, 4, 6],
+                "b1f8bf99a11c39f04e2a032183c1ead12956ad322dd06799c50f20fb8cf6b0ac279210ef5a2920a7be3ec2aa0911ace7b96811a98f3c1cceba4a2147ae763b3ba036f47bc21c39179f2b395e0ab1ac49017ea5b27848547bedd27be481c1dfc0b73372346feb94ab16189d4c525652b8d3361bab43463700720ecfb0ee75e595ea1b13330615011050a0dfcffdb21af356dd39bf8bcbfd41bf95d913f4c9b2979e1ed2ca10ac7e881bb6a271722549681e398d29e9ba4eac8848b168eddd5e4acec7df4103e2ed165e6e32edc80f0a3b28c36fb39ca19b4b8acee570deadba2da9ec20d1f236b571e0d4c2ea3b826fe924175ed4dfffbf18a9cfa98546c241efb9164c444d970e8c89849bc8601e96cf228fdefe38ab3b7e289cac859e68d9cbb0e648faf692b27df5ff6539c30da17e5444a65143de02ca64cee7b0823be65865cdc310be038ec6b594b99280072ae067bad1117b0ff3201a5506a8533b925c7ffae9cdb64558857db0ac5f5e0f18e750ae77ec9cf35263474fef3f78138c7a1ef5cfbc878975458239824fad3ce05326ba3969b1f5451bd82bd1f8075f3d32ece2d61d89a064ab4804c3c892d651d11bc325464a71cd7aacc2d956a811aaff13ea4c35cef7842b656e8ba4758e7558",
+            ),
+        ];
+
+        let header = hex::decode(fixtures::HEADER).unwrap();
+        let presentation_header = hex::decode(fixtures::PRESENTATION_HEADER).unwrap();
+        for (suite, count, indices, expected) in vectors.iter() {
+            let (sk, pk) = fixtures::key_pair(*suite);
+            let messages = fixtures::messages(*suite, *count);
+            let sig = Signature::new(*suite, &sk, &pk, &header, &messages).unwrap();
+            let indices = indices.iter().copied().collect::<BTreeSet<usize>>();
+            let random_scalars = fixtures::mocked_random_scalars(*suite, 5 + count - indices.len());
+            let proof = Proof::new_with_random_scalars(
+                *suite,
+                &pk,
+                &sig,
+                &header,
+                &presentation_header,
+                &messages,
+                &indices,
+                &random_scalars,
+            )
+            .unwrap();
+            assert_eq!(*expected, hex::encode(proof.to_bytes_compressed_form()));
+
+            let proof = Proof::from_bytes_compressed_form(hex::decode(expected).unwrap()).unwrap();
+            let revealed = disclosed(&messages, &indices);
+            assert!(proof
+                .verify(*suite, &pk, &header, &presentation_header, &revealed)
+                .unwrap());
+            assert!(!proof.verify(*suite, &pk, &header, &[], &revealed).unwrap());
+            assert!(!proof
+                .verify(*suite, &pk, &[], &presentation_header, &revealed)
+                .unwrap());
+        }
+    }
+
+    #[test]
+    fn proof_wrong_disclosed_message() {
+        let suite = Ciphersuite::Bls12381Sha256;
+        let (pk, sig, messages) = setup(suite);
+        let indices = [1, 3].iter().copied().collect::<BTreeSet<usize>>();
+        let proof = Proof::new(
+            suite,
+            &pk,
+            &sig,
+            HEADER,
+            PRESENTATION_HEADER,
+            &messages,
+            &indices,
+        )
+        .unwrap();
+
+        let mut revealed = disclosed(&messages, &indices);
+        revealed.insert(1, messages[0]);
+        assert!(!proof
+            .verify(suite, &pk, HEADER, PRESENTATION_HEADER, &revealed)
+            .unwrap());
+
+        // Same messages claimed at different indices
+        let mut revealed = BTreeMap::new();
+        revealed.insert(0, messages[1]);
+        revealed.insert(3, messages[3]);
+        assert!(!proof
+            .verify(suite, &pk, HEADER, PRESENTATION_HEADER, &revealed)
+            .unwrap());
+
+        let mut revealed = disclosed(&messages, &indices);
+        revealed.insert(9, messages[0]);
+        assert!(proof
+            .verify(suite, &pk, HEADER, PRESENTATION_HEADER, &revealed)
+            .is_err());
+    }
+
+    #[test]
+    fn proof_invalid_index() {
+        let suite = Ciphersuite::Bls12381Sha256;
+        let (pk, sig, messages) = setup(suite);
+        let indices = [5].iter().copied().collect::<BTreeSet<usize>>();
+        assert!(Proof::new(
+            suite,
+            &pk,
+            &sig,
+            HEADER,
+            PRESENTATION_HEADER,
+            &messages,
+            &indices
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn proof_serialization() {
+        let suite = Ciphersuite::Bls12381Sha256;
+        let (pk, sig, messages) = setup(suite);
+        let indices = [0, 4].iter().copied().collect::<BTreeSet<usize>>();
+        let proof = Proof::new(
+            suite,
+            &pk,
+            &sig,
+            HEADER,
+            PRESENTATION_HEADER,
+            &messages,
+            &indices,
+        )
+        .unwrap();
+
+        let bytes = proof.to_bytes_compressed_form();
+        assert_eq!(bytes.len(), 3 * 48 + (4 + 3) * 32);
+        let proof_2 = Proof::from_bytes_compressed_form(&bytes).unwrap();
+        assert_eq!(proof, proof_2);
+        assert!(proof_2
+            .verify(
+                suite,
+                &pk,
+                HEADER,
+                PRESENTATION_HEADER,
+                &disclosed(&messages, &indices)
+            )
+            .unwrap());
+
+        let bytes = proof.to_bytes_uncompressed_form();
+        assert_eq!(proof, Proof::from_bytes_uncompressed_form(&bytes).unwrap());
+
+        assert!(Proof::from_bytes_compressed_form(&bytes[1..]).is_err());
+        assert!(Proof::from_bytes_compressed_form(&[0u8; 3 * 48 + 4 * 32][..]).is_err());
+    }
+
+    #[test]
+    fn proof_is_randomized() {
+        let suite = Ciphersuite::Bls12381Shake256;
+        let (pk, sig, messages) = setup(suite);
+        let indices = BTreeSet::new();
+        let p1 = Proof::new(suite, &pk, &sig, HEADER, &[], &messages, &indices).unwrap();
+        let p2 = Proof::new(suite, &pk, &sig, HEADER, &[], &messages, &indices).unwrap();
+        assert_ne!(p1, p2);
+    }
+}
@@ -0,0 +1,292 @@
+use super::{calculate_domain, compute_b, create_generators, Ciphersuite};
+use crate::errors::prelude::*;
+use crate::keys::{DeterministicPublicKey, SecretKey};
+use crate::{SignatureMessage, FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE};
+use ff_zeroize::Field;
+use pairing_plus::{
+    bls12_381::{Bls12, Fq12, Fr, G1, G2},
+    serdes::SerDes,
+    CurveAffine, CurveProjective, Engine,
+};
+use serde::{
+    de::{Error as DError, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+/// The number of bytes in a ciphersuite signature
+pub const SIGNATURE_UNCOMPRESSED_SIZE: usize = G1_UNCOMPRESSED_SIZE + FR_COMPRESSED_SIZE;
+/// The number of bytes in a compressed ciphersuite signature.
+/// This is the octet encoding defined by the draft.
+pub const SIGNATURE_COMPRESSED_SIZE: usize = G1_COMPRESSED_SIZE + FR_COMPRESSED_SIZE;
+
+/// A BBS signature as defined by the ciphersuites in the draft
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// A
+    pub(crate) a: G1,
+    /// e
+    pub(crate) e: Fr,
+}
+
+impl Signature {
+    /// Sign `messages` and `header` as described in section 3.5.1.
+    /// Messages are expected to be mapped to scalars with `messages_to_scalars`.
+    /// Signing is deterministic so the same inputs always produce the same signature.
+    pub fn new(
+        suite: Ciphersuite,
+        signkey: &SecretKey,
+        verkey: &DeterministicPublicKey,
+        header: &[u8],
+        messages: &[SignatureMessage],
+    ) -> Result<Self, BBSError> {
+        signkey.validate()?;
+        let generators = create_generators(suite, messages.len() + 1);
+        let (q_1, h_points) = generators.split_first().unwrap();
+        let domain = calculate_domain(suite, verkey, q_1, h_points, header);
+
+        let mut e_input = Vec::new();
+        super::push_scalar(&mut e_input, &signkey.0);
+        for m in messages {
+            super::push_scalar(&mut e_input, &m.0);
+        }
+        super::push_scalar(&mut e_input, &domain);
+        let e = suite.hash_to_scalar(&e_input, suite.with_api_id(b"H2S_"));
+
+        let mut exp = signkey.0;
+        exp.add_assign(&e);
+        let exp = exp.inverse().ok_or_else(|| BBSErrorKind::GeneralError {
+            msg: "Invalid signing key".to_string(),
+        })?;
+
+        let mut a = compute_b(suite, q_1, h_points, &domain, messages);
+        a.mul_assign(exp);
+        Ok(Self { a, e })
+    }
+
+    /// Verify a signature as described in section 3.5.2
+    pub fn verify(
+        &self,
+        suite: Ciphersuite,
+        verkey: &DeterministicPublicKey,
+        header: &[u8],
+        messages: &[SignatureMessage],
+    ) -> Result<bool, BBSError> {
+        self.validate()?;
+        if verkey.0.is_zero() {
+            return Err(BBSErrorKind::MalformedPublicKey.into());
+        }
+        let generators = create_generators(suite, messages.len() + 1);
+        let (q_1, h_points) = generators.split_first().unwrap();
+        let domain = calculate_domain(suite, verkey, q_1, h_points, header);
+        let mut b = compute_b(suite, q_1, h_points, &domain, messages);
+        b.negate();
+
+        let mut w = G2::one();
+        w.mul_assign(self.e);
+        w.add_assign(&verkey.0);
+
+        let a1 = self.a.into_affine().prepare();
+        let a2 = w.into_affine().prepare();
+        let b1 = b.into_affine().prepare();
+        let b2 = G2::one().into_affine().prepare();
+        // e(A, W + BP2 * e) * e(B, -BP2) == 1
+        Ok(
+            match Bls12::final_exponentiation(&Bls12::miller_loop(&[(&a1, &a2), (&b1, &b2)])) {
+                None => false,
+                Some(product) => product == Fq12::one(),
+            },
+        )
+    }
+
+    /// Check if the signature is a valid form i.e. not infinity since it will always validate
+    /// if that is the case
+    pub fn validate(&self) -> Result<(), BBSError> {
+        if self.a.is_zero() || self.e.is_zero() {
+            return Err(BBSErrorKind::MalformedSignature.into());
+        }
+        Ok(())
+    }
+
+    /// Convert to the octet encoding defined by the draft
+    pub fn to_bytes_compressed_form(&self) -> [u8; SIGNATURE_COMPRESSED_SIZE] {
+        let mut out = Vec::with_capacity(SIGNATURE_COMPRESSED_SIZE);
+        self.a.serialize(&mut out, true).unwrap();
+        self.e.serialize(&mut out, true).unwrap();
+        *array_ref![out, 0, SIGNATURE_COMPRESSED_SIZE]
+    }
+
+    /// Convert to raw bytes with an uncompressed A
+    pub fn to_bytes_uncompressed_form(&self) -> [u8; SIGNATURE_UNCOMPRESSED_SIZE] {
+        let mut out = Vec::with_capacity(SIGNATURE_UNCOMPRESSED_SIZE);
+        self.a.serialize(&mut out, false).unwrap();
+        self.e.serialize(&mut out, true).unwrap();
+        *array_ref![out, 0, SIGNATURE_UNCOMPRESSED_SIZE]
+    }
+}
+
+impl TryFrom<&[u8]> for Signature {
+    type Error = BBSError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let compressed = match value.len() {
+            SIGNATURE_COMPRESSED_SIZE => true,
+            SIGNATURE_UNCOMPRESSED_SIZE => false,
+            _ => return Err(BBSErrorKind::MalformedSignature.into()),
+        };
+        let mut value = value;
+        let a = G1::deserialize(&mut value, compressed)?;
+        let e = Fr::deserialize(&mut value, true)?;
+        let sig = Self { a, e };
+        sig.validate()?;
+        Ok(sig)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Signature {
+    type Error = BBSError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+serdes_impl!(Signature);
+display_impl!(Signature);
+multibase_impl!(Signature);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ietf::{fixtures, keygen, messages_to_scalars, sk_to_pk};
+
+    const HEADER: &[u8] = b"signature header";
+
+    fn setup(suite: Ciphersuite) -> (SecretKey, DeterministicPublicKey, Vec<SignatureMessage>) {
+        let sk = keygen(suite, &[7u8; 32], b"signature tests", None).unwrap();
+        let pk = sk_to_pk(&sk);
+        let messages = messages_to_scalars(suite, &[&b"alice"[..], b"bob", b"", b"charlie"]);
+        (sk, pk, messages)
+    }
+
+    #[test]
+    fn sign_verify() {
+        for suite in [Ciphersuite::Bls12381Sha256, Ciphersuite::Bls12381Shake256].iter() {
+            let (sk, pk, messages) = setup(*suite);
+            let sig = Signature::new(*suite, &sk, &pk, HEADER, &messages).unwrap();
+            assert!(sig.verify(*suite, &pk, HEADER, &messages).unwrap());
+            // Signing is deterministic
+            assert_eq!(
+                sig,
+                Signature::new(*suite, &sk, &pk, HEADER, &messages).unwrap()
+            );
+
+            assert!(!sig.verify(*suite, &pk, b"", &messages).unwrap());
+            assert!(!sig.verify(*suite, &pk, HEADER, &messages[1..]).unwrap());
+            let mut swapped = messages.clone();
+            swapped.swap(0, 1);
+            assert!(!sig.verify(*suite, &pk, HEADER, &swapped).unwrap());
+        }
+    }
+
+    // Single message signature fixture from the draft
+    #[test]
+    fn sign_sha256_vector() {
+        let suite = Ciphersuite::Bls12381Sha256;
+        let sk = SecretKey::try_from(
+            hex::decode("60e55110f76883a13d030b2f6bd11883422d5abde717569fc0731f51237169fc")
+                .unwrap(),
+        )
+        .unwrap();
+        let pk = sk_to_pk(&sk);
+        let header = hex::decode("11223344556677889900aabbccddeeff").unwrap();
+        let messages = messages_to_scalars(
+            suite,
+            &[
+                hex::decode("9872ad089e452c7b6e283dfac2a80d58e8d0ff71cc4d5e310a1debdda4a45f02")
+                    .unwrap(),
+            ],
+        );
+        let sig = Signature::new(suite, &sk, &pk, &header, &messages).unwrap();
+        assert_eq!(
+            "84773160b824e194073a57493dac1a20b667af70cd2352d8af241c77658da5253aa8458317cca0eae615690d55b1f27164657dcafee1d5c1973947aa70e2cfbb4c892340be5969920d0916067b4565a0",
+            hex::encode(&sig.to_bytes_compressed_form()[..])
+        );
+        assert!(sig.verify(suite, &pk, &header, &messages).unwrap());
+    }
+
+    // Single message signature fixture from the draft
+    #[test]
+    fn sign_shake256_vector() {
+        let suite = Ciphersuite::Bls12381Shake256;
+        let (sk, pk) = fixtures::key_pair(suite);
+        let header = hex::decode(fixtures::HEADER).unwrap();
+        let messages = fixtures::messages(suite, 1);
+        let sig = Signature::new(suite, &sk, &pk, &header, &messages).unwrap();
+        assert_eq!(
+            "b9a622a4b404e6ca4c85c15739d2124a1deb16df750be202e2430e169bc27fb71c44d98e6d40792033e1c452145ada95030832c5dc778334f2f1b528eced21b0b97a12025a283d78b7136bb9825d04ef",
+            hex::encode(&sig.to_bytes_compressed_form()[..])
+        );
+        assert!(sig.verify(suite, &pk, &header, &messages).unwrap());
+    }
+
+    // Multi-message signature fixtures from the draft
+    #[test]
+    fn sign_multi_message_vectors() {
+        for (suite, expected) in [
+            (
+                Ciphersuite::Bls12381Sha256,
+                "8339b285a4acd89dec7777c09543a43e3cc60684b0a6f8ab335da4825c96e1463e28f8c5f4fd0641d19cec5920d3a8ff4bedb6c9691454597bbd298288abed3632078557b2ace7d44caed846e1a0a1e8",
+            ),
+            (
+                Ciphersuite::Bls12381Shake256,
+                "956a3427b1b8e3642e60e6a7990b67626811adeec7a0a6cb4f770cdd7c20cf08faabb913ac94d18e1e92832e924cb6e202912b624261fc6c59b0fea801547f67fb7d3253e1e2acbcf90ef59a6911931e",
+            ),
+        ]
+        .iter()
+        {
+            let (sk, pk) = fixtures::key_pair(*suite);
+            let header = hex::decode(fixtures::HEADER).unwrap();
+            let messages = fixtures::messages(*suite, 10);
+            let sig = Signature::new(*suite, &sk, &pk, &header, &messages).unwrap();
+            assert_eq!(*expected, hex::encode(&sig.to_bytes_compressed_form()[..]));
+            assert!(sig.verify(*suite, &pk, &header, &messages).unwrap());
+            assert!(!sig.verify(*suite, &pk, &[], &messages).unwrap());
+        }
+    }
+
+    #[test]
+    fn sign_no_messages() {
+        let suite = Ciphersuite::Bls12381Sha256;
+        let (sk, pk, _) = setup(suite);
+        let sig = Signature::new(suite, &sk, &pk, HEADER, &[]).unwrap();
+        assert!(sig.verify(suite, &pk, HEADER, &[]).unwrap());
+    }
+
+    #[test]
+    fn ciphersuites_are_separated() {
+        let (sk, pk, messages) = setup(Ciphersuite::Bls12381Sha256);
+        let sig = Signature::new(Ciphersuite::Bls12381Sha256, &sk, &pk, HEADER, &messages).unwrap();
+        assert!(!sig
+            .verify(Ciphersuite::Bls12381Shake256, &pk, HEADER, &messages)
+            .unwrap());
+    }
+
+    #[test]
+    fn signature_serialization() {
+        let suite = Ciphersuite::Bls12381Sha256;
+        let (sk, pk, messages) = setup(suite);
+        let sig = Signature::new(suite, &sk, &pk, HEADER, &messages).unwrap();
+
+        let bytes = sig.to_bytes_compressed_form();
+        assert_eq!(bytes.len(), 80);
+        assert_eq!(sig, Signature::try_from(&bytes[..]).unwrap());
+
+        let bytes = sig.to_bytes_uncompressed_form();
+        assert_eq!(sig, Signature::try_from(&bytes[..]).unwrap());
+
+        assert!(Signature::try_from(&[0u8; SIGNATURE_COMPRESSED_SIZE][..]).is_err());
+        assert!(Signature::try_from(&bytes[1..]).is_err());
+    }
+}
@@ -0,0 +1,487 @@
+use crate::errors::prelude::*;
+use crate::pok_sig::prelude::*;
+use crate::pok_vc::prelude::*;
+use crate::{
+    hash_to_g1, multi_scalar_mul_const_time_g1, rand_non_zero_fr, Commitment, GeneratorG1,
+    ProofChallenge, ProofNonce, SignatureMessage, SignatureProof, FR_COMPRESSED_SIZE,
+};
+use ff_zeroize::Field;
+use pairing_plus::{
+    bls12_381::{Fr, G1},
+    serdes::SerDes,
+    CurveProjective,
+};
+use rand::{CryptoRng, RngCore};
+use std::collections::BTreeMap;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{InequalityProof, InequalityTarget};
+}
+
+/// What a hidden message is proved to be different from
+#[derive(Copy, Clone, Debug)]
+pub enum InequalityTarget {
+    /// A public value
+    Value(SignatureMessage),
+    /// A hidden message in another signature proof and the blinding factor it was given
+    /// with `HiddenMessage::ExternalBlinding` in that proof
+    Hidden(SignatureMessage, ProofNonce),
+}
+
+/// The prover's state for proving a hidden message `m` is not equal to a target `t`.
+///
+/// The prover commits to the difference `C = g^(m - t) * h^r` and proves
+/// 1. knowledge of the opening of `C` where the blinding factor for `m - t` is derived from
+///    the blinding factor of `m` in the signature proof so the responses are linked
+/// 2. knowledge of `a = (m - t)^-1` and `b = -r * a` such that `g = C^a * h^b`
+///    which is only possible if `m - t` is invertible i.e. not zero
+#[derive(Debug, Clone)]
+pub(crate) struct ProverCommittedInequality {
+    message_index: usize,
+    value: Option<SignatureMessage>,
+    /// The hidden target and its blinding factor
+    hidden: Option<(Fr, Fr)>,
+    commitment: G1,
+    /// m - t and r
+    secrets_opening: [Fr; 2],
+    opening: ProverCommittedG1,
+    /// (m - t)^-1 and -r * (m - t)^-1
+    secrets_inverse: [Fr; 2],
+    inverse: ProverCommittedG1,
+}
+
+/// A proof that a hidden message in a signature proof of knowledge is not equal
+/// to a public value or to a hidden message in another signature proof.
+///
+/// Checked by `PoKOfSignatureProof::verify`. When the target is hidden the verifier must
+/// also check the target is the message in the other proof with `verify_hidden_target`
+/// or use `MultiSignatureProof::verify_with_inequalities` which does both.
+#[derive(Debug, Clone)]
+pub struct InequalityProof {
+    /// The index of the hidden message in the signature
+    pub message_index: usize,
+    /// The public value the message is not equal to. `None` if the target is hidden.
+    pub value: Option<SignatureMessage>,
+    /// The response for the hidden target
+    pub(crate) target_response: Option<SignatureMessage>,
+    /// g^(m - t) * h^r
+    pub(crate) commitment: G1,
+    /// Proof of knowledge of the opening of the commitment
+    pub(crate) opening: ProofG1,
+    /// Proof of knowledge of the inverse of m - t
+    pub(crate) inverse: ProofG1,
+}
+
+impl ProverCommittedInequality {
+    /// Commit to `message` - `target` where `blinding` is the blinding factor
+    /// for `message` in the signature proof
+    pub(crate) fn new<R: RngCore + CryptoRng>(
+        message_index: usize,
+        message: Fr,
+        blinding: Fr,
+        target: &InequalityTarget,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        let (t, mut t_blinding, value, hidden) = match target {
+            InequalityTarget::Value(v) => (v.0, Fr::zero(), Some(*v), None),
+            InequalityTarget::Hidden(m, b) => (m.0, b.0, None, Some((m.0, b.0))),
+        };
+        let mut difference = message;
+        difference.sub_assign(&t);
+        let a = difference.inverse().ok_or_else(|| {
+            BBSError::from_kind(BBSErrorKind::GeneralError {
+                msg: format!("Message {} is equal to the target", message_index),
+            })
+        })?;
+
+        let g = generator_g();
+        let h = generator_h();
+        let r = rand_non_zero_fr(rng);
+        let commitment = multi_scalar_mul_const_time_g1([g, h], [difference, r]);
+
+        // The blinding factor of m - t is the difference of the blinding factors
+        // so its response is the difference of the responses
+        let mut committing = ProverCommittingG1::new();
+        t_blinding.negate();
+        t_blinding.add_assign(&blinding);
+        committing.commit_with(GeneratorG1(g), SignatureMessage(t_blinding));
+        committing.commit_with_rng(GeneratorG1(h), rng);
+        let opening = committing.finish();
+
+        let mut b = r;
+        b.mul_assign(&a);
+        b.negate();
+        let mut committing = ProverCommittingG1::new();
+        committing.commit_with_rng(GeneratorG1(commitment), rng);
+        committing.commit_with_rng(GeneratorG1(h), rng);
+        let inverse = committing.finish();
+
+        Ok(Self {
+            message_index,
+            value,
+            hidden,
+            commitment,
+            secrets_opening: [difference, r],
+            opening,
+            secrets_inverse: [a, b],
+            inverse,
+        })
+    }
+
+    /// Return the bytes used in computing the challenge
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        challenge_bytes(
+            self.message_index,
+            self.value,
+            &self.commitment,
+            &self.opening.commitment(),
+            &self.inverse.commitment(),
+        )
+    }
+
+    /// Compute the responses for `challenge`
+    pub(crate) fn gen_proof(self, challenge: &ProofChallenge) -> Result<InequalityProof, BBSError> {
+        let target_response = self.hidden.map(|(t, t_blinding)| {
+            let mut s = challenge.0;
+            s.mul_assign(&t);
+            s.negate();
+            s.add_assign(&t_blinding);
+            SignatureMessage(s)
+        });
+        let opening = self.opening.gen_proof(
+            challenge,
+            &[
+                SignatureMessage(self.secrets_opening[0]),
+                SignatureMessage(self.secrets_opening[1]),
+            ],
+        )?;
+        let inverse = self.inverse.gen_proof(
+            challenge,
+            &[
+                SignatureMessage(self.secrets_inverse[0]),
+                SignatureMessage(self.secrets_inverse[1]),
+            ],
+        )?;
+        Ok(InequalityProof {
+            message_index: self.message_index,
+            value: self.value,
+            target_response,
+            commitment: self.commitment,
+            opening,
+            inverse,
+        })
+    }
+}
+
+impl InequalityProof {
+    /// The response for the hidden target which must equal the response
+    /// for that message in the other signature proof.
+    /// `None` if the target is a public value.
+    pub fn target_response(&self) -> Option<SignatureMessage> {
+        self.target_response
+    }
+
+    /// Check the hidden target is the message at `message_index` in `other`.
+    /// Both proofs must be verified with the same challenge.
+    pub fn verify_hidden_target(
+        &self,
+        other: &SignatureProof,
+        message_index: usize,
+    ) -> Result<(), BBSError> {
+        let target_response = self.target_response.ok_or_else(|| {
+            BBSError::from_kind(BBSErrorKind::GeneralError {
+                msg: "The inequality target is a public value".to_string(),
+            })
+        })?;
+        if other.revealed_messages.contains_key(&message_index) {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Message {} is revealed but must be hidden to prove inequality",
+                    message_index
+                ),
+            }
+            .into());
+        }
+        // Responses are only present for hidden messages
+        let hidden_index = message_index - other.revealed_messages.range(..message_index).count();
+        if other.proof.get_resp_for_message(hidden_index)? != target_response {
+            return Err(BBSErrorKind::InvalidProof {
+                status: PoKOfSignatureProofStatus::BadHiddenMessage,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Return the bytes used in computing the challenge
+    pub(crate) fn get_bytes_for_challenge(&self) -> Vec<u8> {
+        challenge_bytes(
+            self.message_index,
+            self.value,
+            &self.commitment,
+            &self.opening.commitment,
+            &self.inverse.commitment,
+        )
+    }
+
+    /// Verify the proof where `message_response` is the response
+    /// for the hidden message in the signature proof
+    pub(crate) fn verify(
+        &self,
+        message_response: &SignatureMessage,
+        challenge: &ProofChallenge,
+    ) -> bool {
+        // The response for m - t
+        let mut expected = message_response.0;
+        match (self.value, self.target_response) {
+            (Some(t), None) => {
+                let mut c_t = challenge.0;
+                c_t.mul_assign(&t.0);
+                expected.add_assign(&c_t);
+            }
+            (None, Some(s_t)) => expected.sub_assign(&s_t.0),
+            _ => return false,
+        }
+        if self.opening.responses.first() != Some(&expected) || self.commitment.is_zero() {
+            return false;
+        }
+
+        let g = generator_g();
+        let h = generator_h();
+        // g^(m - t) * h^r == C
+        let opening = self.opening.verify(
+            &[GeneratorG1(g), GeneratorG1(h)],
+            &Commitment(self.commitment),
+            challenge,
+        );
+        // C^a * h^b == g
+        let inverse = self.inverse.verify(
+            &[GeneratorG1(self.commitment), GeneratorG1(h)],
+            &Commitment(g),
+            challenge,
+        );
+        matches!((opening, inverse), (Ok(true), Ok(true)))
+    }
+
+    /// The number of bytes in a proof
+    pub(crate) fn size(g1_size: usize) -> usize {
+        4 + 1 + FR_COMPRESSED_SIZE + g1_size + 2 * (g1_size + 4 + 2 * FR_COMPRESSED_SIZE)
+    }
+
+    pub(crate) fn to_bytes(&self, compressed: bool) -> Vec<u8> {
+        let mut output = Vec::new();
+        output.extend_from_slice(&(self.message_index as u32).to_be_bytes()[..]);
+        match (self.value, self.target_response) {
+            (Some(t), _) => {
+                output.push(0u8);
+                t.0.serialize(&mut output, compressed).unwrap();
+            }
+            (None, s_t) => {
+                output.push(1u8);
+                s_t.unwrap_or_default()
+                    .0
+                    .serialize(&mut output, compressed)
+                    .unwrap();
+            }
+        }
+        self.commitment.serialize(&mut output, compressed).unwrap();
+        output.append(&mut self.opening.to_bytes(compressed));
+        output.append(&mut self.inverse.to_bytes(compressed));
+        output
+    }
+
+    pub(crate) fn from_bytes(
+        data: &[u8],
+        g1_size: usize,
+        compressed: bool,
+    ) -> Result<Self, BBSError> {
+        let size = Self::size(g1_size);
+        if data.len() < size {
+            return Err(BBSErrorKind::InvalidNumberOfBytes(size, data.len()).into());
+        }
+        let message_index = u32::from_be_bytes(*array_ref![data, 0, 4]) as usize;
+        let mut offset = 5;
+        let t = SignatureMessage(slice_to_elem!(
+            &mut &data[offset..offset + FR_COMPRESSED_SIZE],
+            Fr,
+            compressed
+        )?);
+        let (value, target_response) = match data[4] {
+            0 => (Some(t), None),
+            1 => (None, Some(t)),
+            _ => {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Invalid inequality target {}", data[4]),
+                }
+                .into())
+            }
+        };
+        offset += FR_COMPRESSED_SIZE;
+        let commitment = slice_to_elem!(&mut &data[offset..offset + g1_size], G1, compressed)?;
+        offset += g1_size;
+        let proof_size = g1_size + 4 + 2 * FR_COMPRESSED_SIZE;
+        let opening = ProofG1::from_bytes(&data[offset..offset + proof_size], g1_size, compressed)?;
+        offset += proof_size;
+        let inverse = ProofG1::from_bytes(&data[offset..offset + proof_size], g1_size, compressed)?;
+        Ok(Self {
+            message_index,
+            value,
+            target_response,
+            commitment,
+            opening,
+            inverse,
+        })
+    }
+}
+
+/// Check the inequality proofs attached to `proof` with the hidden message responses
+pub(crate) fn verify_inequalities(
+    proof: &PoKOfSignatureProof,
+    message_count: usize,
+    revealed_msgs: &BTreeMap<usize, SignatureMessage>,
+    challenge: &ProofChallenge,
+) -> bool {
+    proof.inequalities.iter().all(|inequality| {
+        let i = inequality.message_index;
+        if i >= message_count || revealed_msgs.contains_key(&i) {
+            return false;
+        }
+        let hidden_index = i - revealed_msgs.range(..i).count();
+        match proof.get_resp_for_message(hidden_index) {
+            Ok(resp) => inequality.verify(&resp, challenge),
+            Err(_) => false,
+        }
+    })
+}
+
+/// The base for the difference of the message and the target
+fn generator_g() -> G1 {
+    G1::one()
+}
+
+/// The base for the blinding factor of the commitment
+fn generator_h() -> G1 {
+    hash_to_g1(b"BBS+ inequality commitment blinding")
+}
+
+fn challenge_bytes(
+    message_index: usize,
+    value: Option<SignatureMessage>,
+    commitment: &G1,
+    opening: &G1,
+    inverse: &G1,
+) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(message_index as u32).to_be_bytes()[..]);
+    match value {
+        Some(t) => {
+            bytes.push(0u8);
+            t.0.serialize(&mut bytes, false).unwrap();
+        }
+        None => bytes.push(1u8),
+    }
+    commitment.serialize(&mut bytes, false).unwrap();
+    opening.serialize(&mut bytes, false).unwrap();
+    inverse.serialize(&mut bytes, false).unwrap();
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn inequality_proofs() {
+        let (pk1, sk1) = Issuer::new_keys(3).unwrap();
+        let (pk2, sk2) = Issuer::new_keys(2).unwrap();
+        let id = SignatureMessage::hash(b"credential id");
+        let messages1 = vec![
+            id,
+            SignatureMessage::hash(b"name"),
+            SignatureMessage::hash(b"other id"),
+        ];
+        let messages2 = vec![SignatureMessage::hash(b"denied id"), messages1[1]];
+        let signature1 = Signature::new(messages1.as_slice(), &sk1, &pk1).unwrap();
+        let signature2 = Signature::new(messages2.as_slice(), &sk2, &pk2).unwrap();
+        let denied = SignatureMessage::hash(b"denied id");
+        let nonce = ProofNonce::random();
+
+        // The id of the first credential is not the denied id or the hidden id in the second
+        let blinding = ProofNonce::random();
+        let mut pok1 = PoKOfSignature::init(
+            &signature1,
+            &pk1,
+            &[
+                pm_hidden_raw!(messages1[0]),
+                pm_revealed_raw!(messages1[1]),
+                pm_hidden_raw!(messages1[2]),
+            ],
+        )
+        .unwrap();
+        pok1.prove_not_equal(0, &InequalityTarget::Value(denied))
+            .unwrap();
+        pok1.prove_not_equal(0, &InequalityTarget::Hidden(messages2[0], blinding))
+            .unwrap();
+        assert!(pok1
+            .prove_not_equal(0, &InequalityTarget::Value(id))
+            .is_err());
+        assert!(pok1
+            .prove_not_equal(1, &InequalityTarget::Value(denied))
+            .is_err());
+        let pok2 = PoKOfSignature::init(
+            &signature2,
+            &pk2,
+            &[
+                pm_hidden_raw!(messages2[0], blinding),
+                pm_revealed_raw!(messages2[1]),
+            ],
+        )
+        .unwrap();
+
+        let challenge =
+            Prover::create_challenge_hash(&[pok1.clone(), pok2.clone()], None, &nonce).unwrap();
+        let revealed1 = pok1.revealed_messages.clone();
+        let revealed2 = pok2.revealed_messages.clone();
+        let proof1 = pok1.gen_proof(&challenge).unwrap();
+        let proof2 = pok2.gen_proof(&challenge).unwrap();
+
+        let proof1 = PoKOfSignatureProof::try_from(proof1.to_bytes_compressed_form()).unwrap();
+        assert_eq!(proof1.inequalities().len(), 2);
+        assert_eq!(proof1.inequalities()[0].value, Some(denied));
+        assert!(proof1
+            .verify(&pk1, &revealed1, &challenge)
+            .unwrap()
+            .is_valid());
+        // The hidden target is linked to the other proof by its response
+        assert_eq!(
+            proof1.inequalities()[1].target_response(),
+            Some(proof2.get_resp_for_message(0).unwrap())
+        );
+        let proof2 = SignatureProof {
+            revealed_messages: revealed2,
+            proof: proof2,
+        };
+        assert!(proof1.inequalities()[1]
+            .verify_hidden_target(&proof2, 0)
+            .is_ok());
+        // The second message of the other proof is revealed
+        assert!(proof1.inequalities()[1]
+            .verify_hidden_target(&proof2, 1)
+            .is_err());
+        assert!(proof1.inequalities()[0]
+            .verify_hidden_target(&proof2, 0)
+            .is_err());
+
+        let mut bad = proof1.clone();
+        bad.inequalities[0].value = Some(id);
+        assert!(!bad.verify(&pk1, &revealed1, &challenge).unwrap().is_valid());
+        let mut bad = proof1.clone();
+        bad.inequalities[0].message_index = 2;
+        assert!(!bad.verify(&pk1, &revealed1, &challenge).unwrap().is_valid());
+        let mut bad = proof1;
+        bad.inequalities[1].target_response = Some(SignatureMessage::random());
+        assert!(!bad.verify(&pk1, &revealed1, &challenge).unwrap().is_valid());
+    }
+}
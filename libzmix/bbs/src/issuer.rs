@@ -1,4 +1,6 @@
+use crate::blind_issuance::prelude::*;
 use crate::errors::prelude::*;
+use crate::ietf;
 use crate::keys::prelude::*;
 use crate::signature::prelude::*;
 /// The issuer generates keys and uses those to sign
@@ -8,7 +10,10 @@ use crate::signature::prelude::*;
 /// to the secret key. `DeterministicPublicKey` can be converted to a
 /// `PublicKey` later. The latter is primarily used for storing a shorter
 /// key and looks just like a regular ECC key.
-use crate::{BlindSignatureContext, ProofNonce, RandomElem, SignatureMessage};
+use crate::{
+    BlindSignatureContext, HashElem, ProofChallenge, ProofNonce, RandomElem, SignatureMessage,
+};
+use rand::{thread_rng, CryptoRng, RngCore};
 use std::collections::{BTreeMap, BTreeSet};
 
 /// This struct represents an Issuer of signatures or Signer.
@@ -23,6 +28,14 @@ impl Issuer {
         generate(message_count)
     }
 
+    /// Same as `new_keys` but the keypair is generated from `rng`
+    pub fn new_keys_with_rng<R: RngCore + CryptoRng>(
+        message_count: usize,
+        rng: &mut R,
+    ) -> Result<(PublicKey, SecretKey), BBSError> {
+        generate_with_rng(message_count, rng)
+    }
+
     /// Create a keypair that uses the short public key
     pub fn new_short_keys(
         option: Option<KeyGenOption>,
@@ -30,26 +43,56 @@ impl Issuer {
         DeterministicPublicKey::new(option)
     }
 
+    /// Same as `new_short_keys` but a random secret key is generated from `rng`
+    pub fn new_short_keys_with_rng<R: RngCore + CryptoRng>(
+        option: Option<KeyGenOption>,
+        rng: &mut R,
+    ) -> Result<(DeterministicPublicKey, SecretKey), BBSError> {
+        DeterministicPublicKey::new_with_rng(option, rng)
+    }
+
     /// Create a signature with no hidden messages
-    pub fn sign(
+    pub fn sign<K: VerificationKey>(
         messages: &[SignatureMessage],
         signkey: &SecretKey,
-        verkey: &PublicKey,
+        verkey: &K,
     ) -> Result<Signature, BBSError> {
         Signature::new(messages, signkey, verkey)
     }
 
+    /// Same as `sign` but the signature randomness is generated from `rng`
+    pub fn sign_with_rng<K: VerificationKey, R: RngCore + CryptoRng>(
+        messages: &[SignatureMessage],
+        signkey: &SecretKey,
+        verkey: &K,
+        rng: &mut R,
+    ) -> Result<Signature, BBSError> {
+        Signature::new_with_rng(messages, signkey, verkey, rng)
+    }
+
     /// Verify a proof of committed messages and generate a blind signature
-    pub fn blind_sign(
+    pub fn blind_sign<K: VerificationKey>(
+        ctx: &BlindSignatureContext,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        signkey: &SecretKey,
+        verkey: &K,
+        nonce: &ProofNonce,
+    ) -> Result<BlindSignature, BBSError> {
+        Self::blind_sign_with_rng(ctx, messages, signkey, verkey, nonce, &mut thread_rng())
+    }
+
+    /// Same as `blind_sign` but the signature randomness is generated from `rng`
+    pub fn blind_sign_with_rng<K: VerificationKey, R: RngCore + CryptoRng>(
         ctx: &BlindSignatureContext,
         messages: &BTreeMap<usize, SignatureMessage>,
         signkey: &SecretKey,
-        verkey: &PublicKey,
+        verkey: &K,
         nonce: &ProofNonce,
+        rng: &mut R,
     ) -> Result<BlindSignature, BBSError> {
         let revealed_messages: BTreeSet<usize> = messages.keys().copied().collect();
-        if ctx.verify(&revealed_messages, verkey, nonce)? {
-            BlindSignature::new(&ctx.commitment, messages, signkey, verkey)
+        if ctx.verify(&revealed_messages, verkey.public_key(), nonce)? {
+            BlindSignature::new_with_rng(&ctx.commitment, messages, signkey, verkey, rng)
         } else {
             Err(BBSErrorKind::GeneralError {
                 msg: "Invalid proof of committed messages".to_string(),
@@ -58,8 +101,108 @@ impl Issuer {
         }
     }
 
+    /// Verify an extended context and generate a blind signature.
+    /// `messages` must be all the messages that are not committed by the holder.
+    pub fn extended_blind_sign<K: VerificationKey>(
+        ctx: &ExtendedBlindSignatureContext,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        signkey: &SecretKey,
+        verkey: &K,
+        nonce: &ProofNonce,
+    ) -> Result<BlindSignature, BBSError> {
+        Self::extended_blind_sign_with_rng(ctx, messages, signkey, verkey, nonce, &mut thread_rng())
+    }
+
+    /// Same as `extended_blind_sign` but the signature randomness is generated from `rng`
+    pub fn extended_blind_sign_with_rng<K: VerificationKey, R: RngCore + CryptoRng>(
+        ctx: &ExtendedBlindSignatureContext,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        signkey: &SecretKey,
+        verkey: &K,
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<BlindSignature, BBSError> {
+        let mut challenge_bytes = ctx.get_bytes_for_challenge(verkey.public_key())?;
+        challenge_bytes.extend_from_slice(&nonce.to_bytes_uncompressed_form()[..]);
+        let challenge = ProofChallenge::hash(&challenge_bytes);
+        Self::extended_blind_sign_with_challenge_and_rng(
+            ctx, messages, signkey, verkey, &challenge, rng,
+        )
+    }
+
+    /// Same as `extended_blind_sign` but the caller computes the challenge
+    /// from `ctx.get_bytes_for_challenge` and any other proofs about the committed messages
+    /// which must be verified separately.
+    pub fn extended_blind_sign_with_challenge<K: VerificationKey>(
+        ctx: &ExtendedBlindSignatureContext,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        signkey: &SecretKey,
+        verkey: &K,
+        challenge: &ProofChallenge,
+    ) -> Result<BlindSignature, BBSError> {
+        Self::extended_blind_sign_with_challenge_and_rng(
+            ctx,
+            messages,
+            signkey,
+            verkey,
+            challenge,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Same as `extended_blind_sign_with_challenge` but the signature randomness
+    /// is generated from `rng`
+    pub fn extended_blind_sign_with_challenge_and_rng<
+        K: VerificationKey,
+        R: RngCore + CryptoRng,
+    >(
+        ctx: &ExtendedBlindSignatureContext,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        signkey: &SecretKey,
+        verkey: &K,
+        challenge: &ProofChallenge,
+        rng: &mut R,
+    ) -> Result<BlindSignature, BBSError> {
+        ctx.check_issuer_messages(messages, verkey.public_key())?;
+        if ctx.verify_with_challenge(verkey.public_key(), challenge)? {
+            BlindSignature::new_with_rng(&ctx.commitment, messages, signkey, verkey, rng)
+        } else {
+            Err(BBSErrorKind::GeneralError {
+                msg: "Invalid proof of committed messages".to_string(),
+            }
+            .into())
+        }
+    }
+
+    /// Create a keypair for one of the draft ciphersuites from `key_material`
+    /// which must be at least 32 bytes of secret entropy
+    pub fn new_ciphersuite_keys(
+        suite: ietf::Ciphersuite,
+        key_material: &[u8],
+        key_info: &[u8],
+    ) -> Result<(DeterministicPublicKey, SecretKey), BBSError> {
+        let sk = ietf::keygen(suite, key_material, key_info, None)?;
+        Ok((ietf::sk_to_pk(&sk), sk))
+    }
+
+    /// Create a signature using one of the draft ciphersuites
+    pub fn ciphersuite_sign(
+        suite: ietf::Ciphersuite,
+        header: &[u8],
+        messages: &[SignatureMessage],
+        signkey: &SecretKey,
+        verkey: &DeterministicPublicKey,
+    ) -> Result<ietf::signature::Signature, BBSError> {
+        ietf::signature::Signature::new(suite, signkey, verkey, header, messages)
+    }
+
     /// Create a nonce used for the blind signing context
     pub fn generate_signing_nonce() -> ProofNonce {
         ProofNonce::random()
     }
+
+    /// Same as `generate_signing_nonce` but the nonce is generated from `rng`
+    pub fn generate_signing_nonce_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> ProofNonce {
+        ProofNonce::random_with_rng(rng)
+    }
 }
@@ -1,16 +1,16 @@
 use crate::errors::prelude::*;
 use crate::{
-    hash_to_g2, GeneratorG1, GeneratorG2, HashElem, RandomElem, ToVariableLengthBytes,
-    FR_COMPRESSED_SIZE, FR_UNCOMPRESSED_SIZE, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
-    G2_COMPRESSED_SIZE, G2_UNCOMPRESSED_SIZE,
+    hash_to_g2, multi_scalar_mul_precomp_g1, GeneratorG1, GeneratorG2, HashElem, RandomElem,
+    SignatureMessage, ToVariableLengthBytes, FR_COMPRESSED_SIZE, FR_UNCOMPRESSED_SIZE,
+    G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE, G2_COMPRESSED_SIZE, G2_UNCOMPRESSED_SIZE,
 };
 use blake2::{digest::generic_array::GenericArray, Blake2b};
 use ff_zeroize::Field;
 use pairing_plus::{
-    bls12_381::{Fr, G1, G2},
+    bls12_381::{Fr, G1Affine, G2Prepared, G1, G2},
     hash_to_field::BaseFromRO,
     serdes::SerDes,
-    CurveProjective,
+    CurveAffine, CurveProjective,
 };
 use rand::prelude::*;
 #[cfg(feature = "rayon")]
@@ -20,6 +20,7 @@ use serde::{
     Deserialize, Deserializer, Serialize, Serializer,
 };
 use std::io::{Cursor, Read};
+use std::ops::Range;
 use std::{
     convert::TryFrom,
     fmt::{Display, Formatter},
@@ -31,7 +32,8 @@ use zeroize::Zeroize;
 /// Convenience importing module
 pub mod prelude {
     pub use super::{
-        generate, DeterministicPublicKey, KeyGenOption, PublicKey, SecretKey,
+        generate, generate_with_rng, DeterministicPublicKey, KeyGenOption, PreparedPublicKey,
+        PublicKey, SecretKey, VerificationKey, BLS12_381_G2_PUB_MULTICODEC,
         DETERMINISTIC_PUBLIC_KEY_COMPRESSED_SIZE,
     };
 }
@@ -70,7 +72,7 @@ from_impl!(SecretKey, Fr, FR_COMPRESSED_SIZE);
 display_impl!(SecretKey);
 serdes_impl!(SecretKey);
 hash_elem_impl!(SecretKey, |data| { generate_secret_key(Some(data)) });
-random_elem_impl!(SecretKey, { generate_secret_key(None) });
+random_elem_impl!(SecretKey, |rng| { generate_secret_key_with_rng(None, rng) });
 
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(SecretKey);
@@ -145,6 +147,44 @@ impl PublicKey {
         Ok(pk)
     }
 
+    /// Derive the generators for messages `message_count()..message_count` and append them.
+    /// Only works for keys from `DeterministicPublicKey::to_prefix_stable_public_key`
+    /// since their message generators don't depend on the message count.
+    /// `h0` is derived again for the new message count.
+    pub fn extend(&mut self, message_count: usize) -> Result<(), BBSError> {
+        self.check_prefix_stable()?;
+        if message_count > self.h.len() {
+            let mut h = prefix_stable_generators(&self.w.0, self.h.len() + 1..message_count + 1);
+            self.h.append(&mut h);
+            self.h0 = prefix_stable_h0(&self.w.0, message_count);
+        }
+        Ok(())
+    }
+
+    /// Create the key for the first `message_count` messages.
+    /// Only works for keys from `DeterministicPublicKey::to_prefix_stable_public_key`
+    /// and derives any missing generators.
+    pub fn prefix(&self, message_count: usize) -> Result<PublicKey, BBSError> {
+        if message_count == 0 {
+            return Err(BBSErrorKind::KeyGenError.into());
+        }
+        let mut pk = self.clone();
+        pk.extend(message_count)?;
+        pk.h.truncate(message_count);
+        pk.h0 = prefix_stable_h0(&pk.w.0, message_count);
+        Ok(pk)
+    }
+
+    fn check_prefix_stable(&self) -> Result<(), BBSError> {
+        if prefix_stable_h0(&self.w.0, self.h.len()) != self.h0 {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "The public key generators depend on the message count".to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
     /// Make sure no generator is identity
     pub fn validate(&self) -> Result<(), BBSError> {
         if self.h0.0.is_zero() || self.w.0.is_zero() || self.h.iter().any(|v| v.0.is_zero()) {
@@ -189,9 +229,140 @@ impl Default for PublicKey {
 try_from_impl!(PublicKey, BBSError);
 display_impl!(PublicKey);
 serdes_impl!(PublicKey);
+multibase_impl!(PublicKey);
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(PublicKey);
 
+/// Implemented by the keys accepted for signing, verifying and proving
+/// so those methods take either a `PublicKey` or a `PreparedPublicKey`
+pub trait VerificationKey {
+    /// The public key with the message generators
+    fn public_key(&self) -> &PublicKey;
+
+    /// The precomputed tables for the public key if there are any
+    fn prepared(&self) -> Option<&PreparedPublicKey> {
+        None
+    }
+}
+
+impl VerificationKey for PublicKey {
+    fn public_key(&self) -> &PublicKey {
+        self
+    }
+}
+
+impl<K: VerificationKey + ?Sized> VerificationKey for &K {
+    fn public_key(&self) -> &PublicKey {
+        (**self).public_key()
+    }
+
+    fn prepared(&self) -> Option<&PreparedPublicKey> {
+        (**self).prepared()
+    }
+}
+
+/// Number of points in the table for each generator
+const PRECOMP_TABLE_SIZE: usize = 256;
+
+/// A `PublicKey` with fixed-base tables for `g1`, `h_0` and each `h_i`
+/// and `w` prepared for pairings.
+/// Creating it is slow and each generator needs 256 points of memory
+/// but verifying signatures and proofs with the key is faster afterwards.
+/// The tables are only used with public scalars. Signing uses the constant time
+/// multiplication since the table lookups would leak the messages and blinding factors.
+#[derive(Clone, Debug)]
+pub struct PreparedPublicKey {
+    key: PublicKey,
+    /// The tables for `g1`, `h_0`, `h_1` ... `h_L` one after another
+    tables: Vec<G1Affine>,
+    w: G2Prepared,
+    g2: G2Prepared,
+}
+
+impl PreparedPublicKey {
+    /// Precompute the tables for `verkey`
+    pub fn new(verkey: PublicKey) -> Result<Self, BBSError> {
+        verkey.validate()?;
+        let mut bases = Vec::with_capacity(verkey.message_count() + 2);
+        bases.push(G1::one());
+        bases.push(verkey.h0.0);
+        bases.extend(verkey.h.iter().map(|h| h.0));
+
+        let table = |b: &G1| {
+            let mut t = vec![G1Affine::zero(); PRECOMP_TABLE_SIZE];
+            b.into_affine().precomp_256(t.as_mut_slice());
+            t
+        };
+        #[cfg(feature = "rayon")]
+        let tables = bases.par_iter().map(table).collect::<Vec<_>>().concat();
+        #[cfg(not(feature = "rayon"))]
+        let tables = bases.iter().map(table).collect::<Vec<_>>().concat();
+
+        Ok(Self {
+            w: verkey.w.0.into_affine().prepare(),
+            g2: G2::one().into_affine().prepare(),
+            key: verkey,
+            tables,
+        })
+    }
+
+    /// Return how many messages this public key can be used to sign
+    pub fn message_count(&self) -> usize {
+        self.key.message_count()
+    }
+
+    /// Compute g<sub>1</sub> * h<sub>0</sub><sup>s</sup> * h<sub>i</sub><sup>m<sub>i</sub></sup>
+    /// for each `(i, m_i)` in `messages`. `h_0` is left out when `s` is `None`.
+    /// Runs in variable time so `s` and `messages` must be public.
+    pub(crate) fn sum_of_products<'a, I>(&self, s: Option<&Fr>, messages: I) -> G1
+    where
+        I: IntoIterator<Item = (usize, &'a SignatureMessage)>,
+    {
+        let mut tables = vec![self.table(0)];
+        let mut scalars = vec![Fr::one()];
+        if let Some(s) = s {
+            tables.push(self.table(1));
+            scalars.push(*s);
+        }
+        for (i, m) in messages {
+            tables.push(self.table(i + 2));
+            scalars.push(m.0);
+        }
+        multi_scalar_mul_precomp_g1(tables.as_slice(), scalars.as_slice())
+    }
+
+    /// The prepared commitment to the secret key
+    pub(crate) fn w(&self) -> &G2Prepared {
+        &self.w
+    }
+
+    /// The prepared generator of G2
+    pub(crate) fn g2(&self) -> &G2Prepared {
+        &self.g2
+    }
+
+    /// The table for `g1` at 0, `h_0` at 1 and `h_i` at i + 2
+    fn table(&self, index: usize) -> &[G1Affine] {
+        &self.tables[index * PRECOMP_TABLE_SIZE..(index + 1) * PRECOMP_TABLE_SIZE]
+    }
+}
+
+impl VerificationKey for PreparedPublicKey {
+    fn public_key(&self) -> &PublicKey {
+        &self.key
+    }
+
+    fn prepared(&self) -> Option<&PreparedPublicKey> {
+        Some(self)
+    }
+}
+
+impl From<PreparedPublicKey> for PublicKey {
+    fn from(prepared: PreparedPublicKey) -> Self {
+        prepared.key
+    }
+}
+
 /// Size of a compressed deterministic public key
 pub const DETERMINISTIC_PUBLIC_KEY_COMPRESSED_SIZE: usize = G2_COMPRESSED_SIZE;
 
@@ -223,15 +394,63 @@ hash_elem_impl!(DeterministicPublicKey, |data| {
     DeterministicPublicKey(hash_to_g2(data))
 });
 
+/// The multicodec prefix for `bls12_381-g2-pub` i.e. the varint of 0xeb
+pub const BLS12_381_G2_PUB_MULTICODEC: [u8; 2] = [0xeb, 0x01];
+
+impl DeterministicPublicKey {
+    /// Convert to the compressed form prefixed with the `bls12_381-g2-pub` multicodec
+    pub fn to_multicodec(&self) -> Vec<u8> {
+        let mut out = BLS12_381_G2_PUB_MULTICODEC.to_vec();
+        out.extend_from_slice(&self.to_bytes_compressed_form()[..]);
+        out
+    }
+
+    /// Convert from the compressed form prefixed with the `bls12_381-g2-pub` multicodec
+    pub fn from_multicodec<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        let data = data.as_ref();
+        if data.len() != BLS12_381_G2_PUB_MULTICODEC.len() + G2_COMPRESSED_SIZE
+            || data[..BLS12_381_G2_PUB_MULTICODEC.len()] != BLS12_381_G2_PUB_MULTICODEC
+        {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Expected a bls12_381-g2-pub multicodec key".to_string(),
+            }
+            .into());
+        }
+        Self::try_from(&data[BLS12_381_G2_PUB_MULTICODEC.len()..])
+    }
+
+    /// Encode the multicodec form with `base`. Using `multibase::Base::Base58Btc`
+    /// produces the `publicKeyMultibase` value of a verification method.
+    pub fn to_multibase(&self, base: multibase::Base) -> String {
+        multibase::encode(base, self.to_multicodec())
+    }
+
+    /// Decode a multibase string holding the multicodec form
+    pub fn from_multibase<I: AsRef<str>>(data: I) -> Result<Self, BBSError> {
+        let (_, bytes) = multibase::decode(data).map_err(|e| BBSErrorKind::GeneralError {
+            msg: format!("Invalid multibase: {}", e),
+        })?;
+        Self::from_multicodec(bytes)
+    }
+}
+
 impl DeterministicPublicKey {
     /// Generates a random `Secretkey` and only creates the commitment to it
     pub fn new(option: Option<KeyGenOption>) -> Result<(Self, SecretKey), BBSError> {
+        Self::new_with_rng(option, &mut thread_rng())
+    }
+
+    /// Same as `new` but a random `SecretKey` is generated from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        option: Option<KeyGenOption>,
+        rng: &mut R,
+    ) -> Result<(Self, SecretKey), BBSError> {
         let secret = match option {
             Some(ref o) => match o {
                 KeyGenOption::UseSeed(ref v) => generate_secret_key(Some(v)),
                 KeyGenOption::FromSecretKey(ref sk) => sk.clone(),
             },
-            None => generate_secret_key(None),
+            None => generate_secret_key_with_rng(None, rng),
         };
 
         secret.validate()?;
@@ -289,32 +508,100 @@ impl DeterministicPublicKey {
     }
 }
 
+impl DeterministicPublicKey {
+    /// Convert to a normal public key where the message generators don't depend on the message count
+    /// so the key for fewer messages is a prefix of the key for more messages.
+    /// The result can be extended later with `PublicKey::extend`.
+    /// h_0 <- H2C(w || I2OSP(1, 1) || I2OSP(0, 4) || I2OSP(message_count, 4))
+    /// h_i <- H2C(w || I2OSP(1, 1) || I2OSP(i, 4))
+    ///
+    /// `h_0` binds the message count into every signature so a signature
+    /// doesn't verify under the key for more messages with trailing zero messages.
+    ///
+    /// Keys from `to_public_key` are unchanged so existing signatures still verify.
+    pub fn to_prefix_stable_public_key(&self, message_count: usize) -> Result<PublicKey, BBSError> {
+        if message_count == 0 {
+            return Err(BBSErrorKind::KeyGenError.into());
+        }
+        Ok(PublicKey {
+            w: GeneratorG2(self.0),
+            h0: prefix_stable_h0(&self.0, message_count),
+            h: prefix_stable_generators(&self.0, 1..message_count + 1),
+        })
+    }
+}
+
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(DeterministicPublicKey);
 
+/// Derive the generators with `indices` that don't depend on the message count
+fn prefix_stable_generators(w: &G2, indices: Range<usize>) -> Vec<GeneratorG1> {
+    let mut data = Vec::with_capacity(5 + G2_UNCOMPRESSED_SIZE);
+    w.serialize(&mut data, false).unwrap();
+    // Separates these generators from the ones that include the message count
+    data.push(1u8);
+    let offset = data.len();
+    // i
+    data.extend_from_slice(&[0u8; 4]);
+
+    let gen_count: Vec<usize> = indices.collect();
+
+    #[cfg(feature = "rayon")]
+    let temp_iter = gen_count.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let temp_iter = gen_count.iter();
+
+    temp_iter
+        .map(|i| {
+            let mut temp = data.clone();
+            temp[offset..].copy_from_slice(&(*i as u32).to_be_bytes()[..]);
+            GeneratorG1::hash(temp)
+        })
+        .collect()
+}
+
+/// Derive the blinding generator of a prefix stable key for `message_count` messages
+fn prefix_stable_h0(w: &G2, message_count: usize) -> GeneratorG1 {
+    let mut data = Vec::with_capacity(9 + G2_UNCOMPRESSED_SIZE);
+    w.serialize(&mut data, false).unwrap();
+    data.push(1u8);
+    data.extend_from_slice(&[0u8; 4]);
+    data.extend_from_slice(&(message_count as u32).to_be_bytes()[..]);
+    GeneratorG1::hash(data)
+}
+
 /// Create a new BBS+ keypair. The generators of the public key are generated at random
 pub fn generate(message_count: usize) -> Result<(PublicKey, SecretKey), BBSError> {
+    generate_with_rng(message_count, &mut thread_rng())
+}
+
+/// Same as `generate` but the secret key and generators come from `rng`
+pub fn generate_with_rng<R: RngCore + CryptoRng>(
+    message_count: usize,
+    rng: &mut R,
+) -> Result<(PublicKey, SecretKey), BBSError> {
     if message_count == 0 {
         return Err(BBSError::from_kind(BBSErrorKind::KeyGenError));
     }
-    let secret = generate_secret_key(None);
+    let secret = generate_secret_key_with_rng(None, rng);
 
     let mut w = G2::one();
     w.mul_assign(secret.0);
-    let gen_count: Vec<usize> = (0..=message_count).collect();
+    let seeds: Vec<[u8; 32]> = (0..=message_count)
+        .map(|_| {
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            seed
+        })
+        .collect();
 
     #[cfg(feature = "rayon")]
-    let temp_iter = gen_count.par_iter();
+    let temp_iter = seeds.par_iter();
     #[cfg(not(feature = "rayon"))]
-    let temp_iter = gen_count.iter();
+    let temp_iter = seeds.iter();
 
     let h = temp_iter
-        .map(|_| {
-            let mut rng = thread_rng();
-            let mut seed = [0u8; 32];
-            rng.fill_bytes(&mut seed);
-            GeneratorG1::hash(seed)
-        })
+        .map(GeneratorG1::hash)
         .collect::<Vec<GeneratorG1>>();
     Ok((
         PublicKey {
@@ -329,6 +616,14 @@ pub fn generate(message_count: usize) -> Result<(PublicKey, SecretKey), BBSError
 /// Similar to https://tools.ietf.org/html/draft-irtf-cfrg-bls-signature-02#section-2.3
 /// info is left blank
 fn generate_secret_key(ikm: Option<&[u8]>) -> SecretKey {
+    generate_secret_key_with_rng(ikm, &mut thread_rng())
+}
+
+/// Same as `generate_secret_key` but the key material comes from `rng` when `ikm` is `None`
+fn generate_secret_key_with_rng<R: RngCore + CryptoRng>(
+    ikm: Option<&[u8]>,
+    rng: &mut R,
+) -> SecretKey {
     let salt = b"BBS-SIG-KEYGEN-SALT-";
     let info = [0u8, FR_UNCOMPRESSED_SIZE as u8]; // I2OSP(L, 2)
     let ikm = match ikm {
@@ -339,7 +634,7 @@ fn generate_secret_key(ikm: Option<&[u8]>) -> SecretKey {
         }
         None => {
             let mut bytes = vec![0u8; FR_COMPRESSED_SIZE + 1];
-            thread_rng().fill_bytes(bytes.as_mut_slice());
+            rng.fill_bytes(bytes.as_mut_slice());
             bytes[FR_COMPRESSED_SIZE] = 0;
             bytes
         }
@@ -353,6 +648,7 @@ fn generate_secret_key(ikm: Option<&[u8]>) -> SecretKey {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::signature::Signature;
 
     #[test]
     fn key_generate() {
@@ -386,6 +682,44 @@ mod tests {
         assert_eq!(public_key_3.unwrap(), public_key);
     }
 
+    #[test]
+    fn prepared_public_key() {
+        use crate::signature::Signature;
+        use crate::{multi_scalar_mul_const_time_g1, SignatureMessage};
+
+        let (pk, sk) = generate(4).unwrap();
+        let prepared = PreparedPublicKey::new(pk.clone()).unwrap();
+        assert_eq!(prepared.message_count(), 4);
+        assert_eq!(prepared.public_key(), &pk);
+
+        let s = Fr::random(&mut thread_rng());
+        let messages = (0..4)
+            .map(|_| SignatureMessage::random())
+            .collect::<Vec<SignatureMessage>>();
+        let mut bases = vec![G1::one(), pk.h0.0];
+        bases.extend(pk.h.iter().map(|h| h.0));
+        let mut scalars = vec![Fr::one(), s];
+        scalars.extend(messages.iter().map(|m| m.0));
+        assert_eq!(
+            prepared.sum_of_products(Some(&s), messages.iter().enumerate()),
+            multi_scalar_mul_const_time_g1(&bases, &scalars)
+        );
+        assert_eq!(
+            prepared.sum_of_products(None, vec![(2, &messages[2])]),
+            multi_scalar_mul_const_time_g1([G1::one(), pk.h[2].0], [Fr::one(), messages[2].0])
+        );
+
+        let signature = Signature::new(&messages, &sk, &prepared).unwrap();
+        assert!(signature.verify(&messages, &pk).unwrap());
+        let signature = Signature::new(&messages, &sk, &pk).unwrap();
+        assert!(signature.verify(&messages, &prepared).unwrap());
+        let mut wrong = messages.clone();
+        wrong[0] = SignatureMessage::random();
+        assert!(!signature.verify(&wrong, &prepared).unwrap());
+
+        assert!(PreparedPublicKey::new(PublicKey::default()).is_err());
+    }
+
     #[test]
     fn key_conversion() {
         let (dpk, _) = DeterministicPublicKey::new(None).unwrap();
@@ -409,6 +743,48 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn prefix_stable_key() {
+        let (dpk, _) = DeterministicPublicKey::new(None).unwrap();
+        let pk3 = dpk.to_prefix_stable_public_key(3).unwrap();
+        let pk5 = dpk.to_prefix_stable_public_key(5).unwrap();
+        assert_ne!(pk3.h0, pk5.h0);
+        assert_eq!(pk3.h[..], pk5.h[..3]);
+        assert_ne!(dpk.to_public_key(3).unwrap().h0, pk3.h0);
+
+        let mut pk = pk3.clone();
+        pk.extend(5).unwrap();
+        assert_eq!(pk, pk5);
+        pk.extend(2).unwrap();
+        assert_eq!(pk, pk5);
+        assert_eq!(pk5.prefix(3).unwrap(), pk3);
+        assert_eq!(pk3.prefix(5).unwrap(), pk5);
+        assert!(pk3.prefix(0).is_err());
+
+        let mut pk = dpk.to_public_key(3).unwrap();
+        assert!(pk.extend(5).is_err());
+        let (pk, _) = generate(3).unwrap();
+        assert!(pk.prefix(2).is_err());
+    }
+
+    #[test]
+    fn prefix_stable_key_binds_message_count() {
+        let (dpk, sk) = DeterministicPublicKey::new(None).unwrap();
+        let pk3 = dpk.to_prefix_stable_public_key(3).unwrap();
+        let mut messages = (0..3)
+            .map(|_| SignatureMessage::random())
+            .collect::<Vec<SignatureMessage>>();
+        let signature = Signature::new(messages.as_slice(), &sk, &pk3).unwrap();
+        assert!(signature.verify(messages.as_slice(), &pk3).unwrap());
+
+        let zero = SignatureMessage::from([0u8; FR_COMPRESSED_SIZE]);
+        for extra in 1..3 {
+            messages.push(zero);
+            let pk = pk3.prefix(3 + extra).unwrap();
+            assert!(!signature.verify(messages.as_slice(), &pk).unwrap());
+        }
+    }
+
     #[test]
     fn key_from_seed() {
         let seed = vec![0u8; 32];
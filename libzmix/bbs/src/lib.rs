@@ -35,16 +35,20 @@ compile_error!("wasm does not support rayon. Remove the dependency on rayon.");
 #[macro_use]
 extern crate arrayref;
 
+/// Re-exported so callers can choose the base used by `to_multibase`
+pub use multibase;
+
 use blake2::digest::{generic_array::GenericArray, Input, VariableOutput};
+use designated::prelude::*;
 use errors::prelude::*;
 use ff_zeroize::{Field, PrimeField};
 use keys::prelude::*;
 use pairing_plus::{
-    bls12_381::{Fr, G1Affine, G1, G2},
+    bls12_381::{Bls12, Fq12, Fr, G1Affine, G2Prepared, G1, G2},
     hash_to_curve::HashToCurve,
     hash_to_field::{BaseFromRO, ExpandMsgXmd},
     serdes::SerDes,
-    CurveAffine, CurveProjective,
+    CurveAffine, CurveProjective, Engine,
 };
 use pok_sig::prelude::*;
 use pok_vc::prelude::*;
@@ -84,23 +88,53 @@ pub mod messages;
 /// Macros and classes used for creating proofs of knowledge
 #[macro_use]
 pub mod pok_vc;
+/// Methods and structs for blind issuance where the committed message indices are part of the proof
+pub mod blind_issuance;
+/// Proof value encodings for verifiable credential data integrity proofs
+pub mod data_integrity;
+/// EIP-2333 hierarchical deterministic derivation of secret keys and link secrets from a seed
+pub mod derivation;
+/// Methods and structs for signature proofs of knowledge that only convince a designated verifier
+pub mod designated;
+/// Encodings of integers, dates, decimals and bytes into messages that can be decoded when revealed
+pub mod encoding;
 /// The errors that BBS+ throws
 pub mod errors;
+/// C callable functions for the issuer, prover and verifier flows
+#[cfg(feature = "ffi")]
+pub mod ffi;
+/// The BBS signature ciphersuites from the IRTF CFRG draft
+pub mod ietf;
+/// Methods and structs for proving a hidden message is not equal to a value or another hidden message
+pub mod inequality;
 /// Represents steps taken by the issuer to create a BBS+ signature
 /// whether its 2PC or all in one
 pub mod issuer;
 /// BBS+ key classes
 pub mod keys;
+/// Methods and structs for proving knowledge of multiple signatures with equal hidden messages
+pub mod multi_proof;
+/// Methods and structs for padding credentials to a fixed message count in a secret schema wide order
+pub mod padding;
 /// Methods and structs for creating signature proofs of knowledge
 pub mod pok_sig;
+/// Verifier requests for presentations of several credentials and the prover's responses
+pub mod presentation;
 /// Represents steps taken by the prover to receive a BBS+ signature
 /// and generate ZKPs
 pub mod prover;
+/// Methods and structs for scope exclusive pseudonyms derived from a link secret
+pub mod pseudonym;
 /// Methods and structs for creating signatures
 pub mod signature;
+/// Methods and structs for threshold issuance where the signing key is shared between issuers
+pub mod threshold;
 /// Represents steps taken by the verifier to request signature proofs of knowledge
 /// and selective disclosure proofs
 pub mod verifier;
+/// JavaScript bindings for the issuer, prover and verifier flows
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 /// Trait for structs that have variable length bytes but use compressed Bls12 elements
 pub trait ToVariableLengthBytes {
@@ -128,7 +162,12 @@ pub trait RandomElem {
     type Output;
 
     /// Return a randomly generated type
-    fn random() -> Self::Output;
+    fn random() -> Self::Output {
+        Self::random_with_rng(&mut thread_rng())
+    }
+
+    /// Return a type generated from `rng`
+    fn random_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> Self::Output;
 }
 
 /// Struct can be generated from hashing
@@ -177,7 +216,7 @@ from_impl!(GeneratorG1, G1, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE);
 display_impl!(GeneratorG1);
 serdes_impl!(GeneratorG1);
 hash_elem_impl!(GeneratorG1, |data| { GeneratorG1(hash_to_g1(data)) });
-random_elem_impl!(GeneratorG1, { Self(G1::random(&mut thread_rng())) });
+random_elem_impl!(GeneratorG1, |rng| { Self(G1::random(rng)) });
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(GeneratorG1);
 
@@ -242,7 +281,7 @@ serdes_impl!(SignatureMessage);
 hash_elem_impl!(SignatureMessage, |data| {
     SignatureMessage(hash_to_fr(data))
 });
-random_elem_impl!(SignatureMessage, { Self(Fr::random(&mut thread_rng())) });
+random_elem_impl!(SignatureMessage, |rng| { Self(Fr::random(rng)) });
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(SignatureMessage);
 
@@ -260,7 +299,7 @@ from_impl!(ProofNonce, Fr, FR_COMPRESSED_SIZE);
 display_impl!(ProofNonce);
 serdes_impl!(ProofNonce);
 hash_elem_impl!(ProofNonce, |data| { ProofNonce(hash_to_fr(data)) });
-random_elem_impl!(ProofNonce, { Self(Fr::random(&mut thread_rng())) });
+random_elem_impl!(ProofNonce, |rng| { Self(Fr::random(rng)) });
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(ProofNonce);
 
@@ -278,7 +317,7 @@ from_impl!(ProofChallenge, Fr, FR_COMPRESSED_SIZE);
 display_impl!(ProofChallenge);
 serdes_impl!(ProofChallenge);
 hash_elem_impl!(ProofChallenge, |data| { ProofChallenge(hash_to_fr(data)) });
-random_elem_impl!(ProofChallenge, { Self(Fr::random(&mut thread_rng())) });
+random_elem_impl!(ProofChallenge, |rng| { Self(Fr::random(rng)) });
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(ProofChallenge);
 
@@ -303,7 +342,7 @@ serdes_impl!(SignatureBlinding);
 hash_elem_impl!(SignatureBlinding, |data| {
     SignatureBlinding(hash_to_fr(data))
 });
-random_elem_impl!(SignatureBlinding, { Self(Fr::random(&mut thread_rng())) });
+random_elem_impl!(SignatureBlinding, |rng| { Self(Fr::random(rng)) });
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(SignatureBlinding);
 
@@ -327,6 +366,33 @@ pub(crate) fn hash_to_fr<I: AsRef<[u8]>>(data: I) -> Fr {
     Fr::from_okm(&res)
 }
 
+/// Check the product of the pairings is the identity using a single final exponentiation
+pub(crate) fn pairing_product_is_one(pairs: &[(G1, G2)]) -> bool {
+    let prepared = pairs
+        .iter()
+        .map(|(_, q)| q.into_affine().prepare())
+        .collect::<Vec<_>>();
+    let pairs = pairs
+        .iter()
+        .zip(prepared.iter())
+        .map(|((p, _), q)| (*p, q))
+        .collect::<Vec<_>>();
+    prepared_pairing_product_is_one(&pairs)
+}
+
+/// Same as `pairing_product_is_one` but the G2 points are already prepared
+pub(crate) fn prepared_pairing_product_is_one(pairs: &[(G1, &G2Prepared)]) -> bool {
+    let prepared = pairs
+        .iter()
+        .map(|(p, q)| (p.into_affine().prepare(), *q))
+        .collect::<Vec<_>>();
+    let refs = prepared.iter().map(|(p, q)| (p, *q)).collect::<Vec<_>>();
+    match Bls12::final_exponentiation(&Bls12::miller_loop(&refs)) {
+        None => false,
+        Some(product) => product == Fq12::one(),
+    }
+}
+
 pub(crate) fn multi_scalar_mul_const_time_g1<G: AsRef<[G1]>, S: AsRef<[Fr]>>(
     bases: G,
     scalars: S,
@@ -384,6 +450,33 @@ pub(crate) fn multi_scalar_mul_var_time_g1<G: AsRef<[G1]>, S: AsRef<[Fr]>>(
     }
 }
 
+/// Compute the sum of products where each base is given by its table from `precomp_256`.
+/// Each table entry `i` holds the base times the sum of 2<sup>32k</sup> for every bit `k` set in `i`
+/// so each round adds one entry per base and only 32 doublings are needed in total.
+/// The table lookups depend on the scalars so this must only be used with public scalars.
+pub(crate) fn multi_scalar_mul_precomp_g1(tables: &[&[G1Affine]], scalars: &[Fr]) -> G1 {
+    let scalars: Vec<[u64; 4]> = scalars
+        .iter()
+        .map(|s| {
+            let mut t = [0u64; 4];
+            t.clone_from_slice(s.into_repr().as_ref());
+            t
+        })
+        .collect();
+    let mut res = G1::zero();
+    for i in (0..32).rev() {
+        res.double();
+        for (table, s) in tables.iter().zip(scalars.iter()) {
+            // Bit k of the index is bit 32k + i of the scalar
+            let index = (0..8).fold(0usize, |acc, k| {
+                acc | ((((s[k / 2] >> (32 * (k % 2) + i)) & 1) as usize) << k)
+            });
+            res.add_assign_mixed(&table[index]);
+        }
+    }
+    res
+}
+
 /// Contains the data used for computing a blind signature and verifying
 /// proof of hidden messages from a prover
 #[derive(Debug, Clone)]
@@ -527,6 +620,9 @@ pub struct ProofRequest {
     /// Allow the prover to know which public key for which the signature must
     /// be valid.
     pub verification_key: PublicKey,
+    /// The verifier that a `DesignatedVerifierProof` is created for.
+    /// `None` for publicly verifiable proofs.
+    pub designated_verifier: Option<DesignatedVerifierPublicKey>,
 }
 
 impl ProofRequest {
@@ -538,6 +634,10 @@ impl ProofRequest {
         let mut output = (temp.len() as u32).to_be_bytes().to_vec();
         output.append(&mut temp);
         output.append(&mut key);
+        // Trailing so requests without a designated verifier keep their encoding
+        if let Some(k) = &self.designated_verifier {
+            k.0.serialize(&mut output, compressed).unwrap();
+        }
         output
     }
 
@@ -555,9 +655,21 @@ impl ProofRequest {
         let offset = 4 + bitvector_len;
         let revealed_messages = bitvector_to_revealed(&data[4..offset]);
         let verification_key = PublicKey::from_bytes(&data[offset..], g1_size, compressed)?;
+        let offset = offset + verification_key.to_bytes(compressed).len();
+        let designated_verifier = match data.len() - offset {
+            0 => None,
+            n if n == g1_size => {
+                let mut c = &data[offset..];
+                Some(DesignatedVerifierPublicKey(slice_to_elem!(
+                    &mut c, G1, compressed
+                )?))
+            }
+            n => return Err(BBSErrorKind::InvalidNumberOfBytes(g1_size, n).into()),
+        };
         Ok(Self {
             revealed_messages,
             verification_key,
+            designated_verifier,
         })
     }
 }
@@ -567,6 +679,7 @@ impl Default for ProofRequest {
         Self {
             revealed_messages: BTreeSet::new(),
             verification_key: PublicKey::default(),
+            designated_verifier: None,
         }
     }
 }
@@ -612,6 +725,90 @@ pub struct SignatureProof {
 }
 
 impl SignatureProof {
+    /// Verify many signature proofs of knowledge like `Verifier::verify_signature_pok`
+    /// but with a random linear combination of their pairing equations
+    /// which only needs one final exponentiation.
+    /// Returns the revealed messages for each proof.
+    /// If any proof is invalid the indices of the invalid proofs are returned
+    /// in `BBSErrorKind::BatchVerificationFailed`.
+    pub fn batch_verify(
+        items: &[(&ProofRequest, &SignatureProof, &ProofNonce)],
+    ) -> Result<Vec<Vec<SignatureMessage>>, BBSError> {
+        Self::batch_verify_with_rng(items, &mut thread_rng())
+    }
+
+    /// Same as `batch_verify` but the random linear combination comes from `rng`
+    pub fn batch_verify_with_rng<R: RngCore + CryptoRng>(
+        items: &[(&ProofRequest, &SignatureProof, &ProofNonce)],
+        rng: &mut R,
+    ) -> Result<Vec<Vec<SignatureMessage>>, BBSError> {
+        let mut failed = Vec::new();
+        let mut batched = Vec::with_capacity(items.len());
+        // e(A'^r, w) for each public key and e(A bar^-r, g2) summed over all proofs
+        let mut pairs: Vec<(G1, G2)> = Vec::new();
+        let mut g2_term = G1::zero();
+        for (i, (proof_request, signature_proof, nonce)) in items.iter().enumerate() {
+            let vk = &proof_request.verification_key;
+            let proof = &signature_proof.proof;
+            let mut challenge_bytes =
+                proof.get_bytes_for_challenge(proof_request.revealed_messages.clone(), vk);
+            challenge_bytes.extend_from_slice(&nonce.to_bytes_uncompressed_form()[..]);
+            let challenge = ProofChallenge::hash(&challenge_bytes);
+
+            if proof
+                .check_inputs(vk, &signature_proof.revealed_messages)
+                .is_err()
+                || proof.a_prime.is_zero()
+            {
+                failed.push(i);
+                continue;
+            }
+            match proof.verify_committed_values(vk, &signature_proof.revealed_messages, &challenge)
+            {
+                Ok(PoKOfSignatureProofStatus::Success) => {}
+                _ => {
+                    failed.push(i);
+                    continue;
+                }
+            }
+            batched.push(i);
+
+            let r = rand_non_zero_fr(rng);
+            let mut a_prime_r = proof.a_prime;
+            a_prime_r.mul_assign(r);
+            match pairs.iter_mut().find(|(_, w)| *w == vk.w.0) {
+                Some((p, _)) => p.add_assign(&a_prime_r),
+                None => pairs.push((a_prime_r, vk.w.0)),
+            }
+            let mut a_bar_r = proof.a_bar;
+            a_bar_r.mul_assign(r);
+            g2_term.sub_assign(&a_bar_r);
+        }
+        pairs.push((g2_term, G2::one()));
+
+        if !batched.is_empty() && !pairing_product_is_one(&pairs) {
+            for i in batched {
+                let proof = &items[i].1.proof;
+                let mut a_bar = proof.a_bar;
+                a_bar.negate();
+                if !pairing_product_is_one(&[
+                    (proof.a_prime, items[i].0.verification_key.w.0),
+                    (a_bar, G2::one()),
+                ]) {
+                    failed.push(i);
+                }
+            }
+            failed.sort_unstable();
+        }
+        if !failed.is_empty() {
+            return Err(BBSErrorKind::BatchVerificationFailed { indices: failed }.into());
+        }
+        Ok(items
+            .iter()
+            .map(|(_, p, _)| p.revealed_messages.values().copied().collect())
+            .collect())
+    }
+
     /// Convert to raw bytes
     pub(crate) fn to_bytes(&self, compressed: bool) -> Vec<u8> {
         let proof_bytes = self.proof.to_bytes(compressed);
@@ -710,6 +907,7 @@ impl Default for SignatureProof {
 
 try_from_impl!(SignatureProof, BBSError);
 serdes_impl!(SignatureProof);
+multibase_impl!(SignatureProof);
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(SignatureProof);
 
@@ -750,26 +948,29 @@ fn bitvector_to_revealed(data: &[u8]) -> BTreeSet<usize> {
     revealed_messages
 }
 
-fn rand_non_zero_fr() -> Fr {
-    let mut rng = thread_rng();
-    let mut r = Fr::random(&mut rng);
+fn rand_non_zero_fr<R: RngCore + CryptoRng>(rng: &mut R) -> Fr {
+    let mut r = Fr::random(rng);
     loop {
         if !r.is_zero() {
             return r;
         }
-        r = Fr::random(&mut rng);
+        r = Fr::random(rng);
     }
 }
 
 /// Convenience importer
 pub mod prelude {
     pub use super::{
-        errors::prelude::*, issuer::Issuer, keys::prelude::*, messages::*, pok_sig::prelude::*,
-        pok_vc::prelude::*, prover::Prover, signature::prelude::*, verifier::Verifier,
-        BlindSignatureContext, Commitment, CommitmentBuilder, GeneratorG1, GeneratorG2, HashElem,
-        ProofChallenge, ProofNonce, ProofRequest, RandomElem, SignatureBlinding, SignatureMessage,
-        SignatureProof, ToVariableLengthBytes, FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE,
-        G1_UNCOMPRESSED_SIZE, G2_COMPRESSED_SIZE, G2_UNCOMPRESSED_SIZE,
+        blind_issuance::prelude::*, data_integrity::prelude::*, derivation::prelude::*,
+        designated::prelude::*, encoding::prelude::*, errors::prelude::*, inequality::prelude::*,
+        issuer::Issuer, keys::prelude::*, messages::*, multi_proof::prelude::*,
+        padding::prelude::*, pok_sig::prelude::*, pok_vc::prelude::*, presentation::prelude::*,
+        prover::Prover, pseudonym::prelude::*, signature::prelude::*, threshold::prelude::*,
+        verifier::Verifier, BlindSignatureContext, Commitment, CommitmentBuilder, GeneratorG1,
+        GeneratorG2, HashElem, ProofChallenge, ProofNonce, ProofRequest, RandomElem,
+        SignatureBlinding, SignatureMessage, SignatureProof, ToVariableLengthBytes,
+        FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE, G2_COMPRESSED_SIZE,
+        G2_UNCOMPRESSED_SIZE,
     };
 }
 
@@ -817,6 +1018,65 @@ mod tests {
         println!("to_public_key = {:?}", std::time::Instant::now() - start);
     }
 
+    #[test]
+    fn batch_verify_proofs_test() {
+        let (pk1, sk1) = generate(4).unwrap();
+        let (pk2, sk2) = generate(3).unwrap();
+        let messages = (0..4)
+            .map(|_| SignatureMessage::random())
+            .collect::<Vec<SignatureMessage>>();
+        let sig1 = Signature::new(&messages, &sk1, &pk1).unwrap();
+        let sig2 = Signature::new(&messages[1..], &sk2, &pk2).unwrap();
+
+        let nonce = Verifier::generate_proof_nonce();
+        let request1 = Verifier::new_proof_request(&[0, 2], &pk1).unwrap();
+        let request2 = Verifier::new_proof_request(&[1], &pk2).unwrap();
+        let proof = |sig: &Signature, request: &ProofRequest, msgs: &[SignatureMessage]| {
+            let proof_messages = msgs
+                .iter()
+                .enumerate()
+                .map(|(i, m)| {
+                    if request.revealed_messages.contains(&i) {
+                        pm_revealed_raw!(*m)
+                    } else {
+                        pm_hidden_raw!(*m)
+                    }
+                })
+                .collect::<Vec<ProofMessage>>();
+            let pok = Prover::commit_signature_pok(request, &proof_messages, sig).unwrap();
+            let mut challenge_bytes = pok.to_bytes();
+            challenge_bytes.extend_from_slice(&nonce.to_bytes_uncompressed_form()[..]);
+            let challenge = ProofChallenge::hash(&challenge_bytes);
+            Prover::generate_signature_pok(pok, &challenge).unwrap()
+        };
+        let proof1 = proof(&sig1, &request1, &messages);
+        let proof2 = proof(&sig2, &request2, &messages[1..]);
+        let proof3 = proof(&sig1, &request1, &messages);
+
+        let items = vec![
+            (&request1, &proof1, &nonce),
+            (&request2, &proof2, &nonce),
+            (&request1, &proof3, &nonce),
+        ];
+        let revealed = SignatureProof::batch_verify(&items).unwrap();
+        assert_eq!(revealed[0], vec![messages[0], messages[2]]);
+        assert_eq!(revealed[1], vec![messages[2]]);
+
+        // Wrong nonce and wrong public key
+        let other_nonce = Verifier::generate_proof_nonce();
+        let wrong_key = Verifier::new_proof_request(&[1], &pk1).unwrap();
+        let items = vec![
+            (&request1, &proof1, &nonce),
+            (&request2, &proof2, &other_nonce),
+            (&request1, &proof3, &nonce),
+            (&wrong_key, &proof2, &nonce),
+        ];
+        match SignatureProof::batch_verify(&items).unwrap_err().kind() {
+            BBSErrorKind::BatchVerificationFailed { indices } => assert_eq!(indices, vec![1, 3]),
+            _ => panic!("Expected a batch verification failure"),
+        }
+    }
+
     #[test]
     fn proof_request_bytes_test() {
         let (pk, _) = generate(5).unwrap();
@@ -882,6 +1142,7 @@ mod tests {
                     commitment: G1::zero(),
                     responses: Vec::with_capacity(1),
                 },
+                inequalities: Vec::new(),
             },
         };
 
@@ -257,11 +257,11 @@ macro_rules! hash_elem_impl {
 }
 
 macro_rules! random_elem_impl {
-    ($name:ident, $func:block) => {
+    ($name:ident, |$rng:ident| $func:block) => {
         impl RandomElem for $name {
             type Output = $name;
 
-            fn random() -> Self::Output $func
+            fn random_with_rng<R: RngCore + CryptoRng>($rng: &mut R) -> Self::Output $func
         }
     };
 }
@@ -286,6 +286,26 @@ macro_rules! default_zero_impl {
     };
 }
 
+macro_rules! multibase_impl {
+    ($name:ident) => {
+        impl $name {
+            /// Encode the compressed form as a multibase string using `base`
+            pub fn to_multibase(&self, base: multibase::Base) -> String {
+                multibase::encode(base, self.to_bytes_compressed_form())
+            }
+
+            /// Decode a multibase string produced by `to_multibase`
+            pub fn from_multibase<I: AsRef<str>>(data: I) -> Result<Self, BBSError> {
+                let (_, bytes) =
+                    multibase::decode(data).map_err(|e| BBSErrorKind::GeneralError {
+                        msg: format!("Invalid multibase: {}", e),
+                    })?;
+                <Self as std::convert::TryFrom<&[u8]>>::try_from(bytes.as_slice())
+            }
+        }
+    };
+}
+
 #[cfg(feature = "wasm")]
 macro_rules! wasm_slice_impl {
     ($name:ident) => {
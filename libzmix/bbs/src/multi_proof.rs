@@ -0,0 +1,734 @@
+use crate::errors::prelude::*;
+use crate::inequality::prelude::*;
+use crate::keys::prelude::*;
+use crate::messages::*;
+use crate::pok_sig::prelude::*;
+use crate::signature::prelude::*;
+use crate::{
+    HashElem, ProofChallenge, ProofNonce, ProofRequest, RandomElem, SignatureMessage,
+    SignatureProof, ToVariableLengthBytes, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
+};
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{EquivalenceClass, HiddenInequality, MessageReference, MultiSignatureProof};
+}
+
+/// Identifies a message by the index of the credential in the proof
+/// and the index of the message in that credential
+pub type MessageReference = (usize, usize);
+
+/// A set of hidden messages across one or more credentials that are proven to be equal
+pub type EquivalenceClass = BTreeSet<MessageReference>;
+
+/// A hidden message and a hidden message in another credential that are proven to be different
+pub type HiddenInequality = (MessageReference, MessageReference);
+
+/// A proof of knowledge of multiple signatures under a single Fiat-Shamir challenge
+/// that also proves hidden messages in each `EquivalenceClass` are equal.
+///
+/// Equal messages use the same blinding factor so their responses are equal
+/// iff the messages are equal.
+#[derive(Debug, Clone)]
+pub struct MultiSignatureProof {
+    /// The signature proofs in the same order as the credentials
+    pub proofs: Vec<SignatureProof>,
+}
+
+impl MultiSignatureProof {
+    /// Create a proof for each `(signature, verkey, proof_messages)` in `credentials`
+    /// where all messages in each of the `equalities` are hidden and equal.
+    /// Messages in an equivalence class that use `HiddenMessage::ExternalBlinding` must all use
+    /// the same blinding factor. Otherwise one is generated for the class.
+    pub fn new(
+        credentials: &[(Signature, PublicKey, Vec<ProofMessage>)],
+        equalities: &[EquivalenceClass],
+        nonce: &ProofNonce,
+    ) -> Result<Self, BBSError> {
+        Self::new_with_rng(credentials, equalities, nonce, &mut thread_rng())
+    }
+
+    /// Same as `new` but all blinding factors are generated from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        credentials: &[(Signature, PublicKey, Vec<ProofMessage>)],
+        equalities: &[EquivalenceClass],
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        Self::new_with_inequalities_with_rng(credentials, equalities, &[], nonce, rng)
+    }
+
+    /// Same as `new` but also proves the first message of each of the `inequalities`
+    /// is not equal to the second one. Both messages must be hidden.
+    pub fn new_with_inequalities(
+        credentials: &[(Signature, PublicKey, Vec<ProofMessage>)],
+        equalities: &[EquivalenceClass],
+        inequalities: &[HiddenInequality],
+        nonce: &ProofNonce,
+    ) -> Result<Self, BBSError> {
+        Self::new_with_inequalities_with_rng(
+            credentials,
+            equalities,
+            inequalities,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Same as `new_with_inequalities` but all blinding factors are generated from `rng`
+    pub fn new_with_inequalities_with_rng<R: RngCore + CryptoRng>(
+        credentials: &[(Signature, PublicKey, Vec<ProofMessage>)],
+        equalities: &[EquivalenceClass],
+        inequalities: &[HiddenInequality],
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        let message_counts = credentials
+            .iter()
+            .map(|(_, _, m)| m.len())
+            .collect::<Vec<usize>>();
+        check_equalities(equalities, &message_counts)?;
+        check_inequalities(inequalities, &message_counts)?;
+
+        // Assign the blinding factor for each equivalence class
+        let mut blindings = BTreeMap::new();
+        for class in equalities {
+            let mut message = None;
+            let mut blinding = None;
+            for (c, m) in class {
+                let (msg, b) = match &credentials[*c].2[*m] {
+                    ProofMessage::Revealed(_) => {
+                        return Err(BBSErrorKind::GeneralError {
+                            msg: format!(
+                                "Message {} in credential {} is revealed but must be hidden to prove equality",
+                                m, c
+                            ),
+                        }
+                        .into())
+                    }
+                    ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(msg)) => {
+                        (*msg, None)
+                    }
+                    ProofMessage::Hidden(HiddenMessage::ExternalBlinding(msg, b)) => {
+                        (*msg, Some(*b))
+                    }
+                };
+                if *message.get_or_insert(msg) != msg {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!(
+                            "Message {} in credential {} is not equal to the others in its class",
+                            m, c
+                        ),
+                    }
+                    .into());
+                }
+                if let Some(b) = b {
+                    if *blinding.get_or_insert(b) != b {
+                        return Err(BBSErrorKind::GeneralError {
+                            msg: format!("Message {} in credential {} has a different external blinding than the others in its class", m, c),
+                        }
+                        .into());
+                    }
+                }
+            }
+            let blinding = blinding.unwrap_or_else(|| ProofNonce::random_with_rng(rng));
+            for r in class {
+                blindings.insert(*r, blinding);
+            }
+        }
+        // The prover needs the blinding factor of each hidden target
+        for (_, (c, m)) in inequalities {
+            if blindings.contains_key(&(*c, *m)) {
+                continue;
+            }
+            let blinding = match &credentials[*c].2[*m] {
+                ProofMessage::Revealed(_) => {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!(
+                            "Message {} in credential {} is revealed but must be hidden to prove inequality",
+                            m, c
+                        ),
+                    }
+                    .into())
+                }
+                ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(_)) => {
+                    ProofNonce::random_with_rng(rng)
+                }
+                ProofMessage::Hidden(HiddenMessage::ExternalBlinding(_, b)) => *b,
+            };
+            blindings.insert((*c, *m), blinding);
+        }
+
+        let mut poks = Vec::with_capacity(credentials.len());
+        for (c, (signature, verkey, messages)) in credentials.iter().enumerate() {
+            let proof_messages = messages
+                .iter()
+                .enumerate()
+                .map(|(m, pm)| match blindings.get(&(c, m)) {
+                    Some(b) => pm_hidden_raw!(pm.get_message(), *b),
+                    None => match pm {
+                        ProofMessage::Revealed(r) => pm_revealed_raw!(*r),
+                        ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(h)) => {
+                            pm_hidden_raw!(*h)
+                        }
+                        ProofMessage::Hidden(HiddenMessage::ExternalBlinding(h, b)) => {
+                            pm_hidden_raw!(*h, *b)
+                        }
+                    },
+                })
+                .collect::<Vec<ProofMessage>>();
+            let mut pok = PoKOfSignature::init_with_rng(signature, verkey, &proof_messages, rng)?;
+            for ((_, m), (tc, tm)) in inequalities.iter().filter(|((ic, _), _)| *ic == c) {
+                let target = InequalityTarget::Hidden(
+                    credentials[*tc].2[*tm].get_message(),
+                    blindings[&(*tc, *tm)],
+                );
+                pok.prove_not_equal_with_rng(*m, &target, rng)?;
+            }
+            poks.push(pok);
+        }
+
+        let mut challenge_bytes = Vec::new();
+        for pok in &poks {
+            challenge_bytes.extend_from_slice(pok.to_bytes().as_slice());
+        }
+        let challenge = compute_challenge(challenge_bytes, equalities, inequalities, nonce);
+
+        let mut proofs = Vec::with_capacity(poks.len());
+        for pok in poks {
+            let revealed_messages = pok.revealed_messages.clone();
+            proofs.push(SignatureProof {
+                revealed_messages,
+                proof: pok.gen_proof(&challenge)?,
+            });
+        }
+        Ok(Self { proofs })
+    }
+
+    /// Verify each signature proof against its corresponding `proof_requests`
+    /// and check the messages in each of the `equalities` are equal.
+    /// Returns the revealed messages for each credential.
+    pub fn verify(
+        &self,
+        proof_requests: &[ProofRequest],
+        equalities: &[EquivalenceClass],
+        nonce: &ProofNonce,
+    ) -> Result<Vec<BTreeMap<usize, SignatureMessage>>, BBSError> {
+        self.verify_with_inequalities(proof_requests, equalities, &[], nonce)
+    }
+
+    /// Same as `verify` but also checks the first message of each of the `inequalities`
+    /// is not equal to the second one.
+    /// Fails if the proofs contain other inequalities with a hidden target.
+    pub fn verify_with_inequalities(
+        &self,
+        proof_requests: &[ProofRequest],
+        equalities: &[EquivalenceClass],
+        inequalities: &[HiddenInequality],
+        nonce: &ProofNonce,
+    ) -> Result<Vec<BTreeMap<usize, SignatureMessage>>, BBSError> {
+        if self.proofs.len() != proof_requests.len() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Expected {} proofs, found {}",
+                    proof_requests.len(),
+                    self.proofs.len()
+                ),
+            }
+            .into());
+        }
+        let message_counts = proof_requests
+            .iter()
+            .map(|r| r.verification_key.message_count())
+            .collect::<Vec<usize>>();
+        check_equalities(equalities, &message_counts)?;
+        check_inequalities(inequalities, &message_counts)?;
+
+        let mut challenge_bytes = Vec::new();
+        for (p, r) in self.proofs.iter().zip(proof_requests.iter()) {
+            let revealed = p
+                .revealed_messages
+                .keys()
+                .copied()
+                .collect::<BTreeSet<usize>>();
+            if revealed != r.revealed_messages {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: "Revealed messages do not match the proof request".to_string(),
+                }
+                .into());
+            }
+            challenge_bytes.extend_from_slice(
+                p.proof
+                    .get_bytes_for_challenge(revealed, &r.verification_key)
+                    .as_slice(),
+            );
+        }
+        let challenge = compute_challenge(challenge_bytes, equalities, inequalities, nonce);
+
+        for (c, (p, r)) in self.proofs.iter().zip(proof_requests.iter()).enumerate() {
+            match p
+                .proof
+                .verify(&r.verification_key, &p.revealed_messages, &challenge)?
+            {
+                PoKOfSignatureProofStatus::Success => {}
+                e => return Err(BBSErrorKind::InvalidProof { status: e }.into()),
+            }
+            // The proof has exactly the hidden target inequalities that were requested
+            // in the order they were requested
+            let proven = p
+                .proof
+                .inequalities()
+                .iter()
+                .filter(|i| i.value.is_none())
+                .collect::<Vec<&InequalityProof>>();
+            let requested = inequalities
+                .iter()
+                .filter(|((ic, _), _)| *ic == c)
+                .collect::<Vec<&HiddenInequality>>();
+            if proven.len() != requested.len() {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!(
+                        "Inequalities in credential {} do not match the requested ones",
+                        c
+                    ),
+                }
+                .into());
+            }
+            for (inequality, ((_, m), (tc, tm))) in proven.iter().zip(requested) {
+                if inequality.message_index != *m {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!(
+                            "Inequalities in credential {} do not match the requested ones",
+                            c
+                        ),
+                    }
+                    .into());
+                }
+                inequality.verify_hidden_target(&self.proofs[*tc], *tm)?;
+            }
+        }
+
+        for class in equalities {
+            let mut response = None;
+            for (c, m) in class {
+                let revealed = &proof_requests[*c].revealed_messages;
+                if revealed.contains(m) {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!(
+                            "Message {} in credential {} is revealed but must be hidden to prove equality",
+                            m, c
+                        ),
+                    }
+                    .into());
+                }
+                // Responses are only present for hidden messages
+                let hidden_index = m - revealed.range(..m).count();
+                let resp = self.proofs[*c].proof.get_resp_for_message(hidden_index)?;
+                if *response.get_or_insert(resp) != resp {
+                    return Err(BBSErrorKind::InvalidProof {
+                        status: PoKOfSignatureProofStatus::BadHiddenMessage,
+                    }
+                    .into());
+                }
+            }
+        }
+
+        Ok(self
+            .proofs
+            .iter()
+            .map(|p| p.revealed_messages.clone())
+            .collect())
+    }
+
+    pub(crate) fn to_bytes(&self, compressed: bool) -> Vec<u8> {
+        let mut output = (self.proofs.len() as u32).to_be_bytes().to_vec();
+        for p in &self.proofs {
+            let proof_bytes = p.to_bytes(compressed);
+            output.extend_from_slice(&(proof_bytes.len() as u32).to_be_bytes()[..]);
+            output.extend_from_slice(proof_bytes.as_slice());
+        }
+        output
+    }
+
+    pub(crate) fn from_bytes(
+        data: &[u8],
+        g1_size: usize,
+        compressed: bool,
+    ) -> Result<Self, BBSError> {
+        if data.len() < 4 {
+            return Err(BBSErrorKind::InvalidNumberOfBytes(4, data.len()).into());
+        }
+        let count = u32::from_be_bytes(*array_ref![data, 0, 4]) as usize;
+        let mut offset = 4;
+        let mut proofs = Vec::new();
+        for _ in 0..count {
+            if data.len() < offset + 4 {
+                return Err(BBSErrorKind::InvalidNumberOfBytes(offset + 4, data.len()).into());
+            }
+            let len = u32::from_be_bytes(*array_ref![data, offset, 4]) as usize;
+            offset += 4;
+            if data.len() < offset + len {
+                return Err(BBSErrorKind::InvalidNumberOfBytes(offset + len, data.len()).into());
+            }
+            proofs.push(SignatureProof::from_bytes(
+                &data[offset..offset + len],
+                g1_size,
+                compressed,
+            )?);
+            offset += len;
+        }
+        Ok(Self { proofs })
+    }
+}
+
+impl ToVariableLengthBytes for MultiSignatureProof {
+    type Output = MultiSignatureProof;
+    type Error = BBSError;
+
+    /// Convert to raw bytes using compressed form for each element.
+    fn to_bytes_compressed_form(&self) -> Vec<u8> {
+        self.to_bytes(true)
+    }
+
+    /// Convert from compressed form raw bytes.
+    fn from_bytes_compressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        Self::from_bytes(data.as_ref(), G1_COMPRESSED_SIZE, true)
+    }
+
+    fn to_bytes_uncompressed_form(&self) -> Vec<u8> {
+        self.to_bytes(false)
+    }
+
+    fn from_bytes_uncompressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self::Output, Self::Error> {
+        Self::from_bytes(data.as_ref(), G1_UNCOMPRESSED_SIZE, false)
+    }
+}
+
+/// Each message must exist and belong to at most one equivalence class
+pub(crate) fn check_equalities(
+    equalities: &[EquivalenceClass],
+    message_counts: &[usize],
+) -> Result<(), BBSError> {
+    let mut seen = BTreeSet::new();
+    for (c, m) in equalities.iter().flatten() {
+        if *c >= message_counts.len() || *m >= message_counts[*c] {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Message {} in credential {} does not exist", m, c),
+            }
+            .into());
+        }
+        if !seen.insert((*c, *m)) {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Message {} in credential {} is in more than one equivalence class",
+                    m, c
+                ),
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Each inequality must be between existing messages in different credentials
+fn check_inequalities(
+    inequalities: &[HiddenInequality],
+    message_counts: &[usize],
+) -> Result<(), BBSError> {
+    for ((c, m), (tc, tm)) in inequalities {
+        for (c, m) in [(c, m), (tc, tm)].iter() {
+            if **c >= message_counts.len() || **m >= message_counts[**c] {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Message {} in credential {} does not exist", m, c),
+                }
+                .into());
+            }
+        }
+        if c == tc {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Message {} in credential {} must be compared to a message in another credential",
+                    m, c
+                ),
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// The equivalence classes and inequalities are included so the proof is bound to the
+/// claimed relations
+fn compute_challenge(
+    mut bytes: Vec<u8>,
+    equalities: &[EquivalenceClass],
+    inequalities: &[HiddenInequality],
+    nonce: &ProofNonce,
+) -> ProofChallenge {
+    bytes.extend_from_slice(&(equalities.len() as u32).to_be_bytes()[..]);
+    for class in equalities {
+        bytes.extend_from_slice(&(class.len() as u32).to_be_bytes()[..]);
+        for (c, m) in class {
+            bytes.extend_from_slice(&(*c as u32).to_be_bytes()[..]);
+            bytes.extend_from_slice(&(*m as u32).to_be_bytes()[..]);
+        }
+    }
+    bytes.extend_from_slice(&(inequalities.len() as u32).to_be_bytes()[..]);
+    for ((c, m), (tc, tm)) in inequalities {
+        for v in [c, m, tc, tm].iter() {
+            bytes.extend_from_slice(&(**v as u32).to_be_bytes()[..]);
+        }
+    }
+    bytes.extend_from_slice(&nonce.to_bytes_uncompressed_form()[..]);
+    ProofChallenge::hash(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::generate;
+
+    fn sign(messages: &[SignatureMessage]) -> (Signature, PublicKey) {
+        let (pk, sk) = generate(messages.len()).unwrap();
+        (Signature::new(messages, &sk, &pk).unwrap(), pk)
+    }
+
+    fn request(pk: &PublicKey, revealed: &[usize]) -> ProofRequest {
+        ProofRequest {
+            revealed_messages: revealed.iter().copied().collect(),
+            verification_key: pk.clone(),
+            designated_verifier: None,
+        }
+    }
+
+    fn class(refs: &[MessageReference]) -> EquivalenceClass {
+        refs.iter().copied().collect()
+    }
+
+    #[test]
+    fn multi_proof_equality() {
+        let subject = SignatureMessage::hash(b"did:example:subject");
+        let messages_1 = vec![
+            subject,
+            SignatureMessage::hash(b"name"),
+            SignatureMessage::hash(b"address"),
+        ];
+        let messages_2 = vec![
+            SignatureMessage::hash(b"degree"),
+            SignatureMessage::hash(b"university"),
+            SignatureMessage::hash(b"year"),
+            subject,
+        ];
+        let (sig_1, pk_1) = sign(&messages_1);
+        let (sig_2, pk_2) = sign(&messages_2);
+
+        let credentials = vec![
+            (
+                sig_1,
+                pk_1.clone(),
+                vec![
+                    pm_hidden_raw!(messages_1[0]),
+                    pm_revealed_raw!(messages_1[1]),
+                    pm_hidden_raw!(messages_1[2]),
+                ],
+            ),
+            (
+                sig_2,
+                pk_2.clone(),
+                vec![
+                    pm_revealed_raw!(messages_2[0]),
+                    pm_hidden_raw!(messages_2[1]),
+                    pm_revealed_raw!(messages_2[2]),
+                    pm_hidden_raw!(messages_2[3]),
+                ],
+            ),
+        ];
+        let equalities = vec![class(&[(0, 0), (1, 3)])];
+        let nonce = ProofNonce::random();
+        let proof = MultiSignatureProof::new(&credentials, &equalities, &nonce).unwrap();
+
+        let requests = vec![request(&pk_1, &[1]), request(&pk_2, &[0, 2])];
+        let revealed = proof.verify(&requests, &equalities, &nonce).unwrap();
+        assert_eq!(revealed[0][&1], messages_1[1]);
+        assert_eq!(revealed[1][&0], messages_2[0]);
+        assert_eq!(revealed[1][&2], messages_2[2]);
+
+        // Serialization
+        let bytes = proof.to_bytes_compressed_form();
+        let proof_2 = MultiSignatureProof::from_bytes_compressed_form(&bytes).unwrap();
+        assert!(proof_2.verify(&requests, &equalities, &nonce).is_ok());
+
+        // The challenge binds the nonce and the claimed equalities
+        assert!(proof
+            .verify(&requests, &equalities, &ProofNonce::random())
+            .is_err());
+        assert!(proof.verify(&requests, &[], &nonce).is_err());
+        assert!(proof
+            .verify(&requests, &[class(&[(0, 2), (1, 1)])], &nonce)
+            .is_err());
+    }
+
+    #[test]
+    fn multi_proof_unequal_messages() {
+        let messages_1 = vec![SignatureMessage::hash(b"a"), SignatureMessage::hash(b"b")];
+        let messages_2 = vec![SignatureMessage::hash(b"c"), SignatureMessage::hash(b"d")];
+        let (sig_1, pk_1) = sign(&messages_1);
+        let (sig_2, pk_2) = sign(&messages_2);
+        let credentials = vec![
+            (
+                sig_1,
+                pk_1,
+                vec![pm_hidden_raw!(messages_1[0]), pm_hidden_raw!(messages_1[1])],
+            ),
+            (
+                sig_2,
+                pk_2,
+                vec![
+                    pm_hidden_raw!(messages_2[0]),
+                    pm_revealed_raw!(messages_2[1]),
+                ],
+            ),
+        ];
+        let nonce = ProofNonce::random();
+        // Different messages
+        assert!(
+            MultiSignatureProof::new(&credentials, &[class(&[(0, 0), (1, 0)])], &nonce).is_err()
+        );
+        // Revealed message
+        assert!(
+            MultiSignatureProof::new(&credentials, &[class(&[(0, 1), (1, 1)])], &nonce).is_err()
+        );
+        // Out of range
+        assert!(
+            MultiSignatureProof::new(&credentials, &[class(&[(0, 0), (2, 0)])], &nonce).is_err()
+        );
+        // Overlapping classes
+        assert!(MultiSignatureProof::new(
+            &credentials,
+            &[class(&[(0, 0)]), class(&[(0, 0), (0, 1)])],
+            &nonce
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn multi_proof_hidden_inequality() {
+        let shared = SignatureMessage::hash(b"shared");
+        let messages_1 = vec![SignatureMessage::hash(b"id 1"), shared];
+        let messages_2 = vec![SignatureMessage::hash(b"id 2"), shared];
+        let (sig_1, pk_1) = sign(&messages_1);
+        let (sig_2, pk_2) = sign(&messages_2);
+        let mut credentials = vec![
+            (
+                sig_1,
+                pk_1.clone(),
+                vec![pm_hidden_raw!(messages_1[0]), pm_hidden_raw!(messages_1[1])],
+            ),
+            (
+                sig_2,
+                pk_2.clone(),
+                vec![
+                    pm_hidden_raw!(messages_2[0]),
+                    pm_revealed_raw!(messages_2[1]),
+                ],
+            ),
+        ];
+        let inequalities = vec![((0, 0), (1, 0))];
+        let nonce = ProofNonce::random();
+        let proof =
+            MultiSignatureProof::new_with_inequalities(&credentials, &[], &inequalities, &nonce)
+                .unwrap();
+
+        let requests = vec![request(&pk_1, &[]), request(&pk_2, &[1])];
+        assert!(proof
+            .verify_with_inequalities(&requests, &[], &inequalities, &nonce)
+            .is_ok());
+        let bytes = proof.to_bytes_compressed_form();
+        let proof_2 = MultiSignatureProof::from_bytes_compressed_form(&bytes).unwrap();
+        assert!(proof_2
+            .verify_with_inequalities(&requests, &[], &inequalities, &nonce)
+            .is_ok());
+
+        // The hidden target must be checked against the other proof
+        assert!(proof.verify(&requests, &[], &nonce).is_err());
+        assert!(proof
+            .verify_with_inequalities(&requests, &[], &[((0, 1), (1, 0))], &nonce)
+            .is_err());
+
+        // The target is the response for message 0 of the second proof
+        let inequality = &proof.proofs[0].proof.inequalities()[0];
+        assert!(inequality.verify_hidden_target(&proof.proofs[1], 0).is_ok());
+        assert!(inequality
+            .verify_hidden_target(&proof.proofs[1], 1)
+            .is_err());
+        assert!(inequality
+            .verify_hidden_target(&proof.proofs[0], 1)
+            .is_err());
+
+        // Several inequalities in both directions
+        assert!(MultiSignatureProof::new_with_inequalities(
+            &credentials,
+            &[],
+            &[((0, 1), (1, 0)), ((1, 0), (0, 0))],
+            &nonce
+        )
+        .is_ok());
+        // Revealed target
+        assert!(MultiSignatureProof::new_with_inequalities(
+            &credentials,
+            &[],
+            &[((0, 0), (1, 1))],
+            &nonce
+        )
+        .is_err());
+        // Same credential
+        assert!(MultiSignatureProof::new_with_inequalities(
+            &credentials,
+            &[],
+            &[((0, 0), (0, 1))],
+            &nonce
+        )
+        .is_err());
+        // Equal messages
+        credentials[1].2[1] = pm_hidden_raw!(messages_2[1]);
+        assert!(MultiSignatureProof::new_with_inequalities(
+            &credentials,
+            &[],
+            &[((0, 1), (1, 1))],
+            &nonce
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn multi_proof_external_blinding() {
+        let shared = SignatureMessage::hash(b"shared");
+        let messages_1 = vec![shared, SignatureMessage::hash(b"a")];
+        let messages_2 = vec![SignatureMessage::hash(b"b"), shared];
+        let (sig_1, pk_1) = sign(&messages_1);
+        let (sig_2, pk_2) = sign(&messages_2);
+        let blinding = ProofNonce::random();
+        let credentials = vec![
+            (
+                sig_1,
+                pk_1.clone(),
+                vec![
+                    pm_hidden_raw!(shared, blinding),
+                    pm_hidden_raw!(messages_1[1]),
+                ],
+            ),
+            (
+                sig_2,
+                pk_2.clone(),
+                vec![pm_revealed_raw!(messages_2[0]), pm_hidden_raw!(shared)],
+            ),
+        ];
+        let equalities = vec![class(&[(0, 0), (1, 1)])];
+        let nonce = ProofNonce::random();
+        let proof = MultiSignatureProof::new(&credentials, &equalities, &nonce).unwrap();
+        let requests = vec![request(&pk_1, &[]), request(&pk_2, &[0])];
+        assert!(proof.verify(&requests, &equalities, &nonce).is_ok());
+    }
+}
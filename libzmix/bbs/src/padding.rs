@@ -0,0 +1,453 @@
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::messages::*;
+use crate::{HashElem, ProofRequest, RandomElem, SignatureMessage};
+use blake2::{
+    digest::{Input, VariableOutput},
+    VarBlake2b,
+};
+use rand::prelude::*;
+use std::collections::BTreeSet;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{MessagePermutation, PaddedMessages, LAYOUT_KEY_SIZE};
+}
+
+/// The number of bytes in a layout key
+pub const LAYOUT_KEY_SIZE: usize = 32;
+
+/// Maps each message of a credential to its position in a credential
+/// padded to a fixed message count.
+///
+/// The mapping is derived from a secret layout key the issuer creates for each schema and
+/// only gives to holders of the schema. All credentials of a schema use the same mapping and
+/// padded count so the revealed padded positions don't distinguish holders, and verifiers
+/// don't know the key so they can't map the padded positions back to the original indices.
+/// One position holds a commitment to the mapping so the signature commits to it.
+/// Proofs only reveal the padded count and the padded positions, not how many messages
+/// the credential has.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MessagePermutation {
+    /// The padded position of each original message
+    positions: Vec<usize>,
+    /// The number of messages after padding
+    padded_count: usize,
+    /// The commitment to the layout key and the positions
+    commitment: SignatureMessage,
+}
+
+impl MessagePermutation {
+    /// Create a random layout key for a schema
+    pub fn generate_layout_key() -> [u8; LAYOUT_KEY_SIZE] {
+        Self::generate_layout_key_with_rng(&mut thread_rng())
+    }
+
+    /// Same as `generate_layout_key` but the key comes from `rng`
+    pub fn generate_layout_key_with_rng<R: RngCore + CryptoRng>(
+        rng: &mut R,
+    ) -> [u8; LAYOUT_KEY_SIZE] {
+        let mut key = [0u8; LAYOUT_KEY_SIZE];
+        rng.fill_bytes(&mut key);
+        key
+    }
+
+    /// Create the mapping of `message_count` messages into `padded_count` positions
+    /// from the schema's `layout_key`. Every holder of the schema derives the same mapping.
+    pub fn new(
+        layout_key: &[u8],
+        message_count: usize,
+        padded_count: usize,
+    ) -> Result<Self, BBSError> {
+        check_layout_key(layout_key)?;
+        check_counts(message_count, padded_count)?;
+        let mut seed = b"BBS+ padded message layout".to_vec();
+        seed.extend_from_slice(&(layout_key.len() as u64).to_be_bytes()[..]);
+        seed.extend_from_slice(layout_key);
+        seed.extend_from_slice(&(padded_count as u64).to_be_bytes()[..]);
+
+        // Fisher-Yates shuffle driven by the seed
+        let mut positions = (0..padded_count).collect::<Vec<usize>>();
+        for i in (1..padded_count).rev() {
+            let mut data = seed.clone();
+            data.extend_from_slice(&(i as u64).to_be_bytes()[..]);
+            let mut hasher = VarBlake2b::new(8).unwrap();
+            hasher.input(&data);
+            let mut j = 0u64;
+            hasher.variable_result(|out| j = u64::from_be_bytes(*array_ref![out, 0, 8]));
+            positions.swap(i, (j % (i as u64 + 1)) as usize);
+        }
+        positions.truncate(message_count);
+        Ok(Self {
+            commitment: layout_commitment(layout_key, &positions, padded_count),
+            positions,
+            padded_count,
+        })
+    }
+
+    /// Create the mapping where the message at index `i` is moved to `positions[i]`
+    /// e.g. a layout the issuer chose for the schema and keeps secret with `layout_key`
+    pub fn from_positions(
+        layout_key: &[u8],
+        positions: Vec<usize>,
+        padded_count: usize,
+    ) -> Result<Self, BBSError> {
+        check_layout_key(layout_key)?;
+        check_counts(positions.len(), padded_count)?;
+        let mut seen = BTreeSet::new();
+        for p in &positions {
+            if *p >= padded_count || !seen.insert(*p) {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Invalid padded position {}", p),
+                }
+                .into());
+            }
+        }
+        Ok(Self {
+            commitment: layout_commitment(layout_key, &positions, padded_count),
+            positions,
+            padded_count,
+        })
+    }
+
+    /// The number of messages before padding
+    pub fn message_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// The number of messages after padding
+    pub fn padded_count(&self) -> usize {
+        self.padded_count
+    }
+
+    /// The padded position of each original message
+    pub fn positions(&self) -> &[usize] {
+        self.positions.as_slice()
+    }
+
+    /// The padded position of the commitment to the layout.
+    /// It is the first position without an original message.
+    pub fn commitment_position(&self) -> usize {
+        (0..self.padded_count)
+            .find(|p| !self.positions.contains(p))
+            .unwrap()
+    }
+
+    /// The commitment to the layout that is signed at `commitment_position`
+    pub fn commitment(&self) -> SignatureMessage {
+        self.commitment
+    }
+
+    /// Map original message indices to their padded positions
+    pub fn padded_indices(&self, indices: &BTreeSet<usize>) -> Result<BTreeSet<usize>, BBSError> {
+        indices
+            .iter()
+            .map(|i| {
+                self.positions.get(*i).copied().ok_or_else(|| {
+                    BBSErrorKind::GeneralError {
+                        msg: format!("Invalid message index {}", i),
+                    }
+                    .into()
+                })
+            })
+            .collect()
+    }
+}
+
+/// The messages of a credential after padding and permutation.
+/// These are the messages the issuer signs and the holder must keep them
+/// including the dummy messages to create proofs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PaddedMessages {
+    messages: Vec<SignatureMessage>,
+    permutation: MessagePermutation,
+}
+
+impl PaddedMessages {
+    /// Place `messages` according to `permutation`, the commitment to the layout at its position
+    /// and fill the remaining positions with random dummy messages
+    pub fn new(
+        messages: &[SignatureMessage],
+        permutation: MessagePermutation,
+    ) -> Result<Self, BBSError> {
+        Self::new_with_rng(messages, permutation, &mut thread_rng())
+    }
+
+    /// Same as `new` but the dummy messages are generated from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        messages: &[SignatureMessage],
+        permutation: MessagePermutation,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        if messages.len() != permutation.message_count() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Expected {} messages, found {}",
+                    permutation.message_count(),
+                    messages.len()
+                ),
+            }
+            .into());
+        }
+        let mut padded = (0..permutation.padded_count())
+            .map(|_| SignatureMessage::random_with_rng(rng))
+            .collect::<Vec<SignatureMessage>>();
+        for (m, p) in messages.iter().zip(permutation.positions.iter()) {
+            padded[*p] = *m;
+        }
+        padded[permutation.commitment_position()] = permutation.commitment;
+        Ok(Self {
+            messages: padded,
+            permutation,
+        })
+    }
+
+    /// Restore from messages that were previously padded with `permutation`.
+    /// Holders use this to check the issuer signed the messages with the schema's layout.
+    pub fn from_padded_messages(
+        messages: Vec<SignatureMessage>,
+        permutation: MessagePermutation,
+    ) -> Result<Self, BBSError> {
+        if messages.len() != permutation.padded_count() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Expected {} padded messages, found {}",
+                    permutation.padded_count(),
+                    messages.len()
+                ),
+            }
+            .into());
+        }
+        if messages[permutation.commitment_position()] != permutation.commitment {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "The padded messages do not commit to the layout".to_string(),
+            }
+            .into());
+        }
+        Ok(Self {
+            messages,
+            permutation,
+        })
+    }
+
+    /// The padded messages in the order they are signed
+    pub fn messages(&self) -> &[SignatureMessage] {
+        self.messages.as_slice()
+    }
+
+    /// The mapping used to pad the messages
+    pub fn permutation(&self) -> &MessagePermutation {
+        &self.permutation
+    }
+
+    /// The messages in their original order without the dummy messages
+    pub fn original_messages(&self) -> Vec<SignatureMessage> {
+        self.permutation
+            .positions
+            .iter()
+            .map(|p| self.messages[*p])
+            .collect()
+    }
+
+    /// Create the proof messages that reveal the messages at the original
+    /// indices `revealed` and hide all others including the dummy messages
+    pub fn proof_messages(
+        &self,
+        revealed: &BTreeSet<usize>,
+    ) -> Result<Vec<ProofMessage>, BBSError> {
+        let revealed = self.permutation.padded_indices(revealed)?;
+        Ok(self
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                if revealed.contains(&i) {
+                    ProofMessage::Revealed(*m)
+                } else {
+                    ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(*m))
+                }
+            })
+            .collect())
+    }
+
+    /// Create the proof request for revealing the messages at the original
+    /// indices `revealed`. The request only contains the padded positions.
+    pub fn proof_request(
+        &self,
+        revealed: &BTreeSet<usize>,
+        verkey: &PublicKey,
+    ) -> Result<ProofRequest, BBSError> {
+        if verkey.message_count() != self.permutation.padded_count() {
+            return Err(BBSErrorKind::PublicKeyGeneratorMessageCountMismatch(
+                self.permutation.padded_count(),
+                verkey.message_count(),
+            )
+            .into());
+        }
+        Ok(ProofRequest {
+            revealed_messages: self.permutation.padded_indices(revealed)?,
+            verification_key: verkey.clone(),
+            designated_verifier: None,
+        })
+    }
+}
+
+/// The commitment to the layout is hashed with the layout key so it cannot be guessed
+fn layout_commitment(
+    layout_key: &[u8],
+    positions: &[usize],
+    padded_count: usize,
+) -> SignatureMessage {
+    let mut data = b"BBS+ padded message layout commitment".to_vec();
+    data.extend_from_slice(&(layout_key.len() as u64).to_be_bytes()[..]);
+    data.extend_from_slice(layout_key);
+    data.extend_from_slice(&(padded_count as u64).to_be_bytes()[..]);
+    for p in positions {
+        data.extend_from_slice(&(*p as u64).to_be_bytes()[..]);
+    }
+    SignatureMessage::hash(data)
+}
+
+fn check_layout_key(layout_key: &[u8]) -> Result<(), BBSError> {
+    if layout_key.len() < LAYOUT_KEY_SIZE {
+        return Err(BBSErrorKind::GeneralError {
+            msg: format!(
+                "The layout key must be at least {} bytes, found {}",
+                LAYOUT_KEY_SIZE,
+                layout_key.len()
+            ),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// One padded position is reserved for the commitment to the layout
+fn check_counts(message_count: usize, padded_count: usize) -> Result<(), BBSError> {
+    if message_count >= padded_count {
+        return Err(BBSErrorKind::GeneralError {
+            msg: format!(
+                "Cannot pad {} messages to {} messages",
+                message_count, padded_count
+            ),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    const LAYOUT_KEY: [u8; LAYOUT_KEY_SIZE] = [7u8; LAYOUT_KEY_SIZE];
+
+    #[test]
+    fn padded_proof() {
+        let messages = (0..3)
+            .map(|i| SignatureMessage::hash(format!("statement {}", i)))
+            .collect::<Vec<SignatureMessage>>();
+        let permutation = MessagePermutation::new(&LAYOUT_KEY, messages.len(), 8).unwrap();
+        let padded = PaddedMessages::new(&messages, permutation.clone()).unwrap();
+        assert_eq!(padded.messages().len(), 8);
+        assert_eq!(padded.original_messages(), messages);
+        assert_eq!(
+            padded.messages()[permutation.commitment_position()],
+            permutation.commitment()
+        );
+
+        let (pk, sk) = Issuer::new_keys(8).unwrap();
+        let signature = Issuer::sign(padded.messages(), &sk, &pk).unwrap();
+
+        // The holder checks the signed messages commit to the layout
+        let restored =
+            PaddedMessages::from_padded_messages(padded.messages().to_vec(), permutation.clone())
+                .unwrap();
+        assert_eq!(restored, padded);
+        let mut other = padded.messages().to_vec();
+        other[permutation.commitment_position()] = SignatureMessage::random();
+        assert!(PaddedMessages::from_padded_messages(other, permutation).is_err());
+
+        let nonce = Verifier::generate_proof_nonce();
+        let revealed = [0usize, 2].iter().copied().collect::<BTreeSet<usize>>();
+        let proof =
+            Prover::generate_padded_signature_pok(&padded, &signature, &pk, &revealed, &nonce)
+                .unwrap();
+        let padded_indices = padded.permutation().padded_indices(&revealed).unwrap();
+        assert_eq!(
+            proof
+                .revealed_messages
+                .keys()
+                .copied()
+                .collect::<BTreeSet<usize>>(),
+            padded_indices
+        );
+
+        // Revealed messages are returned by their padded position
+        let expected = revealed
+            .iter()
+            .map(|i| (padded.permutation().positions()[*i], messages[*i]))
+            .collect::<BTreeMap<usize, SignatureMessage>>();
+        assert_eq!(
+            Verifier::verify_padded_signature_pok(&pk, &proof, &nonce).unwrap(),
+            expected
+        );
+
+        let (pk, _) = Issuer::new_keys(3).unwrap();
+        assert!(padded.proof_request(&revealed, &pk).is_err());
+    }
+
+    #[test]
+    fn same_schema_same_layout() {
+        let (pk, sk) = Issuer::new_keys(8).unwrap();
+        let revealed = [0usize, 2].iter().copied().collect::<BTreeSet<usize>>();
+        let nonce = Verifier::generate_proof_nonce();
+
+        let revealed_indices = |holder: &str| {
+            let messages = (0..3)
+                .map(|i| SignatureMessage::hash(format!("{} statement {}", holder, i)))
+                .collect::<Vec<SignatureMessage>>();
+            let permutation = MessagePermutation::new(&LAYOUT_KEY, messages.len(), 8).unwrap();
+            let padded = PaddedMessages::new(&messages, permutation).unwrap();
+            let signature = Issuer::sign(padded.messages(), &sk, &pk).unwrap();
+            let proof =
+                Prover::generate_padded_signature_pok(&padded, &signature, &pk, &revealed, &nonce)
+                    .unwrap();
+            assert!(Verifier::verify_padded_signature_pok(&pk, &proof, &nonce).is_ok());
+            proof
+                .revealed_messages
+                .keys()
+                .copied()
+                .collect::<BTreeSet<usize>>()
+        };
+        assert_eq!(revealed_indices("alice"), revealed_indices("bob"));
+
+        assert_eq!(
+            MessagePermutation::new(&LAYOUT_KEY, 3, 8).unwrap(),
+            MessagePermutation::new(&LAYOUT_KEY, 3, 8).unwrap()
+        );
+        // Without the layout key the layout cannot be derived
+        let other_key = MessagePermutation::generate_layout_key();
+        assert_ne!(
+            MessagePermutation::new(&LAYOUT_KEY, 7, 8).unwrap(),
+            MessagePermutation::new(&other_key, 7, 8).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_permutation() {
+        assert!(MessagePermutation::new(&LAYOUT_KEY, 4, 3).is_err());
+        // A position is needed for the commitment
+        assert!(MessagePermutation::new(&LAYOUT_KEY, 3, 3).is_err());
+        assert!(MessagePermutation::new(&LAYOUT_KEY[..16], 2, 3).is_err());
+        assert!(MessagePermutation::from_positions(&LAYOUT_KEY, vec![0, 0], 3).is_err());
+        assert!(MessagePermutation::from_positions(&LAYOUT_KEY, vec![0, 3], 3).is_err());
+        let permutation = MessagePermutation::from_positions(&LAYOUT_KEY, vec![2, 0], 3).unwrap();
+        assert_eq!(permutation.commitment_position(), 1);
+        assert!(permutation
+            .padded_indices(&[2usize].iter().copied().collect())
+            .is_err());
+        assert!(PaddedMessages::new(&[SignatureMessage::random()], permutation).is_err());
+    }
+}
@@ -1,20 +1,22 @@
 use crate::errors::prelude::*;
-use crate::keys::PublicKey;
+use crate::inequality::{prelude::*, verify_inequalities, ProverCommittedInequality};
+use crate::keys::{PublicKey, VerificationKey};
 use crate::messages::*;
 use crate::pok_vc::prelude::*;
 use crate::signature::Signature;
 use crate::{
-    multi_scalar_mul_const_time_g1, rand_non_zero_fr, Commitment, CommitmentBuilder, GeneratorG1,
-    ProofChallenge, SignatureMessage, ToVariableLengthBytes, G1_COMPRESSED_SIZE,
-    G1_UNCOMPRESSED_SIZE,
+    multi_scalar_mul_const_time_g1, pairing_product_is_one, prepared_pairing_product_is_one,
+    rand_non_zero_fr, Commitment, CommitmentBuilder, GeneratorG1, ProofChallenge, SignatureMessage,
+    ToVariableLengthBytes, FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
 };
 
 use ff_zeroize::{Field, PrimeField};
 use pairing_plus::serdes::SerDes;
 use pairing_plus::{
-    bls12_381::{Bls12, Fq12, Fr, FrRepr, G1, G2},
-    CurveAffine, CurveProjective, Engine,
+    bls12_381::{Fr, FrRepr, G1, G2},
+    CurveProjective,
 };
+use rand::{thread_rng, CryptoRng, RngCore};
 use serde::{
     de::{Error as DError, Visitor},
     Deserialize, Deserializer, Serialize, Serializer,
@@ -54,6 +56,8 @@ pub struct PoKOfSignature {
     secrets_2: Vec<Fr>,
     /// revealed messages
     pub(crate) revealed_messages: BTreeMap<usize, SignatureMessage>,
+    /// Proofs that hidden messages are not equal to a target
+    inequalities: Vec<ProverCommittedInequality>,
 }
 
 /// Indicates the status returned from `PoKOfSignatureProof`
@@ -110,15 +114,28 @@ pub struct PoKOfSignatureProof {
     pub(crate) proof_vc_1: ProofG1,
     /// Proof of relation g1 * h1^m1 * h2^m2.... for all disclosed messages m_i == d^r3 * h_0^{-s_prime} * h1^-m1 * h2^-m2.... for all undisclosed messages m_i
     pub(crate) proof_vc_2: ProofG1,
+    /// Proofs that hidden messages are not equal to a target
+    pub(crate) inequalities: Vec<InequalityProof>,
 }
 
 impl PoKOfSignature {
     /// Creates the initial proof data before a Fiat-Shamir calculation
-    pub fn init(
+    pub fn init<K: VerificationKey>(
         signature: &Signature,
-        vk: &PublicKey,
+        vk: &K,
         messages: &[ProofMessage],
     ) -> Result<Self, BBSError> {
+        Self::init_with_rng(signature, vk, messages, &mut thread_rng())
+    }
+
+    /// Same as `init` but the randomness and blinding factors are generated from `rng`
+    pub fn init_with_rng<K: VerificationKey, R: RngCore + CryptoRng>(
+        signature: &Signature,
+        verkey: &K,
+        messages: &[ProofMessage],
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        let vk = verkey.public_key();
         if messages.len() != vk.message_count() {
             return Err(BBSError::from_kind(
                 BBSErrorKind::PublicKeyGeneratorMessageCountMismatch(
@@ -131,15 +148,15 @@ impl PoKOfSignature {
             .iter()
             .map(|m| m.get_message())
             .collect::<Vec<SignatureMessage>>();
-        if !signature.verify(sig_messages.as_slice(), &vk)? {
+        if !signature.verify(sig_messages.as_slice(), verkey)? {
             return Err(BBSErrorKind::PoKVCError {
                 msg: "The messages and signature do not match.".to_string(),
             }
             .into());
         }
 
-        let r1 = rand_non_zero_fr();
-        let r2 = rand_non_zero_fr();
+        let r1 = rand_non_zero_fr(rng);
+        let r2 = rand_non_zero_fr(rng);
 
         let mut temp: Vec<SignatureMessage> = Vec::new();
         for i in 0..messages.len() {
@@ -150,7 +167,7 @@ impl PoKOfSignature {
             }
         }
 
-        let b = signature.get_b(temp.as_slice(), &vk);
+        let b = signature.get_b(temp.as_slice(), verkey);
 
         let mut a_prime = signature.a;
         a_prime.mul_assign(r1);
@@ -183,12 +200,12 @@ impl PoKOfSignature {
         let mut committing_1 = ProverCommittingG1::new();
         let mut secrets_1 = Vec::with_capacity(2);
         // For a_prime^{-e}
-        committing_1.commit(&GeneratorG1(a_prime));
+        committing_1.commit_with_rng(&GeneratorG1(a_prime), rng);
         let mut sig_e = signature.e;
         sig_e.negate();
         secrets_1.push(sig_e);
         // For h_0^r2
-        committing_1.commit(&vk.h0);
+        committing_1.commit_with_rng(&vk.h0, rng);
         secrets_1.push(r2);
         let pok_vc_1 = committing_1.finish();
 
@@ -202,12 +219,12 @@ impl PoKOfSignature {
         let mut committing_2 = ProverCommittingG1::new();
         let mut secrets_2 = Vec::with_capacity(2 + messages.len());
         // For d^-r3
-        committing_2.commit(&GeneratorG1(d));
+        committing_2.commit_with_rng(&GeneratorG1(d), rng);
         let mut r3_d = r3;
         r3_d.negate();
         secrets_2.push(r3_d);
         // h_0^s_prime
-        committing_2.commit(&vk.h0);
+        committing_2.commit_with_rng(&vk.h0, rng);
         secrets_2.push(s_prime);
 
         let mut revealed_messages = BTreeMap::new();
@@ -218,7 +235,7 @@ impl PoKOfSignature {
                     revealed_messages.insert(i, *r);
                 }
                 ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(m)) => {
-                    committing_2.commit(&vk.h[i]);
+                    committing_2.commit_with_rng(&vk.h[i], rng);
                     secrets_2.push(m.0);
                 }
                 ProofMessage::Hidden(HiddenMessage::ExternalBlinding(e, b)) => {
@@ -238,9 +255,49 @@ impl PoKOfSignature {
             pok_vc_2,
             secrets_2,
             revealed_messages,
+            inequalities: Vec::new(),
         })
     }
 
+    /// Prove the hidden message at `message_index` is not equal to `target`.
+    /// Must be called before the challenge is computed.
+    pub fn prove_not_equal(
+        &mut self,
+        message_index: usize,
+        target: &InequalityTarget,
+    ) -> Result<(), BBSError> {
+        self.prove_not_equal_with_rng(message_index, target, &mut thread_rng())
+    }
+
+    /// Same as `prove_not_equal` but the blinding factors are generated from `rng`
+    pub fn prove_not_equal_with_rng<R: RngCore + CryptoRng>(
+        &mut self,
+        message_index: usize,
+        target: &InequalityTarget,
+        rng: &mut R,
+    ) -> Result<(), BBSError> {
+        // 2 elements in self.secrets_2 are reserved for `r3` and `s_prime`
+        let hidden_count = self.secrets_2.len() - 2;
+        if message_index >= hidden_count + self.revealed_messages.len()
+            || self.revealed_messages.contains_key(&message_index)
+        {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Message {} must be hidden", message_index),
+            }
+            .into());
+        }
+        let idx = 2 + message_index - self.revealed_messages.range(..message_index).count();
+        let blinding = self.pok_vc_2.blinding_factor(idx).unwrap();
+        self.inequalities.push(ProverCommittedInequality::new(
+            message_index,
+            self.secrets_2[idx],
+            blinding,
+            target,
+            rng,
+        )?);
+        Ok(())
+    }
+
     /// Return byte representation of public elements so they can be used for challenge computation.
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = vec![];
@@ -254,6 +311,10 @@ impl PoKOfSignature {
         // self.d is included as part of self.pok_vc_2
         bytes.append(&mut self.pok_vc_2.to_bytes());
 
+        for inequality in &self.inequalities {
+            bytes.append(&mut inequality.to_bytes());
+        }
+
         bytes
     }
 
@@ -279,6 +340,11 @@ impl PoKOfSignature {
         let proof_vc_2 = self
             .pok_vc_2
             .gen_proof(challenge_hash, secrets_2.as_slice())?;
+        let inequalities = self
+            .inequalities
+            .into_iter()
+            .map(|i| i.gen_proof(challenge_hash))
+            .collect::<Result<Vec<InequalityProof>, BBSError>>()?;
 
         Ok(PoKOfSignatureProof {
             a_prime: self.a_prime,
@@ -286,6 +352,7 @@ impl PoKOfSignature {
             d: self.d,
             proof_vc_1,
             proof_vc_2,
+            inequalities,
         })
     }
 }
@@ -318,9 +385,17 @@ impl PoKOfSignatureProof {
             .commitment
             .serialize(&mut bytes, false)
             .unwrap();
+        for inequality in &self.inequalities {
+            bytes.append(&mut inequality.get_bytes_for_challenge());
+        }
         bytes
     }
 
+    /// The proofs that hidden messages are not equal to a target
+    pub fn inequalities(&self) -> &[InequalityProof] {
+        &self.inequalities
+    }
+
     /// Get the response from post-challenge phase of the Sigma protocol for the given message index `msg_idx`.
     /// Used when comparing message equality
     pub fn get_resp_for_message(&self, msg_idx: usize) -> Result<SignatureMessage, BBSError> {
@@ -339,45 +414,60 @@ impl PoKOfSignatureProof {
     }
 
     /// Validate the proof
-    pub fn verify(
+    pub fn verify<K: VerificationKey>(
         &self,
-        vk: &PublicKey,
+        vk: &K,
         revealed_msgs: &BTreeMap<usize, SignatureMessage>,
         challenge: &ProofChallenge,
     ) -> Result<PoKOfSignatureProofStatus, BBSError> {
-        vk.validate()?;
-        for i in revealed_msgs.keys() {
-            if *i >= vk.message_count() {
-                return Err(BBSError::from_kind(BBSErrorKind::GeneralError {
-                    msg: format!("Index {} should be less than {}", i, vk.message_count()),
-                }));
-            }
-        }
-
+        self.check_inputs(vk.public_key(), revealed_msgs)?;
         if self.a_prime.is_zero() {
             return Ok(PoKOfSignatureProofStatus::BadSignature);
         }
 
         let mut a_bar = self.a_bar;
         a_bar.negate();
-        match Bls12::final_exponentiation(&Bls12::miller_loop(&[
-            (
-                &self.a_prime.into_affine().prepare(),
-                &vk.w.0.into_affine().prepare(),
-            ),
-            (
-                &a_bar.into_affine().prepare(),
-                &G2::one().into_affine().prepare(),
-            ),
-        ])) {
-            None => return Ok(PoKOfSignatureProofStatus::BadSignature),
-            Some(product) => {
-                if product != Fq12::one() {
-                    return Ok(PoKOfSignatureProofStatus::BadSignature);
-                }
+        let valid = match vk.prepared() {
+            Some(prepared) => prepared_pairing_product_is_one(&[
+                (self.a_prime, prepared.w()),
+                (a_bar, prepared.g2()),
+            ]),
+            None => {
+                pairing_product_is_one(&[(self.a_prime, vk.public_key().w.0), (a_bar, G2::one())])
             }
         };
+        if !valid {
+            return Ok(PoKOfSignatureProofStatus::BadSignature);
+        }
+
+        self.verify_committed_values(vk, revealed_msgs, challenge)
+    }
+
+    /// Check the revealed message indices and the public key are valid
+    pub(crate) fn check_inputs(
+        &self,
+        vk: &PublicKey,
+        revealed_msgs: &BTreeMap<usize, SignatureMessage>,
+    ) -> Result<(), BBSError> {
+        vk.validate()?;
+        for i in revealed_msgs.keys() {
+            if *i >= vk.message_count() {
+                return Err(BBSError::from_kind(BBSErrorKind::GeneralError {
+                    msg: format!("Index {} should be less than {}", i, vk.message_count()),
+                }));
+            }
+        }
+        Ok(())
+    }
 
+    /// Verify the proofs of committed values without the pairing check on A' and A bar
+    pub(crate) fn verify_committed_values<K: VerificationKey>(
+        &self,
+        verkey: &K,
+        revealed_msgs: &BTreeMap<usize, SignatureMessage>,
+        challenge: &ProofChallenge,
+    ) -> Result<PoKOfSignatureProofStatus, BBSError> {
+        let vk = verkey.public_key();
         let mut bases = vec![];
         bases.push(GeneratorG1(self.a_prime));
         bases.push(vk.h0);
@@ -412,17 +502,24 @@ impl PoKOfSignatureProof {
             }
         }
         // pr = g1 * h1^-m1 * h2^-m2.... = (g1 * h1^m1 * h2^m2....)^-1 for all disclosed messages m_i
-        let mut pr = Commitment(multi_scalar_mul_const_time_g1(&bases_disclosed, &exponents));
+        let mut pr = match verkey.prepared() {
+            Some(prepared) => Commitment(
+                prepared.sum_of_products(None, revealed_msgs.iter().map(|(i, m)| (*i, m))),
+            ),
+            None => Commitment(multi_scalar_mul_const_time_g1(&bases_disclosed, &exponents)),
+        };
         pr.0.negate();
         match self
             .proof_vc_2
             .verify(bases_pok_vc_2.as_slice(), &pr, challenge)
         {
             Ok(b) => {
-                if b {
-                    Ok(PoKOfSignatureProofStatus::Success)
-                } else {
+                if !b {
                     Ok(PoKOfSignatureProofStatus::BadRevealedMessage)
+                } else if !verify_inequalities(self, vk.message_count(), revealed_msgs, challenge) {
+                    Ok(PoKOfSignatureProofStatus::BadHiddenMessage)
+                } else {
+                    Ok(PoKOfSignatureProofStatus::Success)
                 }
             }
             Err(_) => Ok(PoKOfSignatureProofStatus::BadRevealedMessage),
@@ -441,6 +538,13 @@ impl PoKOfSignatureProof {
         output.append(&mut proof1_bytes);
         let mut proof2_bytes = self.proof_vc_2.to_bytes(compressed);
         output.append(&mut proof2_bytes);
+        // Only appended when present so proofs without them are unchanged
+        if !self.inequalities.is_empty() {
+            output.extend_from_slice(&(self.inequalities.len() as u32).to_be_bytes()[..]);
+            for inequality in &self.inequalities {
+                output.append(&mut inequality.to_bytes(compressed));
+            }
+        }
         output
     }
 
@@ -477,12 +581,34 @@ impl PoKOfSignatureProof {
         let proof_vc_1 = ProofG1::from_bytes(&data[offset..end], g1_size, compressed)?;
 
         let proof_vc_2 = ProofG1::from_bytes(&data[end..], g1_size, compressed)?;
+
+        offset = end + g1_size + 4 + proof_vc_2.responses.len() * FR_COMPRESSED_SIZE;
+        let mut inequalities = Vec::new();
+        if data.len() >= offset + 4 {
+            let count = u32::from_be_bytes(*array_ref![data, offset, 4]) as usize;
+            offset += 4;
+            let size = InequalityProof::size(g1_size);
+            if data.len() < offset + count * size {
+                return Err(
+                    BBSErrorKind::InvalidNumberOfBytes(offset + count * size, data.len()).into(),
+                );
+            }
+            for _ in 0..count {
+                inequalities.push(InequalityProof::from_bytes(
+                    &data[offset..offset + size],
+                    g1_size,
+                    compressed,
+                )?);
+                offset += size;
+            }
+        }
         Ok(Self {
             a_prime,
             a_bar,
             d,
             proof_vc_1,
             proof_vc_2,
+            inequalities,
         })
     }
 }
@@ -518,12 +644,14 @@ impl Default for PoKOfSignatureProof {
             d: G1::zero(),
             proof_vc_1: ProofG1::default(),
             proof_vc_2: ProofG1::default(),
+            inequalities: Vec::new(),
         }
     }
 }
 
 try_from_impl!(PoKOfSignatureProof, BBSError);
 serdes_impl!(PoKOfSignatureProof);
+multibase_impl!(PoKOfSignatureProof);
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(PoKOfSignatureProof);
 
@@ -25,6 +25,7 @@ use pairing_plus::{
     serdes::SerDes,
     CurveAffine, CurveProjective,
 };
+use rand::{thread_rng, CryptoRng, RngCore};
 use serde::{
     de::{Error as DError, Visitor},
     Deserialize, Deserializer, Serialize, Serializer,
@@ -183,9 +184,18 @@ impl ProverCommittingG1 {
     /// Commit a base point with a blinding factor.
     /// The blinding factor is generated randomly
     pub fn commit<B: AsRef<G1>>(&mut self, base: B) -> usize {
+        self.commit_with_rng(base, &mut thread_rng())
+    }
+
+    /// Commit a base point with a blinding factor generated from `rng`
+    pub fn commit_with_rng<B: AsRef<G1>, R: RngCore + CryptoRng>(
+        &mut self,
+        base: B,
+        rng: &mut R,
+    ) -> usize {
         let idx = self.bases.len();
         self.bases.push(base.as_ref().clone());
-        let r = rand_non_zero_fr();
+        let r = rand_non_zero_fr(rng);
         self.blinding_factors.push(r);
         idx
     }
@@ -244,6 +254,16 @@ impl ProverCommittedG1 {
         bytes
     }
 
+    /// The commitment to the blinding factors
+    pub(crate) fn commitment(&self) -> G1 {
+        self.commitment
+    }
+
+    /// The blinding factor at `idx` so other proofs can be linked to its response
+    pub(crate) fn blinding_factor(&self, idx: usize) -> Option<Fr> {
+        self.blinding_factors.get(idx).copied()
+    }
+
     /// This step will be done by the main protocol for which this PoK is a sub-protocol
     pub fn gen_challenge<I: AsRef<[u8]>>(&self, extra: I) -> ProofChallenge {
         let mut bytes = self.to_bytes();
@@ -0,0 +1,827 @@
+use crate::errors::prelude::*;
+use crate::inequality::prelude::*;
+use crate::keys::prelude::*;
+use crate::messages::*;
+use crate::multi_proof::{check_equalities, EquivalenceClass, MessageReference};
+use crate::pok_sig::prelude::*;
+use crate::pseudonym::{scope_generator, Pseudonym};
+use crate::signature::prelude::*;
+use crate::{
+    HashElem, ProofChallenge, ProofNonce, RandomElem, SignatureMessage, SignatureProof,
+    ToVariableLengthBytes, FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
+};
+use pairing_plus::{bls12_381::G1, serdes::SerDes, CurveProjective};
+use rand::{thread_rng, CryptoRng, RngCore};
+use serde::{
+    de::{Error as DError, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt::Formatter;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{
+        CredentialRequest, Predicate, PresentationRequest, PresentationResponse,
+        PRESENTATION_REQUEST_VERSION,
+    };
+}
+
+/// The version of `PresentationRequest` this crate creates and understands
+pub const PRESENTATION_REQUEST_VERSION: u16 = 1;
+
+/// A statement proven about a hidden message without revealing it.
+///
+/// Range and set membership predicates are not supported since this crate has no range
+/// or set membership proofs. `zmix::predicates::SignatureProofWithPredicates` proves
+/// them about a single credential with bulletproofs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Predicate {
+    /// The message is a link secret and the response contains its pseudonym
+    /// for the domain of the request
+    Pseudonym,
+    /// The message is not equal to the value
+    NotEqual(SignatureMessage),
+}
+
+impl Predicate {
+    fn to_bytes(self, output: &mut Vec<u8>) {
+        match self {
+            Predicate::Pseudonym => output.push(1),
+            Predicate::NotEqual(value) => {
+                output.push(2);
+                output.extend_from_slice(&value.to_bytes_compressed_form()[..]);
+            }
+        }
+    }
+
+    /// Returns the predicate and the number of bytes read
+    fn from_bytes(data: &[u8]) -> Result<(Self, usize), BBSError> {
+        match data.first() {
+            Some(1) => Ok((Predicate::Pseudonym, 1)),
+            Some(2) => {
+                if data.len() < 1 + FR_COMPRESSED_SIZE {
+                    return Err(BBSErrorKind::InvalidNumberOfBytes(
+                        1 + FR_COMPRESSED_SIZE,
+                        data.len(),
+                    )
+                    .into());
+                }
+                let value = SignatureMessage::from(array_ref![data, 1, FR_COMPRESSED_SIZE]);
+                Ok((Predicate::NotEqual(value), 1 + FR_COMPRESSED_SIZE))
+            }
+            Some(t) => Err(BBSErrorKind::GeneralError {
+                msg: format!("Unknown predicate {}", t),
+            }
+            .into()),
+            None => Err(BBSErrorKind::InvalidNumberOfBytes(1, 0).into()),
+        }
+    }
+}
+
+/// What a verifier requires from one credential in a presentation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CredentialRequest {
+    /// The public key for which the signature must be valid
+    pub verification_key: PublicKey,
+    /// The messages that must be revealed
+    pub revealed_messages: BTreeSet<usize>,
+    /// The predicates that must hold for hidden messages
+    pub predicates: BTreeMap<usize, Predicate>,
+}
+
+impl CredentialRequest {
+    /// Require a signature from `verkey` that reveals `revealed_message_indices`
+    pub fn new(verkey: &PublicKey, revealed_message_indices: &[usize]) -> Self {
+        Self {
+            verification_key: verkey.clone(),
+            revealed_messages: revealed_message_indices.iter().copied().collect(),
+            predicates: BTreeMap::new(),
+        }
+    }
+}
+
+/// Everything a verifier asks of a prover in a presentation of one or more credentials.
+/// The whole request is bound to the proof so a response is only valid for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresentationRequest {
+    /// The version of the request format
+    pub version: u16,
+    /// The verifier's nonce
+    pub nonce: ProofNonce,
+    /// The verifier's domain. Pseudonyms are derived for this domain.
+    pub domain: Option<String>,
+    /// The requirements for each credential in the order they are presented
+    pub credentials: Vec<CredentialRequest>,
+    /// Hidden messages across credentials that must be equal
+    pub equalities: Vec<EquivalenceClass>,
+}
+
+impl PresentationRequest {
+    /// Check the request can be satisfied
+    pub fn validate(&self) -> Result<(), BBSError> {
+        if self.version != PRESENTATION_REQUEST_VERSION {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Unsupported presentation request version {}", self.version),
+            }
+            .into());
+        }
+        if self.credentials.is_empty() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "At least one credential is required".to_string(),
+            }
+            .into());
+        }
+        let message_counts = self
+            .credentials
+            .iter()
+            .map(|c| c.verification_key.message_count())
+            .collect::<Vec<usize>>();
+        for (c, credential) in self.credentials.iter().enumerate() {
+            for m in credential
+                .revealed_messages
+                .iter()
+                .chain(credential.predicates.keys())
+            {
+                if *m >= message_counts[c] {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!("Message {} in credential {} does not exist", m, c),
+                    }
+                    .into());
+                }
+            }
+            for (m, predicate) in &credential.predicates {
+                if credential.revealed_messages.contains(m) {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!(
+                            "Message {} in credential {} is revealed but must be hidden for a predicate",
+                            m, c
+                        ),
+                    }
+                    .into());
+                }
+                if *predicate == Predicate::Pseudonym && self.domain.is_none() {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: "A domain is required for pseudonyms".to_string(),
+                    }
+                    .into());
+                }
+            }
+        }
+        check_equalities(&self.equalities, &message_counts)?;
+        for (c, m) in self.equalities.iter().flatten() {
+            if self.credentials[*c].revealed_messages.contains(m) {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!(
+                        "Message {} in credential {} is revealed but must be hidden to prove equality",
+                        m, c
+                    ),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// The messages with a `Predicate::Pseudonym`
+    fn pseudonym_references(&self) -> BTreeSet<MessageReference> {
+        let mut references = BTreeSet::new();
+        for (c, credential) in self.credentials.iter().enumerate() {
+            for (m, predicate) in &credential.predicates {
+                if *predicate == Predicate::Pseudonym {
+                    references.insert((c, *m));
+                }
+            }
+        }
+        references
+    }
+
+    pub(crate) fn to_bytes(&self, compressed: bool) -> Vec<u8> {
+        let mut output = self.version.to_be_bytes().to_vec();
+        output.extend_from_slice(&self.nonce.to_bytes_compressed_form()[..]);
+        match &self.domain {
+            Some(d) => {
+                output.push(1);
+                push_u32(&mut output, d.len());
+                output.extend_from_slice(d.as_bytes());
+            }
+            None => output.push(0),
+        }
+        push_u32(&mut output, self.credentials.len());
+        for credential in &self.credentials {
+            let key = credential.verification_key.to_bytes(compressed);
+            push_u32(&mut output, key.len());
+            output.extend_from_slice(key.as_slice());
+            push_u32(&mut output, credential.revealed_messages.len());
+            for m in &credential.revealed_messages {
+                push_u32(&mut output, *m);
+            }
+            push_u32(&mut output, credential.predicates.len());
+            for (m, predicate) in &credential.predicates {
+                push_u32(&mut output, *m);
+                predicate.to_bytes(&mut output);
+            }
+        }
+        push_u32(&mut output, self.equalities.len());
+        for class in &self.equalities {
+            push_u32(&mut output, class.len());
+            for (c, m) in class {
+                push_u32(&mut output, *c);
+                push_u32(&mut output, *m);
+            }
+        }
+        output
+    }
+
+    pub(crate) fn from_bytes(
+        data: &[u8],
+        g1_size: usize,
+        compressed: bool,
+    ) -> Result<Self, BBSError> {
+        if data.len() < 2 + FR_COMPRESSED_SIZE + 1 {
+            return Err(
+                BBSErrorKind::InvalidNumberOfBytes(2 + FR_COMPRESSED_SIZE + 1, data.len()).into(),
+            );
+        }
+        let version = u16::from_be_bytes(*array_ref![data, 0, 2]);
+        if version != PRESENTATION_REQUEST_VERSION {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Unsupported presentation request version {}", version),
+            }
+            .into());
+        }
+        let nonce = ProofNonce::from(array_ref![data, 2, FR_COMPRESSED_SIZE]);
+        let mut offset = 2 + FR_COMPRESSED_SIZE;
+        let domain = match data[offset] {
+            0 => {
+                offset += 1;
+                None
+            }
+            1 => {
+                offset += 1;
+                let len = read_u32(data, &mut offset)?;
+                let bytes = read_slice(data, &mut offset, len)?;
+                Some(
+                    String::from_utf8(bytes.to_vec()).map_err(|_| BBSErrorKind::GeneralError {
+                        msg: "The domain is not valid UTF-8".to_string(),
+                    })?,
+                )
+            }
+            _ => {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: "Invalid domain".to_string(),
+                }
+                .into())
+            }
+        };
+
+        let count = read_u32(data, &mut offset)?;
+        let mut credentials = Vec::new();
+        for _ in 0..count {
+            let len = read_u32(data, &mut offset)?;
+            let verification_key =
+                PublicKey::from_bytes(read_slice(data, &mut offset, len)?, g1_size, compressed)?;
+            let mut revealed_messages = BTreeSet::new();
+            for _ in 0..read_u32(data, &mut offset)? {
+                revealed_messages.insert(read_u32(data, &mut offset)?);
+            }
+            let mut predicates = BTreeMap::new();
+            for _ in 0..read_u32(data, &mut offset)? {
+                let m = read_u32(data, &mut offset)?;
+                let (predicate, len) = Predicate::from_bytes(&data[offset..])?;
+                offset += len;
+                predicates.insert(m, predicate);
+            }
+            credentials.push(CredentialRequest {
+                verification_key,
+                revealed_messages,
+                predicates,
+            });
+        }
+
+        let count = read_u32(data, &mut offset)?;
+        let mut equalities = Vec::new();
+        for _ in 0..count {
+            let mut class = EquivalenceClass::new();
+            for _ in 0..read_u32(data, &mut offset)? {
+                let c = read_u32(data, &mut offset)?;
+                let m = read_u32(data, &mut offset)?;
+                class.insert((c, m));
+            }
+            equalities.push(class);
+        }
+
+        Ok(Self {
+            version,
+            nonce,
+            domain,
+            credentials,
+            equalities,
+        })
+    }
+}
+
+impl Default for PresentationRequest {
+    fn default() -> Self {
+        Self {
+            version: PRESENTATION_REQUEST_VERSION,
+            nonce: ProofNonce::default(),
+            domain: None,
+            credentials: Vec::new(),
+            equalities: Vec::new(),
+        }
+    }
+}
+
+impl ToVariableLengthBytes for PresentationRequest {
+    type Output = PresentationRequest;
+    type Error = BBSError;
+
+    /// Convert to raw bytes using compressed form for each element.
+    fn to_bytes_compressed_form(&self) -> Vec<u8> {
+        self.to_bytes(true)
+    }
+
+    /// Convert from compressed form raw bytes.
+    fn from_bytes_compressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        Self::from_bytes(data.as_ref(), G1_COMPRESSED_SIZE, true)
+    }
+
+    fn to_bytes_uncompressed_form(&self) -> Vec<u8> {
+        self.to_bytes(false)
+    }
+
+    fn from_bytes_uncompressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self::Output, Self::Error> {
+        Self::from_bytes(data.as_ref(), G1_UNCOMPRESSED_SIZE, false)
+    }
+}
+
+try_from_impl!(PresentationRequest, BBSError);
+serdes_impl!(PresentationRequest);
+
+/// A prover's response to a `PresentationRequest`.
+///
+/// Hidden messages in an equivalence class or with a predicate use the same blinding factor
+/// wherever they appear so their responses are equal iff the messages are equal.
+#[derive(Debug, Clone, Default)]
+pub struct PresentationResponse {
+    /// The signature proofs in the same order as the credentials in the request
+    pub proofs: Vec<SignatureProof>,
+    /// The pseudonym for each message with a `Predicate::Pseudonym`
+    pub pseudonyms: BTreeMap<MessageReference, Pseudonym>,
+    /// `H(domain)^blinding` for each pseudonym
+    pub(crate) commitments: BTreeMap<MessageReference, G1>,
+}
+
+impl PresentationResponse {
+    /// Create a response to `request` from the signature and messages of each credential
+    /// in the same order as the credentials in `request`
+    pub fn new(
+        request: &PresentationRequest,
+        credentials: &[(Signature, Vec<SignatureMessage>)],
+    ) -> Result<Self, BBSError> {
+        Self::new_with_rng(request, credentials, &mut thread_rng())
+    }
+
+    /// Same as `new` but all blinding factors are generated from `rng`
+    pub fn new_with_rng<R: RngCore + CryptoRng>(
+        request: &PresentationRequest,
+        credentials: &[(Signature, Vec<SignatureMessage>)],
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        request.validate()?;
+        if credentials.len() != request.credentials.len() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Expected {} credentials, found {}",
+                    request.credentials.len(),
+                    credentials.len()
+                ),
+            }
+            .into());
+        }
+
+        // Messages in an equivalence class share a blinding factor
+        let mut blindings = BTreeMap::new();
+        for class in &request.equalities {
+            let mut message = None;
+            for (c, m) in class {
+                let msg = credentials[*c].1.get(*m).copied().ok_or_else(|| {
+                    BBSError::from(BBSErrorKind::GeneralError {
+                        msg: format!("Message {} in credential {} does not exist", m, c),
+                    })
+                })?;
+                if *message.get_or_insert(msg) != msg {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!(
+                            "Message {} in credential {} is not equal to the others in its class",
+                            m, c
+                        ),
+                    }
+                    .into());
+                }
+            }
+            let blinding = ProofNonce::random_with_rng(rng);
+            for r in class {
+                blindings.insert(*r, blinding);
+            }
+        }
+        let pseudonym_references = request.pseudonym_references();
+        for r in &pseudonym_references {
+            if !blindings.contains_key(r) {
+                blindings.insert(*r, ProofNonce::random_with_rng(rng));
+            }
+        }
+
+        let mut poks = Vec::with_capacity(credentials.len());
+        for (c, ((signature, messages), credential)) in credentials
+            .iter()
+            .zip(request.credentials.iter())
+            .enumerate()
+        {
+            let proof_messages = messages
+                .iter()
+                .enumerate()
+                .map(|(m, msg)| {
+                    if credential.revealed_messages.contains(&m) {
+                        pm_revealed_raw!(*msg)
+                    } else {
+                        match blindings.get(&(c, m)) {
+                            Some(b) => pm_hidden_raw!(*msg, *b),
+                            None => pm_hidden_raw!(*msg),
+                        }
+                    }
+                })
+                .collect::<Vec<ProofMessage>>();
+            let mut pok = PoKOfSignature::init_with_rng(
+                signature,
+                &credential.verification_key,
+                &proof_messages,
+                rng,
+            )?;
+            for (m, predicate) in &credential.predicates {
+                if let Predicate::NotEqual(value) = predicate {
+                    pok.prove_not_equal_with_rng(*m, &InequalityTarget::Value(*value), rng)?;
+                }
+            }
+            poks.push(pok);
+        }
+
+        let domain = request.domain.as_deref().unwrap_or_default();
+        let mut pseudonyms = BTreeMap::new();
+        let mut commitments = BTreeMap::new();
+        for (c, m) in &pseudonym_references {
+            let generator = scope_generator(domain);
+            pseudonyms.insert(
+                (*c, *m),
+                Pseudonym::new_from_generator(generator, &credentials[*c].1[*m]),
+            );
+            let mut commitment = generator;
+            commitment.mul_assign(blindings[&(*c, *m)].0);
+            commitments.insert((*c, *m), commitment);
+        }
+
+        let mut challenge_bytes = Vec::new();
+        for pok in &poks {
+            challenge_bytes.extend_from_slice(pok.to_bytes().as_slice());
+        }
+        let challenge = compute_challenge(challenge_bytes, request, &pseudonyms, &commitments);
+
+        let mut proofs = Vec::with_capacity(poks.len());
+        for pok in poks {
+            let revealed_messages = pok.revealed_messages.clone();
+            proofs.push(SignatureProof {
+                revealed_messages,
+                proof: pok.gen_proof(&challenge)?,
+            });
+        }
+        Ok(Self {
+            proofs,
+            pseudonyms,
+            commitments,
+        })
+    }
+
+    /// Verify the response satisfies `request`.
+    /// Returns the revealed messages for each credential.
+    pub fn verify(
+        &self,
+        request: &PresentationRequest,
+    ) -> Result<Vec<BTreeMap<usize, SignatureMessage>>, BBSError> {
+        request.validate()?;
+        if self.proofs.len() != request.credentials.len() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "Expected {} proofs, found {}",
+                    request.credentials.len(),
+                    self.proofs.len()
+                ),
+            }
+            .into());
+        }
+        let pseudonym_references = request.pseudonym_references();
+        if self.pseudonyms.keys().copied().collect::<BTreeSet<_>>() != pseudonym_references
+            || self.commitments.keys().copied().collect::<BTreeSet<_>>() != pseudonym_references
+        {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Pseudonyms do not match the presentation request".to_string(),
+            }
+            .into());
+        }
+
+        let mut challenge_bytes = Vec::new();
+        for (p, r) in self.proofs.iter().zip(request.credentials.iter()) {
+            let revealed = p
+                .revealed_messages
+                .keys()
+                .copied()
+                .collect::<BTreeSet<usize>>();
+            if revealed != r.revealed_messages {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: "Revealed messages do not match the presentation request".to_string(),
+                }
+                .into());
+            }
+            challenge_bytes.extend_from_slice(
+                p.proof
+                    .get_bytes_for_challenge(revealed, &r.verification_key)
+                    .as_slice(),
+            );
+        }
+        let challenge = compute_challenge(
+            challenge_bytes,
+            request,
+            &self.pseudonyms,
+            &self.commitments,
+        );
+
+        for (p, r) in self.proofs.iter().zip(request.credentials.iter()) {
+            match p
+                .proof
+                .verify(&r.verification_key, &p.revealed_messages, &challenge)?
+            {
+                PoKOfSignatureProofStatus::Success => {}
+                e => return Err(BBSErrorKind::InvalidProof { status: e }.into()),
+            }
+            // The proof has exactly the inequalities that were requested
+            let requested = r
+                .predicates
+                .iter()
+                .filter_map(|(m, predicate)| match predicate {
+                    Predicate::NotEqual(value) => Some((*m, Some(*value))),
+                    _ => None,
+                })
+                .collect::<BTreeSet<_>>();
+            let proven = p
+                .proof
+                .inequalities()
+                .iter()
+                .map(|i| (i.message_index, i.value))
+                .collect::<BTreeSet<_>>();
+            if requested != proven || p.proof.inequalities().len() != requested.len() {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: "Inequalities do not match the presentation request".to_string(),
+                }
+                .into());
+            }
+        }
+
+        for class in &request.equalities {
+            let mut response = None;
+            for r in class {
+                let resp = self.response_for(request, r)?;
+                if *response.get_or_insert(resp) != resp {
+                    return Err(BBSErrorKind::InvalidProof {
+                        status: PoKOfSignatureProofStatus::BadHiddenMessage,
+                    }
+                    .into());
+                }
+            }
+        }
+
+        // H(domain)^resp * nym^c == H(domain)^blinding
+        let domain = request.domain.as_deref().unwrap_or_default();
+        for r in &pseudonym_references {
+            let nym = self.pseudonyms[r];
+            if nym.0.is_zero() {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: "Invalid pseudonym".to_string(),
+                }
+                .into());
+            }
+            let resp = self.response_for(request, r)?;
+            let mut lhs = scope_generator(domain);
+            lhs.mul_assign(resp.0);
+            let mut nym_c = nym.0;
+            nym_c.mul_assign(challenge.0);
+            lhs.add_assign(&nym_c);
+            if lhs != self.commitments[r] {
+                return Err(BBSErrorKind::InvalidProof {
+                    status: PoKOfSignatureProofStatus::BadHiddenMessage,
+                }
+                .into());
+            }
+        }
+
+        Ok(self
+            .proofs
+            .iter()
+            .map(|p| p.revealed_messages.clone())
+            .collect())
+    }
+
+    /// Responses are only present for hidden messages
+    fn response_for(
+        &self,
+        request: &PresentationRequest,
+        (c, m): &MessageReference,
+    ) -> Result<ProofNonce, BBSError> {
+        let revealed = &request.credentials[*c].revealed_messages;
+        let hidden_index = m - revealed.range(..m).count();
+        Ok(ProofNonce(
+            self.proofs[*c].proof.get_resp_for_message(hidden_index)?.0,
+        ))
+    }
+
+    pub(crate) fn to_bytes(&self, compressed: bool) -> Vec<u8> {
+        let mut output = Vec::new();
+        push_u32(&mut output, self.proofs.len());
+        for p in &self.proofs {
+            let proof_bytes = p.to_bytes(compressed);
+            push_u32(&mut output, proof_bytes.len());
+            output.extend_from_slice(proof_bytes.as_slice());
+        }
+        push_u32(&mut output, self.pseudonyms.len());
+        for ((c, m), nym) in &self.pseudonyms {
+            push_u32(&mut output, *c);
+            push_u32(&mut output, *m);
+            nym.0.serialize(&mut output, compressed).unwrap();
+            self.commitments[&(*c, *m)]
+                .serialize(&mut output, compressed)
+                .unwrap();
+        }
+        output
+    }
+
+    pub(crate) fn from_bytes(
+        data: &[u8],
+        g1_size: usize,
+        compressed: bool,
+    ) -> Result<Self, BBSError> {
+        let mut offset = 0;
+        let mut proofs = Vec::new();
+        for _ in 0..read_u32(data, &mut offset)? {
+            let len = read_u32(data, &mut offset)?;
+            proofs.push(SignatureProof::from_bytes(
+                read_slice(data, &mut offset, len)?,
+                g1_size,
+                compressed,
+            )?);
+        }
+        let mut pseudonyms = BTreeMap::new();
+        let mut commitments = BTreeMap::new();
+        for _ in 0..read_u32(data, &mut offset)? {
+            let c = read_u32(data, &mut offset)?;
+            let m = read_u32(data, &mut offset)?;
+            let mut nym = read_slice(data, &mut offset, g1_size)?;
+            pseudonyms.insert((c, m), Pseudonym(slice_to_elem!(&mut nym, G1, compressed)?));
+            let mut commitment = read_slice(data, &mut offset, g1_size)?;
+            commitments.insert((c, m), slice_to_elem!(&mut commitment, G1, compressed)?);
+        }
+        Ok(Self {
+            proofs,
+            pseudonyms,
+            commitments,
+        })
+    }
+}
+
+impl ToVariableLengthBytes for PresentationResponse {
+    type Output = PresentationResponse;
+    type Error = BBSError;
+
+    /// Convert to raw bytes using compressed form for each element.
+    fn to_bytes_compressed_form(&self) -> Vec<u8> {
+        self.to_bytes(true)
+    }
+
+    /// Convert from compressed form raw bytes.
+    fn from_bytes_compressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        Self::from_bytes(data.as_ref(), G1_COMPRESSED_SIZE, true)
+    }
+
+    fn to_bytes_uncompressed_form(&self) -> Vec<u8> {
+        self.to_bytes(false)
+    }
+
+    fn from_bytes_uncompressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self::Output, Self::Error> {
+        Self::from_bytes(data.as_ref(), G1_UNCOMPRESSED_SIZE, false)
+    }
+}
+
+try_from_impl!(PresentationResponse, BBSError);
+serdes_impl!(PresentationResponse);
+
+/// The request and pseudonyms are included so the proof is bound to them
+fn compute_challenge(
+    mut bytes: Vec<u8>,
+    request: &PresentationRequest,
+    pseudonyms: &BTreeMap<MessageReference, Pseudonym>,
+    commitments: &BTreeMap<MessageReference, G1>,
+) -> ProofChallenge {
+    bytes.extend_from_slice(request.to_bytes(false).as_slice());
+    for (r, nym) in pseudonyms {
+        nym.0.serialize(&mut bytes, false).unwrap();
+        if let Some(commitment) = commitments.get(r) {
+            commitment.serialize(&mut bytes, false).unwrap();
+        }
+    }
+    ProofChallenge::hash(&bytes)
+}
+
+fn push_u32(output: &mut Vec<u8>, value: usize) {
+    output.extend_from_slice(&(value as u32).to_be_bytes()[..]);
+}
+
+fn read_u32(data: &[u8], offset: &mut usize) -> Result<usize, BBSError> {
+    let bytes = read_slice(data, offset, 4)?;
+    Ok(u32::from_be_bytes(*array_ref![bytes, 0, 4]) as usize)
+}
+
+fn read_slice<'a>(data: &'a [u8], offset: &mut usize, len: usize) -> Result<&'a [u8], BBSError> {
+    let end = offset.saturating_add(len);
+    if data.len() < end {
+        return Err(BBSErrorKind::InvalidNumberOfBytes(end, data.len()).into());
+    }
+    let slice = &data[*offset..end];
+    *offset = end;
+    Ok(slice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn request_serialization() {
+        let (pk1, _) = Issuer::new_keys(3).unwrap();
+        let (pk2, _) = Issuer::new_keys(2).unwrap();
+        let mut first = CredentialRequest::new(&pk1, &[1]);
+        first.predicates.insert(0, Predicate::Pseudonym);
+        first
+            .predicates
+            .insert(2, Predicate::NotEqual(SignatureMessage::hash(b"denied")));
+        let request = PresentationRequest {
+            nonce: Verifier::generate_proof_nonce(),
+            domain: Some("verifier.example".to_string()),
+            credentials: vec![first, CredentialRequest::new(&pk2, &[])],
+            equalities: vec![[(0, 0), (1, 0)].iter().copied().collect()],
+            ..PresentationRequest::default()
+        };
+        assert!(request.validate().is_ok());
+
+        let bytes = request.to_bytes_compressed_form();
+        assert_eq!(
+            PresentationRequest::from_bytes_compressed_form(&bytes).unwrap(),
+            request
+        );
+        let bytes = request.to_bytes_uncompressed_form();
+        assert_eq!(
+            PresentationRequest::from_bytes_uncompressed_form(&bytes).unwrap(),
+            request
+        );
+
+        let mut bytes = request.to_bytes_compressed_form();
+        bytes[1] = 2;
+        assert!(PresentationRequest::from_bytes_compressed_form(&bytes).is_err());
+    }
+
+    #[test]
+    fn invalid_requests() {
+        let (pk, _) = Issuer::new_keys(3).unwrap();
+        let mut credential = CredentialRequest::new(&pk, &[0]);
+        credential.predicates.insert(0, Predicate::Pseudonym);
+        let mut request = PresentationRequest {
+            credentials: vec![credential],
+            domain: Some("verifier.example".to_string()),
+            ..PresentationRequest::default()
+        };
+        // Predicates are only on hidden messages
+        assert!(request.validate().is_err());
+
+        request.credentials[0].revealed_messages.clear();
+        assert!(request.validate().is_ok());
+        request.domain = None;
+        assert!(request.validate().is_err());
+
+        request.domain = Some("verifier.example".to_string());
+        request.credentials[0].revealed_messages.insert(3);
+        assert!(request.validate().is_err());
+
+        request.credentials[0].revealed_messages.clear();
+        request.version = PRESENTATION_REQUEST_VERSION + 1;
+        assert!(request.validate().is_err());
+    }
+}
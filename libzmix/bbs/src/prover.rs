@@ -1,8 +1,15 @@
+use crate::blind_issuance::prelude::*;
+use crate::designated::prelude::*;
 use crate::errors::prelude::*;
+use crate::ietf;
 use crate::keys::prelude::*;
 use crate::messages::*;
+use crate::multi_proof::prelude::*;
+use crate::padding::prelude::*;
 use crate::pok_sig::prelude::*;
 use crate::pok_vc::prelude::*;
+use crate::presentation::prelude::*;
+use crate::pseudonym::prelude::*;
 use crate::signature::prelude::*;
 /// The prover of a signature or credential receives it from an
 /// issuer and later proves to a verifier.
@@ -12,7 +19,8 @@ use crate::{
     BlindSignatureContext, CommitmentBuilder, HashElem, ProofChallenge, ProofNonce, ProofRequest,
     RandomElem, SignatureBlinding, SignatureMessage, SignatureProof,
 };
-use std::collections::BTreeMap;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{BTreeMap, BTreeSet};
 
 /// This struct represents a Prover who receives signatures or proves with them.
 /// Provided are methods for 2PC where some are only known to the prover and a blind signature
@@ -29,20 +37,36 @@ impl Prover {
         SignatureMessage::random()
     }
 
+    /// Same as `new_link_secret` but the link secret is generated from `rng`
+    pub fn new_link_secret_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> SignatureMessage {
+        SignatureMessage::random_with_rng(rng)
+    }
+
     /// Create the structures need to send to an issuer to complete a blinded signature
     pub fn new_blind_signature_context(
         verkey: &PublicKey,
         messages: &BTreeMap<usize, SignatureMessage>,
         nonce: &ProofNonce,
     ) -> Result<(BlindSignatureContext, SignatureBlinding), BBSError> {
-        let blinding_factor = Signature::generate_blinding();
+        Self::new_blind_signature_context_with_rng(verkey, messages, nonce, &mut thread_rng())
+    }
+
+    /// Same as `new_blind_signature_context` but the signature blinding and
+    /// proof blinding factors are generated from `rng`
+    pub fn new_blind_signature_context_with_rng<R: RngCore + CryptoRng>(
+        verkey: &PublicKey,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<(BlindSignatureContext, SignatureBlinding), BBSError> {
+        let blinding_factor = Signature::generate_blinding_with_rng(rng);
         let mut builder = CommitmentBuilder::new();
 
         // h0^blinding_factor*hi^mi.....
         builder.add(&verkey.h0, &blinding_factor);
 
         let mut committing = ProverCommittingG1::new();
-        committing.commit(&verkey.h0);
+        committing.commit_with_rng(&verkey.h0, rng);
         let mut secrets = Vec::new();
         secrets.push(SignatureMessage(blinding_factor.0));
         for (i, m) in messages {
@@ -55,7 +79,7 @@ impl Prover {
             }
             secrets.push(*m);
             builder.add(&verkey.h[*i], &m);
-            committing.commit(&verkey.h[*i]);
+            committing.commit_with_rng(&verkey.h[*i], rng);
         }
 
         // Create a random commitment, compute challenges and response.
@@ -82,6 +106,28 @@ impl Prover {
         ))
     }
 
+    /// Create the context for an issuer to complete a blinded signature where the
+    /// committed message indices are part of the proof. Messages that use
+    /// `HiddenMessage::ExternalBlinding` can be linked to other proofs.
+    pub fn new_extended_blind_signature_context(
+        verkey: &PublicKey,
+        messages: &BTreeMap<usize, HiddenMessage>,
+        nonce: &ProofNonce,
+    ) -> Result<(ExtendedBlindSignatureContext, SignatureBlinding), BBSError> {
+        ExtendedBlindSignatureContext::new(verkey, messages, nonce)
+    }
+
+    /// Same as `new_extended_blind_signature_context` but the signature blinding and
+    /// proof blinding factors are generated from `rng`
+    pub fn new_extended_blind_signature_context_with_rng<R: RngCore + CryptoRng>(
+        verkey: &PublicKey,
+        messages: &BTreeMap<usize, HiddenMessage>,
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<(ExtendedBlindSignatureContext, SignatureBlinding), BBSError> {
+        ExtendedBlindSignatureContext::new_with_rng(verkey, messages, nonce, rng)
+    }
+
     /// Unblinds and verifies a signature received from an issuer
     pub fn complete_signature(
         verkey: &PublicKey,
@@ -100,6 +146,78 @@ impl Prover {
         }
     }
 
+    /// Verify a signature received from an issuer using one of the draft ciphersuites
+    pub fn verify_ciphersuite_signature(
+        suite: ietf::Ciphersuite,
+        verkey: &DeterministicPublicKey,
+        header: &[u8],
+        messages: &[SignatureMessage],
+        signature: &ietf::signature::Signature,
+    ) -> Result<(), BBSError> {
+        if signature.verify(suite, verkey, header, messages)? {
+            Ok(())
+        } else {
+            Err(BBSErrorKind::GeneralError {
+                msg: "Invalid signature.".to_string(),
+            }
+            .into())
+        }
+    }
+
+    /// Create a selective disclosure proof for a draft ciphersuite signature
+    ///
+    /// # Arguments
+    /// * `revealed_message_indices` - the 0 based indices of the messages to disclose
+    /// * `presentation_header` - data bound to the proof like a nonce from the verifier
+    pub fn generate_ciphersuite_proof(
+        suite: ietf::Ciphersuite,
+        verkey: &DeterministicPublicKey,
+        signature: &ietf::signature::Signature,
+        header: &[u8],
+        presentation_header: &[u8],
+        messages: &[SignatureMessage],
+        revealed_message_indices: &[usize],
+    ) -> Result<ietf::proof::Proof, BBSError> {
+        Self::generate_ciphersuite_proof_with_rng(
+            suite,
+            verkey,
+            signature,
+            header,
+            presentation_header,
+            messages,
+            revealed_message_indices,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Same as `generate_ciphersuite_proof` but the random scalars are generated from `rng`
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_ciphersuite_proof_with_rng<R: RngCore + CryptoRng>(
+        suite: ietf::Ciphersuite,
+        verkey: &DeterministicPublicKey,
+        signature: &ietf::signature::Signature,
+        header: &[u8],
+        presentation_header: &[u8],
+        messages: &[SignatureMessage],
+        revealed_message_indices: &[usize],
+        rng: &mut R,
+    ) -> Result<ietf::proof::Proof, BBSError> {
+        let revealed_messages = revealed_message_indices
+            .iter()
+            .copied()
+            .collect::<BTreeSet<usize>>();
+        ietf::proof::Proof::new_with_rng(
+            suite,
+            verkey,
+            signature,
+            header,
+            presentation_header,
+            messages,
+            &revealed_messages,
+            rng,
+        )
+    }
+
     /// Create a new signature proof of knowledge and selective disclosure proof
     /// from a verifier's request
     ///
@@ -116,6 +234,27 @@ impl Prover {
         PoKOfSignature::init(&signature, &request.verification_key, proof_messages)
     }
 
+    /// Same as `commit_signature_pok` but the randomness and blinding factors
+    /// are generated from `rng`
+    pub fn commit_signature_pok_with_rng<R: RngCore + CryptoRng>(
+        request: &ProofRequest,
+        proof_messages: &[ProofMessage],
+        signature: &Signature,
+        rng: &mut R,
+    ) -> Result<PoKOfSignature, BBSError> {
+        PoKOfSignature::init_with_rng(signature, &request.verification_key, proof_messages, rng)
+    }
+
+    /// Same as `commit_signature_pok` but takes the public key directly so a
+    /// `PreparedPublicKey` can be used when many proofs are made with the same key
+    pub fn commit_signature_pok_with_key<K: VerificationKey>(
+        verkey: &K,
+        proof_messages: &[ProofMessage],
+        signature: &Signature,
+    ) -> Result<PoKOfSignature, BBSError> {
+        PoKOfSignature::init(signature, verkey, proof_messages)
+    }
+
     /// Create the challenge hash for a set of proofs
     ///
     /// # Arguments
@@ -143,7 +282,7 @@ impl Prover {
         Ok(challenge)
     }
 
-    /// Convert the a committed proof of signature knowledge to the proof
+    /// Convert a committed proof of signature knowledge to the proof
     pub fn generate_signature_pok(
         pok_sig: PoKOfSignature,
         challenge: &ProofChallenge,
@@ -156,4 +295,170 @@ impl Prover {
             proof,
         })
     }
+
+    /// Create a signature proof of knowledge for a credential signed over `padded` messages
+    /// that reveals the messages at the original indices `revealed`.
+    /// The proof only contains the padded positions of the revealed messages.
+    ///
+    /// # Arguments
+    /// * `padded` - the padded messages that were signed
+    /// * `signature` - the signature over the padded messages
+    /// * `verkey` - the issuer's public key for the padded message count
+    /// * `revealed` - the indices of the messages to reveal before padding
+    /// * `nonce` - the verifier's nonce
+    pub fn generate_padded_signature_pok(
+        padded: &PaddedMessages,
+        signature: &Signature,
+        verkey: &PublicKey,
+        revealed: &BTreeSet<usize>,
+        nonce: &ProofNonce,
+    ) -> Result<SignatureProof, BBSError> {
+        Self::generate_padded_signature_pok_with_rng(
+            padded,
+            signature,
+            verkey,
+            revealed,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Same as `generate_padded_signature_pok` but the randomness and blinding factors
+    /// are generated from `rng`
+    pub fn generate_padded_signature_pok_with_rng<R: RngCore + CryptoRng>(
+        padded: &PaddedMessages,
+        signature: &Signature,
+        verkey: &PublicKey,
+        revealed: &BTreeSet<usize>,
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<SignatureProof, BBSError> {
+        let request = padded.proof_request(revealed, verkey)?;
+        let proof_messages = padded.proof_messages(revealed)?;
+        let pok = Self::commit_signature_pok_with_rng(
+            &request,
+            proof_messages.as_slice(),
+            signature,
+            rng,
+        )?;
+        let challenge = Self::create_challenge_hash(std::slice::from_ref(&pok), None, nonce)?;
+        Self::generate_signature_pok(pok, &challenge)
+    }
+
+    /// Convert a committed proof of signature knowledge into a proof that only
+    /// convinces the designated verifier in `proof_request`
+    pub fn generate_designated_signature_pok(
+        pok: PoKOfSignature,
+        proof_request: &ProofRequest,
+        nonce: &ProofNonce,
+    ) -> Result<DesignatedVerifierProof, BBSError> {
+        DesignatedVerifierProof::new(pok, proof_request, nonce)
+    }
+
+    /// Same as `generate_designated_signature_pok` but all randomness is generated from `rng`
+    pub fn generate_designated_signature_pok_with_rng<R: RngCore + CryptoRng>(
+        pok: PoKOfSignature,
+        proof_request: &ProofRequest,
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<DesignatedVerifierProof, BBSError> {
+        DesignatedVerifierProof::new_with_rng(pok, proof_request, nonce, rng)
+    }
+
+    /// Create a single proof of knowledge for several signatures where the hidden messages
+    /// in each equivalence class are proven to be equal e.g. a subject identifier shared
+    /// by multiple credentials
+    ///
+    /// # Arguments
+    /// * `credentials` - the signature, issuer's public key and proof messages for each credential
+    /// * `equalities` - sets of `(credential index, message index)` with equal hidden messages
+    /// * `nonce` - the verifier's nonce
+    pub fn generate_multi_signature_pok(
+        credentials: &[(Signature, PublicKey, Vec<ProofMessage>)],
+        equalities: &[EquivalenceClass],
+        nonce: &ProofNonce,
+    ) -> Result<MultiSignatureProof, BBSError> {
+        MultiSignatureProof::new(credentials, equalities, nonce)
+    }
+
+    /// Same as `generate_multi_signature_pok` but all randomness is generated from `rng`
+    pub fn generate_multi_signature_pok_with_rng<R: RngCore + CryptoRng>(
+        credentials: &[(Signature, PublicKey, Vec<ProofMessage>)],
+        equalities: &[EquivalenceClass],
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<MultiSignatureProof, BBSError> {
+        MultiSignatureProof::new_with_rng(credentials, equalities, nonce, rng)
+    }
+
+    /// Respond to a verifier's presentation request
+    ///
+    /// # Arguments
+    /// * `request` - the verifier's presentation request
+    /// * `credentials` - the signature and messages for each credential in the order they are requested
+    pub fn generate_presentation(
+        request: &PresentationRequest,
+        credentials: &[(Signature, Vec<SignatureMessage>)],
+    ) -> Result<PresentationResponse, BBSError> {
+        PresentationResponse::new(request, credentials)
+    }
+
+    /// Same as `generate_presentation` but all randomness is generated from `rng`
+    pub fn generate_presentation_with_rng<R: RngCore + CryptoRng>(
+        request: &PresentationRequest,
+        credentials: &[(Signature, Vec<SignatureMessage>)],
+        rng: &mut R,
+    ) -> Result<PresentationResponse, BBSError> {
+        PresentationResponse::new_with_rng(request, credentials, rng)
+    }
+
+    /// Create a signature proof of knowledge with a pseudonym for `scope` derived from
+    /// the hidden link secret. The pseudonym is the same for every proof in a scope
+    /// so a verifier can recognize a returning prover without linking them across scopes.
+    ///
+    /// # Arguments
+    /// * `signature` - the signature containing the link secret
+    /// * `verkey` - the issuer's public key
+    /// * `proof_messages` - the messages in the signature
+    /// * `link_secret_index` - the index of the link secret in `proof_messages`
+    /// * `scope` - the scope of the pseudonym e.g. the verifier's domain
+    /// * `nonce` - the verifier's nonce
+    pub fn generate_pseudonym_pok<I: AsRef<[u8]>>(
+        signature: &Signature,
+        verkey: &PublicKey,
+        proof_messages: &[ProofMessage],
+        link_secret_index: usize,
+        scope: I,
+        nonce: &ProofNonce,
+    ) -> Result<PseudonymProof, BBSError> {
+        PseudonymProof::new(
+            signature,
+            verkey,
+            proof_messages,
+            link_secret_index,
+            scope,
+            nonce,
+        )
+    }
+
+    /// Same as `generate_pseudonym_pok` but all randomness is generated from `rng`
+    pub fn generate_pseudonym_pok_with_rng<I: AsRef<[u8]>, R: RngCore + CryptoRng>(
+        signature: &Signature,
+        verkey: &PublicKey,
+        proof_messages: &[ProofMessage],
+        link_secret_index: usize,
+        scope: I,
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<PseudonymProof, BBSError> {
+        PseudonymProof::new_with_rng(
+            signature,
+            verkey,
+            proof_messages,
+            link_secret_index,
+            scope,
+            nonce,
+            rng,
+        )
+    }
 }
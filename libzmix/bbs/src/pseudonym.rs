@@ -0,0 +1,408 @@
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::messages::*;
+use crate::pok_sig::prelude::*;
+use crate::signature::prelude::*;
+use crate::{
+    hash_to_g1, HashElem, ProofChallenge, ProofNonce, ProofRequest, RandomElem, SignatureMessage,
+    SignatureProof, ToVariableLengthBytes, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
+};
+use pairing_plus::{bls12_381::G1, serdes::SerDes, CurveProjective};
+use rand::{thread_rng, CryptoRng, RngCore};
+use serde::{
+    de::{Error as DError, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{Pseudonym, PseudonymProof};
+}
+
+/// A scope exclusive pseudonym `H(scope)^link_secret`.
+/// The same link secret always gives the same pseudonym for a scope
+/// but pseudonyms for different scopes cannot be linked.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Pseudonym(pub(crate) G1);
+
+impl Pseudonym {
+    /// Derive the pseudonym for `link_secret` in `scope` e.g. a verifier's domain
+    pub fn new<I: AsRef<[u8]>>(scope: I, link_secret: &SignatureMessage) -> Self {
+        Self::new_from_generator(scope_generator(scope), link_secret)
+    }
+
+    pub(crate) fn new_from_generator(generator: G1, link_secret: &SignatureMessage) -> Self {
+        let mut nym = generator;
+        nym.mul_assign(link_secret.0);
+        Self(nym)
+    }
+
+    to_fixed_length_bytes_impl!(Pseudonym, G1, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE);
+}
+
+default_zero_impl!(Pseudonym, G1);
+as_ref_impl!(Pseudonym, G1);
+from_impl!(Pseudonym, G1, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE);
+display_impl!(Pseudonym);
+serdes_impl!(Pseudonym);
+#[cfg(feature = "wasm")]
+wasm_slice_impl!(Pseudonym);
+
+/// A signature proof of knowledge that also proves a pseudonym
+/// was derived from the hidden link secret in the signature.
+///
+/// The link secret uses the same blinding factor in both proofs
+/// so their responses are equal iff the link secrets are equal.
+#[derive(Debug, Clone)]
+pub struct PseudonymProof {
+    /// The pseudonym for the scope
+    pub pseudonym: Pseudonym,
+    /// The signature proof of knowledge
+    pub signature_proof: SignatureProof,
+    /// `H(scope)^blinding` for the link secret
+    pub(crate) commitment: G1,
+}
+
+impl PseudonymProof {
+    /// Create a signature proof of knowledge and derive the pseudonym for `scope`
+    /// from the hidden message at `link_secret_index`.
+    pub fn new<I: AsRef<[u8]>>(
+        signature: &Signature,
+        verkey: &PublicKey,
+        proof_messages: &[ProofMessage],
+        link_secret_index: usize,
+        scope: I,
+        nonce: &ProofNonce,
+    ) -> Result<Self, BBSError> {
+        Self::new_with_rng(
+            signature,
+            verkey,
+            proof_messages,
+            link_secret_index,
+            scope,
+            nonce,
+            &mut thread_rng(),
+        )
+    }
+
+    /// Same as `new` but all blinding factors are generated from `rng`
+    pub fn new_with_rng<I: AsRef<[u8]>, R: RngCore + CryptoRng>(
+        signature: &Signature,
+        verkey: &PublicKey,
+        proof_messages: &[ProofMessage],
+        link_secret_index: usize,
+        scope: I,
+        nonce: &ProofNonce,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        let (link_secret, blinding) = match proof_messages.get(link_secret_index) {
+            Some(ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(m))) => {
+                (*m, ProofNonce::random_with_rng(rng))
+            }
+            Some(ProofMessage::Hidden(HiddenMessage::ExternalBlinding(m, b))) => (*m, *b),
+            Some(ProofMessage::Revealed(_)) => {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Link secret {} must be hidden", link_secret_index),
+                }
+                .into())
+            }
+            None => {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Link secret {} does not exist", link_secret_index),
+                }
+                .into())
+            }
+        };
+
+        let proof_messages = proof_messages
+            .iter()
+            .enumerate()
+            .map(|(i, pm)| {
+                if i == link_secret_index {
+                    return pm_hidden_raw!(link_secret, blinding);
+                }
+                match pm {
+                    ProofMessage::Revealed(r) => pm_revealed_raw!(*r),
+                    ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(h)) => {
+                        pm_hidden_raw!(*h)
+                    }
+                    ProofMessage::Hidden(HiddenMessage::ExternalBlinding(h, b)) => {
+                        pm_hidden_raw!(*h, *b)
+                    }
+                }
+            })
+            .collect::<Vec<ProofMessage>>();
+        let pok = PoKOfSignature::init_with_rng(signature, verkey, &proof_messages, rng)?;
+
+        let generator = scope_generator(scope);
+        let pseudonym = Pseudonym::new_from_generator(generator, &link_secret);
+        let mut commitment = generator;
+        commitment.mul_assign(blinding.0);
+
+        let challenge = compute_challenge(pok.to_bytes(), &pseudonym, &commitment, nonce);
+        let revealed_messages = pok.revealed_messages.clone();
+        Ok(Self {
+            pseudonym,
+            signature_proof: SignatureProof {
+                revealed_messages,
+                proof: pok.gen_proof(&challenge)?,
+            },
+            commitment,
+        })
+    }
+
+    /// Verify the signature proof of knowledge against `proof_request` and that the pseudonym
+    /// is derived for `scope` from the hidden message at `link_secret_index`.
+    /// Returns the revealed messages.
+    pub fn verify<I: AsRef<[u8]>>(
+        &self,
+        proof_request: &ProofRequest,
+        link_secret_index: usize,
+        scope: I,
+        nonce: &ProofNonce,
+    ) -> Result<BTreeMap<usize, SignatureMessage>, BBSError> {
+        let revealed = self
+            .signature_proof
+            .revealed_messages
+            .keys()
+            .copied()
+            .collect::<BTreeSet<usize>>();
+        if revealed != proof_request.revealed_messages {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Revealed messages do not match the proof request".to_string(),
+            }
+            .into());
+        }
+        if link_secret_index >= proof_request.verification_key.message_count()
+            || revealed.contains(&link_secret_index)
+        {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Link secret {} must be hidden", link_secret_index),
+            }
+            .into());
+        }
+        if self.pseudonym.0.is_zero() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Invalid pseudonym".to_string(),
+            }
+            .into());
+        }
+
+        let challenge = compute_challenge(
+            self.signature_proof
+                .proof
+                .get_bytes_for_challenge(revealed.clone(), &proof_request.verification_key),
+            &self.pseudonym,
+            &self.commitment,
+            nonce,
+        );
+        match self.signature_proof.proof.verify(
+            &proof_request.verification_key,
+            &self.signature_proof.revealed_messages,
+            &challenge,
+        )? {
+            PoKOfSignatureProofStatus::Success => {}
+            e => return Err(BBSErrorKind::InvalidProof { status: e }.into()),
+        }
+
+        // H(scope)^resp * nym^c == H(scope)^blinding
+        let hidden_index = link_secret_index - revealed.range(..link_secret_index).count();
+        let resp = self
+            .signature_proof
+            .proof
+            .get_resp_for_message(hidden_index)?;
+        let mut lhs = scope_generator(scope);
+        lhs.mul_assign(resp.0);
+        let mut nym_c = self.pseudonym.0;
+        nym_c.mul_assign(challenge.0);
+        lhs.add_assign(&nym_c);
+        if lhs != self.commitment {
+            return Err(BBSErrorKind::InvalidProof {
+                status: PoKOfSignatureProofStatus::BadHiddenMessage,
+            }
+            .into());
+        }
+
+        Ok(self.signature_proof.revealed_messages.clone())
+    }
+
+    pub(crate) fn to_bytes(&self, compressed: bool) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.pseudonym.0.serialize(&mut output, compressed).unwrap();
+        self.commitment.serialize(&mut output, compressed).unwrap();
+        output.extend_from_slice(self.signature_proof.to_bytes(compressed).as_slice());
+        output
+    }
+
+    pub(crate) fn from_bytes(
+        data: &[u8],
+        g1_size: usize,
+        compressed: bool,
+    ) -> Result<Self, BBSError> {
+        if data.len() < g1_size * 2 {
+            return Err(BBSErrorKind::InvalidNumberOfBytes(g1_size * 2, data.len()).into());
+        }
+        let mut c = &data[..g1_size];
+        let pseudonym = Pseudonym(slice_to_elem!(&mut c, G1, compressed)?);
+        let mut c = &data[g1_size..g1_size * 2];
+        let commitment = slice_to_elem!(&mut c, G1, compressed)?;
+        let signature_proof =
+            SignatureProof::from_bytes(&data[g1_size * 2..], g1_size, compressed)?;
+        Ok(Self {
+            pseudonym,
+            signature_proof,
+            commitment,
+        })
+    }
+}
+
+impl ToVariableLengthBytes for PseudonymProof {
+    type Output = PseudonymProof;
+    type Error = BBSError;
+
+    /// Convert to raw bytes using compressed form for each element.
+    fn to_bytes_compressed_form(&self) -> Vec<u8> {
+        self.to_bytes(true)
+    }
+
+    /// Convert from compressed form raw bytes.
+    fn from_bytes_compressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self, BBSError> {
+        Self::from_bytes(data.as_ref(), G1_COMPRESSED_SIZE, true)
+    }
+
+    fn to_bytes_uncompressed_form(&self) -> Vec<u8> {
+        self.to_bytes(false)
+    }
+
+    fn from_bytes_uncompressed_form<I: AsRef<[u8]>>(data: I) -> Result<Self::Output, Self::Error> {
+        Self::from_bytes(data.as_ref(), G1_UNCOMPRESSED_SIZE, false)
+    }
+}
+
+/// The base for pseudonyms in `scope`
+pub(crate) fn scope_generator<I: AsRef<[u8]>>(scope: I) -> G1 {
+    let mut data = b"BBS+ pseudonym scope:".to_vec();
+    data.extend_from_slice(scope.as_ref());
+    hash_to_g1(data)
+}
+
+/// The pseudonym and its commitment are included so the proof is bound to them
+fn compute_challenge(
+    mut bytes: Vec<u8>,
+    pseudonym: &Pseudonym,
+    commitment: &G1,
+    nonce: &ProofNonce,
+) -> ProofChallenge {
+    pseudonym.0.serialize(&mut bytes, false).unwrap();
+    commitment.serialize(&mut bytes, false).unwrap();
+    bytes.extend_from_slice(&nonce.to_bytes_uncompressed_form()[..]);
+    ProofChallenge::hash(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    fn setup() -> (PublicKey, Signature, Vec<SignatureMessage>) {
+        let (pk, sk) = Issuer::new_keys(3).unwrap();
+        let messages = vec![
+            Prover::new_link_secret(),
+            SignatureMessage::hash(b"name"),
+            SignatureMessage::hash(b"age"),
+        ];
+        let signature = Signature::new(&messages, &sk, &pk).unwrap();
+        (pk, signature, messages)
+    }
+
+    #[test]
+    fn pseudonym_proof() {
+        let (pk, signature, messages) = setup();
+        let nonce = Verifier::generate_proof_nonce();
+        let proof_request = Verifier::new_proof_request(&[1], &pk).unwrap();
+        let proof_messages = vec![
+            pm_hidden_raw!(messages[0]),
+            pm_revealed_raw!(messages[1]),
+            pm_hidden_raw!(messages[2]),
+        ];
+
+        let proof =
+            PseudonymProof::new(&signature, &pk, &proof_messages, 0, b"example.com", &nonce)
+                .unwrap();
+        assert_eq!(
+            proof.pseudonym,
+            Pseudonym::new(b"example.com", &messages[0])
+        );
+        let revealed = proof
+            .verify(&proof_request, 0, b"example.com", &nonce)
+            .unwrap();
+        assert_eq!(revealed[&1], messages[1]);
+
+        // Same scope gives the same pseudonym, different scopes are unlinkable
+        let proof2 =
+            PseudonymProof::new(&signature, &pk, &proof_messages, 0, b"example.com", &nonce)
+                .unwrap();
+        assert_eq!(proof.pseudonym, proof2.pseudonym);
+        let proof3 =
+            PseudonymProof::new(&signature, &pk, &proof_messages, 0, b"example.org", &nonce)
+                .unwrap();
+        assert_ne!(proof.pseudonym, proof3.pseudonym);
+
+        assert!(proof
+            .verify(&proof_request, 0, b"example.org", &nonce)
+            .is_err());
+        assert!(proof
+            .verify(&proof_request, 2, b"example.com", &nonce)
+            .is_err());
+        assert!(proof
+            .verify(
+                &proof_request,
+                0,
+                b"example.com",
+                &Verifier::generate_proof_nonce()
+            )
+            .is_err());
+
+        let mut forged = proof.clone();
+        forged.pseudonym = Pseudonym::new(b"example.com", &Prover::new_link_secret());
+        assert!(forged
+            .verify(&proof_request, 0, b"example.com", &nonce)
+            .is_err());
+
+        let bytes = proof.to_bytes_compressed_form();
+        let proof2 = PseudonymProof::from_bytes_compressed_form(&bytes).unwrap();
+        assert!(proof2
+            .verify(&proof_request, 0, b"example.com", &nonce)
+            .is_ok());
+        let bytes = proof.to_bytes_uncompressed_form();
+        let proof2 = PseudonymProof::from_bytes_uncompressed_form(&bytes).unwrap();
+        assert!(proof2
+            .verify(&proof_request, 0, b"example.com", &nonce)
+            .is_ok());
+    }
+
+    #[test]
+    fn revealed_link_secret() {
+        let (pk, signature, messages) = setup();
+        let nonce = Verifier::generate_proof_nonce();
+        let proof_messages = vec![
+            pm_revealed_raw!(messages[0]),
+            pm_hidden_raw!(messages[1]),
+            pm_hidden_raw!(messages[2]),
+        ];
+        assert!(
+            PseudonymProof::new(&signature, &pk, &proof_messages, 0, b"example.com", &nonce)
+                .is_err()
+        );
+        assert!(
+            PseudonymProof::new(&signature, &pk, &proof_messages, 3, b"example.com", &nonce)
+                .is_err()
+        );
+    }
+}
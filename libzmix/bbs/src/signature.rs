@@ -1,9 +1,9 @@
 use crate::errors::prelude::*;
 use crate::keys::prelude::*;
 use crate::{
-    multi_scalar_mul_const_time_g1, multi_scalar_mul_var_time_g1, rand_non_zero_fr, Commitment,
-    RandomElem, SignatureBlinding, SignatureMessage, FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE,
-    G1_UNCOMPRESSED_SIZE,
+    multi_scalar_mul_const_time_g1, multi_scalar_mul_var_time_g1, pairing_product_is_one,
+    prepared_pairing_product_is_one, rand_non_zero_fr, Commitment, RandomElem, SignatureBlinding,
+    SignatureMessage, FR_COMPRESSED_SIZE, G1_COMPRESSED_SIZE, G1_UNCOMPRESSED_SIZE,
 };
 use ff_zeroize::{Field, PrimeField};
 use pairing_plus::{
@@ -11,6 +11,7 @@ use pairing_plus::{
     serdes::SerDes,
     CurveAffine, CurveProjective, Engine,
 };
+use rand::{thread_rng, CryptoRng, RngCore};
 use serde::{
     de::{Error as DError, Visitor},
     Deserialize, Deserializer, Serialize, Serializer,
@@ -149,12 +150,24 @@ impl BlindSignature {
     /// `messages`: Messages to be signed where each value is 0 < m ≤ r and the key is the index in the public.h to which is used as base
     /// `signkey`: The secret key for signing
     /// `verkey`: The corresponding public key to secret key
-    pub fn new(
+    pub fn new<K: VerificationKey>(
         commitment: &Commitment,
         messages: &BTreeMap<usize, SignatureMessage>,
         signkey: &SecretKey,
-        verkey: &PublicKey,
+        verkey: &K,
     ) -> Result<Self, BBSError> {
+        Self::new_with_rng(commitment, messages, signkey, verkey, &mut thread_rng())
+    }
+
+    /// Same as `new` but `e` and `s` are generated from `rng`
+    pub fn new_with_rng<K: VerificationKey, R: RngCore + CryptoRng>(
+        commitment: &Commitment,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        signkey: &SecretKey,
+        verkey: &K,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        let verkey = verkey.public_key();
         check_verkey_message!(
             messages.len() > verkey.message_count(),
             verkey.message_count(),
@@ -162,8 +175,8 @@ impl BlindSignature {
         );
         signkey.validate()?;
         verkey.validate()?;
-        let e = rand_non_zero_fr();
-        let s = rand_non_zero_fr();
+        let e = rand_non_zero_fr(rng);
+        let s = rand_non_zero_fr(rng);
 
         let mut points = Vec::with_capacity(messages.len() + 3);
         let mut scalars = Vec::with_capacity(messages.len() + 3);
@@ -247,20 +260,31 @@ pub struct Signature {
 // https://eprint.iacr.org/2016/663.pdf Section 4.3
 impl Signature {
     /// No committed messages, All messages known to signer.
-    pub fn new(
+    pub fn new<K: VerificationKey>(
         messages: &[SignatureMessage],
         signkey: &SecretKey,
-        verkey: &PublicKey,
+        verkey: &K,
     ) -> Result<Self, BBSError> {
+        Self::new_with_rng(messages, signkey, verkey, &mut thread_rng())
+    }
+
+    /// Same as `new` but `e` and `s` are generated from `rng`
+    pub fn new_with_rng<K: VerificationKey, R: RngCore + CryptoRng>(
+        messages: &[SignatureMessage],
+        signkey: &SecretKey,
+        verkey: &K,
+        rng: &mut R,
+    ) -> Result<Self, BBSError> {
+        let vk = verkey.public_key();
         check_verkey_message!(
-            messages.len() > verkey.message_count(),
-            verkey.message_count(),
+            messages.len() > vk.message_count(),
+            vk.message_count(),
             messages.len()
         );
         signkey.validate()?;
-        verkey.validate()?;
-        let e = rand_non_zero_fr();
-        let s = rand_non_zero_fr();
+        vk.validate()?;
+        let e = rand_non_zero_fr(rng);
+        let s = rand_non_zero_fr(rng);
         let mut b = Self::compute_b(&s, messages, verkey);
         let mut exp = signkey.0;
         exp.add_assign(&e);
@@ -273,26 +297,43 @@ impl Signature {
         SignatureBlinding::random()
     }
 
+    /// Same as `generate_blinding` but the blinding factor is generated from `rng`
+    pub fn generate_blinding_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> SignatureBlinding {
+        SignatureBlinding::random_with_rng(rng)
+    }
+
     /// Verify a signature. During proof of knowledge also, this method is used after extending the verkey
-    pub fn verify(
+    pub fn verify<K: VerificationKey>(
         &self,
         messages: &[SignatureMessage],
-        verkey: &PublicKey,
+        verkey: &K,
     ) -> Result<bool, BBSError> {
+        let vk = verkey.public_key();
         check_verkey_message!(
-            messages.len() != verkey.message_count(),
-            verkey.message_count(),
+            messages.len() != vk.message_count(),
+            vk.message_count(),
             messages.len()
         );
-        verkey.validate()?;
+        vk.validate()?;
         self.validate()?;
 
+        if let Some(prepared) = verkey.prepared() {
+            // pair(a^(1/x+e), w) * pair(a^(e/x+e) / b, g2) with the prepared w and g2
+            let mut a_e = self.a;
+            a_e.mul_assign(self.e);
+            a_e.sub_assign(&self.get_b(messages, verkey));
+            return Ok(prepared_pairing_product_is_one(&[
+                (self.a, prepared.w()),
+                (a_e, prepared.g2()),
+            ]));
+        }
+
         let mut pqz = Vec::new();
         let mut a = G2::one();
         a.mul_assign(self.e);
-        a.add_assign(&verkey.w.0);
+        a.add_assign(&vk.w.0);
 
-        let mut b = self.get_b(messages, verkey);
+        let mut b = self.get_b(messages, vk);
         b.negate();
         let b = b.into_affine().prepare();
         let g2 = G2::one().into_affine().prepare();
@@ -311,6 +352,69 @@ impl Signature {
         )
     }
 
+    /// Verify many signatures with a random linear combination of their pairing equations
+    /// which only needs one final exponentiation.
+    /// If the batch fails each signature is verified on its own and the indices of the
+    /// invalid signatures are returned in `BBSErrorKind::BatchVerificationFailed`.
+    pub fn batch_verify(
+        items: &[(&Signature, &[SignatureMessage], &PublicKey)],
+    ) -> Result<(), BBSError> {
+        Self::batch_verify_with_rng(items, &mut thread_rng())
+    }
+
+    /// Same as `batch_verify` but the random linear combination comes from `rng`
+    pub fn batch_verify_with_rng<R: RngCore + CryptoRng>(
+        items: &[(&Signature, &[SignatureMessage], &PublicKey)],
+        rng: &mut R,
+    ) -> Result<(), BBSError> {
+        let mut failed = Vec::new();
+        let mut batched = Vec::with_capacity(items.len());
+        // e(A^r, w) for each public key and e(A^(r*e) * B^-r, g2) summed over all signatures
+        let mut pairs: Vec<(G1, G2)> = Vec::new();
+        let mut g2_term = G1::zero();
+        for (i, (signature, messages, verkey)) in items.iter().enumerate() {
+            if messages.len() != verkey.message_count()
+                || verkey.validate().is_err()
+                || signature.validate().is_err()
+            {
+                failed.push(i);
+                continue;
+            }
+            batched.push(i);
+
+            let r = rand_non_zero_fr(rng);
+            let mut a_r = signature.a;
+            a_r.mul_assign(r);
+            match pairs.iter_mut().find(|(_, w)| *w == verkey.w.0) {
+                Some((p, _)) => p.add_assign(&a_r),
+                None => pairs.push((a_r, verkey.w.0)),
+            }
+
+            let mut a_re = a_r;
+            a_re.mul_assign(signature.e);
+            let mut b_r = signature.get_b(messages, verkey);
+            b_r.mul_assign(r);
+            a_re.sub_assign(&b_r);
+            g2_term.add_assign(&a_re);
+        }
+        pairs.push((g2_term, G2::one()));
+
+        if !batched.is_empty() && !pairing_product_is_one(&pairs) {
+            for i in batched {
+                let (signature, messages, verkey) = items[i];
+                if !signature.verify(messages, verkey)? {
+                    failed.push(i);
+                }
+            }
+            failed.sort_unstable();
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(BBSErrorKind::BatchVerificationFailed { indices: failed }.into())
+        }
+    }
+
     /// Check if the signature is a valid form i.e. not infinity since it will always validate
     /// if that is the case
     pub fn validate(&self) -> Result<(), BBSError> {
@@ -321,7 +425,15 @@ impl Signature {
     }
 
     /// Helper function for computing the `b` value. Internal helper function
-    pub(crate) fn get_b(&self, messages: &[SignatureMessage], verkey: &PublicKey) -> G1 {
+    pub(crate) fn get_b<K: VerificationKey>(
+        &self,
+        messages: &[SignatureMessage],
+        verkey: &K,
+    ) -> G1 {
+        if let Some(prepared) = verkey.prepared() {
+            return prepared.sum_of_products(Some(&self.s), messages.iter().enumerate());
+        }
+        let verkey = verkey.public_key();
         // Self::compute_b(&self.s, messages, verkey)
         let mut bases = Vec::with_capacity(messages.len() + 2);
         let mut scalars = Vec::with_capacity(messages.len() + 2);
@@ -338,7 +450,14 @@ impl Signature {
         multi_scalar_mul_var_time_g1(&bases, &scalars)
     }
 
-    fn compute_b(s: &Fr, messages: &[SignatureMessage], verkey: &PublicKey) -> G1 {
+    /// Compute the `b` value when signing. The messages and `s` are secret so the
+    /// constant time multiplication is used instead of the precomputed tables.
+    pub(crate) fn compute_b<K: VerificationKey>(
+        s: &Fr,
+        messages: &[SignatureMessage],
+        verkey: &K,
+    ) -> G1 {
+        let verkey = verkey.public_key();
         let mut bases = Vec::with_capacity(messages.len() + 2);
         let mut scalars = Vec::with_capacity(messages.len() + 2);
         // g1*h0^blinding_factor*hi^mi.....
@@ -383,6 +502,7 @@ from_bytes_impl!(
 );
 try_from_impl!(Signature);
 serdes_impl!(Signature);
+multibase_impl!(Signature);
 display_impl!(Signature);
 #[cfg(feature = "wasm")]
 wasm_slice_impl!(Signature);
@@ -393,7 +513,6 @@ mod tests {
     use crate::keys::generate;
     use crate::pok_vc::ProverCommittingG1;
     use crate::CommitmentBuilder;
-    use rand::prelude::*;
 
     #[test]
     fn signature_serialization() {
@@ -414,6 +533,35 @@ mod tests {
         assert_eq!(sig, sig_2);
     }
 
+    #[test]
+    fn batch_verify_signatures() {
+        let (pk1, sk1) = generate(3).unwrap();
+        let (pk2, sk2) = generate(2).unwrap();
+        let messages = (0..5)
+            .map(|_| SignatureMessage::random())
+            .collect::<Vec<SignatureMessage>>();
+        let sig1 = Signature::new(&messages[..3], &sk1, &pk1).unwrap();
+        let sig2 = Signature::new(&messages[2..], &sk1, &pk1).unwrap();
+        let sig3 = Signature::new(&messages[3..], &sk2, &pk2).unwrap();
+
+        let mut items = vec![
+            (&sig1, &messages[..3], &pk1),
+            (&sig2, &messages[2..], &pk1),
+            (&sig3, &messages[3..], &pk2),
+        ];
+        assert!(Signature::batch_verify(&items).is_ok());
+        assert!(Signature::batch_verify(&[]).is_ok());
+
+        // Wrong messages, wrong key and wrong message count
+        items.push((&sig1, &messages[1..4], &pk1));
+        items.push((&sig3, &messages[3..], &pk1));
+        items.push((&sig3, &messages[2..], &pk2));
+        match Signature::batch_verify(&items).unwrap_err().kind() {
+            BBSErrorKind::BatchVerificationFailed { indices } => assert_eq!(indices, vec![3, 4, 5]),
+            _ => panic!("Expected a batch verification failure"),
+        }
+    }
+
     #[test]
     fn gen_signature() {
         let message_count = 5;
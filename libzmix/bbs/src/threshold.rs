@@ -0,0 +1,774 @@
+use crate::errors::prelude::*;
+use crate::keys::prelude::*;
+use crate::signature::prelude::*;
+use crate::{hash_to_fr, hash_to_g1, SignatureMessage};
+use ff_zeroize::{Field as FFField, PrimeField};
+use pairing_plus::{
+    bls12_381::{Fr, FrRepr, G1, G2},
+    serdes::SerDes,
+    CurveProjective,
+};
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::BTreeSet;
+use ursa_sharing::{
+    error::{SharingError, SharingResult},
+    generic_array::GenericArray,
+    pedersen::{PedersenVerifier, Scheme as PedersenScheme},
+    shamir::{Scheme as ShamirScheme, Share},
+    typenum::{U32, U48, U96},
+    Field, Group,
+};
+
+/// Convenience importing module
+pub mod prelude {
+    pub use super::{
+        DkgCommitment, DkgParticipant, DkgShare, PartialSignature, PresignatureShare,
+        PublicKeyShare, SecretShare,
+    };
+}
+
+const DKG_BLINDING_GENERATOR: &[u8] = b"BBS+ threshold DKG blinding generator";
+
+/// One participant in the distributed key generation for threshold issuance by n issuers.
+///
+/// Each participant shares a random value with Pedersen's verifiable secret sharing.
+/// The sum of all the random values is the secret and each participant's share is the sum
+/// of the shares it received, so no single party ever holds the secret.
+///
+/// The key threshold t is the number of shares that determine the key, so fewer than t
+/// participants learn nothing about it. Signing multiplies two values shared with threshold t
+/// so it needs 2t - 1 participants, not t.
+///
+/// The same protocol creates the randomness for signing in `PresignatureShare`.
+#[derive(Debug)]
+pub struct DkgParticipant {
+    identifier: usize,
+    threshold: usize,
+    limit: usize,
+    secret_shares: Vec<Share>,
+    blinding_shares: Vec<Share>,
+    verifier: PedersenVerifier<FrShare, G1Share>,
+}
+
+/// The commitments to a participant's polynomial. Broadcast to all participants.
+#[derive(Debug, Clone)]
+pub struct DkgCommitment {
+    sender: usize,
+    verifier: PedersenVerifier<FrShare, G1Share>,
+}
+
+/// The share for one participant. Sent privately to that participant.
+#[derive(Debug, Clone)]
+pub struct DkgShare {
+    sender: usize,
+    share: Share,
+    blinding: Share,
+}
+
+/// A participant's share of a secret created by `DkgParticipant`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretShare {
+    identifier: usize,
+    threshold: usize,
+    value: Fr,
+}
+
+/// The public key for a participant's `SecretShare` of the signing key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKeyShare {
+    identifier: usize,
+    w: G2,
+}
+
+/// The randomness one participant uses for a single threshold signature.
+/// It is created from two runs of `DkgParticipant`: a random value `r` with
+/// the key threshold t and a random value with threshold 2t - 2 that masks
+/// the participant's part of `r * (x + e)` when it is revealed.
+/// A presignature must only be used once.
+#[derive(Debug)]
+pub struct PresignatureShare {
+    identifier: usize,
+    threshold: usize,
+    r: Fr,
+    zero: Fr,
+}
+
+/// One participant's contribution to a threshold signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialSignature {
+    identifier: usize,
+    /// B^r_i
+    r_b: G1,
+    /// r_i * (x_i + e) masked with a sharing of zero
+    u: Fr,
+}
+
+impl DkgParticipant {
+    /// Start the distributed key generation of the signing key or of the randomness `r`
+    /// of a presignature as participant `identifier` in 1..=`limit`.
+    /// Signing needs 2 * `key_threshold` - 1 participants so `key_threshold` must be at least 2
+    /// and 2 * `key_threshold` - 1 must not exceed `limit`.
+    pub fn new(identifier: usize, key_threshold: usize, limit: usize) -> Result<Self, BBSError> {
+        check_key_threshold(key_threshold, limit)?;
+        Self::new_with_threshold(identifier, key_threshold, limit)
+    }
+
+    /// Start the distributed key generation of the mask of a presignature.
+    /// The mask is shared with threshold 2 * `key_threshold` - 2.
+    pub fn new_mask(
+        identifier: usize,
+        key_threshold: usize,
+        limit: usize,
+    ) -> Result<Self, BBSError> {
+        check_key_threshold(key_threshold, limit)?;
+        Self::new_with_threshold(identifier, 2 * key_threshold - 2, limit)
+    }
+
+    fn new_with_threshold(
+        identifier: usize,
+        threshold: usize,
+        limit: usize,
+    ) -> Result<Self, BBSError> {
+        if identifier == 0 || identifier > limit {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Invalid participant identifier {}", identifier),
+            }
+            .into());
+        }
+        let mut rng = thread_rng();
+        let secret = FrShare::random(&mut rng);
+        let result = PedersenScheme::new(threshold, limit)
+            .map_err(sharing_error)?
+            .split_secret(&mut rng, &secret, Some(G1Share(G1::one())), Some(dkg_h()))
+            .map_err(sharing_error)?;
+        Ok(Self {
+            identifier,
+            threshold,
+            limit,
+            secret_shares: result.secret_shares,
+            blinding_shares: result.blinding_shares,
+            verifier: result.verifier,
+        })
+    }
+
+    /// The commitments to broadcast to all other participants
+    pub fn commitment(&self) -> DkgCommitment {
+        DkgCommitment {
+            sender: self.identifier,
+            verifier: self.verifier.clone(),
+        }
+    }
+
+    /// The share to send to participant `identifier`
+    pub fn share_for(&self, identifier: usize) -> Result<DkgShare, BBSError> {
+        if identifier == 0 || identifier > self.limit {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!("Invalid participant identifier {}", identifier),
+            }
+            .into());
+        }
+        Ok(DkgShare {
+            sender: self.identifier,
+            share: self.secret_shares[identifier - 1].clone(),
+            blinding: self.blinding_shares[identifier - 1].clone(),
+        })
+    }
+
+    /// Verify the shares received from every participant, including this one,
+    /// and combine them into this participant's share of the secret
+    pub fn finish(self, received: &[(DkgCommitment, DkgShare)]) -> Result<SecretShare, BBSError> {
+        let senders = received
+            .iter()
+            .map(|(c, s)| {
+                if c.sender == s.sender {
+                    Ok(s.sender)
+                } else {
+                    Err(BBSErrorKind::GeneralError {
+                        msg: format!("Share from {} does not match its commitment", s.sender),
+                    }
+                    .into())
+                }
+            })
+            .collect::<Result<BTreeSet<usize>, BBSError>>()?;
+        if senders.len() != received.len() || senders != (1..=self.limit).collect() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "A share is required from every participant".to_string(),
+            }
+            .into());
+        }
+
+        let scheme = PedersenScheme::new(self.threshold, self.limit).map_err(sharing_error)?;
+        let h = dkg_h();
+        let mut value = Fr::zero();
+        for (commitment, share) in received {
+            let verifier = &commitment.verifier;
+            if share.share.identifier() as usize != self.identifier
+                || verifier.g.0 != G1::one()
+                || verifier.h.0 != h.0
+                || verifier.commitments.len() != self.threshold
+            {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Invalid share from {}", share.sender),
+                }
+                .into());
+            }
+            scheme
+                .verify_share(&share.share, &share.blinding, verifier)
+                .map_err(sharing_error)?;
+            value.add_assign(
+                &FrShare::from_bytes(share.share.value())
+                    .map_err(sharing_error)?
+                    .0,
+            );
+        }
+        Ok(SecretShare {
+            identifier: self.identifier,
+            threshold: self.threshold,
+            value,
+        })
+    }
+}
+
+impl SecretShare {
+    /// The participant's identifier
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// The number of shares needed to use the secret
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The public key for this share when it is a share of the signing key
+    pub fn public_share(&self) -> PublicKeyShare {
+        let mut w = G2::one();
+        w.mul_assign(self.value);
+        PublicKeyShare {
+            identifier: self.identifier,
+            w,
+        }
+    }
+}
+
+impl PublicKeyShare {
+    /// The participant's identifier
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Combine `threshold` or more public key shares into the issuer's public key.
+    /// Use `DeterministicPublicKey::to_public_key` to get the message generators.
+    pub fn combine(
+        shares: &[PublicKeyShare],
+        threshold: usize,
+    ) -> Result<DeterministicPublicKey, BBSError> {
+        let shares = shares
+            .iter()
+            .map(|s| Share::new(s.identifier, G2Share(s.w).to_bytes()))
+            .collect::<Vec<Share>>();
+        let w = ShamirScheme::new(threshold, shares.len().max(threshold))
+            .map_err(sharing_error)?
+            .combine_shares::<FrShare, G2Share>(&shares)
+            .map_err(sharing_error)?;
+        Ok(DeterministicPublicKey(w.0))
+    }
+}
+
+impl PresignatureShare {
+    /// Create a presignature from this participant's share of a random value with the key
+    /// threshold t and its share of a random value with threshold 2t - 2
+    pub fn new(randomness: &SecretShare, mask: &SecretShare) -> Result<Self, BBSError> {
+        if randomness.identifier != mask.identifier
+            || mask.threshold != 2 * randomness.threshold - 2
+        {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "The mask must be shared with threshold 2t - 2 for the same participant"
+                    .to_string(),
+            }
+            .into());
+        }
+        // identifier * mask is a share of a degree 2t - 2 polynomial that is zero at 0
+        let mut zero = FrShare::from_usize(mask.identifier).0;
+        zero.mul_assign(&mask.value);
+        Ok(Self {
+            identifier: randomness.identifier,
+            threshold: randomness.threshold,
+            r: randomness.value,
+            zero,
+        })
+    }
+}
+
+impl PartialSignature {
+    /// Create this participant's part of the signature on `messages`.
+    /// All participants must use the same `session` and presignatures from the same
+    /// distributed key generation. `session` must be unique for each signature.
+    pub fn new(
+        messages: &[SignatureMessage],
+        key: &SecretShare,
+        presignature: PresignatureShare,
+        verkey: &PublicKey,
+        session: &[u8],
+    ) -> Result<Self, BBSError> {
+        if messages.len() != verkey.message_count() {
+            return Err(BBSErrorKind::PublicKeyGeneratorMessageCountMismatch(
+                verkey.message_count(),
+                messages.len(),
+            )
+            .into());
+        }
+        if key.identifier != presignature.identifier || key.threshold != presignature.threshold {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "The presignature is for another participant".to_string(),
+            }
+            .into());
+        }
+        let (e, s) = derive_e_s(messages, session);
+
+        let mut r_b = Signature::compute_b(&s, messages, verkey);
+        r_b.mul_assign(presignature.r);
+
+        // r_i * (x_i + e) + zero_i
+        let mut u = key.value;
+        u.add_assign(&e);
+        u.mul_assign(&presignature.r);
+        u.add_assign(&presignature.zero);
+        Ok(Self {
+            identifier: key.identifier,
+            r_b,
+            u,
+        })
+    }
+
+    /// The participant's identifier
+    pub fn identifier(&self) -> usize {
+        self.identifier
+    }
+
+    /// Combine 2 * `key_threshold` - 1 or more partial signatures into a signature
+    /// that verifies with `verkey`. `key_threshold` is the key threshold t, which must be at least 2.
+    pub fn combine(
+        partials: &[PartialSignature],
+        key_threshold: usize,
+        messages: &[SignatureMessage],
+        verkey: &PublicKey,
+        session: &[u8],
+    ) -> Result<Signature, BBSError> {
+        if key_threshold < 2 {
+            return Err(sharing_error(SharingError::ShareMinThreshold));
+        }
+        let needed = 2 * key_threshold - 1;
+        if partials.len() < needed {
+            return Err(BBSErrorKind::GeneralError {
+                msg: format!(
+                    "{} partial signatures are needed but only {} were given",
+                    needed,
+                    partials.len()
+                ),
+            }
+            .into());
+        }
+        let u_shares = partials
+            .iter()
+            .map(|p| Share::new(p.identifier, FrShare(p.u).to_bytes()))
+            .collect::<Vec<Share>>();
+        let b_shares = partials
+            .iter()
+            .map(|p| Share::new(p.identifier, G1Share(p.r_b).to_bytes()))
+            .collect::<Vec<Share>>();
+
+        // u = r * (x + e) and B^r
+        let u = ShamirScheme::new(needed, partials.len())
+            .map_err(sharing_error)?
+            .combine_shares::<FrShare, FrShare>(&u_shares)
+            .map_err(sharing_error)?;
+        let r_b = ShamirScheme::new(key_threshold, partials.len())
+            .map_err(sharing_error)?
+            .combine_shares::<FrShare, G1Share>(&b_shares)
+            .map_err(sharing_error)?;
+
+        // A = B^(r / (r * (x + e)))
+        let mut a = r_b.0;
+        match u.0.inverse() {
+            Some(u_inv) => a.mul_assign(u_inv),
+            None => return Err(BBSErrorKind::MalformedSignature.into()),
+        };
+        let (e, s) = derive_e_s(messages, session);
+        let signature = Signature { a, e, s };
+        if signature.verify(messages, verkey)? {
+            Ok(signature)
+        } else {
+            Err(BBSErrorKind::GeneralError {
+                msg: "The partial signatures do not combine into a valid signature".to_string(),
+            }
+            .into())
+        }
+    }
+}
+
+/// All participants compute the same e and s from the session and the messages
+fn derive_e_s(messages: &[SignatureMessage], session: &[u8]) -> (Fr, Fr) {
+    let mut data = Vec::new();
+    data.extend_from_slice(&(session.len() as u32).to_be_bytes());
+    data.extend_from_slice(session);
+    for m in messages {
+        data.extend_from_slice(&m.to_bytes_compressed_form());
+    }
+    let mut e_data = b"BBS+ threshold e".to_vec();
+    e_data.extend_from_slice(&data);
+    let mut s_data = b"BBS+ threshold s".to_vec();
+    s_data.extend_from_slice(&data);
+    (hash_to_fr(e_data), hash_to_fr(s_data))
+}
+
+/// Signing needs 2t - 1 of the `limit` participants
+fn check_key_threshold(key_threshold: usize, limit: usize) -> Result<(), BBSError> {
+    if key_threshold < 2 {
+        return Err(sharing_error(SharingError::ShareMinThreshold));
+    }
+    if 2 * key_threshold - 1 > limit {
+        return Err(BBSErrorKind::GeneralError {
+            msg: format!(
+                "Signing with key threshold {} needs {} participants but there are only {}",
+                key_threshold,
+                2 * key_threshold - 1,
+                limit
+            ),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+fn dkg_h() -> G1Share {
+    G1Share(hash_to_g1(DKG_BLINDING_GENERATOR))
+}
+
+fn sharing_error(e: SharingError) -> BBSError {
+    BBSErrorKind::GeneralError {
+        msg: format!("Secret sharing failed: {}", e),
+    }
+    .into()
+}
+
+/// Fr for the sharing schemes
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FrShare(Fr);
+
+impl Field for FrShare {
+    fn one() -> Self {
+        Self(Fr::one())
+    }
+
+    fn from_usize(value: usize) -> Self {
+        Self(Fr::from_repr(FrRepr::from(value as u64)).unwrap())
+    }
+
+    fn scalar_div_assign(&mut self, rhs: &Self) {
+        self.0.mul_assign(&rhs.0.inverse().unwrap());
+    }
+}
+
+impl Group for FrShare {
+    type Size = U32;
+
+    fn zero() -> Self {
+        Self(Fr::zero())
+    }
+
+    fn from_bytes<B: AsRef<[u8]>>(value: B) -> SharingResult<Self> {
+        Fr::deserialize(&mut value.as_ref(), true)
+            .map(Self)
+            .map_err(|_| SharingError::ShareInvalidValue)
+    }
+
+    fn random(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        Self(Fr::random(rng))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    fn is_valid(&self) -> bool {
+        !self.0.is_zero()
+    }
+
+    fn negate(&mut self) {
+        self.0.negate();
+    }
+
+    fn add_assign(&mut self, rhs: &Self) {
+        self.0.add_assign(&rhs.0);
+    }
+
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.0.sub_assign(&rhs.0);
+    }
+
+    fn scalar_mul_assign(&mut self, rhs: &Self) {
+        self.0.mul_assign(&rhs.0);
+    }
+
+    fn to_bytes(&self) -> GenericArray<u8, Self::Size> {
+        let mut r = GenericArray::default();
+        self.0.serialize(&mut r.as_mut_slice(), true).unwrap();
+        r
+    }
+}
+
+/// G1 for the sharing schemes
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct G1Share(G1);
+
+impl Group<FrShare> for G1Share {
+    type Size = U48;
+
+    fn zero() -> Self {
+        Self(G1::zero())
+    }
+
+    fn from_bytes<B: AsRef<[u8]>>(value: B) -> SharingResult<Self> {
+        G1::deserialize(&mut value.as_ref(), true)
+            .map(Self)
+            .map_err(|_| SharingError::InvalidPoint)
+    }
+
+    fn random(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        Self(G1::random(rng))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    fn is_valid(&self) -> bool {
+        !self.0.is_zero()
+    }
+
+    fn negate(&mut self) {
+        self.0.negate();
+    }
+
+    fn add_assign(&mut self, rhs: &Self) {
+        self.0.add_assign(&rhs.0);
+    }
+
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.0.sub_assign(&rhs.0);
+    }
+
+    fn scalar_mul_assign(&mut self, rhs: &FrShare) {
+        self.0.mul_assign(rhs.0);
+    }
+
+    fn to_bytes(&self) -> GenericArray<u8, Self::Size> {
+        let mut r = GenericArray::default();
+        self.0.serialize(&mut r.as_mut_slice(), true).unwrap();
+        r
+    }
+}
+
+/// G2 for the sharing schemes
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct G2Share(G2);
+
+impl Group<FrShare> for G2Share {
+    type Size = U96;
+
+    fn zero() -> Self {
+        Self(G2::zero())
+    }
+
+    fn from_bytes<B: AsRef<[u8]>>(value: B) -> SharingResult<Self> {
+        G2::deserialize(&mut value.as_ref(), true)
+            .map(Self)
+            .map_err(|_| SharingError::InvalidPoint)
+    }
+
+    fn random(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        Self(G2::random(rng))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    fn is_valid(&self) -> bool {
+        !self.0.is_zero()
+    }
+
+    fn negate(&mut self) {
+        self.0.negate();
+    }
+
+    fn add_assign(&mut self, rhs: &Self) {
+        self.0.add_assign(&rhs.0);
+    }
+
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.0.sub_assign(&rhs.0);
+    }
+
+    fn scalar_mul_assign(&mut self, rhs: &FrShare) {
+        self.0.mul_assign(rhs.0);
+    }
+
+    fn to_bytes(&self) -> GenericArray<u8, Self::Size> {
+        let mut r = GenericArray::default();
+        self.0.serialize(&mut r.as_mut_slice(), true).unwrap();
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HashElem;
+
+    /// Run the distributed key generation for all `limit` participants
+    fn dkg(threshold: usize, limit: usize, mask: bool) -> Vec<SecretShare> {
+        let participants = (1..=limit)
+            .map(|i| {
+                if mask {
+                    DkgParticipant::new_mask(i, threshold, limit).unwrap()
+                } else {
+                    DkgParticipant::new(i, threshold, limit).unwrap()
+                }
+            })
+            .collect::<Vec<DkgParticipant>>();
+        let commitments = participants
+            .iter()
+            .map(|p| p.commitment())
+            .collect::<Vec<DkgCommitment>>();
+        let all_shares = participants
+            .iter()
+            .map(|p| {
+                (1..=limit)
+                    .map(|j| p.share_for(j).unwrap())
+                    .collect::<Vec<DkgShare>>()
+            })
+            .collect::<Vec<Vec<DkgShare>>>();
+        participants
+            .into_iter()
+            .enumerate()
+            .map(|(j, p)| {
+                let received = commitments
+                    .iter()
+                    .cloned()
+                    .zip(all_shares.iter().map(|s| s[j].clone()))
+                    .collect::<Vec<(DkgCommitment, DkgShare)>>();
+                p.finish(&received).unwrap()
+            })
+            .collect()
+    }
+
+    fn presignatures(threshold: usize, limit: usize) -> Vec<PresignatureShare> {
+        dkg(threshold, limit, false)
+            .iter()
+            .zip(dkg(threshold, limit, true).iter())
+            .map(|(r, m)| PresignatureShare::new(r, m).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn threshold_signature() {
+        let (threshold, limit) = (2, 4);
+        let keys = dkg(threshold, limit, false);
+
+        // Any t public shares give the same key
+        let public_shares = keys.iter().map(|k| k.public_share()).collect::<Vec<_>>();
+        let dpk = PublicKeyShare::combine(&public_shares[..threshold], threshold).unwrap();
+        assert_eq!(
+            dpk,
+            PublicKeyShare::combine(&public_shares[limit - threshold..], threshold).unwrap()
+        );
+        let pk = dpk.to_public_key(3).unwrap();
+
+        let messages = vec![
+            SignatureMessage::hash(b"message_1"),
+            SignatureMessage::hash(b"message_2"),
+            SignatureMessage::hash(b"message_3"),
+        ];
+        let session = b"signature 1";
+        // Participants 2, 3, 4 sign
+        let partials = keys
+            .iter()
+            .zip(presignatures(threshold, limit))
+            .skip(1)
+            .map(|(k, p)| PartialSignature::new(&messages, k, p, &pk, session).unwrap())
+            .collect::<Vec<PartialSignature>>();
+        let signature =
+            PartialSignature::combine(&partials, threshold, &messages, &pk, session).unwrap();
+        assert!(signature.verify(&messages, &pk).unwrap());
+
+        // Not enough partial signatures
+        assert!(
+            PartialSignature::combine(&partials[1..], threshold, &messages, &pk, session).is_err()
+        );
+        // Different messages or session
+        assert!(PartialSignature::combine(&partials, threshold, &messages, &pk, b"other").is_err());
+        // A threshold below 2 is rejected instead of underflowing
+        assert!(PartialSignature::combine(&partials, 0, &messages, &pk, session).is_err());
+        assert!(PartialSignature::combine(&partials, 1, &messages, &pk, session).is_err());
+    }
+
+    #[test]
+    fn signing_needs_2t_minus_1_participants() {
+        // A key threshold of 3 needs all 5 participants to sign
+        let (threshold, limit) = (3, 5);
+        let keys = dkg(threshold, limit, false);
+        let pk = PublicKeyShare::combine(
+            &keys.iter().map(|k| k.public_share()).collect::<Vec<_>>(),
+            threshold,
+        )
+        .unwrap()
+        .to_public_key(1)
+        .unwrap();
+        let messages = vec![SignatureMessage::hash(b"message")];
+        let partials = keys
+            .iter()
+            .zip(presignatures(threshold, limit))
+            .map(|(k, p)| PartialSignature::new(&messages, k, p, &pk, b"session").unwrap())
+            .collect::<Vec<PartialSignature>>();
+        let signature =
+            PartialSignature::combine(&partials, threshold, &messages, &pk, b"session").unwrap();
+        assert!(signature.verify(&messages, &pk).unwrap());
+        assert!(
+            PartialSignature::combine(&partials[1..], threshold, &messages, &pk, b"session")
+                .is_err()
+        );
+
+        // 2t - 1 participants are required when the key generation starts
+        assert!(DkgParticipant::new(1, 2, 2).is_err());
+        assert!(DkgParticipant::new_mask(1, 2, 2).is_err());
+        assert!(DkgParticipant::new(1, 3, 4).is_err());
+        assert!(DkgParticipant::new(1, 1, 3).is_err());
+        assert!(DkgParticipant::new(1, 2, 3).is_ok());
+        assert!(DkgParticipant::new_mask(1, 2, 3).is_ok());
+    }
+
+    #[test]
+    fn dkg_rejects_bad_shares() {
+        let (threshold, limit) = (2, 3);
+        let participants = (1..=limit)
+            .map(|i| DkgParticipant::new(i, threshold, limit).unwrap())
+            .collect::<Vec<DkgParticipant>>();
+        let mut received = participants
+            .iter()
+            .map(|p| (p.commitment(), p.share_for(1).unwrap()))
+            .collect::<Vec<(DkgCommitment, DkgShare)>>();
+        // Share from participant 2 with the commitment from participant 3
+        received[1].0 = participants[2].commitment();
+        received[1].0.sender = 2;
+        let mut participants = participants.into_iter();
+        let first = participants.next().unwrap();
+        assert!(first.finish(&received).is_err());
+
+        // Missing participant
+        let second = participants.next().unwrap();
+        let received = vec![(second.commitment(), second.share_for(2).unwrap())];
+        assert!(second.finish(&received).is_err());
+    }
+}
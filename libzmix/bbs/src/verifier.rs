@@ -1,13 +1,19 @@
+use crate::designated::prelude::*;
 use crate::errors::prelude::*;
+use crate::ietf;
 use crate::keys::prelude::*;
+use crate::multi_proof::prelude::*;
 use crate::pok_sig::prelude::*;
+use crate::presentation::prelude::*;
+use crate::pseudonym::prelude::*;
 /// The verifier of a signature or credential asks for messages to be revealed from
 /// a prover and checks the signature proof of knowledge against a trusted issuer's public key.
 use crate::{
     HashElem, ProofChallenge, ProofNonce, ProofRequest, RandomElem, SignatureMessage,
     SignatureProof,
 };
-use std::collections::BTreeSet;
+use rand::{thread_rng, CryptoRng, RngCore};
+use std::collections::{BTreeMap, BTreeSet};
 
 /// This struct represents an Verifier of signatures.
 /// Provided are methods for generating a context to ask for revealed messages
@@ -37,6 +43,7 @@ impl Verifier {
         Ok(ProofRequest {
             revealed_messages,
             verification_key: verkey.clone(),
+            designated_verifier: None,
         })
     }
 
@@ -46,6 +53,28 @@ impl Verifier {
         signature_proof: &SignatureProof,
         nonce: &ProofNonce,
     ) -> Result<Vec<SignatureMessage>, BBSError> {
+        Self::verify_signature_pok_with_key(
+            proof_request,
+            &proof_request.verification_key,
+            signature_proof,
+            nonce,
+        )
+    }
+
+    /// Same as `verify_signature_pok` but checks the proof with `verkey` which
+    /// can be a `PreparedPublicKey` for the public key in `proof_request`
+    pub fn verify_signature_pok_with_key<K: VerificationKey>(
+        proof_request: &ProofRequest,
+        verkey: &K,
+        signature_proof: &SignatureProof,
+        nonce: &ProofNonce,
+    ) -> Result<Vec<SignatureMessage>, BBSError> {
+        if verkey.public_key() != &proof_request.verification_key {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "The public key does not match the proof request".to_string(),
+            }
+            .into());
+        }
         let mut challenge_bytes = signature_proof.proof.get_bytes_for_challenge(
             proof_request.revealed_messages.clone(),
             &proof_request.verification_key,
@@ -54,7 +83,7 @@ impl Verifier {
 
         let challenge_verifier = ProofChallenge::hash(&challenge_bytes);
         match signature_proof.proof.verify(
-            &proof_request.verification_key,
+            verkey,
             &signature_proof.revealed_messages,
             &challenge_verifier,
         )? {
@@ -67,11 +96,166 @@ impl Verifier {
         }
     }
 
+    /// Check a signature proof of knowledge over padded messages where the prover chose
+    /// which padded positions to reveal. `verkey` must be for the padded message count.
+    /// Returns the revealed messages by their padded position.
+    pub fn verify_padded_signature_pok(
+        verkey: &PublicKey,
+        signature_proof: &SignatureProof,
+        nonce: &ProofNonce,
+    ) -> Result<BTreeMap<usize, SignatureMessage>, BBSError> {
+        let revealed_message_indices = signature_proof
+            .revealed_messages
+            .keys()
+            .copied()
+            .collect::<Vec<usize>>();
+        let proof_request = Self::new_proof_request(&revealed_message_indices, verkey)?;
+        Self::verify_signature_pok(&proof_request, signature_proof, nonce)?;
+        Ok(signature_proof.revealed_messages.clone())
+    }
+
+    /// Check many signature proofs of knowledge at once. Faster than calling
+    /// `verify_signature_pok` for each proof. Returns the revealed messages for each proof
+    /// or the indices of the invalid proofs in `BBSErrorKind::BatchVerificationFailed`.
+    pub fn batch_verify_signature_pok(
+        items: &[(&ProofRequest, &SignatureProof, &ProofNonce)],
+    ) -> Result<Vec<Vec<SignatureMessage>>, BBSError> {
+        SignatureProof::batch_verify(items)
+    }
+
+    /// Same as `batch_verify_signature_pok` but the random linear combination comes from `rng`
+    pub fn batch_verify_signature_pok_with_rng<R: RngCore + CryptoRng>(
+        items: &[(&ProofRequest, &SignatureProof, &ProofNonce)],
+        rng: &mut R,
+    ) -> Result<Vec<Vec<SignatureMessage>>, BBSError> {
+        SignatureProof::batch_verify_with_rng(items, rng)
+    }
+
+    /// Check a selective disclosure proof for one of the draft ciphersuites.
+    /// `revealed_messages` are keyed by their 0 based index in the signed messages.
+    pub fn verify_ciphersuite_proof(
+        suite: ietf::Ciphersuite,
+        verkey: &DeterministicPublicKey,
+        proof: &ietf::proof::Proof,
+        header: &[u8],
+        presentation_header: &[u8],
+        revealed_messages: &BTreeMap<usize, SignatureMessage>,
+    ) -> Result<Vec<SignatureMessage>, BBSError> {
+        if proof.verify(
+            suite,
+            verkey,
+            header,
+            presentation_header,
+            revealed_messages,
+        )? {
+            Ok(revealed_messages.values().copied().collect())
+        } else {
+            Err(BBSErrorKind::GeneralError {
+                msg: "Invalid proof.".to_string(),
+            }
+            .into())
+        }
+    }
+
+    /// Create a key pair for receiving proofs that cannot be shown to anyone else
+    pub fn new_designated_verifier_keys(
+    ) -> (DesignatedVerifierPublicKey, DesignatedVerifierSecretKey) {
+        Self::new_designated_verifier_keys_with_rng(&mut thread_rng())
+    }
+
+    /// Same as `new_designated_verifier_keys` but the secret key is generated from `rng`
+    pub fn new_designated_verifier_keys_with_rng<R: RngCore + CryptoRng>(
+        rng: &mut R,
+    ) -> (DesignatedVerifierPublicKey, DesignatedVerifierSecretKey) {
+        let secret_key = DesignatedVerifierSecretKey::random_with_rng(rng);
+        (DesignatedVerifierPublicKey::new(&secret_key), secret_key)
+    }
+
+    /// Same as `new_proof_request` but the proof must be a `DesignatedVerifierProof`
+    /// for `designated_verifier`
+    pub fn new_designated_proof_request(
+        revealed_message_indices: &[usize],
+        verkey: &PublicKey,
+        designated_verifier: &DesignatedVerifierPublicKey,
+    ) -> Result<ProofRequest, BBSError> {
+        let mut proof_request = Self::new_proof_request(revealed_message_indices, verkey)?;
+        proof_request.designated_verifier = Some(*designated_verifier);
+        Ok(proof_request)
+    }
+
+    /// Check a designated verifier proof. Returns the revealed messages.
+    pub fn verify_designated_signature_pok(
+        proof_request: &ProofRequest,
+        proof: &DesignatedVerifierProof,
+        nonce: &ProofNonce,
+    ) -> Result<BTreeMap<usize, SignatureMessage>, BBSError> {
+        proof.verify(proof_request, nonce)
+    }
+
+    /// Check a proof of knowledge of several signatures and that the hidden messages
+    /// in each equivalence class are equal. Returns the revealed messages for each credential.
+    pub fn verify_multi_signature_pok(
+        proof_requests: &[ProofRequest],
+        proof: &MultiSignatureProof,
+        equalities: &[EquivalenceClass],
+        nonce: &ProofNonce,
+    ) -> Result<Vec<BTreeMap<usize, SignatureMessage>>, BBSError> {
+        proof.verify(proof_requests, equalities, nonce)
+    }
+
+    /// Check a signature proof of knowledge and that its pseudonym is derived for `scope`
+    /// from the hidden link secret. Returns the revealed messages.
+    pub fn verify_pseudonym_pok<I: AsRef<[u8]>>(
+        proof_request: &ProofRequest,
+        proof: &PseudonymProof,
+        link_secret_index: usize,
+        scope: I,
+        nonce: &ProofNonce,
+    ) -> Result<BTreeMap<usize, SignatureMessage>, BBSError> {
+        proof.verify(proof_request, link_secret_index, scope, nonce)
+    }
+
+    /// Create a presentation request with a fresh nonce
+    ///
+    /// # Arguments
+    /// * `credentials` - the requirements for each credential
+    /// * `equalities` - sets of `(credential index, message index)` with equal hidden messages
+    /// * `domain` - the verifier's domain which pseudonyms are derived for
+    pub fn new_presentation_request(
+        credentials: Vec<CredentialRequest>,
+        equalities: Vec<EquivalenceClass>,
+        domain: Option<String>,
+    ) -> Result<PresentationRequest, BBSError> {
+        let request = PresentationRequest {
+            version: PRESENTATION_REQUEST_VERSION,
+            nonce: ProofNonce::random(),
+            domain,
+            credentials,
+            equalities,
+        };
+        request.validate()?;
+        Ok(request)
+    }
+
+    /// Check a response satisfies a presentation request.
+    /// Returns the revealed messages for each credential.
+    pub fn verify_presentation(
+        request: &PresentationRequest,
+        response: &PresentationResponse,
+    ) -> Result<Vec<BTreeMap<usize, SignatureMessage>>, BBSError> {
+        response.verify(request)
+    }
+
     /// Create a nonce used for the proof request context
     pub fn generate_proof_nonce() -> ProofNonce {
         ProofNonce::random()
     }
 
+    /// Same as `generate_proof_nonce` but the nonce is generated from `rng`
+    pub fn generate_proof_nonce_with_rng<R: RngCore + CryptoRng>(rng: &mut R) -> ProofNonce {
+        ProofNonce::random_with_rng(rng)
+    }
+
     /// create the challenge hash for a set of proofs
     ///
     /// # Arguments
@@ -0,0 +1,343 @@
+use crate::errors::prelude::*;
+use crate::issuer::Issuer;
+use crate::keys::prelude::*;
+use crate::messages::*;
+use crate::prover::Prover;
+use crate::signature::prelude::*;
+use crate::verifier::Verifier;
+use crate::{
+    BlindSignatureContext, HashElem, ProofNonce, SignatureBlinding, SignatureMessage,
+    SignatureProof,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+/// The error object thrown to JavaScript.
+/// `name` is the `BBSErrorKind` variant and `message` is the error description.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmError {
+    /// The `BBSErrorKind` variant
+    pub name: String,
+    /// The error description
+    pub message: String,
+}
+
+impl From<BBSError> for WasmError {
+    fn from(err: BBSError) -> Self {
+        let kind = err.kind();
+        let name = match kind {
+            BBSErrorKind::KeyGenError => "KeyGenError",
+            BBSErrorKind::PublicKeyGeneratorMessageCountMismatch(..) => {
+                "PublicKeyGeneratorMessageCountMismatch"
+            }
+            BBSErrorKind::SignatureIncorrectSize(..) => "SignatureIncorrectSize",
+            BBSErrorKind::SignatureValueIncorrectSize => "SignatureValueIncorrectSize",
+            BBSErrorKind::MalformedSignature => "MalformedSignature",
+            BBSErrorKind::MalformedSecretKey => "MalformedSecretKey",
+            BBSErrorKind::MalformedPublicKey => "MalformedPublicKey",
+            BBSErrorKind::PoKVCError { .. } => "PoKVCError",
+            BBSErrorKind::InvalidNumberOfBytes(..) => "InvalidNumberOfBytes",
+            BBSErrorKind::InvalidProof { .. } => "InvalidProof",
+            BBSErrorKind::BatchVerificationFailed { .. } => "BatchVerificationFailed",
+            BBSErrorKind::GeneralError { .. } => "GeneralError",
+        };
+        Self {
+            name: name.to_string(),
+            message: kind.to_string(),
+        }
+    }
+}
+
+impl From<WasmError> for JsValue {
+    fn from(err: WasmError) -> Self {
+        serde_wasm_bindgen::to_value(&err)
+            .unwrap_or_else(|_| JsValue::from_str(err.message.as_str()))
+    }
+}
+
+/// A message known to the issuer at its index in the signature
+#[derive(Debug, Deserialize)]
+struct IndexedMessage {
+    index: usize,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyPair<P> {
+    public_key: P,
+    secret_key: SecretKey,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ShortPublicKeyRequest {
+    public_key: DeterministicPublicKey,
+    message_count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignRequest {
+    secret_key: SecretKey,
+    public_key: PublicKey,
+    messages: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyRequest {
+    public_key: PublicKey,
+    signature: Signature,
+    messages: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlindSignatureContextRequest {
+    public_key: PublicKey,
+    messages: Vec<IndexedMessage>,
+    nonce: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlindSignatureContextResponse {
+    context: BlindSignatureContext,
+    blinding: SignatureBlinding,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlindSignRequest {
+    context: BlindSignatureContext,
+    secret_key: SecretKey,
+    public_key: PublicKey,
+    messages: Vec<IndexedMessage>,
+    nonce: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompleteSignatureRequest {
+    public_key: PublicKey,
+    blind_signature: BlindSignature,
+    blinding: SignatureBlinding,
+    messages: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateProofRequest {
+    public_key: PublicKey,
+    signature: Signature,
+    messages: Vec<String>,
+    revealed: Vec<usize>,
+    nonce: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyProofRequest {
+    public_key: PublicKey,
+    proof: SignatureProof,
+    messages: Vec<IndexedMessage>,
+    nonce: String,
+}
+
+/// Create a keypair capable of signing `message_count` messages.
+/// Returns `{ publicKey, secretKey }`.
+#[wasm_bindgen(js_name = generateKeyPair)]
+pub fn generate_key_pair(message_count: usize) -> Result<JsValue, JsValue> {
+    let (public_key, secret_key) = generate(message_count).map_err(WasmError::from)?;
+    to_js(&KeyPair {
+        public_key,
+        secret_key,
+    })
+}
+
+/// Create a keypair with a short public key, from `seed` if it is given.
+/// Returns `{ publicKey, secretKey }`.
+#[wasm_bindgen(js_name = generateShortKeyPair)]
+pub fn generate_short_key_pair(seed: Option<Vec<u8>>) -> Result<JsValue, JsValue> {
+    let (public_key, secret_key) =
+        DeterministicPublicKey::new(seed.map(KeyGenOption::UseSeed)).map_err(WasmError::from)?;
+    to_js(&KeyPair {
+        public_key,
+        secret_key,
+    })
+}
+
+/// Convert `{ publicKey, messageCount }` where `publicKey` is a short public key
+/// to a public key capable of signing `messageCount` messages
+#[wasm_bindgen(js_name = shortToPublicKey)]
+pub fn short_to_public_key(request: JsValue) -> Result<JsValue, JsValue> {
+    let request: ShortPublicKeyRequest = from_js(request)?;
+    let public_key = request
+        .public_key
+        .to_public_key(request.message_count)
+        .map_err(WasmError::from)?;
+    to_js(&public_key)
+}
+
+/// Sign `{ secretKey, publicKey, messages }` where each message is a string.
+/// Returns the signature.
+#[wasm_bindgen]
+pub fn sign(request: JsValue) -> Result<JsValue, JsValue> {
+    let request: SignRequest = from_js(request)?;
+    let messages = hash_messages(&request.messages);
+    let signature = Signature::new(
+        messages.as_slice(),
+        &request.secret_key,
+        &request.public_key,
+    )
+    .map_err(WasmError::from)?;
+    to_js(&signature)
+}
+
+/// Verify `{ publicKey, signature, messages }` where each message is a string
+#[wasm_bindgen]
+pub fn verify(request: JsValue) -> Result<bool, JsValue> {
+    let request: VerifyRequest = from_js(request)?;
+    let messages = hash_messages(&request.messages);
+    Ok(request
+        .signature
+        .verify(messages.as_slice(), &request.public_key)
+        .map_err(WasmError::from)?)
+}
+
+/// Commit to the hidden messages in `{ publicKey, messages, nonce }`
+/// where `messages` is a list of `{ index, message }`.
+/// Returns `{ context, blinding }`. `context` is sent to the issuer and
+/// `blinding` is kept to complete the signature.
+#[wasm_bindgen(js_name = blindSignatureContext)]
+pub fn blind_signature_context(request: JsValue) -> Result<JsValue, JsValue> {
+    let request: BlindSignatureContextRequest = from_js(request)?;
+    let messages = hash_indexed_messages(&request.messages);
+    let nonce = ProofNonce::hash(&request.nonce);
+    let (context, blinding) =
+        Prover::new_blind_signature_context(&request.public_key, &messages, &nonce)
+            .map_err(WasmError::from)?;
+    to_js(&BlindSignatureContextResponse { context, blinding })
+}
+
+/// Verify the context and sign the known messages in
+/// `{ context, secretKey, publicKey, messages, nonce }`
+/// where `messages` is a list of `{ index, message }`.
+/// Returns the blind signature.
+#[wasm_bindgen(js_name = blindSign)]
+pub fn blind_sign(request: JsValue) -> Result<JsValue, JsValue> {
+    let request: BlindSignRequest = from_js(request)?;
+    let messages = hash_indexed_messages(&request.messages);
+    let nonce = ProofNonce::hash(&request.nonce);
+    let signature = Issuer::blind_sign(
+        &request.context,
+        &messages,
+        &request.secret_key,
+        &request.public_key,
+        &nonce,
+    )
+    .map_err(WasmError::from)?;
+    to_js(&signature)
+}
+
+/// Unblind `{ publicKey, blindSignature, blinding, messages }` where `messages`
+/// are all the signed messages. Returns the signature if it is valid.
+#[wasm_bindgen(js_name = completeSignature)]
+pub fn complete_signature(request: JsValue) -> Result<JsValue, JsValue> {
+    let request: CompleteSignatureRequest = from_js(request)?;
+    let messages = hash_messages(&request.messages);
+    let signature = Prover::complete_signature(
+        &request.public_key,
+        messages.as_slice(),
+        &request.blind_signature,
+        &request.blinding,
+    )
+    .map_err(WasmError::from)?;
+    to_js(&signature)
+}
+
+/// Create a proof from `{ publicKey, signature, messages, revealed, nonce }`
+/// that reveals the messages at the indices in `revealed` and hides all others.
+/// Returns the proof.
+#[wasm_bindgen(js_name = createProof)]
+pub fn create_proof(request: JsValue) -> Result<JsValue, JsValue> {
+    let request: CreateProofRequest = from_js(request)?;
+    let proof_request = Verifier::new_proof_request(&request.revealed, &request.public_key)
+        .map_err(WasmError::from)?;
+    let proof_messages = hash_messages(&request.messages)
+        .into_iter()
+        .enumerate()
+        .map(|(i, m)| {
+            if proof_request.revealed_messages.contains(&i) {
+                ProofMessage::Revealed(m)
+            } else {
+                ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(m))
+            }
+        })
+        .collect::<Vec<ProofMessage>>();
+    let nonce = ProofNonce::hash(&request.nonce);
+    let pok = Prover::commit_signature_pok(
+        &proof_request,
+        proof_messages.as_slice(),
+        &request.signature,
+    )
+    .map_err(WasmError::from)?;
+    let challenge = Prover::create_challenge_hash(std::slice::from_ref(&pok), None, &nonce)
+        .map_err(WasmError::from)?;
+    let proof = Prover::generate_signature_pok(pok, &challenge).map_err(WasmError::from)?;
+    to_js(&proof)
+}
+
+/// Verify `{ publicKey, proof, messages, nonce }` where `messages` is a list of
+/// `{ index, message }` that must be exactly the messages revealed by the proof
+#[wasm_bindgen(js_name = verifyProof)]
+pub fn verify_proof(request: JsValue) -> Result<bool, JsValue> {
+    let request: VerifyProofRequest = from_js(request)?;
+    let messages = hash_indexed_messages(&request.messages);
+    if messages != request.proof.revealed_messages {
+        return Ok(false);
+    }
+    let revealed = messages.keys().copied().collect::<Vec<usize>>();
+    let proof_request = Verifier::new_proof_request(revealed.as_slice(), &request.public_key)
+        .map_err(WasmError::from)?;
+    let nonce = ProofNonce::hash(&request.nonce);
+    match Verifier::verify_signature_pok(&proof_request, &request.proof, &nonce) {
+        Ok(_) => Ok(true),
+        Err(e) => match e.kind() {
+            BBSErrorKind::InvalidProof { .. } => Ok(false),
+            _ => Err(WasmError::from(e).into()),
+        },
+    }
+}
+
+fn hash_messages(messages: &[String]) -> Vec<SignatureMessage> {
+    messages.iter().map(SignatureMessage::hash).collect()
+}
+
+fn hash_indexed_messages(messages: &[IndexedMessage]) -> BTreeMap<usize, SignatureMessage> {
+    messages
+        .iter()
+        .map(|m| (m.index, SignatureMessage::hash(&m.message)))
+        .collect()
+}
+
+fn from_js<T: for<'a> Deserialize<'a>>(value: JsValue) -> Result<T, JsValue> {
+    serde_wasm_bindgen::from_value(value).map_err(|e| {
+        WasmError::from(BBSError::from(BBSErrorKind::GeneralError {
+            msg: format!("Invalid request: {}", e),
+        }))
+        .into()
+    })
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(value).map_err(|e| {
+        WasmError::from(BBSError::from(BBSErrorKind::GeneralError {
+            msg: format!("{}", e),
+        }))
+        .into()
+    })
+}
@@ -3,6 +3,7 @@ extern crate bbs;
 
 use bbs::prelude::*;
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
 
 #[test]
 fn keygen() {
@@ -129,6 +130,65 @@ fn blind_sign_simple() {
     assert!(res.is_ok());
 }
 
+#[test]
+fn prepared_public_key() {
+    let (pk, sk) = Issuer::new_keys(5).unwrap();
+    let prepared = PreparedPublicKey::new(pk.clone()).unwrap();
+    let signing_nonce = Issuer::generate_signing_nonce();
+
+    let link_secret = Prover::new_link_secret();
+    let mut messages = BTreeMap::new();
+    messages.insert(0, link_secret);
+    let (ctx, signature_blinding) =
+        Prover::new_blind_signature_context(&pk, &messages, &signing_nonce).unwrap();
+
+    let messages = sm_map![
+        1 => b"message_1",
+        2 => b"message_2",
+        3 => b"message_3",
+        4 => b"message_4"
+    ];
+    let blind_signature =
+        Issuer::blind_sign(&ctx, &messages, &sk, &prepared, &signing_nonce).unwrap();
+    let mut msgs = messages
+        .values()
+        .copied()
+        .collect::<Vec<SignatureMessage>>();
+    msgs.insert(0, link_secret);
+    let signature =
+        Prover::complete_signature(&pk, msgs.as_slice(), &blind_signature, &signature_blinding)
+            .unwrap();
+    assert!(signature.verify(msgs.as_slice(), &prepared).unwrap());
+
+    let nonce = Verifier::generate_proof_nonce();
+    let proof_request = Verifier::new_proof_request(&[1, 3], &pk).unwrap();
+    let proof_messages = vec![
+        pm_hidden_raw!(msgs[0]),
+        pm_revealed_raw!(msgs[1]),
+        pm_hidden_raw!(msgs[2]),
+        pm_revealed_raw!(msgs[3]),
+        pm_hidden_raw!(msgs[4]),
+    ];
+    let pok =
+        Prover::commit_signature_pok_with_key(&prepared, proof_messages.as_slice(), &signature)
+            .unwrap();
+    let challenge =
+        Prover::create_challenge_hash(std::slice::from_ref(&pok), None, &nonce).unwrap();
+    let proof = Prover::generate_signature_pok(pok, &challenge).unwrap();
+
+    assert_eq!(
+        Verifier::verify_signature_pok_with_key(&proof_request, &prepared, &proof, &nonce).unwrap(),
+        vec![msgs[1], msgs[3]]
+    );
+    assert!(Verifier::verify_signature_pok(&proof_request, &proof, &nonce).is_ok());
+
+    let (pk, _) = Issuer::new_keys(5).unwrap();
+    let other = PreparedPublicKey::new(pk).unwrap();
+    assert!(
+        Verifier::verify_signature_pok_with_key(&proof_request, &other, &proof, &nonce).is_err()
+    );
+}
+
 #[test]
 fn pok_sig() {
     let (pk, sk) = Issuer::new_keys(5).unwrap();
@@ -170,6 +230,49 @@ fn pok_sig() {
     };
 }
 
+#[test]
+fn pok_sig_not_equal() {
+    let (pk, sk) = Issuer::new_keys(3).unwrap();
+    let messages = vec![
+        SignatureMessage::hash(b"credential id"),
+        SignatureMessage::hash(b"message_2"),
+        SignatureMessage::hash(b"message_3"),
+    ];
+    let signature = Signature::new(messages.as_slice(), &sk, &pk).unwrap();
+
+    let nonce = Verifier::generate_proof_nonce();
+    let proof_request = Verifier::new_proof_request(&[1], &pk).unwrap();
+    let denied = SignatureMessage::hash(b"denied id");
+
+    // The hidden credential id is not the denied id
+    let proof_messages = vec![
+        pm_hidden!(b"credential id"),
+        pm_revealed!(b"message_2"),
+        pm_hidden!(b"message_3"),
+    ];
+    let mut pok =
+        Prover::commit_signature_pok(&proof_request, proof_messages.as_slice(), &signature)
+            .unwrap();
+    pok.prove_not_equal(0, &InequalityTarget::Value(denied))
+        .unwrap();
+    let challenge = Prover::create_challenge_hash(&[pok.clone()], None, &nonce).unwrap();
+    let proof = Prover::generate_signature_pok(pok, &challenge).unwrap();
+    let proof = SignatureProof::try_from(proof.to_bytes_compressed_form()).unwrap();
+
+    // The verifier checks the proof is about the denied id
+    assert_eq!(proof.proof.inequalities()[0].message_index, 0);
+    assert_eq!(proof.proof.inequalities()[0].value, Some(denied));
+    assert!(Verifier::verify_signature_pok(&proof_request, &proof, &nonce).is_ok());
+
+    // The id cannot be proven not equal to itself
+    let mut pok =
+        Prover::commit_signature_pok(&proof_request, proof_messages.as_slice(), &signature)
+            .unwrap();
+    assert!(pok
+        .prove_not_equal(0, &InequalityTarget::Value(messages[0]))
+        .is_err());
+}
+
 #[test]
 fn pok_sig_extra_message() {
     let (pk, sk) = Issuer::new_keys(5).unwrap();
@@ -695,3 +798,137 @@ fn bbs_demo() {
         proof2.proof.get_resp_for_message(3).unwrap()
     );
 }
+
+#[test]
+fn ciphersuite_sign_and_prove() {
+    use bbs::ietf::prelude::*;
+
+    for suite in [Ciphersuite::Bls12381Sha256, Ciphersuite::Bls12381Shake256].iter() {
+        let suite = *suite;
+        let (pk, sk) =
+            Issuer::new_ciphersuite_keys(suite, b"some very secret 32 byte key ikm", b"").unwrap();
+        let header = b"credential schema";
+        let messages =
+            messages_to_scalars(suite, &[&b"name"[..], b"date of birth", b"address", b"id"]);
+
+        // Issuer signs all messages
+        let signature = Issuer::ciphersuite_sign(suite, header, &messages, &sk, &pk).unwrap();
+
+        // Prover checks the signature
+        assert!(
+            Prover::verify_ciphersuite_signature(suite, &pk, header, &messages, &signature).is_ok()
+        );
+
+        // Verifier sends a nonce and the prover discloses the name and id
+        let nonce = Verifier::generate_proof_nonce().to_bytes_compressed_form();
+        let proof = Prover::generate_ciphersuite_proof(
+            suite,
+            &pk,
+            &signature,
+            header,
+            &nonce,
+            &messages,
+            &[0, 3],
+        )
+        .unwrap();
+
+        // Proof is sent as the draft octets
+        let proof = Proof::from_bytes_compressed_form(proof.to_bytes_compressed_form()).unwrap();
+
+        let mut revealed_messages = BTreeMap::new();
+        revealed_messages.insert(0, messages[0]);
+        revealed_messages.insert(3, messages[3]);
+        let revealed = Verifier::verify_ciphersuite_proof(
+            suite,
+            &pk,
+            &proof,
+            header,
+            &nonce,
+            &revealed_messages,
+        )
+        .unwrap();
+        assert_eq!(revealed, vec![messages[0], messages[3]]);
+
+        // A proof for a different nonce is rejected
+        assert!(Verifier::verify_ciphersuite_proof(
+            suite,
+            &pk,
+            &proof,
+            header,
+            b"old nonce",
+            &revealed_messages,
+        )
+        .is_err());
+    }
+}
+
+#[test]
+fn presentation() {
+    let (pk1, sk1) = Issuer::new_keys(3).unwrap();
+    let (pk2, sk2) = Issuer::new_keys(2).unwrap();
+    let link_secret = Prover::new_link_secret();
+
+    // Both credentials contain the link secret at index 0
+    let messages1 = vec![
+        link_secret,
+        SignatureMessage::hash(b"name"),
+        SignatureMessage::hash(b"date of birth"),
+    ];
+    let messages2 = vec![link_secret, SignatureMessage::hash(b"degree")];
+    let signature1 = Signature::new(messages1.as_slice(), &sk1, &pk1).unwrap();
+    let signature2 = Signature::new(messages2.as_slice(), &sk2, &pk2).unwrap();
+
+    // Verifier asks for the name and degree from the same holder with a pseudonym
+    let mut first = CredentialRequest::new(&pk1, &[1]);
+    first.predicates.insert(0, Predicate::Pseudonym);
+    // and the date of birth is not a known fake
+    first.predicates.insert(
+        2,
+        Predicate::NotEqual(SignatureMessage::hash(b"1900-01-01")),
+    );
+    let request = Verifier::new_presentation_request(
+        vec![first, CredentialRequest::new(&pk2, &[1])],
+        vec![[(0, 0), (1, 0)].iter().copied().collect()],
+        Some("example.com".to_string()),
+    )
+    .unwrap();
+
+    // Request is sent to the prover as bytes
+    let request =
+        PresentationRequest::try_from(request.to_bytes_compressed_form().as_slice()).unwrap();
+    let credentials = vec![
+        (signature1.clone(), messages1.clone()),
+        (signature2, messages2),
+    ];
+    let response = Prover::generate_presentation(&request, &credentials).unwrap();
+    let response =
+        PresentationResponse::try_from(response.to_bytes_compressed_form().as_slice()).unwrap();
+
+    let revealed = Verifier::verify_presentation(&request, &response).unwrap();
+    assert_eq!(revealed[0][&1], SignatureMessage::hash(b"name"));
+    assert_eq!(revealed[1][&1], SignatureMessage::hash(b"degree"));
+    assert_eq!(
+        response.pseudonyms[&(0, 0)],
+        Pseudonym::new(b"example.com", &link_secret)
+    );
+
+    // The response is bound to the request
+    let mut other = request.clone();
+    other.nonce = Verifier::generate_proof_nonce();
+    assert!(Verifier::verify_presentation(&other, &response).is_err());
+    let mut other = request.clone();
+    other.domain = Some("other.example.com".to_string());
+    assert!(Verifier::verify_presentation(&other, &response).is_err());
+    let mut other = request.clone();
+    other.credentials[0].predicates.remove(&2);
+    assert!(Verifier::verify_presentation(&other, &response).is_err());
+
+    // Credentials that do not share the link secret cannot satisfy the equality
+    let messages3 = vec![Prover::new_link_secret(), SignatureMessage::hash(b"degree")];
+    let signature3 = Signature::new(messages3.as_slice(), &sk2, &pk2).unwrap();
+    assert!(Prover::generate_presentation(
+        &request,
+        &[(signature1, messages1), (signature3, messages3)]
+    )
+    .is_err());
+}
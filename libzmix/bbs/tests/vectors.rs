@@ -0,0 +1,103 @@
+#[macro_use]
+extern crate bbs;
+
+use bbs::prelude::*;
+use rand_chacha::{rand_core::SeedableRng, ChaChaRng};
+
+const SEED: [u8; 32] = [7u8; 32];
+const SECRET_KEY: &str = "48cba59611a5cd4bd163610601d28d4c75ef1e98ec1a5d54f97e30a764fec0e0";
+const PUBLIC_KEY: &str = concat!(
+    "8f7cca5c4f9b6e1e2aa4f09423fb657c4a099d68dc1a3103e86bc2390fb20737f428cb953fc9673e",
+    "3f5c364dda6bca080771d5ce30dbec147323409bf9edccf3cb68a1cda0471232293214ae0b251eb1",
+    "f4db0ffa93feb1dbaa810517480afa58",
+);
+const SIGNATURE: &str = concat!(
+    "a5d69230500077059fc7ab35fbb6db19d50dfd9734292910ac99f7a48089d3f45010ac3a251c95e5",
+    "832d2af352f1f0424c581cca9678ffc99d0c9fa55d21aad824b1ffefd037705eff1ac9e7bcb26d9f",
+    "32b43e767ad95a852bf6ff0d9ccfa5631fd23b4858db9dd5175391a037c64613",
+);
+const NONCE: &str = "4ccb0a4e5c3e1448ebf340bf98f72ef5f70ea8c77b36eada5c6ec818455e60f9";
+const PROOF: &str = concat!(
+    "000001bc89c9a83da88693535a9c14610b22b035ead62801668aba848c412f691134b94f9b30f524",
+    "27699a749c4efe2b8448cf3895e2b80d7855876a4011d011d2e6adb5d8319441b9b11f389544cad9",
+    "dc85928b9e0bcd3c9571a9061b7b5a93a1c738ed93e04e9900907f5a19d6cf9b85a6e382a7736e5b",
+    "de42ea6b04f1150eb40a39668e8bd4c9e93020e77ff5ab1dd0861360000000748d0cce95273327ce",
+    "82d2312fd025c06bbc1031849da6d0c7f08b4aea52747fbd4424dd9e5caf2b96ea29d7b0368ac694",
+    "00000002504235f2cf33624ccc46fb859df85ce021f47e524531f7254add0477573cd1e932f99fbc",
+    "ae0b9ef2835605ffc9fab24ba3b415718e438fd9477fee3011422fc2927182603a4d738d0b978c0e",
+    "142e93675a31f4d29b05d4e5b4db444dba6e2680c3378ea3a2a79c38f0eaaeb5729534ec00000004",
+    "65d39437c075cc7fb5f81a14e14c3866d2ef3ea552b6f274a2eb62fe0fbbfc2d44f090769daf7319",
+    "d1006c3b40874268fcfbc672ca4f2ce2c3c1d1b7a3d3d816386bddb0f45161b760802f22201847be",
+    "a5782da6083c20a306d2e9b0eb61ca152a3497307b5cb70a45993820f5070f77b1ec82ff52f6ef07",
+    "7b86dc24c83b53df00000001000000011df29947c42faedf1c7dc7d9aab735dc3c62b8289df46c68",
+    "aad268b9fee01a69",
+);
+
+struct Vectors {
+    secret_key: String,
+    public_key: String,
+    signature: String,
+    nonce: String,
+    proof: String,
+}
+
+/// Run keygen, signing and a proof of knowledge using only the seeded rng
+fn replay(seed: [u8; 32]) -> Vectors {
+    let mut rng = ChaChaRng::from_seed(seed);
+    let (dpk, sk) = Issuer::new_short_keys_with_rng(None, &mut rng).unwrap();
+    let pk = dpk.to_public_key(3).unwrap();
+    let messages = vec![
+        SignatureMessage::hash(b"message 1"),
+        SignatureMessage::hash(b"message 2"),
+        SignatureMessage::hash(b"message 3"),
+    ];
+    let signature = Issuer::sign_with_rng(messages.as_slice(), &sk, &pk, &mut rng).unwrap();
+
+    let nonce = Verifier::generate_proof_nonce_with_rng(&mut rng);
+    let request = Verifier::new_proof_request(&[1], &pk).unwrap();
+    let proof_messages = vec![
+        pm_hidden_raw!(messages[0]),
+        pm_revealed_raw!(messages[1]),
+        pm_hidden_raw!(messages[2]),
+    ];
+    let pok = Prover::commit_signature_pok_with_rng(
+        &request,
+        proof_messages.as_slice(),
+        &signature,
+        &mut rng,
+    )
+    .unwrap();
+    let challenge =
+        Prover::create_challenge_hash(std::slice::from_ref(&pok), None, &nonce).unwrap();
+    let proof = Prover::generate_signature_pok(pok, &challenge).unwrap();
+    assert_eq!(
+        Verifier::verify_signature_pok(&request, &proof, &nonce).unwrap(),
+        vec![messages[1]]
+    );
+
+    Vectors {
+        secret_key: hex::encode(sk.to_bytes_compressed_form()),
+        public_key: hex::encode(dpk.to_bytes_compressed_form()),
+        signature: hex::encode(signature.to_bytes_compressed_form()),
+        nonce: hex::encode(nonce.to_bytes_compressed_form()),
+        proof: hex::encode(proof.to_bytes_compressed_form()),
+    }
+}
+
+#[test]
+fn replay_fixed_vectors() {
+    let v = replay(SEED);
+    assert_eq!(v.secret_key, SECRET_KEY);
+    assert_eq!(v.public_key, PUBLIC_KEY);
+    assert_eq!(v.signature, SIGNATURE);
+    assert_eq!(v.nonce, NONCE);
+    assert_eq!(v.proof, PROOF);
+}
+
+#[test]
+fn different_seeds_differ() {
+    let v = replay([8u8; 32]);
+    assert_ne!(v.secret_key, SECRET_KEY);
+    assert_ne!(v.signature, SIGNATURE);
+    assert_ne!(v.proof, PROOF);
+}
@@ -0,0 +1,266 @@
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use bbs::prelude::*;
+use bbs::wasm::*;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyPair {
+    public_key: PublicKey,
+    secret_key: SecretKey,
+}
+
+#[derive(Serialize)]
+struct IndexedMessage<'a> {
+    index: usize,
+    message: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignRequest<'a> {
+    secret_key: &'a SecretKey,
+    public_key: &'a PublicKey,
+    messages: &'a [&'a str],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyRequest<'a> {
+    public_key: &'a PublicKey,
+    signature: &'a Signature,
+    messages: &'a [&'a str],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateProofRequest<'a> {
+    public_key: &'a PublicKey,
+    signature: &'a Signature,
+    messages: &'a [&'a str],
+    revealed: &'a [usize],
+    nonce: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyProofRequest<'a> {
+    public_key: &'a PublicKey,
+    proof: &'a SignatureProof,
+    messages: &'a [IndexedMessage<'a>],
+    nonce: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlindSignatureContextRequest<'a> {
+    public_key: &'a PublicKey,
+    messages: &'a [IndexedMessage<'a>],
+    nonce: &'a str,
+}
+
+#[derive(Deserialize)]
+struct BlindSignatureContextResponse {
+    context: BlindSignatureContext,
+    blinding: SignatureBlinding,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlindSignRequest<'a> {
+    context: &'a BlindSignatureContext,
+    secret_key: &'a SecretKey,
+    public_key: &'a PublicKey,
+    messages: &'a [IndexedMessage<'a>],
+    nonce: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CompleteSignatureRequest<'a> {
+    public_key: &'a PublicKey,
+    blind_signature: &'a BlindSignature,
+    blinding: &'a SignatureBlinding,
+    messages: &'a [&'a str],
+}
+
+#[derive(Deserialize)]
+struct JsError {
+    name: String,
+}
+
+const MESSAGES: [&str; 3] = ["name", "age", "address"];
+
+fn to_js<T: Serialize>(value: &T) -> JsValue {
+    serde_wasm_bindgen::to_value(value).unwrap()
+}
+
+fn from_js<T: for<'a> Deserialize<'a>>(value: JsValue) -> T {
+    serde_wasm_bindgen::from_value(value).unwrap()
+}
+
+fn keys() -> KeyPair {
+    from_js(generate_key_pair(MESSAGES.len()).unwrap())
+}
+
+fn sign_messages(keys: &KeyPair) -> Signature {
+    from_js(
+        sign(to_js(&SignRequest {
+            secret_key: &keys.secret_key,
+            public_key: &keys.public_key,
+            messages: &MESSAGES,
+        }))
+        .unwrap(),
+    )
+}
+
+#[wasm_bindgen_test]
+fn sign_and_verify() {
+    let keys = keys();
+    let signature = sign_messages(&keys);
+
+    let valid = verify(to_js(&VerifyRequest {
+        public_key: &keys.public_key,
+        signature: &signature,
+        messages: &MESSAGES,
+    }))
+    .unwrap();
+    assert!(valid);
+
+    let valid = verify(to_js(&VerifyRequest {
+        public_key: &keys.public_key,
+        signature: &signature,
+        messages: &["name", "age", "phone"],
+    }))
+    .unwrap();
+    assert!(!valid);
+}
+
+#[wasm_bindgen_test]
+fn blind_sign_and_complete() {
+    let keys = keys();
+    let nonce = "issuer nonce";
+    let hidden = [IndexedMessage {
+        index: 0,
+        message: MESSAGES[0],
+    }];
+    let known = [
+        IndexedMessage {
+            index: 1,
+            message: MESSAGES[1],
+        },
+        IndexedMessage {
+            index: 2,
+            message: MESSAGES[2],
+        },
+    ];
+
+    let response: BlindSignatureContextResponse = from_js(
+        blind_signature_context(to_js(&BlindSignatureContextRequest {
+            public_key: &keys.public_key,
+            messages: &hidden,
+            nonce,
+        }))
+        .unwrap(),
+    );
+    let blind_signature: BlindSignature = from_js(
+        blind_sign(to_js(&BlindSignRequest {
+            context: &response.context,
+            secret_key: &keys.secret_key,
+            public_key: &keys.public_key,
+            messages: &known,
+            nonce,
+        }))
+        .unwrap(),
+    );
+    let signature: Signature = from_js(
+        complete_signature(to_js(&CompleteSignatureRequest {
+            public_key: &keys.public_key,
+            blind_signature: &blind_signature,
+            blinding: &response.blinding,
+            messages: &MESSAGES,
+        }))
+        .unwrap(),
+    );
+
+    let valid = verify(to_js(&VerifyRequest {
+        public_key: &keys.public_key,
+        signature: &signature,
+        messages: &MESSAGES,
+    }))
+    .unwrap();
+    assert!(valid);
+}
+
+#[wasm_bindgen_test]
+fn create_and_verify_proof() {
+    let keys = keys();
+    let signature = sign_messages(&keys);
+    let nonce = "verifier nonce";
+
+    let proof: SignatureProof = from_js(
+        create_proof(to_js(&CreateProofRequest {
+            public_key: &keys.public_key,
+            signature: &signature,
+            messages: &MESSAGES,
+            revealed: &[1],
+            nonce,
+        }))
+        .unwrap(),
+    );
+
+    let revealed = [IndexedMessage {
+        index: 1,
+        message: MESSAGES[1],
+    }];
+    let valid = verify_proof(to_js(&VerifyProofRequest {
+        public_key: &keys.public_key,
+        proof: &proof,
+        messages: &revealed,
+        nonce,
+    }))
+    .unwrap();
+    assert!(valid);
+
+    let valid = verify_proof(to_js(&VerifyProofRequest {
+        public_key: &keys.public_key,
+        proof: &proof,
+        messages: &revealed,
+        nonce: "another nonce",
+    }))
+    .unwrap();
+    assert!(!valid);
+
+    let wrong = [IndexedMessage {
+        index: 1,
+        message: "not the age",
+    }];
+    let valid = verify_proof(to_js(&VerifyProofRequest {
+        public_key: &keys.public_key,
+        proof: &proof,
+        messages: &wrong,
+        nonce,
+    }))
+    .unwrap();
+    assert!(!valid);
+}
+
+#[wasm_bindgen_test]
+fn errors_have_a_name() {
+    let keys = keys();
+    let err = sign(to_js(&SignRequest {
+        secret_key: &keys.secret_key,
+        public_key: &keys.public_key,
+        messages: &MESSAGES[..2],
+    }))
+    .unwrap_err();
+    let err: JsError = from_js(err);
+    assert_eq!(err.name, "PublicKeyGeneratorMessageCountMismatch");
+
+    let err = generate_key_pair(0).unwrap_err();
+    let err: JsError = from_js(err);
+    assert_eq!(err.name, "KeyGenError");
+}
@@ -8,6 +8,9 @@ extern crate arrayref;
 extern crate serde;
 extern crate serde_json;
 
+#[cfg(feature = "bbs")]
+#[macro_use]
+extern crate bbs;
 extern crate bulletproofs_amcl as bulletproofs;
 #[cfg(feature = "hash2curve")]
 pub extern crate hash2curve;
@@ -26,6 +29,8 @@ pub extern crate ursa;
 pub mod commitments;
 #[macro_use]
 pub mod errors;
+#[cfg(feature = "bbs")]
+pub mod predicates;
 pub mod signatures;
 #[cfg(feature = "ver_enc")]
 pub mod verifiable_encryption;
@@ -0,0 +1,714 @@
+// Predicate proofs on hidden BBS+ messages using the bulletproofs gadgets.
+//
+// bbs uses pairing-plus and bulletproofs uses amcl_wrapper but both are over BLS12-381 so
+// scalars are moved between them through their canonical byte encoding.
+//
+// For each hidden message m with a predicate the prover creates a Pedersen commitment
+// C = g^m * h^r in amcl's G1 and a Schnorr proof of the opening of C. The Schnorr proof uses the
+// same blinding for m as the signature proof of knowledge (`HiddenMessage::ExternalBlinding`)
+// and both are answered with the same challenge, so the responses for m are equal iff the
+// committed message is the signed message. C is then used as the committed value of the
+// bound check or set membership gadget.
+
+use amcl_wrapper::constants::FieldElement_SIZE;
+use amcl_wrapper::field_elem::FieldElement;
+use amcl_wrapper::group_elem::GroupElement;
+use amcl_wrapper::group_elem_g1::{G1Vector, G1};
+use bbs::prelude::*;
+use bulletproofs::r1cs::gadgets::bound_check::{prove_bounded_num, verify_bounded_num};
+use bulletproofs::r1cs::gadgets::set_membership::{prove_set_membership, verify_set_membership};
+use bulletproofs::r1cs::{Prover as R1CSProver, R1CSProof, Verifier as R1CSVerifier};
+use bulletproofs::utils::get_generators;
+use merlin::Transcript;
+use rand::rngs::ThreadRng;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Number of bits in values that are bound checked
+const MAX_BITS_IN_VAL: usize = 64;
+/// The largest bound allowed in a predicate. amcl_wrapper converts integers into a single
+/// 58 bit limb so the gadgets fail for anything larger.
+pub const MAX_PREDICATE_VALUE: u64 = (1 << 58) - 1;
+const TRANSCRIPT_LABEL: &[u8] = b"BBSPredicateProof";
+
+/// A statement about a hidden message
+#[derive(Clone, Debug, PartialEq)]
+pub enum Predicate {
+    /// The encoded message is in [value, MAX_PREDICATE_VALUE].
+    /// Bounds are encoded values like `SignatureMessage::from_date(2000, 1, 1)?.to_u64()?`
+    /// so they work for any integer encoding in `bbs::encoding`.
+    GreaterOrEqual(u64),
+    /// The encoded message is in [min, max].
+    /// `max` cannot exceed `MAX_PREDICATE_VALUE`.
+    Range(u64, u64),
+    /// The message is one of the messages in the set
+    SetMembership(Vec<SignatureMessage>),
+}
+
+impl Predicate {
+    fn bounds(&self) -> Option<(u64, u64)> {
+        match self {
+            Predicate::GreaterOrEqual(min) => Some((*min, MAX_PREDICATE_VALUE)),
+            Predicate::Range(min, max) => Some((*min, *max)),
+            Predicate::SetMembership(_) => None,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), BBSError> {
+        let valid = match self {
+            Predicate::SetMembership(set) => !set.is_empty(),
+            _ => {
+                let (min, max) = self.bounds().unwrap();
+                min <= max && max <= MAX_PREDICATE_VALUE
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(BBSErrorKind::GeneralError {
+                msg: format!("Invalid predicate {:?}", self),
+            }
+            .into())
+        }
+    }
+
+    /// Number of multiplication gates used by the gadget
+    fn multiplier_count(&self) -> usize {
+        match self {
+            Predicate::SetMembership(set) => set.len(),
+            _ => 2 * MAX_BITS_IN_VAL,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            Predicate::GreaterOrEqual(min) => {
+                bytes.push(0u8);
+                bytes.extend_from_slice(&min.to_be_bytes());
+            }
+            Predicate::Range(min, max) => {
+                bytes.push(1u8);
+                bytes.extend_from_slice(&min.to_be_bytes());
+                bytes.extend_from_slice(&max.to_be_bytes());
+            }
+            Predicate::SetMembership(set) => {
+                bytes.push(2u8);
+                bytes.extend_from_slice(&(set.len() as u32).to_be_bytes());
+                for m in set {
+                    bytes.extend_from_slice(&m.to_bytes_compressed_form());
+                }
+            }
+        }
+        bytes
+    }
+}
+
+/// The proof that a hidden message satisfies a `Predicate`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PredicateProof {
+    /// Pedersen commitment g^m * h^r to the hidden message
+    pub commitment: G1,
+    /// Schnorr commitment g^m~ * h^r~ for the opening of `commitment`
+    pub blinded_commitment: G1,
+    /// Schnorr response for r
+    pub resp_blinding: FieldElement,
+    /// The bulletproof for the gadget
+    pub proof: R1CSProof,
+    /// The commitments created by the gadget other than `commitment`
+    pub gadget_commitments: Vec<G1>,
+}
+
+/// A signature proof of knowledge combined with predicate proofs on its hidden messages
+#[derive(Clone, Debug)]
+pub struct SignatureProofWithPredicates {
+    /// The signature proof of knowledge
+    pub signature_proof: SignatureProof,
+    /// The predicate proofs keyed by message index
+    pub predicate_proofs: BTreeMap<usize, PredicateProof>,
+}
+
+impl SignatureProofWithPredicates {
+    /// Create a signature proof of knowledge where each message in `predicates` is hidden and
+    /// proven to satisfy its predicate. All proofs are bound to `nonce`.
+    pub fn new(
+        signature: &Signature,
+        verkey: &PublicKey,
+        proof_messages: &[ProofMessage],
+        predicates: &BTreeMap<usize, Predicate>,
+        nonce: &ProofNonce,
+    ) -> Result<Self, BBSError> {
+        let mut openings = BTreeMap::new();
+        for (i, predicate) in predicates {
+            let (message, blinding) = match proof_messages.get(*i) {
+                Some(ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(m))) => {
+                    (*m, ProofNonce::random())
+                }
+                Some(ProofMessage::Hidden(HiddenMessage::ExternalBlinding(m, b))) => (*m, *b),
+                Some(ProofMessage::Revealed(_)) => {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!("Message {} must be hidden to prove a predicate", i),
+                    }
+                    .into())
+                }
+                None => {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!("Message {} does not exist", i),
+                    }
+                    .into())
+                }
+            };
+            openings.insert(
+                *i,
+                (blinding, PredicateCommitment::new(predicate, message, &blinding)?),
+            );
+        }
+
+        let proof_messages = proof_messages
+            .iter()
+            .enumerate()
+            .map(|(i, pm)| match openings.get(&i) {
+                Some((b, o)) => pm_hidden_raw!(o.message, *b),
+                None => match pm {
+                    ProofMessage::Revealed(m) => pm_revealed_raw!(*m),
+                    ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(m)) => {
+                        pm_hidden_raw!(*m)
+                    }
+                    ProofMessage::Hidden(HiddenMessage::ExternalBlinding(m, b)) => {
+                        pm_hidden_raw!(*m, *b)
+                    }
+                },
+            })
+            .collect::<Vec<ProofMessage>>();
+        let pok = PoKOfSignature::init(signature, verkey, &proof_messages)?;
+
+        let commitments = openings
+            .iter()
+            .map(|(i, (_, o))| (*i, &o.commitment, &o.blinded_commitment))
+            .collect::<Vec<(usize, &G1, &G1)>>();
+        let challenge = compute_challenge(pok.to_bytes(), &commitments, predicates, nonce);
+
+        let mut revealed_messages = BTreeMap::new();
+        for (i, pm) in proof_messages.iter().enumerate() {
+            if let ProofMessage::Revealed(m) = pm {
+                revealed_messages.insert(i, *m);
+            }
+        }
+        let signature_proof = SignatureProof {
+            revealed_messages,
+            proof: pok.gen_proof(&challenge)?,
+        };
+
+        let mut predicate_proofs = BTreeMap::new();
+        for (i, (_, opening)) in openings {
+            let proof = opening.gen_proof(&predicates[&i], i, &challenge, nonce)?;
+            predicate_proofs.insert(i, proof);
+        }
+
+        Ok(Self {
+            signature_proof,
+            predicate_proofs,
+        })
+    }
+
+    /// Verify the signature proof of knowledge against `proof_request` and each of the `predicates`.
+    /// Returns the revealed messages.
+    pub fn verify(
+        &self,
+        proof_request: &ProofRequest,
+        predicates: &BTreeMap<usize, Predicate>,
+        nonce: &ProofNonce,
+    ) -> Result<BTreeMap<usize, SignatureMessage>, BBSError> {
+        let vk = &proof_request.verification_key;
+        let revealed = self
+            .signature_proof
+            .revealed_messages
+            .keys()
+            .copied()
+            .collect::<BTreeSet<usize>>();
+        if revealed != proof_request.revealed_messages {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Revealed messages do not match the proof request".to_string(),
+            }
+            .into());
+        }
+        if predicates.keys().ne(self.predicate_proofs.keys()) {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Predicate proofs do not match the predicates".to_string(),
+            }
+            .into());
+        }
+        for (i, predicate) in predicates {
+            if *i >= vk.message_count() || revealed.contains(i) {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Message {} must be hidden to prove a predicate", i),
+                }
+                .into());
+            }
+            predicate.validate()?;
+        }
+
+        let commitments = self
+            .predicate_proofs
+            .iter()
+            .map(|(i, p)| (*i, &p.commitment, &p.blinded_commitment))
+            .collect::<Vec<(usize, &G1, &G1)>>();
+        let challenge = compute_challenge(
+            self.signature_proof
+                .proof
+                .get_bytes_for_challenge(revealed.clone(), vk),
+            &commitments,
+            predicates,
+            nonce,
+        );
+        match self.signature_proof.proof.verify(
+            vk,
+            &self.signature_proof.revealed_messages,
+            &challenge,
+        )? {
+            PoKOfSignatureProofStatus::Success => {}
+            e => return Err(BBSErrorKind::InvalidProof { status: e }.into()),
+        }
+
+        for (i, predicate) in predicates {
+            // The response for the message in the signature proof opens the commitment
+            let hidden_index = i - revealed.range(..i).count();
+            let resp_message = self
+                .signature_proof
+                .proof
+                .get_resp_for_message(hidden_index)?;
+            self.predicate_proofs[i].verify(predicate, *i, &resp_message, &challenge, nonce)?;
+        }
+
+        Ok(self.signature_proof.revealed_messages.clone())
+    }
+}
+
+/// The Pedersen commitment to a hidden message and the Schnorr commitment for its opening
+/// before the challenge is known. `blinding` is the blinding for the message in the proof
+/// this predicate is linked to.
+pub(crate) struct PredicateCommitment {
+    pub(crate) message: SignatureMessage,
+    r: FieldElement,
+    r_blinding: FieldElement,
+    pub(crate) commitment: G1,
+    pub(crate) blinded_commitment: G1,
+}
+
+impl PredicateCommitment {
+    pub(crate) fn new(
+        predicate: &Predicate,
+        message: SignatureMessage,
+        blinding: &ProofNonce,
+    ) -> Result<Self, BBSError> {
+        check_predicate(predicate, &message)?;
+        let (g, h) = pedersen_generators();
+        let m = to_field_element(&message.to_bytes_compressed_form());
+        let m_blinding = to_field_element(&blinding.to_bytes_compressed_form());
+        let r = FieldElement::random();
+        let r_blinding = FieldElement::random();
+        Ok(Self {
+            message,
+            commitment: g.binary_scalar_mul(&h, &m, &r),
+            blinded_commitment: g.binary_scalar_mul(&h, &m_blinding, &r_blinding),
+            r,
+            r_blinding,
+        })
+    }
+
+    /// Create the bulletproof for the message at `index` and the response for `challenge`
+    pub(crate) fn gen_proof(
+        self,
+        predicate: &Predicate,
+        index: usize,
+        challenge: &ProofChallenge,
+        nonce: &ProofNonce,
+    ) -> Result<PredicateProof, BBSError> {
+        let (g, h) = pedersen_generators();
+        let (big_g, big_h) = bulletproof_generators(predicate);
+        let mut transcript = new_transcript(index, nonce);
+        let mut prover = R1CSProver::new(&g, &h, &mut transcript);
+        let m = to_field_element(&self.message.to_bytes_compressed_form());
+        let mut comms = match predicate {
+            Predicate::SetMembership(set) => prove_set_membership(
+                m,
+                Some(self.r.clone()),
+                &to_field_elements(set),
+                None::<&mut ThreadRng>,
+                &mut prover,
+            ),
+            _ => {
+                let (min, max) = predicate.bounds().unwrap();
+                prove_bounded_num(
+                    self.message.to_u64()?,
+                    Some(self.r.clone()),
+                    min,
+                    max,
+                    MAX_BITS_IN_VAL,
+                    None::<&mut ThreadRng>,
+                    &mut prover,
+                )
+            }
+        }
+        .map_err(r1cs_error)?;
+        let proof = prover.prove(&big_g, &big_h).map_err(r1cs_error)?;
+        // The first commitment is the committed message
+        comms.remove(0);
+
+        let c = to_field_element(&challenge.to_bytes_compressed_form());
+        Ok(PredicateProof {
+            commitment: self.commitment,
+            blinded_commitment: self.blinded_commitment,
+            resp_blinding: self.r_blinding - &c * &self.r,
+            proof,
+            gadget_commitments: comms,
+        })
+    }
+}
+
+impl PredicateProof {
+    /// Check the proof for the message at `index` where `resp_message` is the response
+    /// for the message in the proof this predicate is linked to
+    pub(crate) fn verify(
+        &self,
+        predicate: &Predicate,
+        index: usize,
+        resp_message: &SignatureMessage,
+        challenge: &ProofChallenge,
+        nonce: &ProofNonce,
+    ) -> Result<(), BBSError> {
+        let (g, h) = pedersen_generators();
+        let c = to_field_element(&challenge.to_bytes_compressed_form());
+        let resp_message = to_field_element(&resp_message.to_bytes_compressed_form());
+        let expected = g.binary_scalar_mul(&h, &resp_message, &self.resp_blinding)
+            + self.commitment.scalar_mul_const_time(&c);
+        if expected != self.blinded_commitment {
+            return Err(BBSErrorKind::InvalidProof {
+                status: PoKOfSignatureProofStatus::BadHiddenMessage,
+            }
+            .into());
+        }
+
+        let (big_g, big_h) = bulletproof_generators(predicate);
+        let mut transcript = new_transcript(index, nonce);
+        let mut verifier = R1CSVerifier::new(&mut transcript);
+        let mut comms = Vec::with_capacity(1 + self.gadget_commitments.len());
+        comms.push(self.commitment.clone());
+        comms.extend_from_slice(&self.gadget_commitments);
+        match predicate {
+            Predicate::SetMembership(set) => {
+                verify_set_membership(&to_field_elements(set), comms, &mut verifier)
+            }
+            _ => {
+                let (min, max) = predicate.bounds().unwrap();
+                verify_bounded_num(min, max, MAX_BITS_IN_VAL, comms, &mut verifier)
+            }
+        }
+        .map_err(r1cs_error)?;
+        verifier
+            .verify(&self.proof, &g, &h, &big_g, &big_h)
+            .map_err(r1cs_error)
+    }
+}
+
+/// Fail early instead of creating an invalid bulletproof
+fn check_predicate(predicate: &Predicate, message: &SignatureMessage) -> Result<(), BBSError> {
+    predicate.validate()?;
+    let satisfied = match predicate {
+        Predicate::SetMembership(set) => set.contains(message),
+        _ => {
+            let (min, max) = predicate.bounds().unwrap();
+            let value = message.to_u64()?;
+            min <= value && value <= max
+        }
+    };
+    if satisfied {
+        Ok(())
+    } else {
+        Err(BBSErrorKind::GeneralError {
+            msg: format!("Message does not satisfy {:?}", predicate),
+        }
+        .into())
+    }
+}
+
+fn compute_challenge(
+    mut bytes: Vec<u8>,
+    commitments: &[(usize, &G1, &G1)],
+    predicates: &BTreeMap<usize, Predicate>,
+    nonce: &ProofNonce,
+) -> ProofChallenge {
+    append_challenge_bytes(&mut bytes, commitments, predicates);
+    bytes.extend_from_slice(&nonce.to_bytes_compressed_form());
+    ProofChallenge::hash(&bytes)
+}
+
+/// Add the predicates and their commitments to the challenge bytes
+pub(crate) fn append_challenge_bytes(
+    bytes: &mut Vec<u8>,
+    commitments: &[(usize, &G1, &G1)],
+    predicates: &BTreeMap<usize, Predicate>,
+) {
+    for (i, c, t) in commitments {
+        bytes.extend_from_slice(&(*i as u32).to_be_bytes());
+        bytes.extend_from_slice(&predicates[i].to_bytes());
+        bytes.extend_from_slice(&c.to_bytes());
+        bytes.extend_from_slice(&t.to_bytes());
+    }
+}
+
+fn new_transcript(index: usize, nonce: &ProofNonce) -> Transcript {
+    let mut transcript = Transcript::new(TRANSCRIPT_LABEL);
+    transcript.append_message(b"index", &(index as u32).to_be_bytes());
+    transcript.append_message(b"nonce", &nonce.to_bytes_compressed_form());
+    transcript
+}
+
+fn pedersen_generators() -> (G1, G1) {
+    (
+        G1::from_msg_hash(b"BBSPredicateProof g"),
+        G1::from_msg_hash(b"BBSPredicateProof h"),
+    )
+}
+
+fn bulletproof_generators(predicate: &Predicate) -> (G1Vector, G1Vector) {
+    let n = predicate.multiplier_count().next_power_of_two();
+    (
+        get_generators("BBSPredicateProof G", n).into(),
+        get_generators("BBSPredicateProof H", n).into(),
+    )
+}
+
+fn to_field_element(bytes: &[u8; FR_COMPRESSED_SIZE]) -> FieldElement {
+    let mut padded = [0u8; FieldElement_SIZE];
+    padded[FieldElement_SIZE - FR_COMPRESSED_SIZE..].copy_from_slice(bytes);
+    FieldElement::from_bytes(&padded).unwrap()
+}
+
+fn to_field_elements(messages: &[SignatureMessage]) -> Vec<FieldElement> {
+    messages
+        .iter()
+        .map(|m| to_field_element(&m.to_bytes_compressed_form()))
+        .collect()
+}
+
+fn r1cs_error(e: bulletproofs::errors::R1CSError) -> BBSError {
+    BBSErrorKind::GeneralError {
+        msg: format!("Predicate proof failed: {:?}", e.kind()),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(messages: &[SignatureMessage]) -> (PublicKey, Signature) {
+        let (pk, sk) = Issuer::new_keys(messages.len()).unwrap();
+        let signature = Issuer::sign(messages, &sk, &pk).unwrap();
+        (pk, signature)
+    }
+
+    fn proof_messages(messages: &[SignatureMessage], revealed: &[usize]) -> Vec<ProofMessage> {
+        messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                if revealed.contains(&i) {
+                    pm_revealed_raw!(*m)
+                } else {
+                    pm_hidden_raw!(*m)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn prove_age_over_18() {
+        let messages = vec![
+            SignatureMessage::hash(b"name"),
+            SignatureMessage::from_u64(25),
+            SignatureMessage::hash(b"DE"),
+        ];
+        let (pk, signature) = setup(&messages);
+        let nonce = Verifier::generate_proof_nonce();
+        let proof_request = Verifier::new_proof_request(&[0], &pk).unwrap();
+
+        let mut predicates = BTreeMap::new();
+        predicates.insert(1, Predicate::GreaterOrEqual(18));
+        predicates.insert(
+            2,
+            Predicate::SetMembership(vec![
+                SignatureMessage::hash(b"FR"),
+                SignatureMessage::hash(b"DE"),
+                SignatureMessage::hash(b"NL"),
+            ]),
+        );
+
+        let proof = SignatureProofWithPredicates::new(
+            &signature,
+            &pk,
+            &proof_messages(&messages, &[0]),
+            &predicates,
+            &nonce,
+        )
+        .unwrap();
+        let revealed = proof.verify(&proof_request, &predicates, &nonce).unwrap();
+        assert_eq!(revealed[&0], messages[0]);
+
+        // Bound to the nonce
+        assert!(proof
+            .verify(
+                &proof_request,
+                &predicates,
+                &Verifier::generate_proof_nonce()
+            )
+            .is_err());
+
+        // Bound to the predicates
+        let mut other_predicates = predicates.clone();
+        other_predicates.insert(1, Predicate::GreaterOrEqual(21));
+        assert!(proof
+            .verify(&proof_request, &other_predicates, &nonce)
+            .is_err());
+        other_predicates.remove(&1);
+        assert!(proof
+            .verify(&proof_request, &other_predicates, &nonce)
+            .is_err());
+    }
+
+    #[test]
+    fn prove_range() {
+        let messages = vec![
+            SignatureMessage::from_u64(1_990),
+            SignatureMessage::hash(b"name"),
+        ];
+        let (pk, signature) = setup(&messages);
+        let nonce = Verifier::generate_proof_nonce();
+        let proof_request = Verifier::new_proof_request(&[], &pk).unwrap();
+
+        let mut predicates = BTreeMap::new();
+        predicates.insert(0, Predicate::Range(1_900, 2_000));
+        let proof = SignatureProofWithPredicates::new(
+            &signature,
+            &pk,
+            &proof_messages(&messages, &[]),
+            &predicates,
+            &nonce,
+        )
+        .unwrap();
+        assert!(proof.verify(&proof_request, &predicates, &nonce).is_ok());
+    }
+
+    #[test]
+    fn prove_date_after() {
+        let messages = vec![
+            SignatureMessage::from_date(1990, 6, 1).unwrap(),
+            SignatureMessage::hash(b"name"),
+        ];
+        let (pk, signature) = setup(&messages);
+        let nonce = Verifier::generate_proof_nonce();
+        let proof_request = Verifier::new_proof_request(&[], &pk).unwrap();
+
+        let min = SignatureMessage::from_date(1970, 1, 1)
+            .unwrap()
+            .to_u64()
+            .unwrap();
+        let mut predicates = BTreeMap::new();
+        predicates.insert(0, Predicate::GreaterOrEqual(min));
+        let proof = SignatureProofWithPredicates::new(
+            &signature,
+            &pk,
+            &proof_messages(&messages, &[]),
+            &predicates,
+            &nonce,
+        )
+        .unwrap();
+        assert!(proof.verify(&proof_request, &predicates, &nonce).is_ok());
+
+        // Born before 2000-01-01
+        let max = SignatureMessage::from_date(1999, 12, 31)
+            .unwrap()
+            .to_u64()
+            .unwrap();
+        predicates.insert(0, Predicate::Range(0, max));
+        assert!(SignatureProofWithPredicates::new(
+            &signature,
+            &pk,
+            &proof_messages(&messages, &[]),
+            &predicates,
+            &nonce,
+        )
+        .is_ok());
+
+        let min = SignatureMessage::from_date(2000, 1, 1)
+            .unwrap()
+            .to_u64()
+            .unwrap();
+        predicates.insert(0, Predicate::GreaterOrEqual(min));
+        assert!(SignatureProofWithPredicates::new(
+            &signature,
+            &pk,
+            &proof_messages(&messages, &[]),
+            &predicates,
+            &nonce,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn unsatisfied_predicates() {
+        let messages = vec![
+            SignatureMessage::from_u64(16),
+            SignatureMessage::hash(b"name"),
+        ];
+        let (pk, signature) = setup(&messages);
+        let nonce = Verifier::generate_proof_nonce();
+
+        let mut predicates = BTreeMap::new();
+        predicates.insert(0, Predicate::GreaterOrEqual(18));
+        assert!(SignatureProofWithPredicates::new(
+            &signature,
+            &pk,
+            &proof_messages(&messages, &[]),
+            &predicates,
+            &nonce,
+        )
+        .is_err());
+
+        // Not an integer
+        let mut predicates = BTreeMap::new();
+        predicates.insert(1, Predicate::Range(0, 10));
+        assert!(SignatureProofWithPredicates::new(
+            &signature,
+            &pk,
+            &proof_messages(&messages, &[]),
+            &predicates,
+            &nonce,
+        )
+        .is_err());
+
+        // Bound too large
+        let mut predicates = BTreeMap::new();
+        predicates.insert(0, Predicate::Range(0, u64::MAX));
+        assert!(SignatureProofWithPredicates::new(
+            &signature,
+            &pk,
+            &proof_messages(&messages, &[]),
+            &predicates,
+            &nonce,
+        )
+        .is_err());
+
+        // Revealed message
+        let mut predicates = BTreeMap::new();
+        predicates.insert(0, Predicate::Range(0, 20));
+        assert!(SignatureProofWithPredicates::new(
+            &signature,
+            &pk,
+            &proof_messages(&messages, &[0]),
+            &predicates,
+            &nonce,
+        )
+        .is_err());
+    }
+}
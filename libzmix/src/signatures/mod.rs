@@ -47,26 +47,10 @@ trait SignatureScheme {
 pub mod delg_cred_cdd;
 #[cfg(any(feature = "PS_Signature_G2", feature = "PS_Signature_G1"))]
 pub mod ps;
+#[cfg(feature = "bbs")]
+pub mod transcript;
 
-/// Convenience class for handling a new signature with committed messages by the User
-/// and the Signer. The User selects messages to be blinded that the Signer will include in the
-/// final signature. There are certain proofs that should be included in this exchange like
-/// a proof of knowledge for the committed values. The Signer should also use a cryptographically
-/// secure nonce. This nonce can be generated by the Signer, or agreed upon by the Signer and User.
-///
-/// `SignatureTranscript` handles all the steps associated with this process
-/// by making the process misuse resistant.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SignatureTranscript {
-    label: String,
-    state: SignatureTranscriptStep,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub enum SignatureTranscriptStep {
-    Offer(SignatureNonce),
-    Request(SignatureRequest),
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SignatureRequest {}
+#[cfg(feature = "bbs")]
+pub use self::transcript::{
+    SignatureOffer, SignatureRequest, SignatureTranscript, SignatureTranscriptStep,
+};
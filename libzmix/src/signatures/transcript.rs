@@ -0,0 +1,357 @@
+// Blind issuance of BBS+ signatures as a state machine.
+//
+// The signer offers a nonce, the holder commits to some messages with an
+// `ExtendedBlindSignatureContext` and proves predicates about them, the signer
+// verifies the request and signs the remaining messages, and the holder unblinds.
+// Each party keeps a `SignatureTranscript` which only allows the next step.
+
+use amcl_wrapper::group_elem_g1::G1;
+use bbs::prelude::*;
+use predicates::{append_challenge_bytes, Predicate, PredicateCommitment, PredicateProof};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Convenience class for handling a new signature with committed messages by the User
+/// and the Signer. The User selects messages to be blinded that the Signer will include in the
+/// final signature. There are certain proofs that should be included in this exchange like
+/// a proof of knowledge for the committed values. The Signer should also use a cryptographically
+/// secure nonce. This nonce can be generated by the Signer, or agreed upon by the Signer and User.
+///
+/// `SignatureTranscript` handles all the steps associated with this process
+/// by making the process misuse resistant.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignatureTranscript {
+    label: String,
+    state: SignatureTranscriptStep,
+}
+
+/// The current step of a `SignatureTranscript`
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SignatureTranscriptStep {
+    /// The Signer created the nonce and waits for a request
+    Offer(ProofNonce),
+    /// The User sent a request and waits for the blind signature
+    Request {
+        /// The signature blinding factor
+        blinding: SignatureBlinding,
+        /// The committed messages
+        messages: BTreeMap<usize, SignatureMessage>,
+    },
+    /// The Signer sent the blind signature
+    Issued,
+    /// The User unblinded and verified the signature
+    Complete(Signature),
+}
+
+/// Sent by the Signer to start the exchange
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignatureOffer {
+    /// Identifies the exchange and is bound to all proofs
+    pub label: String,
+    /// The signing nonce
+    pub nonce: ProofNonce,
+}
+
+/// Sent by the User with the committed messages and proofs about them
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignatureRequest {
+    /// The commitment to the messages and proof of knowledge of them
+    pub context: ExtendedBlindSignatureContext,
+    /// Predicate proofs on committed messages keyed by message index
+    pub predicate_proofs: BTreeMap<usize, PredicateProof>,
+}
+
+impl SignatureTranscript {
+    /// Start the exchange as the Signer
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            state: SignatureTranscriptStep::Offer(Issuer::generate_signing_nonce()),
+        }
+    }
+
+    /// The current step
+    pub fn state(&self) -> &SignatureTranscriptStep {
+        &self.state
+    }
+
+    /// The offer the Signer sends to the User
+    pub fn offer(&self) -> Result<SignatureOffer, BBSError> {
+        match &self.state {
+            SignatureTranscriptStep::Offer(nonce) => Ok(SignatureOffer {
+                label: self.label.clone(),
+                nonce: *nonce,
+            }),
+            _ => Err(invalid_step("offer")),
+        }
+    }
+
+    /// Answer `offer` as the User by committing to `messages` and proving each of the `predicates`
+    /// about them. Returns the User's transcript and the request to send to the Signer.
+    pub fn request(
+        offer: &SignatureOffer,
+        verkey: &PublicKey,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        predicates: &BTreeMap<usize, Predicate>,
+    ) -> Result<(Self, SignatureRequest), BBSError> {
+        let mut hidden = BTreeMap::new();
+        let mut openings = BTreeMap::new();
+        for (i, m) in messages {
+            match predicates.get(i) {
+                Some(predicate) => {
+                    let blinding = ProofNonce::random();
+                    openings.insert(*i, PredicateCommitment::new(predicate, *m, &blinding)?);
+                    hidden.insert(*i, HiddenMessage::ExternalBlinding(*m, blinding));
+                }
+                None => {
+                    hidden.insert(*i, HiddenMessage::ProofSpecificBlinding(*m));
+                }
+            }
+        }
+        if openings.len() != predicates.len() {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Predicates can only be proven on committed messages".to_string(),
+            }
+            .into());
+        }
+
+        let blinding = Signature::generate_blinding();
+        let committing = ExtendedBlindSignatureContextCommitting::new(verkey, &hidden, &blinding)?;
+        let commitments = openings
+            .iter()
+            .map(|(i, o)| (*i, &o.commitment, &o.blinded_commitment))
+            .collect::<Vec<_>>();
+        let challenge = compute_challenge(
+            committing.to_bytes(),
+            &offer.label,
+            &commitments,
+            predicates,
+            &offer.nonce,
+        );
+        let context = committing.gen_proof(&challenge)?;
+
+        let mut predicate_proofs = BTreeMap::new();
+        for (i, opening) in openings {
+            let proof = opening.gen_proof(&predicates[&i], i, &challenge, &offer.nonce)?;
+            predicate_proofs.insert(i, proof);
+        }
+
+        Ok((
+            Self {
+                label: offer.label.clone(),
+                state: SignatureTranscriptStep::Request {
+                    blinding,
+                    messages: messages.clone(),
+                },
+            },
+            SignatureRequest {
+                context,
+                predicate_proofs,
+            },
+        ))
+    }
+
+    /// Verify `request` as the Signer and sign the committed messages with `messages`.
+    /// `predicates` are the statements the Signer requires about committed messages.
+    pub fn issue(
+        &mut self,
+        request: &SignatureRequest,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        predicates: &BTreeMap<usize, Predicate>,
+        signkey: &SecretKey,
+        verkey: &PublicKey,
+    ) -> Result<BlindSignature, BBSError> {
+        let nonce = match &self.state {
+            SignatureTranscriptStep::Offer(nonce) => *nonce,
+            _ => return Err(invalid_step("issue")),
+        };
+        let context = &request.context;
+        if predicates.keys().ne(request.predicate_proofs.keys()) {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Predicate proofs do not match the predicates".to_string(),
+            }
+            .into());
+        }
+        let committed = predicates.keys().copied().collect::<BTreeSet<usize>>();
+        if !committed.is_subset(&context.committed_messages) {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Predicates can only be proven on committed messages".to_string(),
+            }
+            .into());
+        }
+        for predicate in predicates.values() {
+            predicate.validate()?;
+        }
+
+        let commitments = request
+            .predicate_proofs
+            .iter()
+            .map(|(i, p)| (*i, &p.commitment, &p.blinded_commitment))
+            .collect::<Vec<_>>();
+        let challenge = compute_challenge(
+            context.get_bytes_for_challenge(verkey)?,
+            &self.label,
+            &commitments,
+            predicates,
+            &nonce,
+        );
+        for (i, predicate) in predicates {
+            let resp_message = context.get_resp_for_message(*i)?;
+            request.predicate_proofs[i].verify(predicate, *i, &resp_message, &challenge, &nonce)?;
+        }
+        let blind_signature = Issuer::extended_blind_sign_with_challenge(
+            context, messages, signkey, verkey, &challenge,
+        )?;
+        self.state = SignatureTranscriptStep::Issued;
+        Ok(blind_signature)
+    }
+
+    /// Unblind the signature from the Signer as the User where `messages` are the
+    /// messages signed by the Signer
+    pub fn complete(
+        &mut self,
+        blind_signature: &BlindSignature,
+        messages: &BTreeMap<usize, SignatureMessage>,
+        verkey: &PublicKey,
+    ) -> Result<Signature, BBSError> {
+        let signature = match &self.state {
+            SignatureTranscriptStep::Request {
+                blinding,
+                messages: committed,
+            } => {
+                let mut all_messages = committed.clone();
+                for (i, m) in messages {
+                    if all_messages.insert(*i, *m).is_some() {
+                        return Err(BBSErrorKind::GeneralError {
+                            msg: format!("Message {} is committed by the holder", i),
+                        }
+                        .into());
+                    }
+                }
+                let all_messages = all_messages.values().copied().collect::<Vec<_>>();
+                Prover::complete_signature(
+                    verkey,
+                    all_messages.as_slice(),
+                    blind_signature,
+                    blinding,
+                )?
+            }
+            _ => return Err(invalid_step("complete")),
+        };
+        self.state = SignatureTranscriptStep::Complete(signature.clone());
+        Ok(signature)
+    }
+}
+
+fn compute_challenge(
+    mut bytes: Vec<u8>,
+    label: &str,
+    commitments: &[(usize, &G1, &G1)],
+    predicates: &BTreeMap<usize, Predicate>,
+    nonce: &ProofNonce,
+) -> ProofChallenge {
+    bytes.extend_from_slice(label.as_bytes());
+    append_challenge_bytes(&mut bytes, commitments, predicates);
+    bytes.extend_from_slice(&nonce.to_bytes_uncompressed_form()[..]);
+    ProofChallenge::hash(&bytes)
+}
+
+fn invalid_step(step: &str) -> BBSError {
+    BBSErrorKind::GeneralError {
+        msg: format!("Cannot {} in the current step of the transcript", step),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (PublicKey, SecretKey, BTreeMap<usize, SignatureMessage>) {
+        let (pk, sk) = Issuer::new_keys(4).unwrap();
+        let mut committed = BTreeMap::new();
+        committed.insert(0, Prover::new_link_secret());
+        committed.insert(2, SignatureMessage::from_u64(1990));
+        (pk, sk, committed)
+    }
+
+    fn issuer_messages(indices: &[usize]) -> BTreeMap<usize, SignatureMessage> {
+        indices
+            .iter()
+            .map(|i| (*i, SignatureMessage::hash(format!("message_{}", i))))
+            .collect()
+    }
+
+    #[test]
+    fn blind_issuance_with_predicates() {
+        let (pk, sk, committed) = setup();
+        let mut predicates = BTreeMap::new();
+        predicates.insert(2, Predicate::Range(1900, 2005));
+
+        let mut signer = SignatureTranscript::new("example credential");
+        let offer = signer.offer().unwrap();
+        let (mut holder, request) =
+            SignatureTranscript::request(&offer, &pk, &committed, &predicates).unwrap();
+
+        let messages = issuer_messages(&[1, 3]);
+        let blind_signature = signer
+            .issue(&request, &messages, &predicates, &sk, &pk)
+            .unwrap();
+        // The nonce cannot be used again
+        assert!(signer.offer().is_err());
+        assert!(signer
+            .issue(&request, &messages, &predicates, &sk, &pk)
+            .is_err());
+
+        let signature = holder.complete(&blind_signature, &messages, &pk).unwrap();
+        let all_messages = vec![committed[&0], messages[&1], committed[&2], messages[&3]];
+        assert!(signature.verify(all_messages.as_slice(), &pk).unwrap());
+        match holder.state() {
+            SignatureTranscriptStep::Complete(_) => {}
+            s => panic!("Unexpected step {:?}", s),
+        }
+    }
+
+    #[test]
+    fn blind_issuance_rejects_invalid_requests() {
+        let (pk, sk, committed) = setup();
+        let mut predicates = BTreeMap::new();
+        predicates.insert(2, Predicate::Range(1900, 2005));
+        let messages = issuer_messages(&[1, 3]);
+
+        let mut signer = SignatureTranscript::new("example credential");
+        let offer = signer.offer().unwrap();
+
+        // The committed message does not satisfy the predicate
+        let mut too_young = BTreeMap::new();
+        too_young.insert(2, Predicate::Range(2006, 2020));
+        assert!(SignatureTranscript::request(&offer, &pk, &committed, &too_young).is_err());
+
+        // The signer requires a predicate the holder did not prove
+        let (_, request) =
+            SignatureTranscript::request(&offer, &pk, &committed, &BTreeMap::new()).unwrap();
+        assert!(signer
+            .issue(&request, &messages, &predicates, &sk, &pk)
+            .is_err());
+
+        // The proof is bound to the offer
+        let other = SignatureTranscript::new("other credential")
+            .offer()
+            .unwrap();
+        let (_, request) =
+            SignatureTranscript::request(&other, &pk, &committed, &predicates).unwrap();
+        assert!(signer
+            .issue(&request, &messages, &predicates, &sk, &pk)
+            .is_err());
+
+        // The signer cannot overwrite a committed message
+        let (_, request) =
+            SignatureTranscript::request(&offer, &pk, &committed, &predicates).unwrap();
+        let overwrite = issuer_messages(&[1, 2, 3]);
+        assert!(signer
+            .issue(&request, &overwrite, &predicates, &sk, &pk)
+            .is_err());
+        assert!(signer
+            .issue(&request, &messages, &predicates, &sk, &pk)
+            .is_ok());
+    }
+}
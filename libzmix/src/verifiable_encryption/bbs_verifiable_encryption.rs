@@ -0,0 +1,482 @@
+// Copyright contributors to Hyperledger Ursa.
+// SPDX-License-Identifier: Apache-2.0
+
+/// Verifiable encryption of hidden BBS+ messages to an auditor using Camenisch-Shoup encryption.
+/// The messages are encrypted with `cs_verifiable_encryption` and the proof of correct encryption
+/// shares its challenge and message responses with the signature proof of knowledge.
+/// BBS+ responses are computed modulo the group order while the encryption proof needs responses
+/// over the integers so the prover computes integer responses s = m~ - c*m with a blinding m~
+/// that is much larger than c*m. The signature proof of knowledge uses m~ mod q as the
+/// blinding so its response for m is s mod q which the verifier checks.
+use super::bn::{BigNumber, BigNumberContext};
+use super::cs_verifiable_encryption::{
+    decrypt, encrypt_and_prove_phase_1, encrypt_and_prove_phase_2,
+    reconstruct_blindings_ciphertext, CSCiphertext, CSEncPrikey, CSEncPubkey,
+};
+use super::errors::prelude::*;
+use bbs::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The order of the BLS12-381 scalar field
+const GROUP_ORDER: &str = "73EDA753299D7D483339D80809A1D80553BDA402FFFE5BFEFFFFFFFF00000001";
+/// Bits in the integer blindings for encrypted messages. Enough to statistically hide the
+/// product of a 256 bit challenge and a 256 bit message.
+/// Verifiers reject integer responses with more bits since an honest response is less than the blinding.
+const BLINDING_BITS: usize = 256 + 256 + 128;
+
+/// A signature proof of knowledge where some hidden messages are encrypted to an auditor
+#[derive(Serialize, Deserialize)]
+pub struct SignatureProofWithEncryption {
+    /// The signature proof of knowledge
+    pub signature_proof: SignatureProof,
+    /// The encrypted messages in index order
+    pub ciphertext: CSCiphertext,
+    /// The commitment for the proof of correct encryption
+    pub blindings_ciphertext: CSCiphertext,
+    /// The response for the encryption randomness
+    pub resp_randomness: BigNumber,
+    /// The integer responses for the encrypted messages in index order
+    pub resp_messages: Vec<BigNumber>,
+}
+
+impl SignatureProofWithEncryption {
+    /// Create a signature proof of knowledge where the hidden messages at `encrypted` are
+    /// encrypted to `auditor_key`. `label` is bound to the ciphertext and must be given to the
+    /// auditor to decrypt it e.g. a description of the conditions for opening.
+    pub fn new(
+        signature: &Signature,
+        verkey: &PublicKey,
+        proof_messages: &[ProofMessage],
+        encrypted: &BTreeSet<usize>,
+        auditor_key: &CSEncPubkey,
+        label: &[u8],
+        nonce: &ProofNonce,
+    ) -> Result<Self, BBSError> {
+        let mut ctx = BigNumber::new_context().map_err(ursa_error)?;
+        let order = group_order()?;
+
+        let mut messages = Vec::with_capacity(encrypted.len());
+        let mut blindings = BTreeMap::new();
+        for i in encrypted {
+            let message = match proof_messages.get(*i) {
+                Some(ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(m))) => *m,
+                Some(ProofMessage::Hidden(HiddenMessage::ExternalBlinding(..))) => {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!("Message {} cannot use an external blinding", i),
+                    }
+                    .into())
+                }
+                Some(ProofMessage::Revealed(_)) => {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!("Message {} must be hidden to be encrypted", i),
+                    }
+                    .into())
+                }
+                None => {
+                    return Err(BBSErrorKind::GeneralError {
+                        msg: format!("Message {} does not exist", i),
+                    }
+                    .into())
+                }
+            };
+            messages.push(message);
+            blindings.insert(*i, BigNumber::rand(BLINDING_BITS).map_err(ursa_error)?);
+        }
+
+        let proof_messages = proof_messages
+            .iter()
+            .enumerate()
+            .map(|(i, pm)| match blindings.get(&i) {
+                Some(b) => Ok(pm_hidden_raw!(
+                    messages[encrypted.range(..i).count()],
+                    to_nonce(b, &order, &mut ctx)?
+                )),
+                None => Ok(match pm {
+                    ProofMessage::Revealed(m) => pm_revealed_raw!(*m),
+                    ProofMessage::Hidden(HiddenMessage::ProofSpecificBlinding(m)) => {
+                        pm_hidden_raw!(*m)
+                    }
+                    ProofMessage::Hidden(HiddenMessage::ExternalBlinding(m, b)) => {
+                        pm_hidden_raw!(*m, *b)
+                    }
+                }),
+            })
+            .collect::<Result<Vec<ProofMessage>, BBSError>>()?;
+        let pok = PoKOfSignature::init(signature, verkey, &proof_messages)?;
+
+        let messages = messages
+            .iter()
+            .map(|m| to_big_number(&m.to_bytes_compressed_form()))
+            .collect::<Result<Vec<BigNumber>, BBSError>>()?;
+        let blindings = blindings
+            .values()
+            .map(|b| b.try_clone())
+            .collect::<UrsaCryptoResult<Vec<BigNumber>>>()
+            .map_err(ursa_error)?;
+        let (ciphertext, blindings_ciphertext, r, r_tilde) =
+            encrypt_and_prove_phase_1(&messages, &blindings, label, auditor_key)
+                .map_err(ursa_error)?;
+
+        let challenge =
+            compute_challenge(pok.to_bytes(), &ciphertext, &blindings_ciphertext, nonce)?;
+        let c = to_big_number(&challenge.to_bytes_compressed_form())?;
+
+        let resp_randomness =
+            encrypt_and_prove_phase_2(&r, &r_tilde, &c, auditor_key, Some(&mut ctx))
+                .map_err(ursa_error)?;
+        let mut resp_messages = Vec::with_capacity(messages.len());
+        for (m, b) in messages.iter().zip(blindings.iter()) {
+            let resp = c
+                .mul(m, Some(&mut ctx))
+                .and_then(|cm| b.sub(&cm))
+                .map_err(ursa_error)?;
+            resp_messages.push(resp);
+        }
+
+        let mut revealed_messages = BTreeMap::new();
+        for (i, pm) in proof_messages.iter().enumerate() {
+            if let ProofMessage::Revealed(m) = pm {
+                revealed_messages.insert(i, *m);
+            }
+        }
+
+        Ok(Self {
+            signature_proof: SignatureProof {
+                revealed_messages,
+                proof: pok.gen_proof(&challenge)?,
+            },
+            ciphertext,
+            blindings_ciphertext,
+            resp_randomness,
+            resp_messages,
+        })
+    }
+
+    /// Verify the signature proof of knowledge against `proof_request` and that the messages at
+    /// `encrypted` are encrypted to `auditor_key` with `label`. Returns the revealed messages.
+    pub fn verify(
+        &self,
+        proof_request: &ProofRequest,
+        encrypted: &BTreeSet<usize>,
+        auditor_key: &CSEncPubkey,
+        label: &[u8],
+        nonce: &ProofNonce,
+    ) -> Result<BTreeMap<usize, SignatureMessage>, BBSError> {
+        let vk = &proof_request.verification_key;
+        let revealed = self
+            .signature_proof
+            .revealed_messages
+            .keys()
+            .copied()
+            .collect::<BTreeSet<usize>>();
+        if revealed != proof_request.revealed_messages {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Revealed messages do not match the proof request".to_string(),
+            }
+            .into());
+        }
+        if encrypted.len() != self.resp_messages.len() || encrypted.len() != self.ciphertext.e.len()
+        {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Encrypted messages do not match the ciphertext".to_string(),
+            }
+            .into());
+        }
+        for i in encrypted {
+            if *i >= vk.message_count() || revealed.contains(i) {
+                return Err(BBSErrorKind::GeneralError {
+                    msg: format!("Message {} must be hidden to be encrypted", i),
+                }
+                .into());
+            }
+        }
+
+        let challenge = compute_challenge(
+            self.signature_proof
+                .proof
+                .get_bytes_for_challenge(revealed.clone(), vk),
+            &self.ciphertext,
+            &self.blindings_ciphertext,
+            nonce,
+        )?;
+        match self.signature_proof.proof.verify(
+            vk,
+            &self.signature_proof.revealed_messages,
+            &challenge,
+        )? {
+            PoKOfSignatureProofStatus::Success => {}
+            e => return Err(BBSErrorKind::InvalidProof { status: e }.into()),
+        }
+
+        // The integer responses must be in range and be the responses in the signature proof.
+        // Without the range check a response that differs by a multiple of q would be accepted.
+        let mut ctx = BigNumber::new_context().map_err(ursa_error)?;
+        let order = group_order()?;
+        for (i, resp) in encrypted.iter().zip(self.resp_messages.iter()) {
+            if resp.num_bits().map_err(ursa_error)? > BLINDING_BITS as i32 {
+                return Err(BBSErrorKind::InvalidProof {
+                    status: PoKOfSignatureProofStatus::BadHiddenMessage,
+                }
+                .into());
+            }
+            let hidden_index = i - revealed.range(..i).count();
+            let expected = self
+                .signature_proof
+                .proof
+                .get_resp_for_message(hidden_index)?;
+            if to_nonce(resp, &order, &mut ctx)?.to_bytes_compressed_form()
+                != expected.to_bytes_compressed_form()
+            {
+                return Err(BBSErrorKind::InvalidProof {
+                    status: PoKOfSignatureProofStatus::BadHiddenMessage,
+                }
+                .into());
+            }
+        }
+
+        let c = to_big_number(&challenge.to_bytes_compressed_form())?;
+        let blindings_ciphertext = reconstruct_blindings_ciphertext(
+            &self.ciphertext,
+            &self.resp_messages,
+            &self.resp_randomness,
+            &c,
+            label,
+            auditor_key,
+        )
+        .map_err(ursa_error)?;
+        if blindings_ciphertext.u != self.blindings_ciphertext.u
+            || blindings_ciphertext.e != self.blindings_ciphertext.e
+            || blindings_ciphertext.v != self.blindings_ciphertext.v
+        {
+            return Err(BBSErrorKind::GeneralError {
+                msg: "Invalid proof of encryption".to_string(),
+            }
+            .into());
+        }
+
+        Ok(self.signature_proof.revealed_messages.clone())
+    }
+}
+
+/// Decrypt the messages in a verified `SignatureProofWithEncryption`. The messages are returned
+/// in index order. Run by the auditor.
+pub fn decrypt_messages(
+    ciphertext: &CSCiphertext,
+    label: &[u8],
+    pub_key: &CSEncPubkey,
+    pri_key: &CSEncPrikey,
+) -> Result<Vec<SignatureMessage>, BBSError> {
+    let mut ctx = BigNumber::new_context().map_err(ursa_error)?;
+    let order = group_order()?;
+    decrypt(label, ciphertext, pub_key, pri_key)
+        .map_err(ursa_error)?
+        .iter()
+        .map(|m| {
+            let bytes = to_nonce(m, &order, &mut ctx)?.to_bytes_compressed_form();
+            Ok(SignatureMessage::from(&bytes))
+        })
+        .collect()
+}
+
+fn compute_challenge(
+    mut bytes: Vec<u8>,
+    ciphertext: &CSCiphertext,
+    blindings_ciphertext: &CSCiphertext,
+    nonce: &ProofNonce,
+) -> Result<ProofChallenge, BBSError> {
+    for c in &[ciphertext, blindings_ciphertext] {
+        let mut values = vec![&c.u];
+        values.extend(c.e.iter());
+        values.push(&c.v);
+        for v in values {
+            let v = v.to_bytes().map_err(ursa_error)?;
+            bytes.extend_from_slice(&(v.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&v);
+        }
+    }
+    bytes.extend_from_slice(&nonce.to_bytes_compressed_form());
+    Ok(ProofChallenge::hash(&bytes))
+}
+
+fn group_order() -> Result<BigNumber, BBSError> {
+    BigNumber::from_hex(GROUP_ORDER).map_err(ursa_error)
+}
+
+fn to_big_number(bytes: &[u8; FR_COMPRESSED_SIZE]) -> Result<BigNumber, BBSError> {
+    BigNumber::from_bytes(&bytes[..]).map_err(ursa_error)
+}
+
+/// Reduce an integer modulo the group order
+fn to_nonce(
+    value: &BigNumber,
+    order: &BigNumber,
+    ctx: &mut BigNumberContext,
+) -> Result<ProofNonce, BBSError> {
+    let value = value
+        .modulus(order, Some(ctx))
+        .and_then(|v| v.to_bytes())
+        .map_err(ursa_error)?;
+    let mut bytes = [0u8; FR_COMPRESSED_SIZE];
+    bytes[FR_COMPRESSED_SIZE - value.len()..].copy_from_slice(&value);
+    Ok(ProofNonce::from(&bytes))
+}
+
+fn ursa_error(e: UrsaCryptoError) -> BBSError {
+    BBSErrorKind::GeneralError {
+        msg: format!("Verifiable encryption failed: {}", e),
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cs_verifiable_encryption::CSKeypair;
+    use super::*;
+
+    #[test]
+    fn encrypt_hidden_messages() {
+        let messages = vec![
+            SignatureMessage::hash(b"name"),
+            SignatureMessage::hash(b"national id"),
+            SignatureMessage::hash(b"address"),
+            SignatureMessage::hash(b"country"),
+        ];
+        let (pk, sk) = Issuer::new_keys(messages.len()).unwrap();
+        let signature = Issuer::sign(&messages, &sk, &pk).unwrap();
+        let auditor = CSKeypair::new(2).unwrap();
+        let label = b"court order";
+        let nonce = Verifier::generate_proof_nonce();
+        let proof_request = Verifier::new_proof_request(&[3], &pk).unwrap();
+
+        let proof_messages = vec![
+            pm_hidden_raw!(messages[0]),
+            pm_hidden_raw!(messages[1]),
+            pm_hidden_raw!(messages[2]),
+            pm_revealed_raw!(messages[3]),
+        ];
+        let encrypted = [0, 1].iter().copied().collect::<BTreeSet<usize>>();
+        let proof = SignatureProofWithEncryption::new(
+            &signature,
+            &pk,
+            &proof_messages,
+            &encrypted,
+            &auditor.pub_key,
+            label,
+            &nonce,
+        )
+        .unwrap();
+
+        let revealed = proof
+            .verify(&proof_request, &encrypted, &auditor.pub_key, label, &nonce)
+            .unwrap();
+        assert_eq!(revealed[&3], messages[3]);
+
+        let decrypted =
+            decrypt_messages(&proof.ciphertext, label, &auditor.pub_key, &auditor.pri_key).unwrap();
+        assert_eq!(decrypted, messages[..2].to_vec());
+
+        // Bound to the label, nonce and the encrypted indices
+        assert!(proof
+            .verify(
+                &proof_request,
+                &encrypted,
+                &auditor.pub_key,
+                b"other",
+                &nonce
+            )
+            .is_err());
+        assert!(decrypt_messages(
+            &proof.ciphertext,
+            b"other",
+            &auditor.pub_key,
+            &auditor.pri_key
+        )
+        .is_err());
+        assert!(proof
+            .verify(
+                &proof_request,
+                &encrypted,
+                &auditor.pub_key,
+                label,
+                &Verifier::generate_proof_nonce()
+            )
+            .is_err());
+        let other = [0, 2].iter().copied().collect::<BTreeSet<usize>>();
+        assert!(proof
+            .verify(&proof_request, &other, &auditor.pub_key, label, &nonce)
+            .is_err());
+    }
+
+    #[test]
+    fn encrypt_out_of_range_response() {
+        let messages = vec![
+            SignatureMessage::hash(b"name"),
+            SignatureMessage::hash(b"national id"),
+        ];
+        let (pk, sk) = Issuer::new_keys(messages.len()).unwrap();
+        let signature = Issuer::sign(&messages, &sk, &pk).unwrap();
+        let auditor = CSKeypair::new(1).unwrap();
+        let label = b"court order";
+        let nonce = Verifier::generate_proof_nonce();
+        let proof_request = Verifier::new_proof_request(&[0], &pk).unwrap();
+        let proof_messages = vec![pm_revealed_raw!(messages[0]), pm_hidden_raw!(messages[1])];
+        let encrypted = [1].iter().copied().collect::<BTreeSet<usize>>();
+        let mut proof = SignatureProofWithEncryption::new(
+            &signature,
+            &pk,
+            &proof_messages,
+            &encrypted,
+            &auditor.pub_key,
+            label,
+            &nonce,
+        )
+        .unwrap();
+        assert!(proof
+            .verify(&proof_request, &encrypted, &auditor.pub_key, label, &nonce)
+            .is_ok());
+
+        // Same response mod q but too large
+        let shift = BigNumber::from_u32(2)
+            .unwrap()
+            .exp(&BigNumber::from_u32(BLINDING_BITS).unwrap(), None)
+            .unwrap();
+        let offset = group_order().unwrap().mul(&shift, None).unwrap();
+        proof.resp_messages[0] = proof.resp_messages[0].add(&offset).unwrap();
+        let err = proof
+            .verify(&proof_request, &encrypted, &auditor.pub_key, label, &nonce)
+            .unwrap_err();
+        match err.kind() {
+            BBSErrorKind::InvalidProof {
+                status: PoKOfSignatureProofStatus::BadHiddenMessage,
+            } => {}
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn encrypt_invalid_messages() {
+        let messages = vec![
+            SignatureMessage::hash(b"name"),
+            SignatureMessage::hash(b"id"),
+        ];
+        let (pk, sk) = Issuer::new_keys(messages.len()).unwrap();
+        let signature = Issuer::sign(&messages, &sk, &pk).unwrap();
+        let auditor = CSKeypair::new(1).unwrap();
+        let nonce = Verifier::generate_proof_nonce();
+        let proof_messages = vec![pm_revealed_raw!(messages[0]), pm_hidden_raw!(messages[1])];
+
+        for encrypted in &[vec![0], vec![2], vec![0, 1]] {
+            let encrypted = encrypted.iter().copied().collect::<BTreeSet<usize>>();
+            assert!(SignatureProofWithEncryption::new(
+                &signature,
+                &pk,
+                &proof_messages,
+                &encrypted,
+                &auditor.pub_key,
+                b"label",
+                &nonce,
+            )
+            .is_err());
+        }
+    }
+}
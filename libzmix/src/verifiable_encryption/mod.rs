@@ -4,3 +4,5 @@ pub use self::ursa::cl;
 pub use self::ursa::errors;
 
 pub mod cs_verifiable_encryption;
+#[cfg(feature = "bbs")]
+pub mod bbs_verifiable_encryption;
@@ -43,7 +43,7 @@ impl Share {
     /// The identifier is the first 4 bytes
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut o = self.identifier.to_be_bytes().to_vec();
-        o.append(&mut self.to_bytes());
+        o.extend_from_slice(self.value.as_slice());
         o
     }
 
@@ -205,3 +205,22 @@ impl Scheme {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn share_bytes_round_trip() {
+        let share = Share::new(7, b"secret value");
+        let bytes = share.to_bytes();
+        assert_eq!(&bytes[..4], &[0u8, 0, 0, 7]);
+        assert_eq!(&bytes[4..], b"secret value");
+
+        let share2 = Share::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(share2.identifier(), 7);
+        assert_eq!(share2.value(), share.value());
+
+        assert!(Share::try_from(&bytes[..3]).is_err());
+    }
+}